@@ -2,6 +2,8 @@
 
 local store = require("codeforge.store")
 local diff_utils = require("codeforge.diff")
+local config = require("codeforge.config")
+local protocol = require("codeforge.protocol")
 
 local M = {}
 
@@ -17,20 +19,85 @@ end
 -- Reference to the WebSocket client (set by init.lua)
 local ws_client = nil
 
+-- Commands waiting for their response, by id (false: no handler)
+---@type table<string, fun(response: table)|false>
+local pending_requests = {}
+local last_request_id = 0
+
 ---Set the WebSocket client reference
 ---@param client table
 function M.set_client(client)
   ws_client = client
+  -- Responses to commands sent on an earlier connection will not come
+  pending_requests = {}
+end
+
+---Send a command to the server
+---Every command carries an id; the server echoes it so the response reaches on_response.
+---Without a handler, failures are reported and successes ignored.
+---@param message table
+---@param on_response? fun(response: table)
+---@return boolean -- false if not connected
+local function request(message, on_response)
+  if not ws_client or not ws_client:is_active() then
+    return false
+  end
+
+  last_request_id = last_request_id + 1
+  message.id = tostring(last_request_id)
+  pending_requests[message.id] = on_response or false
+  ws_client:send_json(message)
+  return true
+end
+
+---Hand a response to the command that asked for it
+---@param message table Response with the command's id
+---@return boolean -- false if no command is waiting for it
+function M.handle_response(message)
+  local handler = message.id and pending_requests[message.id]
+  if handler == nil then
+    return false
+  end
+  pending_requests[message.id] = nil
+
+  if handler then
+    handler(message)
+  elseif not message.success then
+    local err = message.error or "unknown error"
+    if message.errors then
+      err = protocol.describe_errors(message.errors)
+    end
+    vim.notify("[codeforge] Command failed: " .. err, vim.log.levels.WARN)
+  end
+  return true
+end
+
+-- Forward declaration: needs the local apply helpers below
+local on_feedback_response
+
+---Find a hunk among the suggestion's pending hunks
+---@param suggestion_id string
+---@param hunk_id string
+---@return Hunk|nil
+local function find_hunk(suggestion_id, hunk_id)
+  local suggestion = store.get_suggestion(suggestion_id)
+  for _, hunk in ipairs(suggestion and suggestion.hunks or {}) do
+    if hunk.id == hunk_id then
+      return hunk
+    end
+  end
+  return nil
 end
 
 ---Send feedback to the server
 ---@param suggestion_id string
 ---@param hunk_id string
----@param action "accept" | "reject" | "modify"
+---@param action "accept" | "reject" | "modify" | "undo" | "request_changes"
 ---@param modified_diff? string
 ---@param comment? string
+---@param partial? boolean -- modified_diff holds the only lines being decided
 ---@return boolean
-function M.send_feedback(suggestion_id, hunk_id, action, modified_diff, comment)
+function M.send_feedback(suggestion_id, hunk_id, action, modified_diff, comment, partial)
   if not ws_client or not ws_client:is_active() then
     vim.notify("[codeforge] Not connected to server", vim.log.levels.ERROR)
     return false
@@ -60,9 +127,34 @@ function M.send_feedback(suggestion_id, hunk_id, action, modified_diff, comment)
     message.comment = comment
   end
 
-  ws_client:send_json(message)
-  return true
+  if partial then
+    message.partial = true
+  end
+
+  if action == "undo" then
+    return request(message, M.handle_undo_response)
+  end
+
+  -- Optimistic locking: tell the server which state of the hunk we decided on
+  local hunk = find_hunk(suggestion_id, hunk_id)
+  if not hunk then
+    return request(message)
+  end
+  message.stateVersion = hunk.stateVersion
+
+  -- Remember what goes into the file here, to take it back if the server refuses
+  local sent = { suggestion_id = suggestion_id, hunk_id = hunk_id, file = hunk.file }
+  if action == "accept" and not hunk.fileChange then
+    sent.applied = hunk.diff
+  elseif action == "modify" then
+    sent.applied = modified_diff
+  end
+
+  return request(message, function(response)
+    on_feedback_response(sent, response)
+  end)
 end
+local send_feedback = M.send_feedback
 
 ---Apply a hunk diff to a buffer (or open the file in a buffer first)
 ---@param file_path string Relative file path
@@ -134,6 +226,55 @@ local function apply_hunk_locally(file_path, diff)
   return true, nil
 end
 
+---Apply a file-level change (rename, delete, mode, ...) locally
+---Binary content can't come from the diff, so it is left to version control.
+---@param change table FileChange from the server
+---@return boolean success
+---@return string|nil error
+local function apply_file_change_locally(change)
+  if not working_dir then
+    return false, "Working directory not set"
+  end
+
+  if change.binary and change.kind ~= "delete" and change.kind ~= "rename" then
+    vim.notify(
+      string.format("[codeforge] Binary change to %s must be synced through version control", change.newPath),
+      vim.log.levels.WARN
+    )
+    return true, nil
+  end
+
+  local ok, err = diff_utils.apply_file_change(working_dir, change)
+  if not ok then
+    return false, err
+  end
+
+  -- Keep loaded buffers pointing at the right file
+  local old_path = working_dir .. "/" .. change.oldPath
+  for _, b in ipairs(vim.api.nvim_list_bufs()) do
+    if vim.api.nvim_buf_is_loaded(b) and vim.api.nvim_buf_get_name(b) == old_path then
+      if change.kind == "rename" then
+        vim.api.nvim_buf_set_name(b, working_dir .. "/" .. change.newPath)
+      elseif change.kind == "delete" then
+        vim.api.nvim_buf_delete(b, { force = true })
+      end
+    end
+  end
+
+  return true, nil
+end
+
+---Apply a hunk from the suggestion locally (content or file-level)
+---@param hunk table
+---@return boolean success
+---@return string|nil error
+local function apply_suggested_hunk(hunk)
+  if hunk.fileChange then
+    return apply_file_change_locally(hunk.fileChange)
+  end
+  return apply_hunk_locally(hunk.file, hunk.diff)
+end
+
 ---Accept the current hunk and apply it locally
 ---@param comment? string
 ---@return boolean
@@ -147,7 +288,7 @@ function M.accept_current(comment)
   end
 
   -- Apply the hunk locally first
-  local apply_ok, apply_err = apply_hunk_locally(hunk.file, hunk.diff)
+  local apply_ok, apply_err = apply_suggested_hunk(hunk)
   if not apply_ok then
     vim.notify("[codeforge] Failed to apply hunk: " .. (apply_err or "unknown error"), vim.log.levels.ERROR)
     return false
@@ -201,6 +342,11 @@ function M.modify_current(modified_diff, comment)
     return false
   end
 
+  if hunk.fileChange then
+    vim.notify("[codeforge] File-level hunks can only be accepted or rejected", vim.log.levels.WARN)
+    return false
+  end
+
   -- Apply the modified diff locally
   local apply_ok, apply_err = apply_hunk_locally(hunk.file, modified_diff)
   if not apply_ok then
@@ -217,6 +363,152 @@ function M.modify_current(modified_diff, comment)
   return success
 end
 
+---Accept or reject a selection of the current hunk's changed lines
+---The hunk narrowed to the selection goes out as a partial modify (accept) or
+---reject; the server keeps the rest of the hunk up for review as a new hunk.
+---@param selected table<number, boolean> Indices into the hunk's parsed changes
+---@param decision "accept" | "reject"
+---@return boolean
+function M.decide_lines_current(selected, decision)
+  local suggestion = store.get_current_suggestion()
+  local hunk = store.get_current_hunk()
+
+  if not suggestion or not hunk then
+    vim.notify("[codeforge] No hunk selected", vim.log.levels.WARN)
+    return false
+  end
+
+  if hunk.fileChange then
+    vim.notify("[codeforge] File-level hunks can only be accepted or rejected whole", vim.log.levels.WARN)
+    return false
+  end
+
+  -- Every change selected: an ordinary decision
+  local total, picked = 0, 0
+  for i, change in ipairs(diff_utils.parse_diff_changes(hunk.diff)) do
+    if change.type ~= "context" then
+      total = total + 1
+      if selected[i] then
+        picked = picked + 1
+      end
+    end
+  end
+  if picked == total then
+    if decision == "accept" then
+      return M.accept_current()
+    end
+    return M.reject_current()
+  end
+
+  local narrowed, err = diff_utils.select_changes(hunk.diff, selected)
+  if not narrowed then
+    vim.notify("[codeforge] " .. err, vim.log.levels.WARN)
+    return false
+  end
+
+  -- Accepted lines go into the file now; the rest waits for review
+  if decision == "accept" then
+    local apply_ok, apply_err = apply_hunk_locally(hunk.file, narrowed)
+    if not apply_ok then
+      vim.notify("[codeforge] Failed to apply selected lines: " .. (apply_err or "unknown error"), vim.log.levels.ERROR)
+      return false
+    end
+  end
+
+  local action = decision == "accept" and "modify" or "reject"
+  local success = send_feedback(suggestion.id, hunk.id, action, narrowed, nil, true)
+  if success then
+    store.set_hunk_state(hunk.id, decision == "accept" and "modified" or "rejected")
+    vim.notify(
+      string.format("%s %d of %d changed lines in %s", decision == "accept" and "Accepted" or "Rejected", picked, total, hunk.file),
+      vim.log.levels.INFO
+    )
+  end
+
+  return success
+end
+
+---Ask the AI to revise the current hunk instead of accepting or rejecting it
+---The hunk stays in the list as "awaiting revision" until the new version arrives.
+---@param instruction string What should change
+---@return boolean
+function M.request_changes_current(instruction)
+  local suggestion = store.get_current_suggestion()
+  local hunk = store.get_current_hunk()
+
+  if not suggestion or not hunk then
+    vim.notify("[codeforge] No hunk selected", vim.log.levels.WARN)
+    return false
+  end
+
+  if not instruction or instruction == "" then
+    vim.notify("[codeforge] Describe the changes you want", vim.log.levels.WARN)
+    return false
+  end
+
+  local success = send_feedback(suggestion.id, hunk.id, "request_changes", nil, instruction)
+  if success then
+    store.mark_revision_requested(hunk.id, instruction)
+    vim.notify(string.format("Requested changes to hunk in %s", hunk.file), vim.log.levels.INFO)
+  end
+
+  return success
+end
+
+---Pending hunks of a suggestion, in list order
+---@param suggestion Suggestion
+---@return Hunk[]
+local function pending_hunks(suggestion)
+  local hunks = {}
+  for _, hunk in ipairs(suggestion.hunks) do
+    local hunk_state = store.get_hunk_state(hunk.id)
+    if hunk_state and hunk_state.status == "pending" then
+      table.insert(hunks, hunk)
+    end
+  end
+  return hunks
+end
+
+-- Forward declaration: defined next to on_feedback_response
+local on_feedback_batch_response
+
+---Decide several hunks of a suggestion with one command
+---The server records all of the decisions or none of them.
+---@param suggestion_id string
+---@param hunks Hunk[]
+---@param action "accept" | "reject"
+---@return boolean -- false if not connected
+local function send_feedback_batch(suggestion_id, hunks, action)
+  local cwd = working_dir or vim.fn.getcwd()
+  local home = vim.fn.expand("~")
+  local relative_cwd = cwd
+  if cwd:sub(1, #home) == home then
+    relative_cwd = cwd:sub(#home + 2)  -- +2 to skip the trailing slash
+  end
+
+  local decisions = {}
+  -- Remember what goes into the files here, to take it back if the server refuses
+  local sent = {}
+  for _, hunk in ipairs(hunks) do
+    table.insert(decisions, { hunkId = hunk.id, action = action, stateVersion = hunk.stateVersion })
+    table.insert(sent, {
+      suggestion_id = suggestion_id,
+      hunk_id = hunk.id,
+      file = hunk.file,
+      applied = action == "accept" and not hunk.fileChange and hunk.diff or nil,
+    })
+  end
+
+  return request({
+    type = "feedback_batch",
+    suggestionId = suggestion_id,
+    decisions = decisions,
+    workingDirectory = relative_cwd,
+  }, function(response)
+    on_feedback_batch_response(suggestion_id, sent, response)
+  end)
+end
+
 ---Accept all pending hunks in current suggestion
 ---@return number -- count of accepted hunks
 function M.accept_all()
@@ -225,33 +517,36 @@ function M.accept_all()
     return 0
   end
 
-  local count = 0
+  if not ws_client or not ws_client:is_active() then
+    vim.notify("[codeforge] Not connected to server", vim.log.levels.ERROR)
+    return 0
+  end
+
+  -- Apply locally first; hunks that don't apply stay pending
+  local accepted = {}
   local errors = 0
-  for _, hunk in ipairs(suggestion.hunks) do
-    local hunk_state = store.get_hunk_state(hunk.id)
-    if hunk_state and hunk_state.status == "pending" then
-      -- Apply locally first
-      local apply_ok, _ = apply_hunk_locally(hunk.file, hunk.diff)
-      if apply_ok then
-        if send_feedback(suggestion.id, hunk.id, "accept") then
-          store.set_hunk_state(hunk.id, "accepted")
-          count = count + 1
-        end
-      else
-        errors = errors + 1
-      end
+  for _, hunk in ipairs(pending_hunks(suggestion)) do
+    if apply_suggested_hunk(hunk) then
+      table.insert(accepted, hunk)
+    else
+      errors = errors + 1
     end
   end
 
-  if count > 0 then
-    local msg = string.format("Accepted %d hunks", count)
-    if errors > 0 then
-      msg = msg .. string.format(" (%d failed)", errors)
-    end
-    vim.notify(msg, vim.log.levels.INFO)
+  if #accepted == 0 or not send_feedback_batch(suggestion.id, accepted, "accept") then
+    return 0
+  end
+  for _, hunk in ipairs(accepted) do
+    store.set_hunk_state(hunk.id, "accepted")
+  end
+
+  local msg = string.format("Accepted %d hunks", #accepted)
+  if errors > 0 then
+    msg = msg .. string.format(" (%d failed)", errors)
   end
+  vim.notify(msg, vim.log.levels.INFO)
 
-  return count
+  return #accepted
 end
 
 ---Reject all pending hunks in current suggestion
@@ -262,22 +557,255 @@ function M.reject_all()
     return 0
   end
 
-  local count = 0
-  for _, hunk in ipairs(suggestion.hunks) do
-    local hunk_state = store.get_hunk_state(hunk.id)
-    if hunk_state and hunk_state.status == "pending" then
-      if send_feedback(suggestion.id, hunk.id, "reject") then
-        store.set_hunk_state(hunk.id, "rejected")
-        count = count + 1
-      end
+  local rejected = pending_hunks(suggestion)
+  if #rejected == 0 then
+    return 0
+  end
+  if not send_feedback_batch(suggestion.id, rejected, "reject") then
+    vim.notify("[codeforge] Not connected to server", vim.log.levels.ERROR)
+    return 0
+  end
+  for _, hunk in ipairs(rejected) do
+    store.set_hunk_state(hunk.id, "rejected")
+  end
+
+  vim.notify(string.format("Rejected %d hunks", #rejected), vim.log.levels.INFO)
+
+  return #rejected
+end
+
+---Ask about or remark on the current hunk without deciding it
+---The AI answers in the hunk's review thread.
+---@param body string
+---@return boolean
+function M.comment_current(body)
+  local suggestion = store.get_current_suggestion()
+  local hunk = store.get_current_hunk()
+
+  if not suggestion or not hunk then
+    vim.notify("[codeforge] No hunk selected", vim.log.levels.WARN)
+    return false
+  end
+
+  if not ws_client or not ws_client:is_active() then
+    vim.notify("[codeforge] Not connected to server", vim.log.levels.ERROR)
+    return false
+  end
+
+  -- Get relative working directory
+  local cwd = working_dir or vim.fn.getcwd()
+  local home = vim.fn.expand("~")
+  local relative_cwd = cwd
+  if cwd:sub(1, #home) == home then
+    relative_cwd = cwd:sub(#home + 2)  -- +2 to skip the trailing slash
+  end
+
+  request({
+    type = "comment",
+    suggestionId = suggestion.id,
+    hunkId = hunk.id,
+    body = body,
+    workingDirectory = relative_cwd,
+  }, function(response)
+    if response.success and response.comment then
+      store.add_comment(response.comment)
+    elseif not response.success then
+      vim.notify("[codeforge] Comment failed: " .. (response.error or "unknown error"), vim.log.levels.WARN)
     end
+  end)
+
+  return true
+end
+
+---Split the current hunk into smaller ones before reviewing it
+---The new hunks arrive via suggestion.hunks_changed.
+---@param line? number Line of the hunk (after the @@ header) to cut before; cuts at context lines if nil
+---@return boolean
+function M.split_current(line)
+  local suggestion = store.get_current_suggestion()
+  local hunk = store.get_current_hunk()
+
+  if not suggestion or not hunk then
+    vim.notify("[codeforge] No hunk selected", vim.log.levels.WARN)
+    return false
+  end
+
+  if hunk.fileChange then
+    vim.notify("[codeforge] File-level hunks can't be split", vim.log.levels.WARN)
+    return false
+  end
+
+  if not ws_client or not ws_client:is_active() then
+    vim.notify("[codeforge] Not connected to server", vim.log.levels.ERROR)
+    return false
+  end
+
+  -- Get relative working directory
+  local cwd = working_dir or vim.fn.getcwd()
+  local home = vim.fn.expand("~")
+  local relative_cwd = cwd
+  if cwd:sub(1, #home) == home then
+    relative_cwd = cwd:sub(#home + 2)  -- +2 to skip the trailing slash
+  end
+
+  request({
+    type = "split",
+    suggestionId = suggestion.id,
+    hunkId = hunk.id,
+    line = line,
+    workingDirectory = relative_cwd,
+  })
+
+  return true
+end
+
+---Find the pending hunk that follows a hunk in its file
+---@param suggestion Suggestion
+---@param hunk Hunk
+---@return Hunk|nil
+local function next_hunk_in_file(suggestion, hunk)
+  local start = hunk.originalStartLine or 0
+  local best = nil
+  for _, h in ipairs(suggestion.hunks) do
+    local h_start = h.originalStartLine or 0
+    local h_state = store.get_hunk_state(h.id)
+    if h.id ~= hunk.id and h.file == hunk.file and not h.fileChange and h_start >= start
+      and (not h_state or h_state.status == "pending")
+      and (not best or h_start < (best.originalStartLine or 0)) then
+      best = h
+    end
+  end
+  return best
+end
+
+---Merge the current hunk with the next one in the same file
+---The merged hunk arrives via suggestion.hunks_changed.
+---@return boolean
+function M.merge_current()
+  local suggestion = store.get_current_suggestion()
+  local hunk = store.get_current_hunk()
+
+  if not suggestion or not hunk then
+    vim.notify("[codeforge] No hunk selected", vim.log.levels.WARN)
+    return false
   end
 
-  if count > 0 then
-    vim.notify(string.format("Rejected %d hunks", count), vim.log.levels.INFO)
+  local other = not hunk.fileChange and next_hunk_in_file(suggestion, hunk) or nil
+  if not other then
+    vim.notify("[codeforge] No following hunk in this file to merge with", vim.log.levels.WARN)
+    return false
   end
 
-  return count
+  if not ws_client or not ws_client:is_active() then
+    vim.notify("[codeforge] Not connected to server", vim.log.levels.ERROR)
+    return false
+  end
+
+  -- Get relative working directory
+  local cwd = working_dir or vim.fn.getcwd()
+  local home = vim.fn.expand("~")
+  local relative_cwd = cwd
+  if cwd:sub(1, #home) == home then
+    relative_cwd = cwd:sub(#home + 2)  -- +2 to skip the trailing slash
+  end
+
+  request({
+    type = "merge",
+    suggestionId = suggestion.id,
+    hunkIds = { hunk.id, other.id },
+    workingDirectory = relative_cwd,
+  })
+
+  return true
+end
+
+---Undo the most recent accept/reject/modify
+---The server restores the hunk and replies with a reverse diff to apply here.
+---@return boolean
+function M.undo_last()
+  local decision = store.get_last_decision()
+  if not decision then
+    vim.notify("[codeforge] Nothing to undo", vim.log.levels.INFO)
+    return false
+  end
+
+  return send_feedback(decision.suggestion_id, decision.hunk_id, "undo")
+end
+
+---Handle the server's reply to an undo request
+---@param message table Response with revertDiff/revertFileChange
+function M.handle_undo_response(message)
+  if not message.success then
+    store.discard_last_decision()
+    vim.notify("[codeforge] Undo failed: " .. (message.error or "unknown error"), vim.log.levels.WARN)
+    return
+  end
+
+  local ok, err = true, nil
+  if message.revertFileChange then
+    ok, err = apply_file_change_locally(message.revertFileChange)
+  elseif message.revertDiff and message.hunk then
+    ok, err = apply_hunk_locally(message.hunk.file, message.revertDiff)
+  end
+
+  if not ok then
+    vim.notify("[codeforge] Hunk restored but could not revert file: " .. (err or "unknown error"), vim.log.levels.WARN)
+  else
+    vim.notify(string.format("[codeforge] Undid %s hunk", message.previousAction or "reviewed"), vim.log.levels.INFO)
+  end
+end
+
+---Handle the server's reply to a decision sent by send_feedback
+---A conflict means another reviewer changed or decided the hunk first: the
+---local edit is taken back and the suggestion refetched.
+---@param sent { suggestion_id: string, hunk_id: string, file: string, applied?: string }
+---@param message table Response to the feedback command
+on_feedback_response = function(sent, message)
+  if message.success then
+    return
+  end
+
+  if message.code ~= "conflict" then
+    vim.notify("[codeforge] Command failed: " .. (message.error or "unknown error"), vim.log.levels.WARN)
+    return
+  end
+
+  if sent.applied then
+    local inverted = diff_utils.invert_hunk(sent.applied)
+    local ok = inverted and apply_hunk_locally(sent.file, inverted)
+    if not ok then
+      vim.notify("[codeforge] Could not take back the change to " .. sent.file, vim.log.levels.WARN)
+    end
+  end
+  store.forget_decision(sent.hunk_id)
+  vim.notify("[codeforge] " .. (message.error or "Hunk changed by another reviewer"), vim.log.levels.WARN)
+  M.request_suggestion(sent.suggestion_id)
+end
+
+---Handle the server's reply to a batch sent by send_feedback_batch
+---The server refused the whole batch: every local edit is taken back and the
+---suggestion refetched.
+---@param suggestion_id string
+---@param sent { suggestion_id: string, hunk_id: string, file: string, applied?: string }[]
+---@param message table Response to the feedback_batch command
+on_feedback_batch_response = function(suggestion_id, sent, message)
+  if message.success then
+    return
+  end
+
+  for i = #sent, 1, -1 do
+    local decision = sent[i]
+    if decision.applied then
+      local inverted = diff_utils.invert_hunk(decision.applied)
+      local ok = inverted and apply_hunk_locally(decision.file, inverted)
+      if not ok then
+        vim.notify("[codeforge] Could not take back the change to " .. decision.file, vim.log.levels.WARN)
+      end
+    end
+    store.forget_decision(decision.hunk_id)
+  end
+  vim.notify("[codeforge] No hunks were decided: " .. (message.error or "unknown error"), vim.log.levels.WARN)
+  M.request_suggestion(suggestion_id)
 end
 
 ---Complete the current suggestion
@@ -303,7 +831,7 @@ function M.complete_suggestion(action)
     relative_cwd = cwd:sub(#home + 2)  -- +2 to skip the trailing slash
   end
 
-  ws_client:send_json({
+  request({
     type = "complete",
     suggestionId = suggestion.id,
     action = action,
@@ -316,6 +844,50 @@ function M.complete_suggestion(action)
   return true
 end
 
+---Run the check pipeline against the current suggestion
+---Results arrive as suggestion.check events.
+---@return boolean
+function M.run_checks()
+  local suggestion = store.get_current_suggestion()
+  if not suggestion then
+    vim.notify("[codeforge] No suggestion selected", vim.log.levels.WARN)
+    return false
+  end
+
+  if not ws_client or not ws_client:is_active() then
+    vim.notify("[codeforge] Not connected to server", vim.log.levels.ERROR)
+    return false
+  end
+
+  -- Get relative working directory
+  local cwd = working_dir or vim.fn.getcwd()
+  local home = vim.fn.expand("~")
+  local relative_cwd = cwd
+  if cwd:sub(1, #home) == home then
+    relative_cwd = cwd:sub(#home + 2)  -- +2 to skip the trailing slash
+  end
+
+  request({
+    type = "run_checks",
+    suggestionId = suggestion.id,
+    workingDirectory = relative_cwd,
+  })
+
+  return true
+end
+
+-- Last presence sent, so navigation doesn't repeat it
+local last_presence = nil
+
+-- Protocol version agreed on subscribe
+local protocol_version = nil
+
+---Protocol version agreed with the server (nil until subscribed)
+---@return number|nil
+function M.get_protocol_version()
+  return protocol_version
+end
+
 ---Subscribe to suggestions for a working directory
 ---@param working_directory string
 function M.subscribe(working_directory)
@@ -323,7 +895,53 @@ function M.subscribe(working_directory)
     return
   end
 
-  ws_client:send_json({ type = "subscribe", workingDirectory = working_directory })
+  -- Reconnecting: the server replays what we missed (or sends a snapshot)
+  local since_seq = store.get_last_seq()
+
+  request({
+    type = "subscribe",
+    workingDirectory = working_directory,
+    protocolVersion = protocol.VERSION,
+    clientName = config.get().client_name or vim.env.USER,
+    sinceSeq = since_seq,
+  }, function(response)
+    if not response.success then
+      vim.notify("[codeforge] Subscribe failed: " .. (response.error or "unknown error"), vim.log.levels.ERROR)
+      return
+    end
+    protocol_version = response.protocolVersion or 1
+    if response.clientId then
+      store.set_client_id(response.clientId)
+    end
+    vim.notify(string.format("[codeforge] Subscribed to: %s", response.subscribed), vim.log.levels.INFO)
+    if since_seq then
+      -- Missed events (or a snapshot) follow this response
+      return
+    end
+    -- First subscription: count events from here and request the list of suggestions for this directory
+    store.set_last_seq(response.seq)
+    M.request_list()
+  end)
+  -- A new subscription starts without presence on the server
+  last_presence = nil
+end
+
+---Tell other reviewers what this editor is looking at
+---Only sent when it changes; nil for both means nothing is open.
+---@param suggestion_id? string
+---@param hunk_id? string
+function M.send_presence(suggestion_id, hunk_id)
+  if not ws_client or not ws_client:is_active() then
+    return
+  end
+
+  local key = (suggestion_id or "") .. "\0" .. (hunk_id or "")
+  if key == last_presence then
+    return
+  end
+  last_presence = key
+
+  request({ type = "presence", suggestionId = suggestion_id, hunkId = hunk_id })
 end
 
 ---Request list of suggestions from server
@@ -340,7 +958,70 @@ function M.request_list()
     relative_cwd = cwd:sub(#home + 2)  -- +2 to skip the trailing slash
   end
 
-  ws_client:send_json({ type = "list", workingDirectory = relative_cwd })
+  request({ type = "list", workingDirectory = relative_cwd }, function(response)
+    if not response.success then
+      vim.notify("[codeforge] Listing suggestions failed: " .. (response.error or "unknown error"), vim.log.levels.WARN)
+      return
+    end
+    -- The list only has briefs: fetch full details of the ones we don't have
+    for _, brief in ipairs(response.suggestions or {}) do
+      local existing = store.get_suggestion(brief.id)
+      if not existing or not existing.hunks or #existing.hunks == 0 then
+        M.request_suggestion(brief.id)
+      end
+    end
+  end)
+end
+
+---Request review statistics for the working directory
+---@param opts { interval?: "day" | "week" | "month", since?: string|number, until?: string|number }
+---@param on_stats fun(stats: table)
+---@return boolean -- false if not connected
+function M.request_stats(opts, on_stats)
+  if not ws_client or not ws_client:is_active() then
+    vim.notify("[codeforge] Not connected to server", vim.log.levels.ERROR)
+    return false
+  end
+
+  -- Get relative working directory
+  local cwd = working_dir or vim.fn.getcwd()
+  local home = vim.fn.expand("~")
+  local relative_cwd = cwd
+  if cwd:sub(1, #home) == home then
+    relative_cwd = cwd:sub(#home + 2)  -- +2 to skip the trailing slash
+  end
+
+  return request({
+    type = "stats",
+    workingDirectory = relative_cwd,
+    interval = opts.interval,
+    since = opts.since,
+    ["until"] = opts["until"],
+  }, function(response)
+    if not response.success then
+      vim.notify("[codeforge] Fetching stats failed: " .. (response.error or "unknown error"), vim.log.levels.WARN)
+      return
+    end
+    on_stats(response.stats)
+  end)
+end
+
+---Request every project the server serves (all registered ones, under the daemon)
+---@param on_projects fun(projects: { workingDirectory: string, suggestions: table[] }[])
+---@return boolean -- false if not connected
+function M.request_projects(on_projects)
+  if not ws_client or not ws_client:is_active() then
+    vim.notify("[codeforge] Not connected to server", vim.log.levels.ERROR)
+    return false
+  end
+
+  return request({ type = "projects" }, function(response)
+    if not response.success then
+      vim.notify("[codeforge] Listing projects failed: " .. (response.error or "unknown error"), vim.log.levels.WARN)
+      return
+    end
+    on_projects(response.projects or {})
+  end)
 end
 
 ---Request details of a specific suggestion
@@ -358,7 +1039,13 @@ function M.request_suggestion(suggestion_id)
     relative_cwd = cwd:sub(#home + 2)  -- +2 to skip the trailing slash
   end
 
-  ws_client:send_json({ type = "get", suggestionId = suggestion_id, workingDirectory = relative_cwd })
+  request({ type = "get", suggestionId = suggestion_id, workingDirectory = relative_cwd }, function(response)
+    if response.success and response.suggestion then
+      store.add_suggestion(response.suggestion)
+    elseif not response.success then
+      vim.notify("[codeforge] Command failed: " .. (response.error or "unknown error"), vim.log.levels.WARN)
+    end
+  end)
 end
 
 return M
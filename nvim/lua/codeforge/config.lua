@@ -1,8 +1,9 @@
 ---@class CodeForgeConfig
----@field server { host: string, port: number }
----@field ui { list_width: number, position: string }
+---@field server { host: string, port: number, socket?: string, token?: string, token_file?: string }
+---@field ui { list_width: number, position: string, layout: "inline" | "split" }
 ---@field keymaps table<string, string>
 ---@field auto_connect boolean
+---@field client_name string|nil
 
 local M = {}
 
@@ -11,10 +12,15 @@ M.defaults = {
   server = {
     host = "127.0.0.1",
     port = 4097,
+    socket = nil,               -- Unix socket path (used instead of host/port; relative to cwd)
+    token = nil,                -- Auth token (read from token_file if nil)
+    token_file = nil,           -- Defaults to <cwd>/.opencode/codeforge.token
   },
   ui = {
     list_width = 40,
     position = "right", -- "left" or "right" for hunk list panel
+    layout = "inline",  -- "inline" (highlighted preview) or "split" (original and proposed side by side)
+    group_by = "file",  -- "file" or "item" (group Rust hunks by the impl/fn/mod they touch)
   },
   keymaps = {
     open = "<leader>cf",        -- Open CodeForge
@@ -24,8 +30,18 @@ M.defaults = {
     reject = "<C-n>",           -- Reject current hunk (no)
     accept_all = "<C-a>",       -- Accept all remaining hunks
     reject_all = "<C-x>",       -- Reject all remaining hunks
+    undo = "u",                 -- Undo last accept/reject
+    comment = "c",              -- Comment on current hunk (AI replies in a thread)
+    request_changes = "r",      -- Ask the AI to revise current hunk
+    split = "s",                -- Split current hunk at its context lines
+    merge = "m",                -- Merge current hunk with the next one in its file
+    run_checks = "!",           -- Run the check pipeline against the current suggestion
+    modify = "<C-s>",           -- Send edits in the proposed pane as a modification (split layout)
+    next_hunk = "]h",           -- Next hunk (split layout)
+    prev_hunk = "[h",           -- Previous hunk (split layout)
   },
   auto_connect = true,          -- Connect to server on setup
+  client_name = nil,            -- Name other reviewers see (defaults to $USER)
   debug = false,                -- Enable debug logging
 }
 
@@ -42,4 +58,52 @@ function M.get()
   return M.options
 end
 
+---Resolve the server auth token
+---Order: server.token, $CODEFORGE_TOKEN, then the token file the server writes
+---@return string|nil
+function M.get_token()
+  local server = (M.options and M.options.server) or {}
+  if server.token and server.token ~= "" then
+    return server.token
+  end
+
+  local env_token = vim.env.CODEFORGE_TOKEN
+  if env_token and env_token ~= "" then
+    return env_token
+  end
+
+  local token_file = server.token_file or (vim.fn.getcwd() .. "/.opencode/codeforge.token")
+  local file = io.open(token_file, "r")
+  if not file then
+    return nil
+  end
+  local token = file:read("*l")
+  file:close()
+  if token then
+    token = vim.trim(token)
+  end
+  if token == "" then
+    return nil
+  end
+  return token
+end
+
+---Resolve the Unix socket to connect to, if any
+---Order: server.socket, then $CODEFORGE_SOCKET; relative paths are relative to cwd
+---@return string|nil
+function M.get_socket()
+  local server = (M.options and M.options.server) or {}
+  local socket = server.socket
+  if not socket or socket == "" then
+    socket = vim.env.CODEFORGE_SOCKET
+  end
+  if not socket or socket == "" then
+    return nil
+  end
+  if socket:sub(1, 1) ~= "/" then
+    socket = vim.fn.getcwd() .. "/" .. socket
+  end
+  return socket
+end
+
 return M
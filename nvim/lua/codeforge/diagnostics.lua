@@ -8,6 +8,8 @@ local M = {}
 
 -- Diagnostic namespace
 local ns = vim.api.nvim_create_namespace("codeforge_diagnostics")
+-- Problems reported by the check pipeline
+local check_ns = vim.api.nvim_create_namespace("codeforge_checks")
 
 -- Track which buffers we've set up code actions for
 local registered_buffers = {}
@@ -177,6 +179,32 @@ local function get_relative_path(full_path)
   return nil
 end
 
+---Describe the compiler problems a hunk introduces
+---@param diagnostics CheckDiagnostic[]|nil
+---@return string|nil -- e.g. "this hunk introduces 1 error, 2 warnings"; nil if none
+function M.summarize_problems(diagnostics)
+  local errors, warnings = 0, 0
+  for _, diag in ipairs(diagnostics or {}) do
+    if diag.severity == "warning" then
+      warnings = warnings + 1
+    else
+      errors = errors + 1
+    end
+  end
+
+  local parts = {}
+  if errors > 0 then
+    table.insert(parts, string.format("%d error%s", errors, errors == 1 and "" or "s"))
+  end
+  if warnings > 0 then
+    table.insert(parts, string.format("%d warning%s", warnings, warnings == 1 and "" or "s"))
+  end
+  if #parts == 0 then
+    return nil
+  end
+  return "this hunk introduces " .. table.concat(parts, ", ")
+end
+
 ---Get hunks for a specific file
 ---@param file_path string Relative file path
 ---@return table[] hunks
@@ -274,11 +302,14 @@ function M.publish_diagnostics(bufnr)
   
   if not rel_path then
     vim.diagnostic.set(ns, bufnr, {})
+    vim.diagnostic.set(check_ns, bufnr, {})
     return
   end
   
   local hunks = M.get_hunks_for_file(rel_path)
   local diagnostics = {}
+  -- Where each pending hunk's hint is shown (1-indexed)
+  local hunk_lines = {}
   
   for _, item in ipairs(hunks) do
     -- Calculate line offset for this hunk (based on all previous hunks in the suggestion)
@@ -305,6 +336,12 @@ function M.publish_diagnostics(bufnr)
     else
       local start_line, line_count = parse_hunk_lines(adjusted_diff)
       if start_line then
+        hunk_lines[item.hunk.id] = start_line
+        local message = item.hunk.description or "AI suggestion available"
+        local problems = M.summarize_problems(item.hunk.diagnostics)
+        if problems then
+          message = string.format("%s (%s)", message, problems)
+        end
         table.insert(diagnostics, {
           lnum = start_line - 1, -- 0-indexed
           end_lnum = start_line - 1 + (line_count or 1) - 1,
@@ -312,7 +349,7 @@ function M.publish_diagnostics(bufnr)
           end_col = 0,
           severity = vim.diagnostic.severity.HINT,
           source = "codeforge",
-          message = item.hunk.description or "AI suggestion available",
+          message = message,
           code = item.hunk.id,
           data = {
             hunk_id = item.hunk.id,
@@ -325,6 +362,59 @@ function M.publish_diagnostics(bufnr)
   end
   
   vim.diagnostic.set(ns, bufnr, diagnostics)
+  vim.diagnostic.set(check_ns, bufnr, M.get_check_diagnostics(rel_path, hunk_lines))
+end
+
+---Build diagnostics for what the compiler and the check pipeline reported in a file
+---Problems inside a pending hunk are shown on that hunk's hint, since the
+---buffer doesn't hold the proposed lines yet; the rest at their own line.
+---@param file_path string Relative file path
+---@param hunk_lines table<string, number> Pending hunk ID -> hint line (1-indexed)
+---@return table[] diagnostics
+function M.get_check_diagnostics(file_path, hunk_lines)
+  local severities = {
+    error = vim.diagnostic.severity.ERROR,
+    warning = vim.diagnostic.severity.WARN,
+  }
+  local diagnostics = {}
+
+  local function add(suggestion_id, source, diag)
+    if diag.file ~= file_path then
+      return
+    end
+    local hint_line = diag.hunkId and hunk_lines[diag.hunkId]
+    table.insert(diagnostics, {
+      lnum = (hint_line or diag.line) - 1, -- 0-indexed
+      col = hint_line and 0 or math.max((diag.column or 1) - 1, 0),
+      severity = severities[diag.severity] or vim.diagnostic.severity.ERROR,
+      source = "codeforge:" .. source,
+      message = diag.message,
+      code = diag.code,
+      data = {
+        hunk_id = diag.hunkId,
+        suggestion_id = suggestion_id,
+        check = source,
+      },
+    })
+  end
+
+  for _, suggestion in ipairs(store.get_suggestions()) do
+    -- Compiler diagnostics sent with the hunks, only while they are pending
+    for _, hunk in ipairs(suggestion.hunks) do
+      if hunk_lines[hunk.id] then
+        for _, diag in ipairs(hunk.diagnostics or {}) do
+          add(suggestion.id, "cargo", diag)
+        end
+      end
+    end
+    for _, check in ipairs(store.get_checks(suggestion.id)) do
+      for _, diag in ipairs(check.diagnostics or {}) do
+        add(suggestion.id, check.name, diag)
+      end
+    end
+  end
+
+  return diagnostics
 end
 
 ---Clear diagnostics for a buffer
@@ -332,6 +422,7 @@ end
 function M.clear_diagnostics(bufnr)
   if vim.api.nvim_buf_is_valid(bufnr) then
     vim.diagnostic.set(ns, bufnr, {})
+    vim.diagnostic.set(check_ns, bufnr, {})
   end
 end
 
@@ -532,6 +623,14 @@ function M.setup()
   store.on("on_hunk_applied", function()
     M.refresh_all()
   end)
+
+  store.on("on_snapshot", function()
+    M.refresh_all()
+  end)
+
+  store.on("on_check", function()
+    M.refresh_all()
+  end)
   
   -- Register code action source (for LSP integration)
   M.register_code_action_source()
@@ -165,4 +165,256 @@ function M.get_removed_lines(diff)
   return removed
 end
 
+---Get the lines a hunk leaves in the file (context and added lines)
+---@param diff string
+---@return string[]
+local function result_lines(diff)
+  local result = {}
+  for _, change in ipairs(M.parse_diff_changes(diff)) do
+    if change.type ~= "remove" then
+      table.insert(result, change.content)
+    end
+  end
+  return result
+end
+
+---Compare what two versions of a hunk would leave in the file
+---Used to show how a revised hunk differs from the version it replaced.
+---@param old_diff string Previous version of the hunk
+---@param new_diff string Revised version of the hunk
+---@return { type: "add"|"remove", content: string }[]
+function M.version_changes(old_diff, new_diff)
+  local between = M.compute_diff(result_lines(old_diff), result_lines(new_diff), 0)
+  local changes = {}
+  for _, change in ipairs(M.parse_diff_changes(between)) do
+    if change.type ~= "context" then
+      table.insert(changes, change)
+    end
+  end
+  return changes
+end
+
+---Find the changes a range of preview lines covers
+---Preview lines are the hunk's context and added lines, numbered from 1. Removed
+---lines aren't shown, so each goes with the line that takes its place.
+---@param diff string
+---@param first number First preview line of the range
+---@param last number Last preview line of the range
+---@return table<number, boolean> -- indices into parse_diff_changes(diff)
+function M.changes_in_range(diff, first, last)
+  local changes = M.parse_diff_changes(diff)
+  local shown = 0
+  for _, change in ipairs(changes) do
+    if change.type ~= "remove" then
+      shown = shown + 1
+    end
+  end
+
+  local selected = {}
+  local line = 0
+  for i, change in ipairs(changes) do
+    local at
+    if change.type == "remove" then
+      at = math.min(line + 1, math.max(shown, 1))
+    else
+      line = line + 1
+      at = line
+    end
+    if change.type ~= "context" and at >= first and at <= last then
+      selected[i] = true
+    end
+  end
+  return selected
+end
+
+---Find where to cut a hunk so that a preview line starts the second part
+---Removed lines go with the line that takes their place, as in changes_in_range.
+---@param diff string
+---@param line number Preview line, numbered from 1
+---@return number|nil -- index into parse_diff_changes(diff) (the server's split line)
+function M.split_index(diff, line)
+  local shown = 0
+  for i, change in ipairs(M.parse_diff_changes(diff)) do
+    local at
+    if change.type == "remove" then
+      at = shown + 1
+    else
+      shown = shown + 1
+      at = shown
+    end
+    if at >= line then
+      return i
+    end
+  end
+  return nil
+end
+
+---Narrow a hunk to a selection of its changes
+---Unselected additions are dropped and unselected removals become context; the
+---header is recomputed. Used to accept or reject part of a hunk.
+---@param diff string
+---@param selected table<number, boolean> Indices into parse_diff_changes(diff)
+---@return string|nil, string|nil -- narrowed hunk, error
+function M.select_changes(diff, selected)
+  local header_line = vim.split(diff, "\n")[1]
+  local header = M.parse_hunk_header(header_line)
+  if not header then
+    return nil, "Invalid hunk header"
+  end
+
+  local body = {}
+  local old_count, new_count, picked = 0, 0, 0
+  for i, change in ipairs(M.parse_diff_changes(diff)) do
+    if change.type == "context" or (change.type == "remove" and not selected[i]) then
+      table.insert(body, " " .. change.content)
+      old_count = old_count + 1
+      new_count = new_count + 1
+    elseif change.type == "remove" then
+      table.insert(body, "-" .. change.content)
+      old_count = old_count + 1
+      picked = picked + 1
+    elseif selected[i] then
+      table.insert(body, "+" .. change.content)
+      new_count = new_count + 1
+      picked = picked + 1
+    end
+  end
+
+  if picked == 0 then
+    return nil, "No changed lines selected"
+  end
+
+  local section = header_line:match("^@@ .- @@(.*)$") or ""
+  table.insert(body, 1, string.format("@@ -%d,%d +%d,%d @@%s", header.old_start, old_count, header.new_start, new_count, section))
+  return table.concat(body, "\n"), nil
+end
+
+---Invert a hunk so applying it undoes apply_hunk
+---Additions and removals swap; the result applies where the hunk was applied
+---(its old start), which is where apply_hunk put the new lines.
+---@param diff string
+---@return string|nil, string|nil -- inverted hunk, error
+function M.invert_hunk(diff)
+  local header_line = vim.split(diff, "\n")[1]
+  local header = M.parse_hunk_header(header_line)
+  if not header then
+    return nil, "Invalid hunk header"
+  end
+
+  local body = {}
+  for _, change in ipairs(M.parse_diff_changes(diff)) do
+    if change.type == "add" then
+      table.insert(body, "-" .. change.content)
+    elseif change.type == "remove" then
+      table.insert(body, "+" .. change.content)
+    else
+      table.insert(body, " " .. change.content)
+    end
+  end
+
+  local section = header_line:match("^@@ .- @@(.*)$") or ""
+  table.insert(body, 1, string.format("@@ -%d,%d +%d,%d @@%s", header.old_start, header.new_count, header.old_start, header.old_count, section))
+  return table.concat(body, "\n"), nil
+end
+
+---Short label for a file-level change (rename, delete, mode change, ...)
+---@param change table FileChange from the server
+---@return string
+function M.describe_file_change(change)
+  local label
+  if change.kind == "rename" or change.kind == "copy" then
+    label = string.format("%s %s → %s", change.kind, change.oldPath, change.newPath)
+    if change.similarity then
+      label = label .. string.format(" (%d%%)", change.similarity)
+    end
+  elseif change.kind == "add" then
+    label = "new " .. (change.binary and "binary" or "empty") .. " file"
+  elseif change.kind == "delete" then
+    label = "delete " .. (change.binary and "binary " or "") .. "file"
+  elseif change.kind == "mode" then
+    label = string.format("mode %s → %s", change.oldMode or "?", change.newMode or "?")
+  else
+    label = "binary change"
+  end
+
+  if change.kind ~= "mode" and change.oldMode and change.newMode and change.oldMode ~= change.newMode then
+    label = label .. string.format(", mode %s → %s", change.oldMode, change.newMode)
+  end
+
+  return label
+end
+
+---Convert a git file mode ("100755") into permission bits
+---@param mode string|nil
+---@return number|nil
+local function permission_bits(mode)
+  if not mode or mode:sub(1, 3) ~= "100" then
+    return nil
+  end
+  return tonumber(mode:sub(-3), 8)
+end
+
+---Apply a file-level change to the file system
+---Binary content can't be reconstructed from a diff and is reported as an error.
+---@param root string Working directory
+---@param change table FileChange from the server
+---@return boolean, string|nil -- success, error
+function M.apply_file_change(root, change)
+  local uv = vim.uv or vim.loop
+  local old_path = root .. "/" .. change.oldPath
+  local new_path = root .. "/" .. change.newPath
+  local exists = function(path)
+    return uv.fs_stat(path) ~= nil
+  end
+
+  if change.binary and change.kind ~= "delete" and change.kind ~= "rename" then
+    return false, string.format("Binary change to %s cannot be applied from a diff; sync it through version control", change.newPath)
+  end
+
+  if change.kind == "add" then
+    if exists(new_path) then
+      return false, "File already exists: " .. change.newPath
+    end
+    vim.fn.mkdir(vim.fn.fnamemodify(new_path, ":h"), "p")
+    vim.fn.writefile({}, new_path)
+  elseif change.kind == "delete" then
+    if not exists(old_path) then
+      return false, "File not found: " .. change.oldPath
+    end
+    local ok, err = os.remove(old_path)
+    if not ok then
+      return false, err
+    end
+    return true, nil
+  elseif change.kind == "rename" or change.kind == "copy" then
+    if not exists(old_path) then
+      return false, "File not found: " .. change.oldPath
+    end
+    if exists(new_path) then
+      return false, "File already exists: " .. change.newPath
+    end
+    vim.fn.mkdir(vim.fn.fnamemodify(new_path, ":h"), "p")
+    local ok, err
+    if change.kind == "rename" then
+      ok, err = uv.fs_rename(old_path, new_path)
+    else
+      ok, err = uv.fs_copyfile(old_path, new_path)
+    end
+    if not ok then
+      return false, err
+    end
+  elseif change.kind == "mode" then
+    if not exists(new_path) then
+      return false, "File not found: " .. change.newPath
+    end
+  end
+
+  local bits = permission_bits(change.newMode)
+  if bits and (change.kind == "add" or change.oldMode ~= change.newMode) then
+    uv.fs_chmod(new_path, bits)
+  end
+
+  return true, nil
+end
+
 return M
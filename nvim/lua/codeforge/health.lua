@@ -20,7 +20,8 @@ function M.check()
   if config_ok then
     local opts = config.get()
     if opts and opts.server then
-      health.info(string.format("Server: %s:%d", opts.server.host, opts.server.port))
+      local socket = config.get_socket()
+      health.info("Server: " .. (socket or string.format("%s:%d", opts.server.host, opts.server.port)))
       health.info(string.format("Auto-connect: %s", tostring(opts.auto_connect)))
     else
       health.warn("Config not initialized - call setup() first")
@@ -55,6 +56,18 @@ function M.check()
     health.error("Failed to load store module")
   end
 
+  -- Check the protocol schema (generated from plugin/src/types.ts)
+  local protocol_ok, protocol_err = pcall(function()
+    local protocol = require("codeforge.protocol")
+    protocol.validate("ServerMessage", { type = "connected", message = "", protocolVersion = protocol.VERSION })
+  end)
+  if protocol_ok then
+    local version = require("codeforge.actions").get_protocol_version()
+    health.ok("Protocol schema loaded" .. (version and string.format(" (speaking version %d)", version) or ""))
+  else
+    health.error("Failed to load protocol schema: " .. tostring(protocol_err))
+  end
+
   -- Check WebSocket module
   local ws_ok, ws_err = pcall(require, "codeforge.websocket")
   if ws_ok then
@@ -82,6 +95,8 @@ function M.check()
 
   local host = opts.server.host
   local port = opts.server.port
+  local socket = config.get_socket()
+  local address = socket or string.format("%s:%d", host, port)
 
   -- Check working directory first (needed for health endpoint)
   health.start("Project")
@@ -99,18 +114,30 @@ function M.check()
   local encoded_cwd = relative_cwd:gsub("([^A-Za-z0-9%-_%.~])", function(c)
     return string.format("%%%02X", string.byte(c))
   end)
-  local curl_handle = io.popen(string.format("curl -s -o /dev/null -w '%%{http_code}' --connect-timeout 2 'http://%s:%d/health?workingDirectory=%s' 2>/dev/null", host, port, encoded_cwd))
+  local token = config.get_token()
+  if token then
+    health.ok("Auth token found")
+  else
+    health.warn("No auth token found - expected .opencode/codeforge.token (or server.token / $CODEFORGE_TOKEN)")
+  end
+  local auth_header = token and string.format("-H 'Authorization: Bearer %s' ", token) or ""
+  local target = socket and string.format("--unix-socket '%s' 'http://localhost", socket)
+    or string.format("'http://%s:%d", host, port)
+  local curl_handle = io.popen(string.format("curl -s -o /dev/null -w '%%{http_code}' --connect-timeout 2 %s%s/health?workingDirectory=%s' 2>/dev/null", auth_header, target, encoded_cwd))
   if curl_handle then
     local status = curl_handle:read("*a")
     curl_handle:close()
     if status == "200" then
       http_ok = true
-      health.ok(string.format("HTTP server responding at %s:%d", host, port))
+      health.ok("HTTP server responding at " .. address)
+    elseif status == "401" then
+      http_ok = true
+      health.error("Server rejected the auth token (401) - check .opencode/codeforge.token")
     end
   end
 
   if not http_ok then
-    health.error(string.format("Cannot connect to server at %s:%d", host, port))
+    health.error("Cannot connect to server at " .. address)
     health.info("Make sure OpenCode is running with the suggestion-manager plugin")
   end
   
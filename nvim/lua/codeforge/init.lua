@@ -5,6 +5,7 @@ local config = require("codeforge.config")
 local store = require("codeforge.store")
 local websocket = require("codeforge.websocket")
 local actions = require("codeforge.actions")
+local protocol = require("codeforge.protocol")
 local ui = require("codeforge.ui")
 
 local M = {}
@@ -18,17 +19,17 @@ local reconnect_attempts = 0
 local max_reconnect_attempts = 10
 local reconnect_delay = 2000 -- ms
 
----Handle a suggestion brief (from list) - fetch full details
----@param brief table Brief suggestion info (id, description, hunkCount, etc.)
-local function handle_suggestion_brief(brief)
-  -- Only request full details if we don't already have hunks
-  local existing = store.get_suggestion(brief.id)
-  if existing and existing.hunks and #existing.hunks > 0 then
-    return
-  end
-
-  -- Request full details
-  actions.request_suggestion(brief.id)
+-- Project being reviewed (Neovim's cwd unless switched with :CodeForgeProjects)
+local project_dir = nil
+
+---Point every module at a project directory
+---@param dir string
+local function set_working_dir(dir)
+  project_dir = dir
+  ui.set_working_dir(dir)
+  actions.set_working_dir(dir)
+  require("codeforge.ui.shadow").set_working_dir(dir)
+  require("codeforge.diagnostics").set_working_dir(dir)
 end
 
 ---Handle incoming WebSocket messages
@@ -40,8 +41,21 @@ local function on_message(data)
     return
   end
 
+  -- Drop anything that doesn't match the protocol schema
+  local errors = protocol.validate("ServerMessage", message)
+  if #errors > 0 then
+    vim.notify("[codeforge] Invalid message from server: " .. protocol.describe_errors(errors), vim.log.levels.WARN)
+    return
+  end
+
   local msg_type = message.type
 
+  -- Logged events carry a seq; a replay after reconnecting may repeat ones already seen
+  if message.seq and msg_type ~= "response" and msg_type ~= "suggestion.snapshot"
+    and not store.advance_seq(message.seq) then
+    return
+  end
+
   if msg_type == "connected" then
     -- Initial connection - server no longer sends suggestions automatically
     -- We need to subscribe and then request the list
@@ -53,6 +67,16 @@ local function on_message(data)
       store.add_suggestion(message.suggestion)
     end
 
+  elseif msg_type == "suggestion.updated" then
+    -- The AI re-published a suggestion after further edits
+    if message.suggestion then
+      store.update_suggestion(message.suggestion, {
+        added = message.added or {},
+        changed = message.changed or {},
+        removed = message.removed or {},
+      })
+    end
+
   elseif msg_type == "suggestion.hunk_applied" then
     -- Hunk was applied/rejected/modified
     local status_map = {
@@ -62,6 +86,51 @@ local function on_message(data)
     }
     local status = status_map[message.action] or "pending"
     store.set_hunk_state(message.hunkId, status)
+    -- Partial decision: the rest of the hunk is back up for review under a new ID
+    if message.remainingHunk and not store.add_remaining_hunk(message.suggestionId, message.hunkId, message.remainingHunk) then
+      actions.request_suggestion(message.suggestionId)
+    end
+
+  elseif msg_type == "suggestion.hunk_reverted" then
+    -- A decision was undone; put the hunk back (refetch if we never had it)
+    if not message.hunk or not store.restore_hunk(message.suggestionId, message.hunk, message.replaces) then
+      actions.request_suggestion(message.suggestionId)
+    end
+
+  elseif msg_type == "suggestion.revision_requested" then
+    store.mark_revision_requested(message.hunkId, message.instruction, message.stateVersion)
+
+  elseif msg_type == "suggestion.hunk_revised" then
+    -- The AI sent a new version of a hunk; swap it in (refetch if we never had it)
+    if not message.hunk or not store.revise_hunk(message.suggestionId, message.hunk, message.previous) then
+      actions.request_suggestion(message.suggestionId)
+    end
+
+  elseif msg_type == "suggestion.hunks_changed" then
+    -- Pending hunks were split or merged; swap in the new ones (refetch if we never had them)
+    if not message.hunks or not store.replace_hunks(message.suggestionId, message.replaced or {}, message.hunks) then
+      actions.request_suggestion(message.suggestionId)
+    end
+
+  elseif msg_type == "suggestion.comment" then
+    -- New message in a hunk's review thread (ours echoed back, or an AI reply)
+    if message.comment then
+      store.add_comment(message.comment)
+    end
+
+  elseif msg_type == "suggestion.check" then
+    -- A check of the pipeline started or finished
+    if message.check then
+      store.set_check(message.check)
+    end
+
+  elseif msg_type == "suggestion.snapshot" then
+    -- Missed more events than the server keeps: take its state wholesale
+    store.apply_snapshot(message.suggestions, message.seq)
+
+  elseif msg_type == "suggestion.presence" then
+    -- Who else is reviewing this working directory, and where
+    store.set_presence(message.clients)
 
   elseif msg_type == "suggestion.status" then
     store.handle_status(message.status, message.message, message.suggestionId)
@@ -74,24 +143,8 @@ local function on_message(data)
     )
 
   elseif msg_type == "response" then
-    -- Response to a command we sent
-    if message.success then
-      -- Handle successful responses
-      if message.suggestions then
-        -- Response to list command - these are brief, request full details
-        for _, brief in ipairs(message.suggestions) do
-          handle_suggestion_brief(brief)
-        end
-      elseif message.suggestion then
-        -- Response to get command - this has full details
-        store.add_suggestion(message.suggestion)
-      elseif message.subscribed then
-        -- Successfully subscribed to working directory
-        vim.notify(string.format("[codeforge] Subscribed to: %s", message.subscribed), vim.log.levels.INFO)
-        -- Request the list of suggestions for this directory
-        actions.request_list()
-      end
-    else
+    -- Response to a command we sent, matched by its id
+    if not actions.handle_response(message) and not message.success then
       vim.notify(
         "[codeforge] Command failed: " .. (message.error or "unknown error"),
         vim.log.levels.WARN
@@ -106,7 +159,7 @@ local function on_connect()
   reconnect_attempts = 0
 
   -- Subscribe to suggestions for this working directory (relative to home)
-  local cwd = vim.fn.getcwd()
+  local cwd = project_dir or vim.fn.getcwd()
   local home = vim.fn.expand("~")
   local relative_cwd = cwd
   if cwd:sub(1, #home) == home then
@@ -146,11 +199,20 @@ function M.connect()
     reconnect_timer = nil
   end
 
+  -- Authenticate with the token the server stored in .opencode/
+  local headers = {}
+  local token = config.get_token()
+  if token then
+    headers["Authorization"] = "Bearer " .. token
+  end
+
   -- Create new client
   ws_client = websocket.create({
     host = opts.server.host,
     port = opts.server.port,
+    socket = config.get_socket(),
     path = "/ws",
+    headers = headers,
   })
 
   if not ws_client then
@@ -178,6 +240,21 @@ function M.disconnect()
   store.set_connected(false)
 end
 
+---Review another project served by the same server (e.g. the daemon)
+---@param dir string Absolute project directory
+function M.switch_project(dir)
+  if dir == project_dir then
+    return
+  end
+  ui.close()
+  -- Suggestions and seqs belong to the old project's store
+  store.clear()
+  set_working_dir(dir)
+  if ws_client and ws_client:is_active() then
+    on_connect()
+  end
+end
+
 ---Check if connected
 ---@return boolean
 function M.is_connected()
@@ -190,13 +267,10 @@ function M.setup(opts)
   config.setup(opts)
 
   -- Set working directory
-  local cwd = vim.fn.getcwd()
-  ui.set_working_dir(cwd)
-  actions.set_working_dir(cwd)
+  set_working_dir(vim.fn.getcwd())
   
   -- Setup shadow buffer save callback
   local shadow = require("codeforge.ui.shadow")
-  shadow.set_working_dir(cwd)
   shadow.set_on_save_callback(function(modified_diff, hunk)
     local success
     if modified_diff then
@@ -216,7 +290,6 @@ function M.setup(opts)
   
   -- Setup diagnostics and code actions
   local diagnostics = require("codeforge.diagnostics")
-  diagnostics.set_working_dir(cwd)
   diagnostics.setup()
 
   -- Setup user commands
@@ -242,6 +315,22 @@ function M.setup(opts)
     ui.refresh()
   end, { desc = "Reject current hunk" })
 
+  vim.api.nvim_create_user_command("CodeForgeAcceptLines", function(opts)
+    ui.decide_lines(opts.line1, opts.line2, "accept")
+  end, { range = true, desc = "Accept the changed lines in range (rest of the hunk stays pending)" })
+
+  vim.api.nvim_create_user_command("CodeForgeRejectLines", function(opts)
+    ui.decide_lines(opts.line1, opts.line2, "reject")
+  end, { range = true, desc = "Reject the changed lines in range (rest of the hunk stays pending)" })
+
+  vim.api.nvim_create_user_command("CodeForgeSplit", function()
+    ui.split()
+  end, { desc = "Split the current hunk (before the cursor line in the preview)" })
+
+  vim.api.nvim_create_user_command("CodeForgeMerge", function()
+    actions.merge_current()
+  end, { desc = "Merge the current hunk with the next one in its file" })
+
   vim.api.nvim_create_user_command("CodeForgeAcceptAll", function()
     actions.accept_all()
     ui.refresh()
@@ -252,6 +341,76 @@ function M.setup(opts)
     ui.refresh()
   end, { desc = "Reject all pending hunks" })
 
+  vim.api.nvim_create_user_command("CodeForgeUndo", function()
+    actions.undo_last()
+  end, { desc = "Undo the last hunk decision" })
+
+  vim.api.nvim_create_user_command("CodeForgeComment", function(opts)
+    if opts.args ~= "" then
+      actions.comment_current(opts.args)
+      return
+    end
+    vim.ui.input({ prompt = "Comment on hunk: " }, function(body)
+      if body and body ~= "" then
+        actions.comment_current(body)
+      end
+    end)
+  end, { nargs = "?", desc = "Comment on the current hunk" })
+
+  vim.api.nvim_create_user_command("CodeForgeRequestChanges", function(opts)
+    if opts.args ~= "" then
+      actions.request_changes_current(opts.args)
+      return
+    end
+    vim.ui.input({ prompt = "Requested changes: " }, function(instruction)
+      if instruction and instruction ~= "" then
+        actions.request_changes_current(instruction)
+      end
+    end)
+  end, { nargs = "?", desc = "Ask the AI to revise the current hunk" })
+
+  vim.api.nvim_create_user_command("CodeForgeCheck", function()
+    actions.run_checks()
+  end, { desc = "Run the check pipeline against the current suggestion" })
+
+  vim.api.nvim_create_user_command("CodeForgeStats", function(opts)
+    local interval = opts.args ~= "" and opts.args or nil
+    actions.request_stats({ interval = interval }, function(stats)
+      require("codeforge.ui.stats").open(stats, interval or "week")
+    end)
+  end, {
+    nargs = "?",
+    complete = function()
+      return { "day", "week", "month" }
+    end,
+    desc = "Show review statistics (acceptance per period: day, week or month)",
+  })
+
+  vim.api.nvim_create_user_command("CodeForgeProjects", function()
+    actions.request_projects(function(projects)
+      if #projects == 0 then
+        vim.notify("[codeforge] No projects on the server", vim.log.levels.INFO)
+        return
+      end
+      vim.ui.select(projects, {
+        prompt = "Review project:",
+        format_item = function(project)
+          local pending = 0
+          for _, suggestion in ipairs(project.suggestions) do
+            pending = pending + suggestion.hunkCount - suggestion.reviewedCount
+          end
+          local current = project.workingDirectory == project_dir and " (current)" or ""
+          return string.format("%s: %d suggestion(s), %d pending hunk(s)%s",
+            project.workingDirectory, #project.suggestions, pending, current)
+        end,
+      }, function(project)
+        if project then
+          M.switch_project(project.workingDirectory)
+        end
+      end)
+    end)
+  end, { desc = "List the server's projects and review one of them" })
+
   -- Setup global keymaps
   local keymap_opts = config.get().keymaps
   vim.keymap.set("n", keymap_opts.open, function()
@@ -0,0 +1,238 @@
+-- WebSocket protocol version and message validation
+-- protocol.schema.json is generated from plugin/src/types.ts (bun run schema in
+-- plugin/); this checks messages against it the way plugin/src/protocol.ts does.
+
+local M = {}
+
+-- Highest protocol version this client speaks (sent on subscribe)
+M.VERSION = 1
+
+---@class ProtocolError
+---@field path string -- JSON path of the field, e.g. "$.hunkId"
+---@field message string
+
+local schema = nil
+
+---Load the schema that sits next to this file
+---@return table
+local function load_schema()
+  if schema then
+    return schema
+  end
+  local dir = debug.getinfo(1, "S").source:sub(2):match("(.*/)") or "./"
+  local file = assert(io.open(dir .. "protocol.schema.json", "r"))
+  local content = file:read("*a")
+  file:close()
+  schema = vim.json.decode(content)
+  return schema
+end
+
+local islist = vim.islist or vim.tbl_islist
+
+---@param node table
+---@return table
+local function resolve(node)
+  if not node["$ref"] then
+    return node
+  end
+  local name = node["$ref"]:gsub("^#/%$defs/", "")
+  local def = load_schema()["$defs"][name]
+  assert(def, "Unknown schema reference: " .. node["$ref"])
+  return resolve(def)
+end
+
+---@param value any
+---@param t string JSON Schema type
+---@return boolean
+local function matches_type(value, t)
+  if t == "string" or t == "boolean" then
+    return type(value) == t
+  elseif t == "number" then
+    return type(value) == "number"
+  elseif t == "integer" then
+    return type(value) == "number" and math.floor(value) == value
+  elseif t == "null" then
+    return value == vim.NIL
+  elseif t == "object" then
+    -- An empty JSON object or array both decode to {}
+    return type(value) == "table" and (next(value) == nil or not islist(value))
+  elseif t == "array" then
+    return type(value) == "table" and (next(value) == nil or islist(value))
+  end
+  return true
+end
+
+---Const properties of an object schema (e.g. type = "feedback"), used to pick anyOf branches
+---@param node table
+---@return { name: string, value: any }[]
+local function const_properties(node)
+  local consts = {}
+  for name, property in pairs(node.properties or {}) do
+    if property.const ~= nil then
+      table.insert(consts, { name = name, value = property.const })
+    end
+  end
+  table.sort(consts, function(a, b) return a.name < b.name end)
+  return consts
+end
+
+---Object branches of an anyOf, with nested unions flattened
+---@param node table
+---@param out table[]
+local function branches(node, out)
+  local resolved = resolve(node)
+  if resolved.anyOf then
+    for _, option in ipairs(resolved.anyOf) do
+      branches(option, out)
+    end
+  else
+    table.insert(out, resolved)
+  end
+  return out
+end
+
+local check
+
+---@param node table
+---@param value any
+---@param path string
+---@param errors ProtocolError[]
+local function check_any_of(node, value, path, errors)
+  local options = branches(node, {})
+  local candidates = options
+  if matches_type(value, "object") then
+    candidates = {}
+    for _, option in ipairs(options) do
+      local fits = true
+      for _, const in ipairs(const_properties(option)) do
+        if value[const.name] ~= const.value then
+          fits = false
+        end
+      end
+      if fits then
+        table.insert(candidates, option)
+      end
+    end
+  end
+
+  if #candidates == 0 then
+    -- Name the discriminating field that matched nothing
+    for _, option in ipairs(options) do
+      local const = const_properties(option)[1]
+      if const then
+        table.insert(errors, {
+          path = path .. "." .. const.name,
+          message = "unknown value " .. vim.json.encode(value[const.name]),
+        })
+        return
+      end
+    end
+    table.insert(errors, { path = path, message = "does not match any allowed shape" })
+    return
+  end
+
+  -- Valid if any candidate accepts it; otherwise report the closest one
+  local best = nil
+  for _, candidate in ipairs(candidates) do
+    local candidate_errors = {}
+    check(candidate, value, path, candidate_errors)
+    if #candidate_errors == 0 then
+      return
+    end
+    if not best or #candidate_errors < #best then
+      best = candidate_errors
+    end
+  end
+  vim.list_extend(errors, best)
+end
+
+---@param node table
+---@param value any
+---@param path string
+---@param errors ProtocolError[]
+check = function(node, value, path, errors)
+  node = resolve(node)
+
+  if node.anyOf then
+    check_any_of(node, value, path, errors)
+    return
+  end
+
+  if node.const ~= nil and value ~= node.const then
+    table.insert(errors, { path = path, message = "must be " .. vim.json.encode(node.const) })
+    return
+  end
+
+  if node.enum and not vim.tbl_contains(node.enum, value) then
+    local allowed = vim.tbl_map(vim.json.encode, node.enum)
+    table.insert(errors, { path = path, message = "must be one of " .. table.concat(allowed, ", ") })
+    return
+  end
+
+  if node.type and not matches_type(value, node.type) then
+    local article = (node.type == "object" or node.type == "array") and "an" or "a"
+    table.insert(errors, { path = path, message = string.format("must be %s %s", article, node.type) })
+    return
+  end
+
+  if node.type == "object" then
+    -- JSON null counts as absent: optional fields may come back as null
+    local properties = node.properties or {}
+    for _, name in ipairs(node.required or {}) do
+      if value[name] == nil or value[name] == vim.NIL then
+        table.insert(errors, { path = path .. "." .. name, message = "is required" })
+      end
+    end
+    local names = vim.tbl_keys(properties)
+    table.sort(names)
+    for _, name in ipairs(names) do
+      if value[name] ~= nil and value[name] ~= vim.NIL then
+        check(properties[name], value[name], path .. "." .. name, errors)
+      end
+    end
+    if type(node.additionalProperties) == "table" then
+      for name, item in pairs(value) do
+        if not properties[name] then
+          check(node.additionalProperties, item, path .. "." .. name, errors)
+        end
+      end
+    end
+  elseif node.type == "array" then
+    local prefix = node.prefixItems or {}
+    if node.minItems and #value < node.minItems then
+      table.insert(errors, { path = path, message = string.format("must have at least %d items", node.minItems) })
+    end
+    if node.maxItems and #value > node.maxItems then
+      table.insert(errors, { path = path, message = string.format("must have at most %d items", node.maxItems) })
+    end
+    for i, item in ipairs(value) do
+      local item_schema = prefix[i] or node.items
+      if item_schema then
+        check(item_schema, item, string.format("%s[%d]", path, i - 1), errors)
+      end
+    end
+  end
+end
+
+---Check a message against a type of the protocol schema
+---@param type_name string e.g. "ServerMessage"
+---@param value any Decoded message
+---@return ProtocolError[] -- empty if valid
+function M.validate(type_name, value)
+  local errors = {}
+  check({ ["$ref"] = "#/$defs/" .. type_name }, value, "$", errors)
+  return errors
+end
+
+---One-line summary of validation errors
+---@param errors ProtocolError[]
+---@return string
+function M.describe_errors(errors)
+  local parts = {}
+  for _, err in ipairs(errors) do
+    table.insert(parts, err.path .. " " .. err.message)
+  end
+  return table.concat(parts, "; ")
+end
+
+return M
@@ -8,6 +8,12 @@ local M = {}
 ---@field diff string
 ---@field originalLines? string[] -- Original content before the change (from server)
 ---@field originalStartLine? number -- Start line in original file (1-indexed)
+---@field version? number -- Bumped each time the AI revises the hunk
+---@field previousDiff? string -- Diff of the version this one replaced (set on revision)
+---@field description? string -- Short one-line description of the change
+---@field diagnostics? CheckDiagnostic[] -- Compiler errors/warnings in the proposed lines (cargo check)
+---@field item? string -- Outermost item the hunk touches (Rust files, e.g. "impl MeshGrid")
+---@field stateVersion? number -- Bumped whenever the hunk changes; sent with feedback to detect conflicts
 
 ---@class Suggestion
 ---@field id string
@@ -16,9 +22,44 @@ local M = {}
 ---@field files string[]
 ---@field hunks Hunk[]
 
+---@class ReviewComment
+---@field id number
+---@field suggestionId string
+---@field hunkId string
+---@field author "reviewer" | "ai"
+---@field body string
+---@field createdAt number
+
+---@class CheckDiagnostic
+---@field file string
+---@field line number
+---@field column? number
+---@field severity "error" | "warning"
+---@field message string
+---@field code? string -- Error code or lint name
+---@field hunkId? string -- Pending hunk whose proposed lines include this position
+
+---@class CheckResult
+---@field suggestionId string
+---@field name string
+---@field command string
+---@field status "running" | "passed" | "failed" | "error"
+---@field exitCode? number
+---@field output? string
+---@field diagnostics CheckDiagnostic[]
+---@field startedAt number
+---@field finishedAt? number
+
+---@class ReviewerPresence
+---@field clientId string
+---@field name? string
+---@field suggestionId? string -- Suggestion the reviewer has open
+---@field hunkId? string -- Hunk the reviewer is looking at
+
 ---@class HunkState
----@field status "pending" | "accepted" | "rejected" | "modified"
+---@field status "pending" | "accepted" | "rejected" | "modified" | "revision_requested"
 ---@field modifiedContent? string[] -- If modified, the new content
+---@field instruction? string -- If revision_requested, what the reviewer asked for
 
 ---@class StoreState
 ---@field connected boolean
@@ -28,6 +69,19 @@ local M = {}
 ---@field current_hunk_index number
 ---@field hunk_states table<string, HunkState> -- hunk_id -> state
 ---@field original_content table<string, string[]> -- file_path -> original lines
+---@field decisions HunkDecision[] -- Stack of reviewed hunks, most recent last
+---@field reviewed_suggestions table<string, Suggestion> -- Suggestions emptied by review
+---@field threads table<string, ReviewComment[]> -- hunk_id -> review thread
+---@field checks table<string, CheckResult[]> -- suggestion_id -> check results, in pipeline order
+---@field client_id string|nil -- This editor's ID on the server
+---@field presence ReviewerPresence[] -- Editors subscribed to the working directory
+---@field last_seq number|nil -- Seq of the last logged event seen, sent on subscribe to catch up
+
+---@class HunkDecision
+---@field suggestion_id string
+---@field hunk_id string
+---@field index number -- Position of the hunk in its suggestion when reviewed
+---@field status "accepted" | "rejected" | "modified"
 
 ---@type StoreState
 local state = {
@@ -38,6 +92,13 @@ local state = {
   current_hunk_index = 1,
   hunk_states = {},
   original_content = {},
+  decisions = {},
+  reviewed_suggestions = {},
+  threads = {},
+  checks = {},
+  client_id = nil,
+  presence = {},
+  last_seq = nil,
 }
 
 -- Event callbacks
@@ -46,6 +107,15 @@ local listeners = {
   on_disconnect = {},
   on_suggestion_ready = {},
   on_hunk_applied = {},
+  on_hunk_reverted = {},
+  on_hunk_remainder = {},
+  on_comment = {},
+  on_check = {},
+  on_presence = {},
+  on_hunk_revised = {},
+  on_hunks_changed = {},
+  on_suggestion_updated = {},
+  on_snapshot = {},
   on_status = {},
   on_error = {},
 }
@@ -77,6 +147,7 @@ function M.set_connected(connected)
   if connected then
     emit("on_connect")
   else
+    state.presence = {}
     emit("on_disconnect")
   end
 end
@@ -113,6 +184,11 @@ function M.add_suggestion(suggestion)
         state.hunk_states[hunk.id] = {
           status = server_state.action or "pending",
         }
+      elseif server_state.state == "revision_requested" then
+        state.hunk_states[hunk.id] = {
+          status = "revision_requested",
+          instruction = server_state.instruction,
+        }
       else
         state.hunk_states[hunk.id] = { status = "pending" }
       end
@@ -122,6 +198,23 @@ function M.add_suggestion(suggestion)
     end
   end
 
+  -- Review threads come with full suggestion details
+  if suggestion.threads then
+    for _, thread in pairs(suggestion.threads) do
+      for _, comment in ipairs(thread) do
+        M.add_comment(comment)
+      end
+    end
+  end
+
+  -- Check results come with full suggestion details
+  if suggestion.checks then
+    state.checks[suggestion.id] = nil
+    for _, check in ipairs(suggestion.checks) do
+      M.set_check(check)
+    end
+  end
+
   -- Set as current if none selected
   if not state.current_suggestion_id then
     state.current_suggestion_id = suggestion.id
@@ -133,6 +226,66 @@ function M.add_suggestion(suggestion)
   end
 end
 
+---Apply a re-published suggestion (suggestion.updated)
+---Unchanged hunks keep their state; changed hunks are pending again and remember
+---the diff they replaced so the list can show what moved.
+---@param suggestion Suggestion Full suggestion as it stands now
+---@param changes { added: string[], changed: string[], removed: string[] }
+function M.update_suggestion(suggestion, changes)
+  for _, hunk_id in ipairs(changes.changed or {}) do
+    state.hunk_states[hunk_id] = { status = "pending" }
+    -- A decision on the old version can no longer be undone
+    for i = #state.decisions, 1, -1 do
+      if state.decisions[i].hunk_id == hunk_id then
+        table.remove(state.decisions, i)
+      end
+    end
+  end
+  for _, hunk_id in ipairs(changes.removed or {}) do
+    state.hunk_states[hunk_id] = nil
+  end
+
+  local existing = state.suggestions[suggestion.id]
+  if not existing then
+    -- Everything had been reviewed here; start over from the server's copy
+    state.reviewed_suggestions[suggestion.id] = nil
+    M.add_suggestion(suggestion)
+    emit("on_suggestion_updated", suggestion, changes)
+    return
+  end
+
+  local previous = {}
+  for _, hunk in ipairs(existing.hunks) do
+    previous[hunk.id] = hunk
+  end
+
+  for _, hunk in ipairs(suggestion.hunks or {}) do
+    local old = previous[hunk.id]
+    if old and vim.tbl_contains(changes.changed or {}, hunk.id) then
+      hunk.previousDiff = old.diff
+    elseif old then
+      hunk.previousDiff = old.previousDiff
+    end
+    if not state.hunk_states[hunk.id] then
+      state.hunk_states[hunk.id] = { status = "pending" }
+    end
+  end
+
+  existing.hunks = suggestion.hunks or {}
+  existing.files = suggestion.files or {}
+  existing.description = suggestion.description
+  existing.jjChangeId = suggestion.jjChangeId
+
+  if #existing.hunks == 0 then
+    state.reviewed_suggestions[existing.id] = existing
+    M.remove_suggestion(existing.id)
+  elseif state.current_suggestion_id == existing.id and state.current_hunk_index > #existing.hunks then
+    state.current_hunk_index = #existing.hunks
+  end
+
+  emit("on_suggestion_updated", existing, changes)
+end
+
 ---Remove a suggestion
 ---@param suggestion_id string
 function M.remove_suggestion(suggestion_id)
@@ -263,7 +416,7 @@ end
 
 ---Set hunk state and remove from suggestion if reviewed
 ---@param hunk_id string
----@param status "pending" | "accepted" | "rejected" | "modified"
+---@param status "pending" | "accepted" | "rejected" | "modified" | "revision_requested"
 ---@param modified_content? string[]
 function M.set_hunk_state(hunk_id, status, modified_content)
   state.hunk_states[hunk_id] = {
@@ -271,14 +424,20 @@ function M.set_hunk_state(hunk_id, status, modified_content)
     modifiedContent = modified_content,
   }
   
-  -- If hunk was reviewed (not pending), remove it from the suggestion
-  if status ~= "pending" then
+  -- If hunk was reviewed (not pending or awaiting revision), remove it from the suggestion
+  if status ~= "pending" and status ~= "revision_requested" then
     local found = false
     for _, suggestion in pairs(state.suggestions) do
       if found then break end
       for i, hunk in ipairs(suggestion.hunks) do
         if hunk.id == hunk_id then
           table.remove(suggestion.hunks, i)
+          table.insert(state.decisions, {
+            suggestion_id = suggestion.id,
+            hunk_id = hunk_id,
+            index = i,
+            status = status,
+          })
           -- Update files list
           local remaining_files = {}
           for _, h in ipairs(suggestion.hunks) do
@@ -294,6 +453,7 @@ function M.set_hunk_state(hunk_id, status, modified_content)
           
           -- Remove suggestion if empty
           if #suggestion.hunks == 0 then
+            state.reviewed_suggestions[suggestion.id] = suggestion
             M.remove_suggestion(suggestion.id)
           end
           found = true
@@ -306,6 +466,382 @@ function M.set_hunk_state(hunk_id, status, modified_content)
   emit("on_hunk_applied", hunk_id, status)
 end
 
+---Peek at the most recent decision that can be undone
+---@return HunkDecision|nil
+function M.get_last_decision()
+  return state.decisions[#state.decisions]
+end
+
+---Forget the most recent decision (e.g. the server refused to undo it)
+function M.discard_last_decision()
+  table.remove(state.decisions)
+end
+
+---Take back a decision the server refused (another reviewer got there first)
+---The hunk is pending again locally; the caller refetches the suggestion.
+---@param hunk_id string
+function M.forget_decision(hunk_id)
+  for i = #state.decisions, 1, -1 do
+    local decision = state.decisions[i]
+    if decision.hunk_id == hunk_id then
+      table.remove(state.decisions, i)
+      state.hunk_states[hunk_id] = { status = "pending" }
+      emit("on_hunk_reverted", decision.suggestion_id, hunk_id)
+      return
+    end
+  end
+end
+
+---Find a suggestion, bringing it back if reviewing its hunks had emptied it
+---@param suggestion_id string
+---@return Suggestion|nil
+local function reopen_suggestion(suggestion_id)
+  local suggestion = state.suggestions[suggestion_id]
+  if suggestion then
+    return suggestion
+  end
+
+  suggestion = state.reviewed_suggestions[suggestion_id]
+  if not suggestion then
+    return nil
+  end
+  state.reviewed_suggestions[suggestion_id] = nil
+  state.suggestions[suggestion_id] = suggestion
+  table.insert(state.suggestion_order, suggestion_id)
+  if not state.current_suggestion_id then
+    state.current_suggestion_id = suggestion_id
+    state.current_hunk_index = 1
+  end
+  return suggestion
+end
+
+---Remove a pending hunk from its suggestion
+---@param suggestion Suggestion
+---@param hunk_id string
+local function drop_hunk(suggestion, hunk_id)
+  for i, h in ipairs(suggestion.hunks) do
+    if h.id == hunk_id then
+      table.remove(suggestion.hunks, i)
+      break
+    end
+  end
+  state.hunk_states[hunk_id] = nil
+end
+
+---Put a reviewed hunk back into its suggestion as pending
+---Called when the server confirms an undo; restores the suggestion too if
+---reviewing the hunk had emptied it.
+---@param suggestion_id string
+---@param hunk Hunk
+---@param replaces? string Pending rest of a partial decision, folded back into the hunk
+---@return boolean -- false if the suggestion is unknown locally
+function M.restore_hunk(suggestion_id, hunk, replaces)
+  local suggestion = reopen_suggestion(suggestion_id)
+  if not suggestion then
+    return false
+  end
+
+  if replaces then
+    drop_hunk(suggestion, replaces)
+  end
+
+  -- Drop the matching decision (undo may have come from another client)
+  local index = #suggestion.hunks + 1
+  for i = #state.decisions, 1, -1 do
+    local decision = state.decisions[i]
+    if decision.hunk_id == hunk.id then
+      index = math.min(decision.index, index)
+      table.remove(state.decisions, i)
+      break
+    end
+  end
+
+  local present = false
+  for _, h in ipairs(suggestion.hunks) do
+    if h.id == hunk.id then
+      present = true
+      break
+    end
+  end
+  if not present then
+    table.insert(suggestion.hunks, index, hunk)
+    if not vim.tbl_contains(suggestion.files, hunk.file) then
+      table.insert(suggestion.files, hunk.file)
+    end
+  end
+
+  state.hunk_states[hunk.id] = { status = "pending" }
+  emit("on_hunk_reverted", suggestion_id, hunk.id)
+  return true
+end
+
+---Put the rest of a partially decided hunk up for review
+---It takes the decided hunk's place in the list, so review carries on there.
+---@param suggestion_id string
+---@param hunk_id string The partially decided hunk
+---@param hunk Hunk The rest of it, under a new ID
+---@return boolean -- false if the suggestion is unknown locally
+function M.add_remaining_hunk(suggestion_id, hunk_id, hunk)
+  local suggestion = reopen_suggestion(suggestion_id)
+  if not suggestion then
+    return false
+  end
+
+  for _, h in ipairs(suggestion.hunks) do
+    if h.id == hunk.id then
+      return true
+    end
+  end
+
+  local index = #suggestion.hunks + 1
+  for i = #state.decisions, 1, -1 do
+    if state.decisions[i].hunk_id == hunk_id then
+      index = math.min(state.decisions[i].index, index)
+      break
+    end
+  end
+
+  table.insert(suggestion.hunks, index, hunk)
+  if not vim.tbl_contains(suggestion.files, hunk.file) then
+    table.insert(suggestion.files, hunk.file)
+  end
+  state.hunk_states[hunk.id] = { status = "pending" }
+
+  emit("on_hunk_remainder", suggestion_id, hunk_id, hunk.id)
+  return true
+end
+
+---Swap pending hunks for the hunks a split or merge turned them into
+---The new hunks take the place of the first replaced one. A hunk that kept its
+---ID keeps its state; a merged hunk takes over the threads of the others.
+---@param suggestion_id string
+---@param replaced string[] IDs of the hunks that were split or merged
+---@param hunks Hunk[] Their replacements, in order
+---@return boolean -- false if the hunks are unknown locally
+function M.replace_hunks(suggestion_id, replaced, hunks)
+  local suggestion = state.suggestions[suggestion_id]
+  if not suggestion then
+    return false
+  end
+
+  local index
+  for i, h in ipairs(suggestion.hunks) do
+    if vim.tbl_contains(replaced, h.id) then
+      index = index or i
+    end
+  end
+  if not index then
+    return false
+  end
+
+  local is_current = state.current_suggestion_id == suggestion_id
+  local current = is_current and M.get_current_hunk() or nil
+
+  local kept = {}
+  for _, hunk in ipairs(hunks) do
+    kept[hunk.id] = true
+  end
+  for _, id in ipairs(replaced) do
+    if not kept[id] then
+      drop_hunk(suggestion, id)
+      if #hunks == 1 and state.threads[id] then
+        local thread = state.threads[hunks[1].id] or {}
+        for _, comment in ipairs(state.threads[id]) do
+          comment.hunkId = hunks[1].id
+          table.insert(thread, comment)
+        end
+        table.sort(thread, function(a, b)
+          return a.id < b.id
+        end)
+        state.threads[hunks[1].id] = thread
+      end
+      state.threads[id] = nil
+    end
+  end
+
+  for i = #suggestion.hunks, 1, -1 do
+    if kept[suggestion.hunks[i].id] then
+      table.remove(suggestion.hunks, i)
+    end
+  end
+  index = math.min(index, #suggestion.hunks + 1)
+  for offset, hunk in ipairs(hunks) do
+    table.insert(suggestion.hunks, index + offset - 1, hunk)
+    state.hunk_states[hunk.id] = state.hunk_states[hunk.id] or { status = "pending" }
+  end
+
+  -- Stay on the same hunk, or on the first of the hunks that replaced it
+  if current then
+    state.current_hunk_index = index
+    if not vim.tbl_contains(replaced, current.id) then
+      for i, h in ipairs(suggestion.hunks) do
+        if h.id == current.id then
+          state.current_hunk_index = i
+          break
+        end
+      end
+    end
+  end
+
+  local ids = {}
+  for _, hunk in ipairs(hunks) do
+    table.insert(ids, hunk.id)
+  end
+  emit("on_hunks_changed", suggestion_id, replaced, ids)
+  return true
+end
+
+---Mark a hunk as waiting for the AI to revise it
+---The hunk stays in its suggestion; accept/reject all skip it until the new version arrives.
+---@param hunk_id string
+---@param instruction? string
+---@param state_version? number -- The hunk's state version after the request
+function M.mark_revision_requested(hunk_id, instruction, state_version)
+  state.hunk_states[hunk_id] = {
+    status = "revision_requested",
+    instruction = instruction,
+  }
+  if state_version then
+    local suggestion = M.get_suggestion_by_hunk_id(hunk_id)
+    for _, hunk in ipairs(suggestion and suggestion.hunks or {}) do
+      if hunk.id == hunk_id then
+        hunk.stateVersion = state_version
+      end
+    end
+  end
+  emit("on_hunk_revised", hunk_id)
+end
+
+---Replace a hunk with the AI's revised version
+---Keeps the previous diff on the hunk so the UI can show what changed between versions.
+---@param suggestion_id string
+---@param hunk Hunk New version from the server
+---@param previous? table HunkVersion that was replaced
+---@return boolean -- false if the hunk is unknown locally
+function M.revise_hunk(suggestion_id, hunk, previous)
+  local suggestion = state.suggestions[suggestion_id]
+  if not suggestion then
+    return false
+  end
+
+  for i, h in ipairs(suggestion.hunks) do
+    if h.id == hunk.id then
+      hunk.previousDiff = previous and previous.diff or h.diff
+      suggestion.hunks[i] = hunk
+      state.hunk_states[hunk.id] = { status = "pending" }
+      emit("on_hunk_revised", hunk.id)
+      return true
+    end
+  end
+
+  return false
+end
+
+---Add a comment to a hunk's review thread
+---Comments arrive both as command responses and broadcasts, so duplicates are ignored.
+---@param comment ReviewComment
+function M.add_comment(comment)
+  local thread = state.threads[comment.hunkId]
+  if not thread then
+    thread = {}
+    state.threads[comment.hunkId] = thread
+  end
+
+  for _, existing in ipairs(thread) do
+    if existing.id == comment.id then
+      return
+    end
+  end
+
+  table.insert(thread, comment)
+  table.sort(thread, function(a, b)
+    return a.id < b.id
+  end)
+  emit("on_comment", comment)
+end
+
+---Get the review thread for a hunk
+---@param hunk_id string
+---@return ReviewComment[]
+function M.get_thread(hunk_id)
+  return state.threads[hunk_id] or {}
+end
+
+---Record a check result (suggestion.check)
+---A result replaces the one with the same name. A check starting again means
+---a new run, so the previous run's results are dropped.
+---@param check CheckResult
+function M.set_check(check)
+  local checks = state.checks[check.suggestionId] or {}
+  state.checks[check.suggestionId] = checks
+
+  for i, existing in ipairs(checks) do
+    if existing.name == check.name then
+      if check.status == "running" and existing.status ~= "running" then
+        checks = { check }
+        state.checks[check.suggestionId] = checks
+      else
+        checks[i] = check
+      end
+      emit("on_check", check)
+      return
+    end
+  end
+
+  table.insert(checks, check)
+  emit("on_check", check)
+end
+
+---Get check results for a suggestion
+---@param suggestion_id string
+---@return CheckResult[]
+function M.get_checks(suggestion_id)
+  return state.checks[suggestion_id] or {}
+end
+
+---Overall state of a suggestion's checks
+---@param suggestion_id string
+---@return "running" | "passed" | "failed" | nil -- nil if no checks ran
+function M.get_check_summary(suggestion_id)
+  local checks = state.checks[suggestion_id]
+  if not checks or #checks == 0 then
+    return nil
+  end
+
+  local summary = "passed"
+  for _, check in ipairs(checks) do
+    if check.status == "running" then
+      return "running"
+    elseif check.status ~= "passed" then
+      summary = "failed"
+    end
+  end
+  return summary
+end
+
+---Get diagnostics attached to a hunk: compiler ones sent with it and check results
+---@param suggestion_id string
+---@param hunk_id string
+---@return CheckDiagnostic[]
+function M.get_hunk_diagnostics(suggestion_id, hunk_id)
+  local result = {}
+  local suggestion = state.suggestions[suggestion_id]
+  for _, hunk in ipairs(suggestion and suggestion.hunks or {}) do
+    if hunk.id == hunk_id then
+      vim.list_extend(result, hunk.diagnostics or {})
+      break
+    end
+  end
+  for _, check in ipairs(M.get_checks(suggestion_id)) do
+    for _, diag in ipairs(check.diagnostics or {}) do
+      if diag.hunkId == hunk_id then
+        table.insert(result, diag)
+      end
+    end
+  end
+  return result
+end
+
 ---Get count of pending hunks for current suggestion
 ---@return number
 function M.get_pending_count()
@@ -344,6 +880,118 @@ function M.get_original_content(file_path)
   return state.original_content[file_path]
 end
 
+---Remember the ID the server gave this editor on subscribe
+---@param client_id string
+function M.set_client_id(client_id)
+  state.client_id = client_id
+  emit("on_presence")
+end
+
+---Replace who is reviewing the working directory (suggestion.presence)
+---@param clients ReviewerPresence[]
+function M.set_presence(clients)
+  state.presence = clients or {}
+  emit("on_presence")
+end
+
+---Other reviewers with a suggestion open (this editor excluded)
+---@param suggestion_id string
+---@return ReviewerPresence[]
+function M.get_reviewers(suggestion_id)
+  local reviewers = {}
+  for _, client in ipairs(state.presence) do
+    if client.clientId ~= state.client_id and client.suggestionId == suggestion_id then
+      table.insert(reviewers, client)
+    end
+  end
+  return reviewers
+end
+
+---Other reviewers looking at a hunk (this editor excluded)
+---@param hunk_id string
+---@return ReviewerPresence[]
+function M.get_hunk_reviewers(hunk_id)
+  local reviewers = {}
+  for _, client in ipairs(state.presence) do
+    if client.clientId ~= state.client_id and client.hunkId == hunk_id then
+      table.insert(reviewers, client)
+    end
+  end
+  return reviewers
+end
+
+---Seq of the last logged event this editor has seen
+---@return number|nil
+function M.get_last_seq()
+  return state.last_seq
+end
+
+---Start counting from the server's latest seq (fresh subscribe)
+---@param seq? number
+function M.set_last_seq(seq)
+  state.last_seq = seq
+end
+
+---Note that a logged event arrived
+---@param seq number
+---@return boolean -- false if it was seen already (a replay overlapping live events)
+function M.advance_seq(seq)
+  if state.last_seq and seq <= state.last_seq then
+    return false
+  end
+  state.last_seq = seq
+  return true
+end
+
+---Replace the suggestions with the server's (suggestion.snapshot)
+---Sent when this editor missed more events than the server could replay.
+---Suggestions that are gone are dropped; decisions made before can no longer be undone from here.
+---@param suggestions Suggestion[] Full details, like get
+---@param seq number Last event the snapshot includes
+function M.apply_snapshot(suggestions, seq)
+  -- Decided hunks leave the list, as they do when hunk_applied arrives live
+  local pending = {}
+  for _, suggestion in ipairs(suggestions) do
+    local states = suggestion.hunkStates or {}
+    suggestion.hunks = vim.tbl_filter(function(hunk)
+      return not (states[hunk.id] and states[hunk.id].reviewed)
+    end, suggestion.hunks or {})
+    if #suggestion.hunks > 0 then
+      local files = {}
+      for _, hunk in ipairs(suggestion.hunks) do
+        if not vim.tbl_contains(files, hunk.file) then
+          table.insert(files, hunk.file)
+        end
+      end
+      suggestion.files = files
+      pending[suggestion.id] = suggestion
+    end
+  end
+
+  for _, id in ipairs(vim.list_slice(state.suggestion_order)) do
+    if not pending[id] then
+      M.remove_suggestion(id)
+    end
+  end
+
+  state.hunk_states = {}
+  state.decisions = {}
+  state.reviewed_suggestions = {}
+  for _, suggestion in ipairs(suggestions) do
+    if pending[suggestion.id] then
+      M.add_suggestion(suggestion)
+    end
+  end
+
+  local current = M.get_current_suggestion()
+  if current then
+    state.current_hunk_index = math.max(1, math.min(state.current_hunk_index, #current.hunks))
+  end
+
+  state.last_seq = seq
+  emit("on_snapshot")
+end
+
 ---Clear all state
 function M.clear()
   state.suggestions = {}
@@ -352,6 +1000,12 @@ function M.clear()
   state.current_hunk_index = 1
   state.hunk_states = {}
   state.original_content = {}
+  state.decisions = {}
+  state.reviewed_suggestions = {}
+  state.threads = {}
+  state.checks = {}
+  state.presence = {}
+  state.last_seq = nil
 end
 
 ---Handle status event
@@ -4,6 +4,7 @@ local store = require("codeforge.store")
 local config = require("codeforge.config")
 local list = require("codeforge.ui.list")
 local shadow = require("codeforge.ui.shadow")
+local split = require("codeforge.ui.split")
 local actions = require("codeforge.actions")
 
 local M = {}
@@ -20,9 +21,17 @@ end
 -- Forward declaration for mutual recursion
 local on_hunk_selected
 
+---Let other reviewers know which hunk is open here (nothing while closed)
+local function update_presence()
+  local suggestion = list.is_open() and store.get_current_suggestion() or nil
+  local hunk = suggestion and store.get_current_hunk() or nil
+  actions.send_presence(suggestion and suggestion.id, hunk and hunk.id)
+end
+
 ---Accept the current hunk (with modification support from shadow buffer)
 local function do_accept()
-  if shadow.is_modified() then
+  local hunk = store.get_current_hunk()
+  if shadow.is_modified() and not (hunk and hunk.fileChange) then
     -- User made changes, compute and send modified diff
     local modified_diff, err = shadow.compute_modified_diff()
     if err then
@@ -67,6 +76,31 @@ local function do_reject()
   end
 end
 
+---Send the edits made in the proposed pane as a modification
+local function do_modify()
+  if not shadow.is_modified() then
+    vim.notify("[codeforge] No edits to send - edit the proposed side first", vim.log.levels.INFO)
+    return
+  end
+  do_accept()
+end
+
+---Move to the next hunk
+local function do_next_hunk()
+  if store.next_hunk() then
+    list.refresh()
+    on_hunk_selected(store.get_current_hunk_index())
+  end
+end
+
+---Move to the previous hunk
+local function do_prev_hunk()
+  if store.prev_hunk() then
+    list.refresh()
+    on_hunk_selected(store.get_current_hunk_index())
+  end
+end
+
 ---Accept all remaining hunks
 local function do_accept_all()
   actions.accept_all()
@@ -88,6 +122,7 @@ end
 on_hunk_selected = function(hunk_index)
   store.set_current_hunk_index(hunk_index)
   local hunk = store.get_current_hunk()
+  update_presence()
 
   if hunk and working_dir then
     shadow.open(hunk, working_dir)
@@ -95,8 +130,82 @@ on_hunk_selected = function(hunk_index)
   end
 end
 
+---Accept or reject the changes on a range of preview lines
+---The rest of the hunk comes back as a new hunk via on_hunk_remainder.
+---@param first number First line in the preview buffer
+---@param last number Last line in the preview buffer
+---@param decision "accept" | "reject"
+function M.decide_lines(first, last, decision)
+  local selected, err = shadow.get_selected_changes(first, last)
+  if not selected then
+    vim.notify("[codeforge] " .. err, vim.log.levels.WARN)
+    return
+  end
+
+  actions.decide_lines_current(selected, decision)
+  list.refresh()
+end
+
+---Split the current hunk (the pieces come back via on_hunks_changed)
+---From the preview, the hunk is cut before the cursor line; elsewhere at its
+---context lines.
+function M.split()
+  local line
+  if vim.api.nvim_get_current_buf() == shadow.get_buffer() then
+    local err
+    line, err = shadow.get_split_line(vim.api.nvim_win_get_cursor(0)[1])
+    if not line then
+      vim.notify("[codeforge] " .. err, vim.log.levels.WARN)
+      return
+    end
+  end
+  actions.split_current(line)
+end
+
+---Merge the current hunk with the next one in its file (via on_hunks_changed)
+local function do_merge()
+  actions.merge_current()
+end
+
+---Run the check pipeline (results arrive via on_check)
+local function do_run_checks()
+  actions.run_checks()
+end
+
+---Undo the most recent decision (the hunk comes back via on_hunk_reverted)
+local function do_undo()
+  actions.undo_last()
+end
+
+---Comment on the current hunk (the AI replies in the hunk's thread)
+local function do_comment()
+  vim.ui.input({ prompt = "Comment on hunk: " }, function(body)
+    if body and body ~= "" then
+      actions.comment_current(body)
+    end
+  end)
+end
+
+---Ask the AI to revise the current hunk (the new version arrives via on_hunk_revised)
+local function do_request_changes()
+  vim.ui.input({ prompt = "Requested changes: " }, function(instruction)
+    if instruction and instruction ~= "" then
+      actions.request_changes_current(instruction)
+    end
+  end)
+end
+
 ---Open the review UI
 function M.open()
+  -- Review keys in both panes of the side-by-side layout
+  split.set_callbacks({
+    on_accept = do_accept,
+    on_reject = do_reject,
+    on_modify = do_modify,
+    on_next = do_next_hunk,
+    on_prev = do_prev_hunk,
+  })
+
   -- Open the list panel with callbacks (shows "No suggestions" if empty)
   list.open({
     on_select = on_hunk_selected,
@@ -104,6 +213,12 @@ function M.open()
     on_reject = do_reject,
     on_accept_all = do_accept_all,
     on_reject_all = do_reject_all,
+    on_undo = do_undo,
+    on_comment = do_comment,
+    on_request_changes = do_request_changes,
+    on_split = M.split,
+    on_merge = do_merge,
+    on_run_checks = do_run_checks,
     on_close = function() M.close() end,
   })
 
@@ -113,12 +228,14 @@ function M.open()
   if suggestion and #suggestion.hunks > 0 and hunk and working_dir then
     shadow.open(hunk, working_dir)
   end
+  update_presence()
 end
 
 ---Close the review UI
 function M.close()
   list.close()
   shadow.close()
+  update_presence()
 end
 
 ---Toggle the review UI
@@ -141,6 +258,8 @@ function M.refresh()
   if list.is_open() then
     list.refresh()
   end
+  -- Reviewed hunks drop out, which moves the current one
+  update_presence()
 end
 
 ---Open CodeForge for a specific file (first hunk in that file)
@@ -201,8 +320,100 @@ store.on("on_suggestion_ready", function(suggestion)
   M.refresh()
 end)
 
+store.on("on_suggestion_updated", function(suggestion, changes)
+  vim.notify(
+    string.format(
+      "[codeforge] Suggestion updated: %d added, %d changed, %d removed",
+      #changes.added, #changes.changed, #changes.removed
+    ),
+    vim.log.levels.INFO
+  )
+  M.refresh()
+  -- The hunk on screen may have changed or gone away
+  local shown = shadow.get_current_hunk()
+  if shown and (vim.tbl_contains(changes.changed, shown.id) or vim.tbl_contains(changes.removed, shown.id)) then
+    local hunk = store.get_current_hunk()
+    if hunk and working_dir then
+      shadow.open(hunk, working_dir)
+    else
+      shadow.close()
+    end
+  end
+end)
+
+store.on("on_snapshot", function()
+  M.refresh()
+  -- The hunk on screen may have been decided or dropped while we were away
+  local shown = shadow.get_current_hunk()
+  if shown and not store.get_suggestion_by_hunk_id(shown.id) then
+    local hunk = store.get_current_hunk()
+    if hunk and working_dir then
+      shadow.open(hunk, working_dir)
+    else
+      shadow.close()
+    end
+  end
+end)
+
 store.on("on_hunk_applied", function(hunk_id, status)
   M.refresh()
 end)
 
+store.on("on_hunk_reverted", function(suggestion_id, hunk_id)
+  M.refresh()
+end)
+
+store.on("on_hunk_remainder", function(suggestion_id, hunk_id, remainder_id)
+  M.refresh()
+  -- Review carries on with the rest of the hunk
+  local hunk = store.get_current_hunk()
+  if hunk and hunk.id == remainder_id and list.is_open() and working_dir then
+    shadow.open(hunk, working_dir)
+  end
+end)
+
+store.on("on_hunk_revised", function(hunk_id)
+  M.refresh()
+  -- Re-open the preview if the revised hunk is the one on screen
+  local hunk = store.get_current_hunk()
+  local shown = shadow.get_current_hunk()
+  if hunk and shown and shown.id == hunk_id and hunk.id == hunk_id and working_dir then
+    shadow.open(hunk, working_dir)
+  end
+end)
+
+store.on("on_hunks_changed", function(suggestion_id, replaced, hunk_ids)
+  M.refresh()
+  -- The hunk on screen was split or merged: show what took its place
+  local shown = shadow.get_current_hunk()
+  local hunk = store.get_current_hunk()
+  if shown and vim.tbl_contains(replaced, shown.id) and hunk and working_dir then
+    shadow.open(hunk, working_dir)
+  end
+end)
+
+store.on("on_comment", function(comment)
+  local hunk = shadow.get_current_hunk()
+  if hunk and hunk.id == comment.hunkId then
+    shadow.show_thread()
+  end
+  if comment.author == "ai" then
+    vim.notify("[codeforge] AI replied on " .. comment.hunkId, vim.log.levels.INFO)
+  end
+end)
+
+store.on("on_presence", function()
+  M.refresh()
+end)
+
+store.on("on_check", function(check)
+  M.refresh()
+  if check.status == "failed" or check.status == "error" then
+    vim.notify(
+      string.format("[codeforge] Check %s failed (%d problems)", check.name, #(check.diagnostics or {})),
+      vim.log.levels.WARN
+    )
+  end
+end)
+
 return M
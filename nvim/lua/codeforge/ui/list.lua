@@ -2,6 +2,7 @@
 
 local store = require("codeforge.store")
 local config = require("codeforge.config")
+local diff_utils = require("codeforge.diff")
 
 local M = {}
 
@@ -15,12 +16,15 @@ local header_win = nil
 
 -- UI state
 local show_help = false
--- file_path -> true/false if explicitly set, nil means use default (auto-expand current file)
+-- group key -> true/false if explicitly set, nil means use default (auto-expand current group)
 local expanded_files = {}
 
 -- Namespace for highlights
 local ns = vim.api.nvim_create_namespace("codeforge_list")
 
+-- Max lines of inter-version diff shown under a revised hunk
+local MAX_VERSION_LINES = 8
+
 ---Get status icon for a hunk
 ---@param status string
 ---@return string
@@ -33,6 +37,8 @@ local function status_icon(status)
     return "✗"
   elseif status == "modified" then
     return "~"
+  elseif status == "revision_requested" then
+    return "⟳"
   end
   return "?"
 end
@@ -49,10 +55,49 @@ local function status_highlight(status)
     return "DiagnosticError"
   elseif status == "modified" then
     return "DiagnosticWarn"
+  elseif status == "revision_requested" then
+    return "DiagnosticInfo"
   end
   return "Normal"
 end
 
+---Describe the check pipeline's state for the header
+---@param suggestion_id string
+---@return string|nil
+local function check_line(suggestion_id)
+  local summary = store.get_check_summary(suggestion_id)
+  if not summary then
+    return nil
+  end
+
+  local checks = store.get_checks(suggestion_id)
+  local names = {}
+  for _, check in ipairs(checks) do
+    if (summary == "running" and check.status == "running")
+      or (summary == "failed" and check.status ~= "passed") then
+      table.insert(names, check.name)
+    end
+  end
+
+  if summary == "running" then
+    return "Checks: … running " .. table.concat(names, ", ")
+  elseif summary == "failed" then
+    return "Checks: ✗ failed " .. table.concat(names, ", ")
+  end
+  return string.format("Checks: ✓ %d passed", #checks)
+end
+
+---Names of other reviewers, for the header and hunk lines
+---@param reviewers ReviewerPresence[]
+---@return string
+local function reviewer_names(reviewers)
+  local names = {}
+  for _, reviewer in ipairs(reviewers) do
+    table.insert(names, reviewer.name or "reviewer")
+  end
+  return table.concat(names, ", ")
+end
+
 ---Scroll the list window to show the current hunk
 ---@param hunk_index number
 local function scroll_to_hunk(hunk_index)
@@ -111,11 +156,24 @@ local function render_header()
     table.insert(lines, desc)
     table.insert(lines, string.format("Pending: %d hunks in %d files", 
       #suggestion.hunks, #suggestion.files))
+    local checks = check_line(suggestion.id)
+    if checks then
+      table.insert(lines, checks)
+    end
+    local reviewers = store.get_reviewers(suggestion.id)
+    if #reviewers > 0 then
+      table.insert(lines, "Also reviewing: " .. reviewer_names(reviewers))
+    end
     
     if show_help then
       table.insert(lines, string.rep("─", 38))
       table.insert(lines, " C-y Accept    C-n Reject")
       table.insert(lines, " C-a Accept all  C-x Reject all")
+      table.insert(lines, " u  Undo last decision")
+      table.insert(lines, " c  Comment on hunk")
+      table.insert(lines, " r  Request changes")
+      table.insert(lines, " s  Split hunk   m  Merge with next")
+      table.insert(lines, " !  Run checks")
       table.insert(lines, " Tab/za  Toggle file")
       table.insert(lines, " q  Close      ?  Hide help")
       table.insert(lines, " j/k to navigate")
@@ -138,11 +196,23 @@ end
 -- Map from display line to hunk index (for navigation)
 ---@type table<number, number>
 local line_to_hunk = {}
--- Map from display line to file path (for toggling)
+-- Map from display line to group key (for toggling)
 ---@type table<number, string>
 local line_to_file = {}
 
----Render the hunk list grouped by file with collapse/expand
+---Group a hunk is listed under: its file, or with ui.group_by = "item" its
+---file and enclosing item (hunks without one stay under their file)
+---@param hunk Hunk
+---@return string key
+---@return string label
+local function hunk_group(hunk)
+  if config.get().ui.group_by == "item" and hunk.item then
+    return hunk.file .. "\0" .. hunk.item, string.format("%s · %s", hunk.item, vim.fn.fnamemodify(hunk.file, ":t"))
+  end
+  return hunk.file, hunk.file
+end
+
+---Render the hunk list grouped by file (or item) with collapse/expand
 local function render_list()
   if not list_buf or not vim.api.nvim_buf_is_valid(list_buf) then
     return
@@ -172,26 +242,29 @@ local function render_list()
 
   local current_index = store.get_current_hunk_index()
 
-  -- Group PENDING hunks by file (skip reviewed hunks)
+  -- Group PENDING hunks by file or item (skip reviewed hunks)
   local files_order = {}
   local hunks_by_file = {}
+  local group_labels = {}
   for i, hunk in ipairs(suggestion.hunks) do
     local hunk_state = store.get_hunk_state(hunk.id) or { status = "pending" }
-    -- Only include pending hunks
-    if hunk_state.status == "pending" then
-      if not hunks_by_file[hunk.file] then
-        hunks_by_file[hunk.file] = {}
-        table.insert(files_order, hunk.file)
+    -- Only include hunks still under review (pending or awaiting a revision)
+    if hunk_state.status == "pending" or hunk_state.status == "revision_requested" then
+      local key, label = hunk_group(hunk)
+      if not hunks_by_file[key] then
+        hunks_by_file[key] = {}
+        group_labels[key] = label
+        table.insert(files_order, key)
       end
-      table.insert(hunks_by_file[hunk.file], { hunk = hunk, index = i })
+      table.insert(hunks_by_file[key], { hunk = hunk, index = i, status = hunk_state.status })
     end
   end
 
-  -- Find which file contains the current hunk (auto-expand it)
+  -- Find which group contains the current hunk (auto-expand it)
   local current_hunk = suggestion.hunks[current_index]
-  local current_file = current_hunk and current_hunk.file
+  local current_file = current_hunk and (hunk_group(current_hunk))
 
-  -- Render grouped by file (only files with pending hunks)
+  -- Render grouped (only groups with pending hunks)
   for _, file_path in ipairs(files_order) do
     local file_hunks = hunks_by_file[file_path]
     local pending_count = #file_hunks
@@ -208,9 +281,15 @@ local function render_list()
     
     -- File header line
     local collapse_icon = is_expanded and "▾" or "▸"
-    local file_display = file_path
-    if #file_display > 28 then
-      file_display = "..." .. file_display:sub(-25)
+    local file_display = group_labels[file_path]
+    if vim.fn.strchars(file_display) > 28 then
+      if file_display == file_path then
+        -- A path: keep its end
+        file_display = "..." .. vim.fn.strcharpart(file_display, vim.fn.strchars(file_display) - 25)
+      else
+        -- An item: keep its start
+        file_display = vim.fn.strcharpart(file_display, 0, 25) .. "..."
+      end
     end
     local count_str = string.format("(%d)", pending_count)
     
@@ -233,13 +312,31 @@ local function render_list()
         local is_current = h.index == current_index
         local prefix = is_current and "  ▶ " or "    "
         
-        -- Show line number if available
+        -- Show line number if available, or what happens to the file
         local line_info = ""
-        if h.hunk.originalStartLine then
+        if h.hunk.fileChange then
+          line_info = diff_utils.describe_file_change(h.hunk.fileChange)
+        elseif h.hunk.originalStartLine then
           line_info = string.format("L%d", h.hunk.originalStartLine)
         end
         
-        local hunk_line = string.format("%s○ %s", prefix, line_info)
+        if h.hunk.version and h.hunk.version > 1 then
+          line_info = string.format("%s v%d", line_info, h.hunk.version)
+        end
+        if h.status == "revision_requested" then
+          line_info = line_info .. " awaiting revision"
+        end
+        local problems = #store.get_hunk_diagnostics(suggestion.id, h.hunk.id)
+        if problems > 0 then
+          line_info = string.format("%s ⚠ %d", line_info, problems)
+        end
+        -- Other reviewers' cursors
+        local reviewers = store.get_hunk_reviewers(h.hunk.id)
+        if #reviewers > 0 then
+          line_info = string.format("%s ◆ %s", line_info, reviewer_names(reviewers))
+        end
+        
+        local hunk_line = string.format("%s%s %s", prefix, status_icon(h.status), line_info)
         table.insert(lines, hunk_line)
         
         local hunk_line_idx = #lines - 1
@@ -248,9 +345,27 @@ local function render_list()
         table.insert(highlights, {
           line = hunk_line_idx,
           icon_col = #prefix,
-          status = "pending",
+          status = h.status,
           is_current = is_current,
         })
+
+        -- Under the selected revised hunk, show what changed since the last version
+        if is_current and h.hunk.previousDiff then
+          local changes = diff_utils.version_changes(h.hunk.previousDiff, h.hunk.diff)
+          for i, change in ipairs(changes) do
+            if i > MAX_VERSION_LINES then
+              table.insert(lines, string.format("      … %d more", #changes - MAX_VERSION_LINES))
+              table.insert(highlights, { line = #lines - 1, group = "Comment" })
+              break
+            end
+            local sign = change.type == "add" and "+" or "-"
+            table.insert(lines, string.format("      %s %s", sign, change.content))
+            table.insert(highlights, {
+              line = #lines - 1,
+              group = change.type == "add" and "DiffAdd" or "DiffDelete",
+            })
+          end
+        end
       end
     end
   end
@@ -267,6 +382,9 @@ local function render_list()
       -- Highlight file headers
       local hl_group = hl.all_done and "Comment" or "Directory"
       vim.api.nvim_buf_add_highlight(list_buf, ns, hl_group, hl.line, 0, -1)
+    elseif hl.group then
+      -- Inter-version diff lines
+      vim.api.nvim_buf_add_highlight(list_buf, ns, hl.group, hl.line, 0, -1)
     else
       -- Highlight the status icon
       vim.api.nvim_buf_add_highlight(
@@ -308,7 +426,7 @@ local function toggle_file_at_cursor()
       local suggestion = store.get_current_suggestion()
       local current_index = store.get_current_hunk_index()
       local current_hunk = suggestion and suggestion.hunks[current_index]
-      local is_current_file = current_hunk and current_hunk.file == file_path
+      local is_current_file = current_hunk and hunk_group(current_hunk) == file_path
       -- Toggle from the effective state
       expanded_files[file_path] = not is_current_file
     else
@@ -356,6 +474,12 @@ end
 ---@field on_reject fun() -- Callback to reject current hunk
 ---@field on_accept_all fun() -- Callback to accept all hunks
 ---@field on_reject_all fun() -- Callback to reject all hunks
+---@field on_undo fun() -- Callback to undo the last decision
+---@field on_comment fun() -- Callback to comment on current hunk
+---@field on_request_changes fun() -- Callback to ask the AI to revise current hunk
+---@field on_split fun() -- Callback to split current hunk
+---@field on_merge fun() -- Callback to merge current hunk with the next one
+---@field on_run_checks fun() -- Callback to run the check pipeline
 ---@field on_close fun() -- Callback to close UI
 
 ---Open the list panel with pinned header
@@ -443,12 +567,24 @@ function M.open(callbacks)
   map_list(keymaps.reject, callbacks.on_reject)
   map_list(keymaps.accept_all, callbacks.on_accept_all)
   map_list(keymaps.reject_all, callbacks.on_reject_all)
+  map_list(keymaps.undo, callbacks.on_undo)
+  map_list(keymaps.comment, callbacks.on_comment)
+  map_list(keymaps.request_changes, callbacks.on_request_changes)
+  map_list(keymaps.split, callbacks.on_split)
+  map_list(keymaps.merge, callbacks.on_merge)
+  map_list(keymaps.run_checks, callbacks.on_run_checks)
   
   -- Also map to header buffer
   map_header(keymaps.accept, callbacks.on_accept)
   map_header(keymaps.reject, callbacks.on_reject)
   map_header(keymaps.accept_all, callbacks.on_accept_all)
   map_header(keymaps.reject_all, callbacks.on_reject_all)
+  map_header(keymaps.undo, callbacks.on_undo)
+  map_header(keymaps.comment, callbacks.on_comment)
+  map_header(keymaps.request_changes, callbacks.on_request_changes)
+  map_header(keymaps.split, callbacks.on_split)
+  map_header(keymaps.merge, callbacks.on_merge)
+  map_header(keymaps.run_checks, callbacks.on_run_checks)
   
   -- Update selection when cursor moves (using CursorMoved autocmd)
   vim.api.nvim_create_autocmd("CursorMoved", {
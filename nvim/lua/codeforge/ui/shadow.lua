@@ -1,7 +1,9 @@
 -- Shadow buffer management for LSP-enabled preview
 
 local store = require("codeforge.store")
+local config = require("codeforge.config")
 local diff_utils = require("codeforge.diff")
+local split = require("codeforge.ui.split")
 
 local M = {}
 
@@ -22,6 +24,10 @@ local lsp_did_change_autocmd = nil  -- Autocmd ID for tracking changes
 local lsp_bootstrap_buf = nil  -- Bootstrap buffer kept alive for LSP
 local renamed_original_buf = nil  -- Original buffer that we renamed temporarily
 
+-- Review thread float
+local thread_buf = nil
+local thread_win = nil
+
 -- Namespace for diff highlights
 local ns = vim.api.nvim_create_namespace("codeforge_shadow")
 
@@ -471,6 +477,15 @@ local function create_shadow_buffer(file_path, content)
     end,
   })
 
+  -- Accept/reject keys on a visual selection decide just those lines
+  local keymaps = config.get().keymaps or {}
+  if keymaps.accept then
+    vim.keymap.set("x", keymaps.accept, ":CodeForgeAcceptLines<CR>", { buffer = buf, silent = true, desc = "Accept selected lines" })
+  end
+  if keymaps.reject then
+    vim.keymap.set("x", keymaps.reject, ":CodeForgeRejectLines<CR>", { buffer = buf, silent = true, desc = "Reject selected lines" })
+  end
+
   -- Store the file path for LSP cleanup
   current_file_path = file_path
 
@@ -671,7 +686,12 @@ function M.open(hunk, working_dir)
   -- Parse header from adjusted diff (not original)
   local header = diff_utils.parse_hunk_header(adjusted_diff:match("^[^\n]+"))
   
-  if not file_exists or #local_content == 0 then
+  if hunk.fileChange then
+    -- File-level hunk (rename, delete, mode, binary): nothing to preview line by line
+    preview_content = { diff_utils.describe_file_change(hunk.fileChange), "" }
+    vim.list_extend(preview_content, vim.split(hunk.diff, "\n"))
+    showing_diff_only = true
+  elseif not file_exists or #local_content == 0 then
     -- File doesn't exist locally - this is a new file, extract content from diff
     preview_content = extract_new_content_from_diff(adjusted_diff)
     showing_diff_only = true
@@ -746,14 +766,15 @@ function M.open(hunk, working_dir)
 
   -- Open in window if needed
   if not shadow_win or not vim.api.nvim_win_is_valid(shadow_win) then
-    -- Find a suitable window (not the list window)
+    -- Find a suitable window (not the list window or the original pane)
     local list = require("codeforge.ui.list")
     local list_win = list.get_window()
+    local original_win = split.get_window()
 
     -- Get list of windows
     local wins = vim.api.nvim_list_wins()
     for _, win in ipairs(wins) do
-      if win ~= list_win then
+      if win ~= list_win and win ~= original_win then
         shadow_win = win
         break
       end
@@ -833,9 +854,91 @@ function M.open(hunk, working_dir)
     highlight_diff(shadow_buf, adjusted_diff, highlight_start, highlight_end)
   end
 
+  -- Side-by-side layout: original next to the preview, only when it's the whole file
+  if split.enabled() and not showing_diff_only then
+    split.open(shadow_win, shadow_buf, file_path, original_content)
+  elseif split.is_open() then
+    split.close()
+  end
+
+  M.show_thread()
+
   return shadow_buf, shadow_win
 end
 
+---Close the review thread float
+function M.close_thread()
+  if thread_win and vim.api.nvim_win_is_valid(thread_win) then
+    vim.api.nvim_win_close(thread_win, true)
+  end
+  thread_win = nil
+  thread_buf = nil
+end
+
+---Show the current hunk's review thread in a float anchored to the hunk
+---Closes the float when the hunk has no thread.
+function M.show_thread()
+  if not current_hunk or not shadow_win or not vim.api.nvim_win_is_valid(shadow_win) then
+    M.close_thread()
+    return
+  end
+
+  local thread = store.get_thread(current_hunk.id)
+  if #thread == 0 then
+    M.close_thread()
+    return
+  end
+
+  local lines = {}
+  for _, comment in ipairs(thread) do
+    local who = comment.author == "ai" and "AI" or "You"
+    for i, line in ipairs(vim.split(comment.body, "\n")) do
+      local prefix = i == 1 and (who .. ": ") or string.rep(" ", #who + 2)
+      table.insert(lines, prefix .. line)
+    end
+  end
+
+  local win_width = vim.api.nvim_win_get_width(shadow_win)
+  local width = 20
+  for _, line in ipairs(lines) do
+    width = math.max(width, vim.fn.strdisplaywidth(line))
+  end
+  width = math.min(width, math.max(win_width - 4, 20))
+  local height = math.min(#lines, 10)
+
+  if not thread_buf or not vim.api.nvim_buf_is_valid(thread_buf) then
+    thread_buf = vim.api.nvim_create_buf(false, true)
+    vim.api.nvim_buf_set_option(thread_buf, "bufhidden", "wipe")
+    vim.api.nvim_buf_set_option(thread_buf, "filetype", "markdown")
+  end
+  vim.api.nvim_buf_set_option(thread_buf, "modifiable", true)
+  vim.api.nvim_buf_set_lines(thread_buf, 0, -1, false, lines)
+  vim.api.nvim_buf_set_option(thread_buf, "modifiable", false)
+
+  -- Pin the float to the first line of the hunk, on the right edge of the window
+  local anchor_line = hunk_region and hunk_region.start_line or 1
+  local win_config = {
+    relative = "win",
+    win = shadow_win,
+    bufpos = { anchor_line - 1, 0 },
+    row = 0,
+    col = math.max(win_width - width - 2, 0),
+    width = width,
+    height = height,
+    style = "minimal",
+    border = "rounded",
+    title = " Review thread ",
+    focusable = true,
+  }
+
+  if thread_win and vim.api.nvim_win_is_valid(thread_win) then
+    vim.api.nvim_win_set_config(thread_win, win_config)
+  else
+    thread_win = vim.api.nvim_open_win(thread_buf, false, win_config)
+    vim.api.nvim_win_set_option(thread_win, "wrap", true)
+  end
+end
+
 ---Get the current content of the shadow buffer
 ---@return string[]|nil
 function M.get_content()
@@ -854,6 +957,52 @@ function M.is_modified()
   return false
 end
 
+---Map a range of preview lines to the current hunk's changes
+---@param first number First buffer line (1-indexed)
+---@param last number Last buffer line
+---@return table<number, boolean>|nil, string|nil -- indices into the hunk's parsed changes, error
+function M.get_selected_changes(first, last)
+  if not current_hunk or not shadow_buf or not vim.api.nvim_buf_is_valid(shadow_buf) then
+    return nil, "No hunk in the preview"
+  end
+  if current_hunk.fileChange then
+    return nil, "File-level hunks can only be accepted or rejected whole"
+  end
+  if M.is_modified() then
+    return nil, "The preview has been edited - save it to send a modification instead"
+  end
+
+  local start_line = M.get_current_boundaries()
+  if not start_line then
+    return nil, "Hunk region not found"
+  end
+
+  return diff_utils.changes_in_range(current_hunk.diff, first - start_line + 1, last - start_line + 1), nil
+end
+
+---Get the line of the current hunk to split it before, from a preview line
+---@param line number Line in the preview buffer
+---@return number|nil, string|nil -- line after the hunk's @@ header, error
+function M.get_split_line(line)
+  if not current_hunk or not shadow_buf or not vim.api.nvim_buf_is_valid(shadow_buf) then
+    return nil, "No hunk in the preview"
+  end
+  if current_hunk.fileChange then
+    return nil, "File-level hunks can't be split"
+  end
+
+  local start_line = M.get_current_boundaries()
+  if not start_line then
+    return nil, "Hunk region not found"
+  end
+
+  local index = diff_utils.split_index(current_hunk.diff, line - start_line + 1)
+  if not index or index <= 1 then
+    return nil, "Move the cursor inside the hunk, below its first line"
+  end
+  return index, nil
+end
+
 ---Get the original content before the hunk was applied
 ---@return string[]|nil
 function M.get_original_content()
@@ -979,6 +1128,9 @@ end
 function M.close()
   -- Store file_path before clearing state (needed for buffer restoration)
   local file_path = current_file_path
+
+  M.close_thread()
+  split.close()
   
   -- Clean up LSP state first (sends didClose notification)
   if current_file_path then
@@ -0,0 +1,148 @@
+-- Side-by-side review layout (ui.layout = "split")
+-- Shows the original file next to the shadow buffer with :diffthis
+
+local config = require("codeforge.config")
+
+local M = {}
+
+-- Original (left) pane
+local original_buf = nil
+local original_win = nil
+-- Proposed (right) pane - the shadow buffer's window
+local proposed_win = nil
+
+-- Review callbacks, set by ui/init.lua (same shape as the list's)
+---@type { on_accept: fun(), on_reject: fun(), on_modify: fun(), on_next: fun(), on_prev: fun() }|nil
+local callbacks = nil
+
+---Set the callbacks the pane keymaps trigger
+---@param cbs { on_accept: fun(), on_reject: fun(), on_modify: fun(), on_next: fun(), on_prev: fun() }
+function M.set_callbacks(cbs)
+  callbacks = cbs
+end
+
+---Check whether the split layout is configured
+---@return boolean
+function M.enabled()
+  local opts = config.get()
+  return opts.ui ~= nil and opts.ui.layout == "split"
+end
+
+---Map the review keys in a pane's buffer
+---@param buf number
+local function map_review_keys(buf)
+  if not callbacks then
+    return
+  end
+
+  local keymaps = config.get().keymaps
+  local function map(key, action)
+    if key and action then
+      vim.keymap.set("n", key, action, { buffer = buf, nowait = true })
+    end
+  end
+
+  map(keymaps.accept, callbacks.on_accept)
+  map(keymaps.reject, callbacks.on_reject)
+  map(keymaps.modify, callbacks.on_modify)
+  map(keymaps.next_hunk, callbacks.on_next)
+  map(keymaps.prev_hunk, callbacks.on_prev)
+end
+
+---Create or refresh the read-only buffer holding the original content
+---@param file_path string
+---@param lines string[]
+---@param filetype string
+local function update_original_buffer(file_path, lines, filetype)
+  if not original_buf or not vim.api.nvim_buf_is_valid(original_buf) then
+    original_buf = vim.api.nvim_create_buf(false, true)
+    vim.api.nvim_buf_set_option(original_buf, "buftype", "nofile")
+    vim.api.nvim_buf_set_option(original_buf, "bufhidden", "wipe")
+    vim.api.nvim_buf_set_option(original_buf, "swapfile", false)
+    map_review_keys(original_buf)
+  end
+
+  local name = file_path .. "#codeforge-original"
+  if vim.api.nvim_buf_get_name(original_buf) ~= name then
+    vim.api.nvim_buf_set_name(original_buf, name)
+  end
+
+  vim.api.nvim_buf_set_option(original_buf, "modifiable", true)
+  vim.api.nvim_buf_set_lines(original_buf, 0, -1, false, lines)
+  vim.api.nvim_buf_set_option(original_buf, "modifiable", false)
+  vim.api.nvim_buf_set_option(original_buf, "filetype", filetype)
+end
+
+---Put a window in diff mode with scroll and cursor bound to its partner
+---@param win number
+local function bind(win)
+  vim.api.nvim_win_call(win, function()
+    vim.cmd("diffthis")
+  end)
+  vim.wo[win].scrollbind = true
+  vim.wo[win].cursorbind = true
+end
+
+---Show the original file to the left of the shadow window, diffed against it
+---@param win number Shadow (proposed) window
+---@param buf number Shadow buffer
+---@param file_path string Full path of the file under review
+---@param lines string[] File content before the hunk
+function M.open(win, buf, file_path, lines)
+  update_original_buffer(file_path, lines, vim.bo[buf].filetype)
+  map_review_keys(buf)
+
+  if not original_win or not vim.api.nvim_win_is_valid(original_win) then
+    vim.api.nvim_win_call(win, function()
+      vim.cmd("leftabove vsplit")
+      original_win = vim.api.nvim_get_current_win()
+    end)
+  end
+  vim.api.nvim_win_set_buf(original_win, original_buf)
+  proposed_win = win
+
+  bind(original_win)
+  bind(proposed_win)
+  vim.api.nvim_win_call(proposed_win, function()
+    vim.cmd("diffupdate")
+  end)
+
+  -- Line up the original pane with the hunk the shadow cursor was moved to
+  vim.api.nvim_win_call(proposed_win, function()
+    vim.cmd("syncbind")
+  end)
+end
+
+---Close the original pane and take the shadow window out of diff mode
+function M.close()
+  if proposed_win and vim.api.nvim_win_is_valid(proposed_win) then
+    vim.api.nvim_win_call(proposed_win, function()
+      vim.cmd("diffoff")
+    end)
+    vim.wo[proposed_win].scrollbind = false
+    vim.wo[proposed_win].cursorbind = false
+  end
+  if original_win and vim.api.nvim_win_is_valid(original_win) then
+    vim.api.nvim_win_close(original_win, true)
+  end
+  if original_buf and vim.api.nvim_buf_is_valid(original_buf) then
+    vim.api.nvim_buf_delete(original_buf, { force = true })
+  end
+  original_buf = nil
+  original_win = nil
+  proposed_win = nil
+end
+
+---Get the original pane's window
+---@return number|nil
+function M.get_window()
+  return original_win
+end
+
+---Check if the original pane is open
+---@return boolean
+function M.is_open()
+  return original_win ~= nil and vim.api.nvim_win_is_valid(original_win)
+end
+
+return M
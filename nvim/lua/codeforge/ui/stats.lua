@@ -0,0 +1,136 @@
+-- Review statistics report (:CodeForgeStats)
+-- Renders the server's ReviewStats into a scratch buffer
+
+local M = {}
+
+-- Report buffer, reused while it is open
+local report_buf = nil
+
+---@param rate number 0..1
+---@return string
+local function percent(rate)
+  return string.format("%d%%", math.floor(rate * 100 + 0.5))
+end
+
+---Human-readable duration
+---@param ms number
+---@return string
+local function duration(ms)
+  local minutes = ms / 60000
+  if minutes < 1 then
+    return string.format("%ds", math.floor(ms / 1000))
+  elseif minutes < 60 then
+    return string.format("%dm", math.floor(minutes))
+  elseif minutes < 24 * 60 then
+    return string.format("%.1fh", minutes / 60)
+  end
+  return string.format("%.1fd", minutes / (24 * 60))
+end
+
+---Add a table of grouped acceptance stats under a heading
+---@param lines string[]
+---@param title string
+---@param groups table[]
+local function add_section(lines, title, groups)
+  table.insert(lines, "")
+  table.insert(lines, "## " .. title)
+  if not groups or #groups == 0 then
+    table.insert(lines, "  (no decisions)")
+    return
+  end
+
+  local width = 0
+  for _, group in ipairs(groups) do
+    width = math.max(width, vim.fn.strdisplaywidth(group.key))
+  end
+  for _, group in ipairs(groups) do
+    table.insert(lines, string.format(
+      "  %s%s  %5s of %-4d (%d accepted, %d modified, %d rejected)",
+      group.key,
+      string.rep(" ", width - vim.fn.strdisplaywidth(group.key)),
+      percent(group.acceptanceRate),
+      group.total,
+      group.accepted,
+      group.modified,
+      group.rejected
+    ))
+  end
+end
+
+---Render stats as report lines
+---@param stats table ReviewStats from the server
+---@param interval? string Period length of the overTime section
+---@return string[]
+function M.format(stats, interval)
+  local overall = stats.overall
+  local lines = {
+    "# CodeForge review statistics",
+    "",
+    string.format("Decisions:       %d", overall.total),
+    string.format(
+      "Acceptance rate: %s (%d accepted, %d modified, %d rejected)",
+      percent(overall.acceptanceRate),
+      overall.accepted,
+      overall.modified,
+      overall.rejected
+    ),
+  }
+
+  local review = stats.timeToReview
+  if review.medianMs then
+    table.insert(lines, string.format("Time to review:  %s median (%d decisions)", duration(review.medianMs), review.samples))
+  else
+    table.insert(lines, "Time to review:  -")
+  end
+
+  local mods = stats.modifications
+  local modified = string.format("Modified:        %d (%s of decisions)", mods.count, percent(mods.rate))
+  if mods.medianLinesChanged then
+    modified = modified .. string.format(
+      ", %g lines changed median, %.1f mean",
+      mods.medianLinesChanged,
+      mods.meanLinesChanged
+    )
+  end
+  table.insert(lines, modified)
+
+  add_section(lines, "Over time" .. (interval and (" (per " .. interval .. ")") or ""), stats.overTime)
+  add_section(lines, "By file", stats.byFile)
+  add_section(lines, "By directory", stats.byDirectory)
+  add_section(lines, "By extension", stats.byExtension)
+  add_section(lines, "By suggestion size (hunks)", stats.bySuggestionSize)
+
+  return lines
+end
+
+---Show stats in the report buffer, opening it in a new tab if needed
+---@param stats table ReviewStats from the server
+---@param interval? string
+---@return number -- buffer
+function M.open(stats, interval)
+  if not report_buf or not vim.api.nvim_buf_is_valid(report_buf) then
+    report_buf = vim.api.nvim_create_buf(false, true)
+    vim.api.nvim_buf_set_option(report_buf, "buftype", "nofile")
+    vim.api.nvim_buf_set_option(report_buf, "bufhidden", "wipe")
+    vim.api.nvim_buf_set_option(report_buf, "swapfile", false)
+    vim.api.nvim_buf_set_option(report_buf, "filetype", "markdown")
+    vim.api.nvim_buf_set_name(report_buf, "codeforge://stats")
+    vim.keymap.set("n", "q", "<cmd>close<cr>", { buffer = report_buf, nowait = true })
+  end
+
+  vim.api.nvim_buf_set_option(report_buf, "modifiable", true)
+  vim.api.nvim_buf_set_lines(report_buf, 0, -1, false, M.format(stats, interval))
+  vim.api.nvim_buf_set_option(report_buf, "modifiable", false)
+
+  local win = vim.fn.bufwinid(report_buf)
+  if win ~= -1 then
+    vim.api.nvim_set_current_win(win)
+  else
+    vim.cmd("tab split")
+    vim.api.nvim_win_set_buf(0, report_buf)
+  end
+
+  return report_buf
+end
+
+return M
@@ -72,20 +72,27 @@ end
 ---@field is_active fun(self: WebSocketClient): boolean
 
 ---Create a new WebSocket client
----@param opts { host: string, port: number, path?: string }
+---Connects to host:port over TCP, or to a Unix domain socket if `socket` is set
+---@param opts { host?: string, port?: number, socket?: string, path?: string, headers?: table<string, string> }
 ---@return WebSocketClient|nil
 function M.create(opts)
   local host = opts.host or "127.0.0.1"
   local port = opts.port or 80
+  local socket = opts.socket
   local path = opts.path or "/"
-
-  local iptable = uv.getaddrinfo(host)
-  if not iptable or #iptable == 0 then
-    return nil
+  local headers = opts.headers or {}
+
+  local client, ipentry
+  if socket then
+    client = uv.new_pipe(false)
+  else
+    local iptable = uv.getaddrinfo(host)
+    if not iptable or #iptable == 0 then
+      return nil
+    end
+    ipentry = iptable[1]
+    client = uv.new_tcp()
   end
-  local ipentry = iptable[1]
-
-  local client = uv.new_tcp()
   if not client then
     return nil
   end
@@ -102,7 +109,7 @@ function M.create(opts)
   function ws:connect(cbs)
     callbacks = cbs or {}
 
-    local ret, err = client:connect(ipentry.addr, port, vim.schedule_wrap(function(connect_err)
+    local on_connected = vim.schedule_wrap(function(connect_err)
       if connect_err then
         if callbacks.on_error then
           callbacks.on_error("Connection error: " .. connect_err)
@@ -240,6 +247,13 @@ function M.create(opts)
                     callbacks.on_error("WebSocket upgrade failed")
                   end
                 end
+              else
+                -- Server refused the upgrade (e.g. 401 Unauthorized)
+                local status_line = http_chunk:match("^([^\r\n]*)") or "unknown response"
+                http_chunk = ""
+                if callbacks.on_error then
+                  callbacks.on_error("WebSocket upgrade rejected: " .. status_line)
+                end
               end
             end
           else
@@ -258,13 +272,24 @@ function M.create(opts)
       -- Send WebSocket handshake
       local websocket_key = base64.encode(generate_websocket_key())
       client:write("GET " .. path .. " HTTP/1.1\r\n")
-      client:write("Host: " .. host .. ":" .. port .. "\r\n")
+      -- A socket has no host; the server only needs the header to be present
+      client:write("Host: " .. (socket and "localhost" or (host .. ":" .. port)) .. "\r\n")
       client:write("Upgrade: websocket\r\n")
       client:write("Connection: Upgrade\r\n")
       client:write("Sec-WebSocket-Key: " .. websocket_key .. "\r\n")
       client:write("Sec-WebSocket-Version: 13\r\n")
+      for name, value in pairs(headers) do
+        client:write(name .. ": " .. value .. "\r\n")
+      end
       client:write("\r\n")
-    end))
+    end)
+
+    local ret, err
+    if socket then
+      ret, err = client:connect(socket, on_connected)
+    else
+      ret, err = client:connect(ipentry.addr, port, on_connected)
+    end
 
     if not ret then
       if callbacks.on_error then
@@ -396,4 +396,61 @@ describe("diagnostics", function()
       os.remove(test_file)
     end)
   end)
+
+  describe("compiler diagnostics", function()
+    it("summarizes what a hunk introduces", function()
+      assert.is_nil(diagnostics.summarize_problems(nil))
+      assert.equals("this hunk introduces 2 warnings", diagnostics.summarize_problems({
+        { file = "a.rs", line = 1, severity = "warning", message = "unused" },
+        { file = "a.rs", line = 2, severity = "warning", message = "unused" },
+      }))
+      assert.equals("this hunk introduces 1 error, 1 warning", diagnostics.summarize_problems({
+        { file = "a.rs", line = 1, severity = "warning", message = "unused" },
+        { file = "a.rs", line = 2, severity = "error", message = "mismatched types" },
+      }))
+    end)
+
+    it("shows the summary on the hint and each problem at the hunk", function()
+      local test_file = "/tmp/test_codeforge/lib.rs"
+      local file = io.open(test_file, "w")
+      file:write("fn a() {}\nfn b() {}\nfn c() {}\n")
+      file:close()
+
+      store.add_suggestion({
+        id = "rust-suggestion",
+        jjChangeId = "abc",
+        description = "Add helper",
+        files = { "lib.rs" },
+        hunks = {
+          {
+            id = "rust-suggestion:lib.rs:0",
+            file = "lib.rs",
+            diff = "@@ -2,1 +2,2 @@\n fn b() {}\n+fn helper(x: u32) {}",
+            description = "Add helper",
+            diagnostics = {
+              { file = "lib.rs", line = 3, column = 11, severity = "warning", message = "unused variable: `x`", code = "unused_variables", hunkId = "rust-suggestion:lib.rs:0" },
+            },
+          },
+        },
+      })
+
+      local bufnr = vim.api.nvim_create_buf(false, true)
+      vim.api.nvim_buf_set_name(bufnr, test_file)
+
+      diagnostics.publish_diagnostics(bufnr)
+
+      local hints = vim.diagnostic.get(bufnr, { namespace = diagnostics._test_get_namespace() })
+      assert.equals(1, #hints)
+      assert.equals("Add helper (this hunk introduces 1 warning)", hints[1].message)
+
+      local problems = diagnostics.get_check_diagnostics("lib.rs", { ["rust-suggestion:lib.rs:0"] = 2 })
+      assert.equals(1, #problems)
+      assert.equals(1, problems[1].lnum)
+      assert.equals(vim.diagnostic.severity.WARN, problems[1].severity)
+      assert.equals("unused_variables", problems[1].code)
+
+      vim.api.nvim_buf_delete(bufnr, { force = true })
+      os.remove(test_file)
+    end)
+  end)
 end)
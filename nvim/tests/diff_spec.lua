@@ -261,4 +261,75 @@ describe("diff", function()
       assert.equals(0, #removed)
     end)
   end)
+
+  describe("version_changes", function()
+    it("shows how the resulting code differs between versions", function()
+      local v1 = "@@ -1,2 +1,2 @@\n context\n-old\n+first try"
+      local v2 = "@@ -1,2 +1,3 @@\n context\n-old\n+second try\n+extra"
+
+      local changes = diff.version_changes(v1, v2)
+
+      assert.equals(3, #changes)
+      assert.same({ type = "remove", content = "first try" }, changes[1])
+      assert.same({ type = "add", content = "second try" }, changes[2])
+      assert.same({ type = "add", content = "extra" }, changes[3])
+    end)
+
+    it("returns empty when both versions leave the same code", function()
+      local v1 = "@@ -1,2 +1,2 @@\n ctx\n-a\n+b"
+      local v2 = "@@ -1,3 +1,2 @@\n ctx\n-a\n-c\n+b"
+
+      assert.equals(0, #diff.version_changes(v1, v2))
+    end)
+  end)
+
+  describe("partial hunks", function()
+    local hunk = "@@ -10,3 +10,4 @@ fn\n ctx\n-old\n+new1\n+new2\n end"
+
+    it("maps preview lines to changes, removals going with their replacement", function()
+      assert.same({ [2] = true, [3] = true }, diff.changes_in_range(hunk, 2, 2))
+      assert.same({ [4] = true }, diff.changes_in_range(hunk, 3, 3))
+      assert.same({}, diff.changes_in_range(hunk, 1, 1))
+    end)
+
+    it("narrows a hunk to the selected changes", function()
+      local narrowed = diff.select_changes(hunk, { [2] = true, [3] = true })
+
+      assert.equals("@@ -10,3 +10,3 @@ fn\n ctx\n-old\n+new1\n end", narrowed)
+    end)
+
+    it("turns unselected removals into context", function()
+      local narrowed = diff.select_changes(hunk, { [4] = true })
+
+      assert.equals("@@ -10,3 +10,4 @@ fn\n ctx\n old\n+new2\n end", narrowed)
+    end)
+
+    it("refuses a selection without changes", function()
+      local narrowed, err = diff.select_changes(hunk, {})
+
+      assert.is_nil(narrowed)
+      assert.equals("No changed lines selected", err)
+    end)
+
+    it("maps a preview line to where a split starts", function()
+      -- "new1" replaces "old", so a cut there starts with the removal
+      assert.equals(2, diff.split_index(hunk, 2))
+      assert.equals(4, diff.split_index(hunk, 3))
+      assert.equals(5, diff.split_index(hunk, 4))
+      assert.is_nil(diff.split_index(hunk, 9))
+    end)
+  end)
+
+  describe("invert_hunk", function()
+    it("undoes an applied hunk", function()
+      local original = { "a", "b", "old", "c" }
+      local hunk = "@@ -2,3 +2,4 @@\n b\n-old\n+new1\n+new2\n c"
+      local applied = diff.apply_hunk(original, hunk)
+
+      local inverted = diff.invert_hunk(hunk)
+
+      assert.equals("@@ -2,4 +2,3 @@\n b\n+old\n-new1\n-new2\n c", inverted)
+      assert.same(original, diff.apply_hunk(applied, inverted))
+    end)
+  end)
 end)
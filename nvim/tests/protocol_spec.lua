@@ -0,0 +1,72 @@
+-- Tests for codeforge.protocol module
+-- Run with: nvim --headless -c "PlenaryBustedDirectory tests/ {minimal_init = 'tests/minimal_init.lua'}"
+
+local protocol = require("codeforge.protocol")
+
+describe("protocol", function()
+  describe("validate", function()
+    it("accepts server messages that match the schema", function()
+      assert.same({}, protocol.validate("ServerMessage", {
+        type = "suggestion.presence",
+        clients = { { clientId = "c1", name = "alice" } },
+      }))
+      assert.same({}, protocol.validate("ServerMessage", {
+        type = "response",
+        id = "1",
+        success = true,
+        suggestions = {},
+      }))
+    end)
+
+    it("reports each bad field by path", function()
+      local errors = protocol.validate("ServerMessage", {
+        type = "suggestion.hunk_applied",
+        suggestionId = "s",
+        action = "maybe",
+      })
+
+      assert.same({
+        { path = "$.hunkId", message = "is required" },
+        { path = "$.action", message = 'must be one of "accepted", "modified", "rejected"' },
+      }, errors)
+      assert.equals('$.hunkId is required; $.action must be one of "accepted", "modified", "rejected"',
+        protocol.describe_errors(errors))
+    end)
+
+    it("names an unknown message type", function()
+      assert.same(
+        { { path = "$.type", message = 'unknown value "suggestion.exploded"' } },
+        protocol.validate("ServerMessage", { type = "suggestion.exploded" })
+      )
+    end)
+
+    it("treats null as an absent optional field", function()
+      assert.same({}, protocol.validate("ServerMessage", {
+        type = "response",
+        success = false,
+        id = vim.NIL,
+        error = "Invalid JSON",
+      }))
+    end)
+
+    it("checks commands the client sends", function()
+      assert.same({}, protocol.validate("ClientCommand", {
+        type = "merge",
+        id = "2",
+        workingDirectory = "project",
+        suggestionId = "s",
+        hunkIds = { "a", "b" },
+      }))
+      assert.same(
+        { { path = "$.hunkIds", message = "must have at least 2 items" } },
+        protocol.validate("ClientCommand", {
+          type = "merge",
+          id = "3",
+          workingDirectory = "project",
+          suggestionId = "s",
+          hunkIds = { "a" },
+        })
+      )
+    end)
+  end)
+end)
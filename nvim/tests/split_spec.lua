@@ -0,0 +1,91 @@
+-- Tests for codeforge.ui.split module
+-- Run with: nvim --headless -c "PlenaryBustedDirectory tests/ {minimal_init = 'tests/minimal_init.lua'}"
+
+local config = require("codeforge.config")
+local split = require("codeforge.ui.split")
+
+describe("split", function()
+  local buf, win
+
+  before_each(function()
+    config.setup({ ui = { layout = "split" } })
+    buf = vim.api.nvim_create_buf(false, true)
+    vim.api.nvim_buf_set_lines(buf, 0, -1, false, { "line1", "added", "line2" })
+    win = vim.api.nvim_get_current_win()
+    vim.api.nvim_win_set_buf(win, buf)
+  end)
+
+  after_each(function()
+    split.close()
+    if vim.api.nvim_buf_is_valid(buf) then
+      vim.api.nvim_buf_delete(buf, { force = true })
+    end
+    config.setup({})
+  end)
+
+  it("follows ui.layout", function()
+    assert.is_true(split.enabled())
+    config.setup({})
+    assert.is_false(split.enabled())
+  end)
+
+  it("opens the original pane diffed against the proposed one", function()
+    split.open(win, buf, "/tmp/example.lua", { "line1", "line2" })
+
+    assert.is_true(split.is_open())
+    local original_win = split.get_window()
+    assert.are_not.equal(win, original_win)
+
+    local original_buf = vim.api.nvim_win_get_buf(original_win)
+    assert.are.same({ "line1", "line2" }, vim.api.nvim_buf_get_lines(original_buf, 0, -1, false))
+    assert.is_false(vim.bo[original_buf].modifiable)
+
+    for _, w in ipairs({ win, original_win }) do
+      assert.is_true(vim.wo[w].diff)
+      assert.is_true(vim.wo[w].scrollbind)
+      assert.is_true(vim.wo[w].cursorbind)
+    end
+  end)
+
+  it("reuses the original pane when the hunk changes", function()
+    split.open(win, buf, "/tmp/example.lua", { "line1", "line2" })
+    local original_win = split.get_window()
+
+    split.open(win, buf, "/tmp/other.lua", { "other" })
+    assert.are.equal(original_win, split.get_window())
+    local original_buf = vim.api.nvim_win_get_buf(original_win)
+    assert.are.same({ "other" }, vim.api.nvim_buf_get_lines(original_buf, 0, -1, false))
+  end)
+
+  it("closes the original pane and leaves diff mode", function()
+    split.open(win, buf, "/tmp/example.lua", { "line1", "line2" })
+    split.close()
+
+    assert.is_false(split.is_open())
+    assert.is_false(vim.wo[win].diff)
+    assert.is_false(vim.wo[win].scrollbind)
+  end)
+
+  it("maps the review keys in both panes", function()
+    local accepted = 0
+    split.set_callbacks({
+      on_accept = function() accepted = accepted + 1 end,
+      on_reject = function() end,
+      on_modify = function() end,
+      on_next = function() end,
+      on_prev = function() end,
+    })
+    split.open(win, buf, "/tmp/example.lua", { "line1", "line2" })
+
+    local function has_accept(b)
+      for _, m in ipairs(vim.api.nvim_buf_get_keymap(b, "n")) do
+        if m.lhs:lower() == config.get().keymaps.accept:lower() then
+          return true
+        end
+      end
+      return false
+    end
+    assert.is_true(has_accept(buf))
+    assert.is_true(has_accept(vim.api.nvim_win_get_buf(split.get_window())))
+  end)
+end)
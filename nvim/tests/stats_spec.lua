@@ -0,0 +1,82 @@
+-- Tests for codeforge.ui.stats module
+-- Run with: nvim --headless -c "PlenaryBustedDirectory tests/ {minimal_init = 'tests/minimal_init.lua'}"
+
+local stats_ui = require("codeforge.ui.stats")
+
+local function group(key, accepted, modified, rejected)
+  local total = accepted + modified + rejected
+  return {
+    key = key,
+    total = total,
+    accepted = accepted,
+    modified = modified,
+    rejected = rejected,
+    acceptanceRate = total == 0 and 0 or (accepted + modified) / total,
+  }
+end
+
+local function sample_stats()
+  return {
+    overall = group("", 2, 1, 1),
+    overTime = { group("2024-01-01", 2, 1, 1) },
+    byFile = { group("src/a.ts", 2, 0, 1), group("README", 0, 1, 0) },
+    byDirectory = { group("src", 2, 0, 1), group(".", 0, 1, 0) },
+    byExtension = { group(".ts", 2, 0, 1), group("(none)", 0, 1, 0) },
+    bySuggestionSize = { group("2-3", 2, 1, 1) },
+    timeToReview = { medianMs = 90 * 60 * 1000, samples = 4 },
+    modifications = { count = 1, rate = 0.25, medianLinesChanged = 3, meanLinesChanged = 3, totalLinesChanged = 3 },
+  }
+end
+
+local function find(lines, pattern)
+  for i, line in ipairs(lines) do
+    if line:find(pattern) then
+      return i, line
+    end
+  end
+  return nil
+end
+
+describe("stats report", function()
+  it("summarizes acceptance, review time and modifications", function()
+    local lines = stats_ui.format(sample_stats(), "week")
+
+    assert.is_not_nil(find(lines, "^Decisions: +4$"))
+    assert.is_not_nil(find(lines, "^Acceptance rate: 75%% %(2 accepted, 1 modified, 1 rejected%)$"))
+    assert.is_not_nil(find(lines, "^Time to review: +1%.5h median %(4 decisions%)$"))
+    assert.is_not_nil(find(lines, "^Modified: +1 %(25%% of decisions%), 3 lines changed median, 3%.0 mean$"))
+  end)
+
+  it("lists each group under its section with aligned keys", function()
+    local lines = stats_ui.format(sample_stats(), "week")
+
+    local heading = find(lines, "^## By file$")
+    assert.is_not_nil(heading)
+    assert.are.equal("  src/a.ts    67% of 3    (2 accepted, 0 modified, 1 rejected)", lines[heading + 1])
+    assert.are.equal("  README     100% of 1    (0 accepted, 1 modified, 0 rejected)", lines[heading + 2])
+    assert.is_not_nil(find(lines, "^## Over time %(per week%)$"))
+  end)
+
+  it("says so when there is nothing to report", function()
+    local empty = sample_stats()
+    empty.overall = group("", 0, 0, 0)
+    empty.overTime, empty.byFile, empty.byDirectory, empty.byExtension, empty.bySuggestionSize = {}, {}, {}, {}, {}
+    empty.timeToReview = { samples = 0 }
+    empty.modifications = { count = 0, rate = 0, totalLinesChanged = 0 }
+
+    local lines = stats_ui.format(empty)
+
+    assert.is_not_nil(find(lines, "^Time to review: +%-$"))
+    local heading = find(lines, "^## By file$")
+    assert.are.equal("  (no decisions)", lines[heading + 1])
+  end)
+
+  it("opens the report in a read-only buffer", function()
+    local buf = stats_ui.open(sample_stats(), "week")
+
+    assert.are.equal(buf, vim.api.nvim_get_current_buf())
+    assert.is_false(vim.bo[buf].modifiable)
+    assert.are.equal("# CodeForge review statistics", vim.api.nvim_buf_get_lines(buf, 0, 1, false)[1])
+    vim.cmd("tabclose")
+  end)
+end)
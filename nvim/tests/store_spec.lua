@@ -288,6 +288,366 @@ describe("store", function()
     end)
   end)
 
+  describe("restore_hunk", function()
+    local function make_suggestion()
+      return {
+        id = "test-1",
+        jjChangeId = "abc123",
+        description = "Test",
+        files = { "a.lua", "b.lua" },
+        hunks = {
+          { id = "hunk-1", file = "a.lua", diff = "diff1" },
+          { id = "hunk-2", file = "b.lua", diff = "diff2" },
+        },
+      }
+    end
+
+    it("records reviewed hunks as decisions", function()
+      store.add_suggestion(make_suggestion())
+      store.set_hunk_state("hunk-1", "accepted")
+
+      local decision = store.get_last_decision()
+      assert.equals("test-1", decision.suggestion_id)
+      assert.equals("hunk-1", decision.hunk_id)
+      assert.equals("accepted", decision.status)
+    end)
+
+    it("puts the hunk back at its original position as pending", function()
+      store.add_suggestion(make_suggestion())
+      store.set_hunk_state("hunk-1", "rejected")
+
+      local ok = store.restore_hunk("test-1", { id = "hunk-1", file = "a.lua", diff = "diff1" })
+
+      assert.is_true(ok)
+      local suggestion = store.get_suggestion("test-1")
+      assert.equals(2, #suggestion.hunks)
+      assert.equals("hunk-1", suggestion.hunks[1].id)
+      assert.is_true(vim.tbl_contains(suggestion.files, "a.lua"))
+      assert.equals("pending", store.get_hunk_state("hunk-1").status)
+      assert.is_nil(store.get_last_decision())
+    end)
+
+    it("restores a suggestion emptied by review", function()
+      store.add_suggestion(make_suggestion())
+      store.set_hunk_state("hunk-1", "accepted")
+      store.set_hunk_state("hunk-2", "accepted")
+      assert.is_nil(store.get_suggestion("test-1"))
+
+      store.restore_hunk("test-1", { id = "hunk-2", file = "b.lua", diff = "diff2" })
+
+      local suggestion = store.get_suggestion("test-1")
+      assert.is_not_nil(suggestion)
+      assert.equals(1, #suggestion.hunks)
+      assert.equals("hunk-2", suggestion.hunks[1].id)
+      assert.equals("hunk-1", store.get_last_decision().hunk_id)
+    end)
+
+    it("returns false for unknown suggestions", function()
+      assert.is_false(store.restore_hunk("missing", { id = "hunk-9", file = "x.lua", diff = "" }))
+    end)
+
+    it("emits on_hunk_reverted event", function()
+      local emitted = nil
+      store.on("on_hunk_reverted", function(suggestion_id, hunk_id)
+        emitted = { suggestion_id, hunk_id }
+      end)
+
+      store.add_suggestion(make_suggestion())
+      store.set_hunk_state("hunk-2", "modified")
+      store.restore_hunk("test-1", { id = "hunk-2", file = "b.lua", diff = "diff2" })
+
+      assert.same({ "test-1", "hunk-2" }, emitted)
+    end)
+  end)
+
+  describe("partial decisions", function()
+    local function make_suggestion()
+      return {
+        id = "test-1",
+        jjChangeId = "abc123",
+        description = "Test",
+        files = { "a.lua", "b.lua" },
+        hunks = {
+          { id = "hunk-1", file = "a.lua", diff = "diff1" },
+          { id = "hunk-2", file = "b.lua", diff = "diff2" },
+        },
+      }
+    end
+
+    it("puts the rest of the hunk in the decided hunk's place", function()
+      store.add_suggestion(make_suggestion())
+      store.set_hunk_state("hunk-1", "modified")
+
+      local ok = store.add_remaining_hunk("test-1", "hunk-1", { id = "hunk-3", file = "a.lua", diff = "rest" })
+
+      assert.is_true(ok)
+      local suggestion = store.get_suggestion("test-1")
+      assert.equals(2, #suggestion.hunks)
+      assert.equals("hunk-3", store.get_current_hunk().id)
+      assert.equals("pending", store.get_hunk_state("hunk-3").status)
+    end)
+
+    it("brings back a suggestion the decision had emptied", function()
+      store.add_suggestion(make_suggestion())
+      store.set_hunk_state("hunk-2", "accepted")
+      store.set_hunk_state("hunk-1", "rejected")
+      assert.is_nil(store.get_suggestion("test-1"))
+
+      store.add_remaining_hunk("test-1", "hunk-1", { id = "hunk-3", file = "a.lua", diff = "rest" })
+
+      local suggestion = store.get_suggestion("test-1")
+      assert.equals(1, #suggestion.hunks)
+      assert.same({ "a.lua" }, suggestion.files)
+    end)
+
+    it("folds the rest back in when the decision is undone", function()
+      store.add_suggestion(make_suggestion())
+      store.set_hunk_state("hunk-1", "modified")
+      store.add_remaining_hunk("test-1", "hunk-1", { id = "hunk-3", file = "a.lua", diff = "rest" })
+
+      store.restore_hunk("test-1", { id = "hunk-1", file = "a.lua", diff = "diff1" }, "hunk-3")
+
+      local suggestion = store.get_suggestion("test-1")
+      assert.equals(2, #suggestion.hunks)
+      assert.equals("hunk-1", suggestion.hunks[1].id)
+      assert.equals("hunk-2", suggestion.hunks[2].id)
+      assert.is_nil(store.get_hunk_state("hunk-3"))
+    end)
+  end)
+
+  describe("split and merge", function()
+    local function make_suggestion()
+      return {
+        id = "test-1",
+        jjChangeId = "abc123",
+        description = "Test",
+        files = { "a.lua", "b.lua" },
+        hunks = {
+          { id = "hunk-1", file = "a.lua", diff = "diff1" },
+          { id = "hunk-2", file = "b.lua", diff = "diff2" },
+        },
+      }
+    end
+
+    it("puts the pieces of a split in the hunk's place", function()
+      store.add_suggestion(make_suggestion())
+
+      local ok = store.replace_hunks("test-1", { "hunk-1" }, {
+        { id = "hunk-1", file = "a.lua", diff = "first" },
+        { id = "hunk-3", file = "a.lua", diff = "second" },
+      })
+
+      assert.is_true(ok)
+      local suggestion = store.get_suggestion("test-1")
+      assert.equals(3, #suggestion.hunks)
+      assert.equals("first", suggestion.hunks[1].diff)
+      assert.equals("hunk-3", suggestion.hunks[2].id)
+      assert.equals("hunk-2", suggestion.hunks[3].id)
+      assert.equals("pending", store.get_hunk_state("hunk-3").status)
+      assert.equals("hunk-1", store.get_current_hunk().id)
+    end)
+
+    it("merges hunks and their threads", function()
+      store.add_suggestion(make_suggestion())
+      store.replace_hunks("test-1", { "hunk-1" }, {
+        { id = "hunk-1", file = "a.lua", diff = "first" },
+        { id = "hunk-3", file = "a.lua", diff = "second" },
+      })
+      store.add_comment({ id = 1, suggestionId = "test-1", hunkId = "hunk-3", author = "reviewer", body = "why?", createdAt = 1 })
+
+      store.replace_hunks("test-1", { "hunk-1", "hunk-3" }, { { id = "hunk-1", file = "a.lua", diff = "diff1" } })
+
+      local suggestion = store.get_suggestion("test-1")
+      assert.equals(2, #suggestion.hunks)
+      assert.equals("diff1", suggestion.hunks[1].diff)
+      assert.is_nil(store.get_hunk_state("hunk-3"))
+      assert.equals(1, #store.get_thread("hunk-1"))
+      assert.same({}, store.get_thread("hunk-3"))
+    end)
+
+    it("reports hunks it doesn't know", function()
+      store.add_suggestion(make_suggestion())
+
+      assert.is_false(store.replace_hunks("test-1", { "hunk-9" }, {}))
+      assert.is_false(store.replace_hunks("test-2", { "hunk-1" }, {}))
+    end)
+  end)
+
+  describe("review threads", function()
+    local function comment(id, author, body)
+      return { id = id, suggestionId = "test-1", hunkId = "hunk-1", author = author, body = body, createdAt = id }
+    end
+
+    it("starts empty", function()
+      assert.same({}, store.get_thread("hunk-1"))
+    end)
+
+    it("keeps comments in order and ignores duplicates", function()
+      store.add_comment(comment(2, "ai", "because"))
+      store.add_comment(comment(1, "reviewer", "why?"))
+      store.add_comment(comment(2, "ai", "because"))
+
+      local thread = store.get_thread("hunk-1")
+      assert.equals(2, #thread)
+      assert.equals("why?", thread[1].body)
+      assert.equals("because", thread[2].body)
+    end)
+
+    it("loads threads sent with a suggestion", function()
+      store.add_suggestion({
+        id = "test-1",
+        jjChangeId = "abc123",
+        description = "Test",
+        files = { "file.lua" },
+        hunks = { { id = "hunk-1", file = "file.lua", diff = "diff1" } },
+        threads = { ["hunk-1"] = { comment(1, "reviewer", "why?") } },
+      })
+
+      assert.equals(1, #store.get_thread("hunk-1"))
+    end)
+
+    it("emits on_comment once per comment", function()
+      local count = 0
+      store.on("on_comment", function()
+        count = count + 1
+      end)
+
+      store.add_comment(comment(1, "reviewer", "why?"))
+      store.add_comment(comment(1, "reviewer", "why?"))
+
+      assert.equals(1, count)
+    end)
+  end)
+
+  describe("hunk revisions", function()
+    local function make_suggestion()
+      return {
+        id = "test-1",
+        jjChangeId = "abc123",
+        description = "Test",
+        files = { "file.lua" },
+        hunks = {
+          { id = "hunk-1", file = "file.lua", diff = "diff1", version = 1 },
+          { id = "hunk-2", file = "file.lua", diff = "diff2" },
+        },
+      }
+    end
+
+    it("keeps a hunk in the suggestion while awaiting revision", function()
+      store.add_suggestion(make_suggestion())
+
+      store.mark_revision_requested("hunk-1", "use a loop")
+
+      local state = store.get_hunk_state("hunk-1")
+      assert.equals("revision_requested", state.status)
+      assert.equals("use a loop", state.instruction)
+      assert.equals(2, #store.get_suggestion("test-1").hunks)
+      assert.equals(1, store.get_pending_count())
+    end)
+
+    it("loads revision_requested state from the server", function()
+      local suggestion = make_suggestion()
+      suggestion.hunkStates = {
+        ["hunk-1"] = { reviewed = false, state = "revision_requested", instruction = "rename it" },
+      }
+      store.add_suggestion(suggestion)
+
+      assert.equals("revision_requested", store.get_hunk_state("hunk-1").status)
+      assert.equals("rename it", store.get_hunk_state("hunk-1").instruction)
+    end)
+
+    it("swaps in the revised hunk and remembers the previous diff", function()
+      store.add_suggestion(make_suggestion())
+      store.mark_revision_requested("hunk-1", "use a loop")
+
+      local revised_id
+      store.on("on_hunk_revised", function(hunk_id)
+        revised_id = hunk_id
+      end)
+
+      local ok = store.revise_hunk("test-1",
+        { id = "hunk-1", file = "file.lua", diff = "diff1-v2", version = 2 },
+        { hunkId = "hunk-1", version = 1, diff = "diff1" })
+
+      assert.is_true(ok)
+      local hunk = store.get_suggestion("test-1").hunks[1]
+      assert.equals("diff1-v2", hunk.diff)
+      assert.equals("diff1", hunk.previousDiff)
+      assert.equals(2, hunk.version)
+      assert.equals("pending", store.get_hunk_state("hunk-1").status)
+      assert.equals("hunk-1", revised_id)
+    end)
+
+    it("returns false for an unknown hunk", function()
+      store.add_suggestion(make_suggestion())
+
+      assert.is_false(store.revise_hunk("test-1", { id = "nope", file = "file.lua", diff = "x" }))
+      assert.is_false(store.revise_hunk("missing", { id = "hunk-1", file = "file.lua", diff = "x" }))
+    end)
+  end)
+
+  describe("update_suggestion", function()
+    local function make_suggestion(hunks)
+      return {
+        id = "test-1",
+        jjChangeId = "abc123",
+        description = "Test",
+        files = { "file.lua" },
+        hunks = hunks,
+      }
+    end
+
+    it("keeps state of unchanged hunks and resets changed ones", function()
+      store.add_suggestion(make_suggestion({
+        { id = "hunk-1", file = "file.lua", diff = "diff1" },
+        { id = "hunk-2", file = "file.lua", diff = "diff2" },
+        { id = "hunk-3", file = "file.lua", diff = "diff3" },
+      }))
+      store.mark_revision_requested("hunk-1", "rename it")
+      store.mark_revision_requested("hunk-2", "use a loop")
+
+      store.update_suggestion(make_suggestion({
+        { id = "hunk-1", file = "file.lua", diff = "diff1" },
+        { id = "hunk-2", file = "file.lua", diff = "diff2-v2", version = 2 },
+        { id = "hunk-4", file = "file.lua", diff = "diff4" },
+      }), { added = { "hunk-4" }, changed = { "hunk-2" }, removed = { "hunk-3" } })
+
+      local suggestion = store.get_suggestion("test-1")
+      assert.equals(3, #suggestion.hunks)
+      assert.equals("revision_requested", store.get_hunk_state("hunk-1").status)
+      assert.equals("pending", store.get_hunk_state("hunk-2").status)
+      assert.equals("diff2", suggestion.hunks[2].previousDiff)
+      assert.equals("pending", store.get_hunk_state("hunk-4").status)
+      assert.is_nil(store.get_hunk_state("hunk-3"))
+    end)
+
+    it("emits on_suggestion_updated with the changes", function()
+      store.add_suggestion(make_suggestion({ { id = "hunk-1", file = "file.lua", diff = "diff1" } }))
+
+      local received
+      store.on("on_suggestion_updated", function(_, changes)
+        received = changes
+      end)
+
+      local changes = { added = {}, changed = { "hunk-1" }, removed = {} }
+      store.update_suggestion(make_suggestion({ { id = "hunk-1", file = "file.lua", diff = "diff1-v2" } }), changes)
+
+      assert.same(changes, received)
+    end)
+
+    it("brings back a suggestion that review had emptied", function()
+      store.add_suggestion(make_suggestion({ { id = "hunk-1", file = "file.lua", diff = "diff1" } }))
+      store.set_hunk_state("hunk-1", "accepted")
+      assert.is_nil(store.get_suggestion("test-1"))
+
+      store.update_suggestion(make_suggestion({ { id = "hunk-1", file = "file.lua", diff = "diff1-v2" } }),
+        { added = {}, changed = { "hunk-1" }, removed = {} })
+
+      assert.is_not_nil(store.get_suggestion("test-1"))
+      assert.equals("pending", store.get_hunk_state("hunk-1").status)
+    end)
+  end)
+
   describe("get_pending_count", function()
     local test_suggestion = {
       id = "test-1",
@@ -322,6 +682,158 @@ describe("store", function()
     end)
   end)
 
+  describe("check results", function()
+    local function check(name, status, diagnostics)
+      return {
+        suggestionId = "test-1",
+        name = name,
+        command = name,
+        status = status,
+        diagnostics = diagnostics or {},
+        startedAt = 0,
+      }
+    end
+
+    it("has no summary before any check ran", function()
+      assert.is_nil(store.get_check_summary("test-1"))
+    end)
+
+    it("summarizes a run as it progresses", function()
+      store.set_check(check("build", "running"))
+      assert.equals("running", store.get_check_summary("test-1"))
+
+      store.set_check(check("build", "passed"))
+      store.set_check(check("test", "running"))
+      assert.equals("running", store.get_check_summary("test-1"))
+
+      store.set_check(check("test", "failed", {
+        { file = "a.lua", line = 3, severity = "error", message = "boom", hunkId = "hunk-1" },
+      }))
+      assert.equals("failed", store.get_check_summary("test-1"))
+      assert.equals(2, #store.get_checks("test-1"))
+      assert.equals(1, #store.get_hunk_diagnostics("test-1", "hunk-1"))
+      assert.equals(0, #store.get_hunk_diagnostics("test-1", "hunk-2"))
+    end)
+
+    it("starts over when a check runs again", function()
+      store.set_check(check("build", "passed"))
+      store.set_check(check("test", "failed"))
+
+      store.set_check(check("build", "running"))
+
+      local checks = store.get_checks("test-1")
+      assert.equals(1, #checks)
+      assert.equals("running", checks[1].status)
+    end)
+  end)
+
+  describe("presence", function()
+    it("lists other reviewers by suggestion and hunk", function()
+      store.set_client_id("me")
+      store.set_presence({
+        { clientId = "me", suggestionId = "test-1", hunkId = "hunk-1" },
+        { clientId = "c2", name = "alice", suggestionId = "test-1", hunkId = "hunk-1" },
+        { clientId = "c3", name = "bob", suggestionId = "test-1", hunkId = "hunk-2" },
+        { clientId = "c4", name = "carol" },
+      })
+
+      local names = vim.tbl_map(function(r) return r.name end, store.get_reviewers("test-1"))
+      assert.same({ "alice", "bob" }, names)
+      assert.equals(1, #store.get_hunk_reviewers("hunk-1"))
+      assert.equals("alice", store.get_hunk_reviewers("hunk-1")[1].name)
+    end)
+
+    it("forgets who was there on disconnect", function()
+      store.set_presence({ { clientId = "c2", suggestionId = "test-1" } })
+
+      store.set_connected(false)
+
+      assert.equals(0, #store.get_reviewers("test-1"))
+    end)
+  end)
+
+  describe("refused decisions", function()
+    it("puts a hunk back to pending when the server refuses its decision", function()
+      store.add_suggestion({
+        id = "test-1",
+        jjChangeId = "abc",
+        description = "Test",
+        files = { "f.lua" },
+        hunks = {
+          { id = "h1", file = "f.lua", diff = "d1", stateVersion = 1 },
+          { id = "h2", file = "f.lua", diff = "d2", stateVersion = 1 },
+        },
+      })
+      store.set_hunk_state("h1", "accepted")
+
+      store.forget_decision("h1")
+
+      assert.equals("pending", store.get_hunk_state("h1").status)
+      assert.is_nil(store.get_last_decision())
+    end)
+  end)
+
+  describe("event replay", function()
+    it("skips logged events it has already seen", function()
+      store.set_last_seq(4)
+
+      assert.is_false(store.advance_seq(3))
+      assert.is_false(store.advance_seq(4))
+      assert.is_true(store.advance_seq(5))
+      assert.equals(5, store.get_last_seq())
+    end)
+
+    it("replaces suggestions with a snapshot", function()
+      store.add_suggestion({
+        id = "gone",
+        jjChangeId = "abc",
+        description = "Finalized elsewhere",
+        files = { "a.lua" },
+        hunks = { { id = "g1", file = "a.lua", diff = "d" } },
+      })
+      store.add_suggestion({
+        id = "kept",
+        jjChangeId = "def",
+        description = "Still open",
+        files = { "b.lua" },
+        hunks = { { id = "k1", file = "b.lua", diff = "d1" } },
+      })
+
+      store.apply_snapshot({
+        {
+          id = "kept",
+          jjChangeId = "def",
+          description = "Still open",
+          files = { "b.lua", "c.lua" },
+          hunks = {
+            { id = "k1", file = "b.lua", diff = "d1" },
+            { id = "k2", file = "c.lua", diff = "d2" },
+          },
+          hunkStates = {
+            k1 = { reviewed = true, action = "accepted" },
+            k2 = { reviewed = false },
+          },
+        },
+      }, 42)
+
+      assert.is_nil(store.get_suggestion("gone"))
+      local kept = store.get_suggestion("kept")
+      assert.same({ "k2" }, vim.tbl_map(function(h) return h.id end, kept.hunks))
+      assert.same({ "c.lua" }, kept.files)
+      assert.equals("pending", store.get_hunk_state("k2").status)
+      assert.equals("kept", store.get_current_suggestion().id)
+      assert.equals(42, store.get_last_seq())
+    end)
+
+    it("forgets the seq on clear", function()
+      store.set_last_seq(7)
+
+      store.clear()
+
+      assert.is_nil(store.get_last_seq())
+    end)
+  end)
+
   describe("original content cache", function()
     it("caches and retrieves content", function()
       local lines = { "line1", "line2", "line3" }
@@ -0,0 +1,125 @@
+/**
+ * Shared-secret authentication for the HTTP + WebSocket server
+ *
+ * A random token is generated on first start and stored next to the project
+ * database (.opencode/codeforge.token). Clients must present it on every REST
+ * request and on the /ws upgrade, either as an `Authorization: Bearer <token>`
+ * header or as a `token` query parameter.
+ */
+
+import { existsSync, mkdirSync, readFileSync, writeFileSync, chmodSync } from "fs";
+import { dirname, join } from "path";
+import { randomBytes, timingSafeEqual } from "crypto";
+
+export const TOKEN_FILE_NAME = "codeforge.token";
+
+/**
+ * File mode for the token file.
+ * Group-readable so the developer can share a group with the sandbox user.
+ */
+const TOKEN_FILE_MODE = 0o640;
+
+/**
+ * Get the token file path for a project directory
+ */
+export function getTokenPath(projectDir: string): string {
+  return join(projectDir, ".opencode", TOKEN_FILE_NAME);
+}
+
+/**
+ * Generate a new random token (256 bits, hex encoded)
+ */
+export function generateToken(): string {
+  return randomBytes(32).toString("hex");
+}
+
+/**
+ * Write a token to disk with restricted permissions
+ */
+export function writeTokenFile(tokenPath: string, token: string): void {
+  mkdirSync(dirname(tokenPath), { recursive: true });
+  writeFileSync(tokenPath, token + "\n", { mode: TOKEN_FILE_MODE });
+  // writeFileSync only applies the mode when creating the file
+  chmodSync(tokenPath, TOKEN_FILE_MODE);
+}
+
+/**
+ * Read the token from disk, generating and storing a new one if missing
+ */
+export function loadOrCreateToken(tokenPath: string): string {
+  if (existsSync(tokenPath)) {
+    const existing = readFileSync(tokenPath, "utf-8").trim();
+    if (existing) {
+      return existing;
+    }
+  }
+
+  const token = generateToken();
+  writeTokenFile(tokenPath, token);
+  return token;
+}
+
+/**
+ * Make sure a project directory has the server's token on disk,
+ * so editors opened in that project can pick it up
+ */
+export function ensureTokenFile(tokenPath: string, token: string): void {
+  try {
+    if (existsSync(tokenPath) && readFileSync(tokenPath, "utf-8").trim() === token) {
+      return;
+    }
+    writeTokenFile(tokenPath, token);
+  } catch (error) {
+    const errorMsg = error instanceof Error ? error.message : String(error);
+    console.warn(`[auth] Could not write token file ${tokenPath}: ${errorMsg}`);
+  }
+}
+
+/**
+ * Extract the token presented by a request
+ * Checks the Authorization header first, then the `token` query parameter
+ */
+export function extractRequestToken(req: Request, url: URL): string | null {
+  const header = req.headers.get("authorization");
+  if (header) {
+    const match = header.match(/^Bearer\s+(\S+)\s*$/i);
+    if (match) {
+      return match[1]!;
+    }
+  }
+  return url.searchParams.get("token");
+}
+
+/**
+ * Compare a presented token against the expected one in constant time
+ */
+export function tokensMatch(provided: string | null, expected: string): boolean {
+  if (!provided) {
+    return false;
+  }
+  const a = Buffer.from(provided);
+  const b = Buffer.from(expected);
+  if (a.length !== b.length) {
+    return false;
+  }
+  return timingSafeEqual(a, b);
+}
+
+/**
+ * Structured 401 response for unauthenticated requests
+ */
+export function unauthorizedResponse(headers: Record<string, string>, hasToken: boolean): Response {
+  return Response.json(
+    {
+      success: false,
+      code: "unauthorized",
+      error: hasToken
+        ? "Invalid authentication token"
+        : "Missing authentication token. Send 'Authorization: Bearer <token>' or '?token=<token>'",
+    },
+    {
+      status: 401,
+      headers: { ...headers, "WWW-Authenticate": 'Bearer realm="codeforge"' },
+    }
+  );
+}
@@ -0,0 +1,389 @@
+/**
+ * Check pipeline: run configured commands (tests, linters) against a
+ * suggestion's change and map what they report back to its hunks
+ *
+ * Checks run one after another in a checkout of the suggestion's change: the
+ * working copy when it holds that change, otherwise a temporary jj workspace or
+ * git worktree (see VcsBackend.checkout). Each result is stored per suggestion
+ * and streamed as a suggestion.check event when the check starts and when it
+ * finishes.
+ */
+
+import { existsSync } from "fs";
+import { isAbsolute, join, relative } from "path";
+import { parseHunkHeader } from "./diff-parser.ts";
+import type { SuggestionStore } from "./suggestion-store.ts";
+import type { SuggestionEventEmitter } from "./event-emitter.ts";
+import type { Checkout, VcsBackend } from "./vcs.ts";
+import type { CheckDiagnostic, CheckResult, Hunk } from "./types.ts";
+
+/**
+ * One command of the pipeline, as configured in codeforge.json
+ */
+export interface CheckConfig {
+  /** Short name shown in the editor (e.g. "test") */
+  name: string;
+  /** Shell command (e.g. "cargo test") */
+  command: string;
+  /** Seconds before the command is killed (default: 600) */
+  timeout?: number;
+}
+
+const DEFAULT_TIMEOUT_SECONDS = 600;
+
+/**
+ * What running a command reports
+ */
+export interface CommandResult {
+  exitCode: number;
+  output: string;
+  timedOut: boolean;
+}
+
+/**
+ * Runs a shell command: runCommand in-process, or the registering plugin
+ * instance when the daemon serves the project (so it keeps that user's rights)
+ */
+export type CommandRunner = (command: string, cwd: string, timeoutSeconds?: number) => Promise<CommandResult>;
+
+/**
+ * Where a project's pipeline comes from and how it runs
+ */
+export interface CheckEnvironment {
+  checks: CheckConfig[];
+  /** Checks out the suggestion's change */
+  vcs: VcsBackend;
+  run: CommandRunner;
+}
+
+/** How much of a check's output is kept */
+const OUTPUT_TAIL_BYTES = 16 * 1024;
+
+/**
+ * Extract file positions from check output
+ *
+ * Understands cargo's `--message-format=json` lines, `path:line[:col]: message`
+ * (gcc, eslint, clippy short output), `path(line,col): message` (tsc), rustc's
+ * `--> path:line:col` below an `error: ...` / `warning: ...` line and Rust test
+ * panics. Only paths inside rootDir that exist are kept, relative to it.
+ */
+export function parseDiagnostics(output: string, rootDir: string): CheckDiagnostic[] {
+  const diagnostics: CheckDiagnostic[] = [];
+  const seen = new Set<string>();
+  let heading: { severity: CheckDiagnostic["severity"]; message: string } | null = null;
+
+  const add = (
+    path: string,
+    line: number,
+    column: number | undefined,
+    text: string,
+    fallback?: typeof heading,
+    code?: string
+  ) => {
+    const file = toRelativePath(path, rootDir);
+    if (!file || line < 1) return;
+
+    let severity: CheckDiagnostic["severity"] = fallback?.severity ?? "error";
+    let message = text.trim() || fallback?.message || "";
+    const tagged = message.match(/^(error|warning)(?:\[[^\]]*\])?:?\s*(.*)$/i);
+    if (tagged) {
+      severity = tagged[1]!.toLowerCase() as CheckDiagnostic["severity"];
+      message = tagged[2]!;
+    }
+
+    const key = `${file}:${line}:${message}`;
+    if (seen.has(key)) return;
+    seen.add(key);
+    diagnostics.push(code ? { file, line, column, severity, message, code } : { file, line, column, severity, message });
+  };
+
+  for (const raw of output.split("\n")) {
+    const line = raw.replace(/\x1b\[[0-9;]*m/g, "").trimEnd();
+
+    if (line.startsWith("{")) {
+      const message = parseCargoMessage(line);
+      if (message) {
+        add(message.file, message.line, message.column, "", message, message.code);
+      }
+      continue;
+    }
+
+    const header = line.match(/^(error|warning)(\[[^\]]*\])?: (.+)$/);
+    if (header) {
+      heading = { severity: header[1] as CheckDiagnostic["severity"], message: header[3]! };
+      continue;
+    }
+
+    const arrow = line.match(/^\s*--> (.+?):(\d+):(\d+)$/);
+    if (arrow) {
+      add(arrow[1]!, parseInt(arrow[2]!, 10), parseInt(arrow[3]!, 10), "", heading);
+      heading = null;
+      continue;
+    }
+
+    const panic = line.match(/panicked at (.+?):(\d+):(\d+):?$/);
+    if (panic) {
+      add(panic[1]!, parseInt(panic[2]!, 10), parseInt(panic[3]!, 10), "panicked");
+      continue;
+    }
+
+    const tsc = line.match(/^(\S+?)\((\d+),(\d+)\): (.+)$/);
+    if (tsc) {
+      add(tsc[1]!, parseInt(tsc[2]!, 10), parseInt(tsc[3]!, 10), tsc[4]!);
+      continue;
+    }
+
+    const plain = line.match(/^(\S+?):(\d+)(?::(\d+))?: (.+)$/);
+    if (plain) {
+      add(plain[1]!, parseInt(plain[2]!, 10), plain[3] ? parseInt(plain[3], 10) : undefined, plain[4]!);
+    }
+  }
+
+  return diagnostics;
+}
+
+/**
+ * Read one line of `cargo --message-format=json` output
+ * Returns the primary span of an error or warning, or null for anything else
+ * (build artifacts, notes, messages without a location).
+ */
+function parseCargoMessage(line: string): {
+  file: string;
+  line: number;
+  column: number;
+  severity: CheckDiagnostic["severity"];
+  message: string;
+  code?: string;
+} | null {
+  let parsed: {
+    reason?: string;
+    message?: {
+      level?: string;
+      message?: string;
+      code?: { code?: string } | null;
+      spans?: Array<{ file_name: string; line_start: number; column_start: number; is_primary: boolean }>;
+    };
+  };
+  try {
+    parsed = JSON.parse(line);
+  } catch {
+    return null;
+  }
+  if (parsed.reason !== "compiler-message" || !parsed.message) return null;
+
+  const { level, message, code, spans } = parsed.message;
+  const severity = level === "warning" ? "warning" : level?.startsWith("error") ? "error" : null;
+  const span = spans?.find((s) => s.is_primary) ?? spans?.[0];
+  if (!severity || !span || !message) return null;
+
+  return {
+    file: span.file_name,
+    line: span.line_start,
+    column: span.column_start,
+    severity,
+    message,
+    code: code?.code ?? undefined,
+  };
+}
+
+/**
+ * Make a reported path relative to the project, or null if it is outside it
+ */
+function toRelativePath(path: string, rootDir: string): string | null {
+  const absolute = isAbsolute(path) ? path : join(rootDir, path);
+  const rel = relative(rootDir, absolute);
+  if (rel === "" || rel.startsWith("..") || isAbsolute(rel)) return null;
+  return existsSync(absolute) ? rel : null;
+}
+
+/**
+ * Attach each diagnostic to the pending hunk whose proposed lines include it
+ */
+export function mapDiagnosticsToHunks(diagnostics: CheckDiagnostic[], hunks: Hunk[]): CheckDiagnostic[] {
+  return diagnostics.map((diagnostic) => {
+    const hunk = hunks.find((h) => {
+      if (h.file !== diagnostic.file || h.fileChange) return false;
+      const header = parseHunkHeader(h.diff.split("\n")[0] ?? "");
+      if (!header) return false;
+      // A pure removal touches the line it was removed before
+      const last = header.newStart + Math.max(header.newCount, 1) - 1;
+      return diagnostic.line >= header.newStart && diagnostic.line <= last;
+    });
+    return hunk ? { ...diagnostic, hunkId: hunk.id } : diagnostic;
+  });
+}
+
+/**
+ * Run `cargo check` and keep what it reports inside the given hunks
+ * Anything outside them was there before the change.
+ */
+export async function runCargoCheck(
+  cwd: string,
+  hunks: Hunk[],
+  timeoutSeconds = DEFAULT_TIMEOUT_SECONDS,
+  runner: CommandRunner = runCommand
+): Promise<CheckDiagnostic[]> {
+  const run = await runner("cargo check --all-targets --message-format=json", cwd, timeoutSeconds);
+  if (run.timedOut) {
+    throw new Error(`cargo check timed out after ${timeoutSeconds}s`);
+  }
+  return mapDiagnosticsToHunks(parseDiagnostics(run.output, cwd), hunks).filter((d) => d.hunkId);
+}
+
+/**
+ * Run one command, collecting its combined output
+ */
+export async function runCommand(
+  command: string,
+  cwd: string,
+  timeoutSeconds = DEFAULT_TIMEOUT_SECONDS
+): Promise<CommandResult> {
+  const proc = Bun.spawn(["sh", "-c", command], {
+    cwd,
+    stdout: "pipe",
+    stderr: "pipe",
+  });
+
+  let timedOut = false;
+  const timer = setTimeout(() => {
+    timedOut = true;
+    proc.kill();
+  }, timeoutSeconds * 1000);
+
+  try {
+    const [stdout, stderr, exitCode] = await Promise.all([
+      new Response(proc.stdout).text(),
+      new Response(proc.stderr).text(),
+      proc.exited,
+    ]);
+    return { exitCode, output: stdout + stderr, timedOut };
+  } finally {
+    clearTimeout(timer);
+  }
+}
+
+// Suggestions with a pipeline in flight (one run per suggestion at a time)
+const inFlight = new Set<string>();
+
+/**
+ * Run the pipeline for a suggestion, storing and emitting each result
+ * Diagnostics are parsed from the full output, before it is cut to its tail.
+ */
+export async function runChecks(params: CheckEnvironment & {
+  suggestionId: string;
+  store: SuggestionStore;
+  emitter: SuggestionEventEmitter;
+}): Promise<CheckResult[]> {
+  const { suggestionId, store, emitter } = params;
+
+  if (inFlight.has(suggestionId)) {
+    throw new Error(`Checks are already running for ${suggestionId}`);
+  }
+  inFlight.add(suggestionId);
+  try {
+    return await runPipeline(suggestionId, params, store, emitter);
+  } finally {
+    inFlight.delete(suggestionId);
+  }
+}
+
+async function runPipeline(
+  suggestionId: string,
+  { checks, vcs, run: runner }: CheckEnvironment,
+  store: SuggestionStore,
+  emitter: SuggestionEventEmitter
+): Promise<CheckResult[]> {
+  store.clearChecks(suggestionId);
+  await emitter.emitStatus("testing", `Running ${checks.length} checks`, suggestionId);
+
+  // Without a checkout of the change there is nothing to check: every check errors
+  let checkout: Checkout | undefined;
+  let checkoutError: string | undefined;
+  const changeId = store.getSuggestion(suggestionId)?.jjChangeId;
+  if (changeId === undefined) {
+    checkoutError = `Suggestion not found: ${suggestionId}`;
+  } else {
+    try {
+      checkout = await vcs.checkout(changeId);
+    } catch (error) {
+      checkoutError = error instanceof Error ? error.message : String(error);
+    }
+  }
+
+  const results: CheckResult[] = [];
+  try {
+    for (const check of checks) {
+      const running: CheckResult = {
+        suggestionId,
+        name: check.name,
+        command: check.command,
+        status: "running",
+        diagnostics: [],
+        startedAt: Date.now(),
+      };
+      store.saveCheck(running);
+      await emitter.emitCheck(running);
+
+      let result: CheckResult;
+      try {
+        if (!checkout) {
+          throw new Error(checkoutError);
+        }
+        const cwd = checkout.dir;
+        const run = await runner(check.command, cwd, check.timeout);
+        // Map against the hunks as they are now: review may have moved on meanwhile
+        const hunks = store.getSuggestion(suggestionId)?.hunks ?? [];
+        const output = run.output.length > OUTPUT_TAIL_BYTES ? run.output.slice(-OUTPUT_TAIL_BYTES) : run.output;
+        result = {
+          ...running,
+          status: run.timedOut ? "error" : run.exitCode === 0 ? "passed" : "failed",
+          exitCode: run.exitCode,
+          output: run.timedOut ? `${output}\n[codeforge] Timed out after ${check.timeout ?? DEFAULT_TIMEOUT_SECONDS}s` : output,
+          diagnostics: mapDiagnosticsToHunks(parseDiagnostics(run.output, cwd), hunks),
+          finishedAt: Date.now(),
+        };
+      } catch (error) {
+        result = {
+          ...running,
+          status: "error",
+          output: error instanceof Error ? error.message : String(error),
+          finishedAt: Date.now(),
+        };
+      }
+
+      store.saveCheck(result);
+      await emitter.emitCheck(result);
+      results.push(result);
+    }
+  } finally {
+    await checkout?.remove().catch((error: unknown) => {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.warn(`[codeforge] Failed to remove the checkout of ${suggestionId}:`, errorMsg);
+    });
+  }
+
+  const failed = results.filter((r) => r.status !== "passed");
+  await emitter.emitStatus(
+    "ready",
+    failed.length === 0
+      ? `All ${results.length} checks passed`
+      : `${failed.length} of ${results.length} checks failed: ${failed.map((r) => r.name).join(", ")}`,
+    suggestionId
+  );
+
+  return results;
+}
+
+/**
+ * Start the pipeline in the background
+ * Returns false if it is already running for this suggestion.
+ */
+export function startChecks(params: Parameters<typeof runChecks>[0]): boolean {
+  if (inFlight.has(params.suggestionId)) return false;
+
+  runChecks(params).catch((error) => {
+    const errorMsg = error instanceof Error ? error.message : String(error);
+    console.error(`[codeforge] Checks failed for ${params.suggestionId}:`, errorMsg);
+  });
+  return true;
+}
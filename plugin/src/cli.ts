@@ -0,0 +1,99 @@
+#!/usr/bin/env bun
+/**
+ * CodeForge command line
+ *
+ * Usage:
+ *   codeforge export [--project DIR]... [--since DATE] [--until DATE] [--context N] [--output FILE]
+ *
+ * export: write the review decisions of each project (default: the current
+ * directory) as a preference dataset, one JSON record per line (see export.ts),
+ * to FILE or stdout. Dates are millisecond timestamps or anything Date.parse
+ * understands; --context is the number of file lines kept around each hunk.
+ */
+
+import { existsSync, writeFileSync } from "fs";
+import { join, resolve } from "path";
+import { parseArgs } from "util";
+import { exportProjects, toJsonl } from "./export.ts";
+import { SuggestionStore } from "./suggestion-store.ts";
+
+const USAGE = "Usage: codeforge export [--project DIR]... [--since DATE] [--until DATE] [--context N] [--output FILE]";
+
+function parseTime(name: string, value: string | undefined): number | undefined {
+  if (value === undefined) return undefined;
+  const ms = /^\d+$/.test(value) ? Number(value) : Date.parse(value);
+  if (Number.isNaN(ms)) {
+    throw new Error(`Invalid --${name}: ${value}`);
+  }
+  return ms;
+}
+
+function runExport(args: string[]): void {
+  const { values } = parseArgs({
+    args,
+    options: {
+      project: { type: "string", multiple: true },
+      since: { type: "string" },
+      until: { type: "string" },
+      context: { type: "string" },
+      output: { type: "string", short: "o" },
+    },
+  });
+
+  const contextLines = values.context === undefined ? undefined : Number(values.context);
+  if (contextLines !== undefined && !Number.isInteger(contextLines)) {
+    throw new Error(`Invalid --context: ${values.context}`);
+  }
+  const query = {
+    since: parseTime("since", values.since),
+    until: parseTime("until", values.until),
+    contextLines,
+  };
+
+  const projects: Array<[string, SuggestionStore]> = [];
+  for (const project of values.project ?? [process.cwd()]) {
+    const projectDir = resolve(project);
+    const dbPath = join(projectDir, ".opencode", "codeforge.db");
+    if (!existsSync(dbPath)) {
+      console.error(`[codeforge] No review database in ${projectDir}, skipping`);
+      continue;
+    }
+    projects.push([projectDir, new SuggestionStore({ dbPath })]);
+  }
+
+  try {
+    const records = exportProjects(projects, query);
+    if (values.output) {
+      writeFileSync(values.output, toJsonl(records));
+      console.error(`[codeforge] Wrote ${records.length} records to ${values.output}`);
+    } else {
+      process.stdout.write(toJsonl(records));
+    }
+  } finally {
+    for (const [, store] of projects) {
+      store.close();
+    }
+  }
+}
+
+function main(argv: string[]): void {
+  // stdout may carry the dataset: the store's own logging goes to stderr
+  console.log = console.error;
+
+  const [command, ...args] = argv;
+  if (command === "export") {
+    runExport(args);
+  } else {
+    console.error(USAGE);
+    process.exit(command === "--help" || command === "-h" ? 0 : 1);
+  }
+}
+
+if (import.meta.main) {
+  try {
+    main(process.argv.slice(2));
+  } catch (error) {
+    console.error(`[codeforge] ${error instanceof Error ? error.message : String(error)}`);
+    process.exit(1);
+  }
+}
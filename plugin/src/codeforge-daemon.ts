@@ -0,0 +1,62 @@
+#!/usr/bin/env bun
+/**
+ * CodeForge daemon
+ *
+ * One process serving editors for every project: it owns the HTTP + WebSocket
+ * server and the per-project stores, and runs the AI's tools for the OpenCode
+ * instances that register with it (see daemon.ts). Editors connect to it once
+ * and subscribe to the project they have open, as with the in-process server.
+ *
+ * Usage: bun src/codeforge-daemon.ts
+ *
+ * The server address (server.host/port/socket), auth and the IPC socket
+ * (daemon.socket, daemon.socketMode/socketGroup, daemon.projects) come from the config as seen from the current directory,
+ * e.g. ~/.config/opencode/codeforge.json. A registered project's own config
+ * is loaded from the project, and its version control and checks run in the
+ * plugin instance that registered it. The token lives in
+ * ~/.local/share/codeforge/ and is handed to each registered project.
+ */
+
+import { join } from "path";
+import { TOKEN_FILE_NAME, loadOrCreateToken } from "./auth.ts";
+import { checkProjectAllowed, getDaemonDir, getDefaultDaemonSocket, listenForPlugins, remoteProcess } from "./daemon.ts";
+import { createProjectHooks, loadConfig, openProject, serveEditors } from "./index.ts";
+import { resolveVcsKind } from "./vcs.ts";
+
+async function main(): Promise<void> {
+  const config = loadConfig(process.cwd());
+  const ipcSocket = config.daemon ?? getDefaultDaemonSocket();
+  const token = config.auth ? loadOrCreateToken(join(getDaemonDir(), TOKEN_FILE_NAME)) : undefined;
+
+  // Anyone who can reach a shared socket may register: only listed projects are opened
+  const socketShared = (config.daemonSocketMode & 0o077) !== 0;
+
+  const plugins = await listenForPlugins(ipcSocket, (registration, client, plugin) => {
+    const { workingDirectory } = registration;
+    checkProjectAllowed(workingDirectory, config.daemonProjects, socketShared);
+    const projectConfig = loadConfig(workingDirectory);
+    const host = remoteProcess(resolveVcsKind(projectConfig.vcs, workingDirectory), plugin);
+    const project = openProject(workingDirectory, projectConfig, client, host);
+    return createProjectHooks(project).tool;
+  }, { mode: config.daemonSocketMode, gid: config.daemonSocketGroup, token });
+  console.log(`[codeforge-daemon] Accepting plugin instances on ${ipcSocket}`);
+
+  const server = await serveEditors(config, token, plugins.clientFor);
+  const address = config.socket ?? `${config.host}:${config.port}`;
+  console.log(`[codeforge-daemon] Serving editors on ${address}`);
+
+  const shutdown = () => {
+    server.stop(true);
+    plugins.server.close();
+    process.exit(0);
+  };
+  process.on("SIGINT", shutdown);
+  process.on("SIGTERM", shutdown);
+}
+
+if (import.meta.main) {
+  main().catch((error) => {
+    console.error(`[codeforge-daemon] ${error instanceof Error ? error.message : String(error)}`);
+    process.exit(1);
+  });
+}
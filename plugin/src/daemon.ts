@@ -0,0 +1,591 @@
+/**
+ * IPC between plugin instances and the CodeForge daemon
+ *
+ * The daemon (codeforge-daemon.ts) owns the editor server and the per-project
+ * stores, so editors connect to one address no matter how many OpenCode
+ * instances are running. Each plugin instance registers its project with the
+ * daemon over a Unix socket and forwards the AI's tool calls to it.
+ *
+ * Messages are newline-delimited JSON:
+ *   plugin -> daemon  {type: "register", id, workingDirectory}
+ *                     {type: "call", id, tool, args, context}
+ *                     {type: "client_result", id, data?, error?}
+ *   daemon -> plugin  {type: "result", id, output?, error?, token?}
+ *                     {type: "client", id, method, params}
+ *
+ * "client" messages let the daemon use the plugin's OpenCode client (event
+ * logging, notifying the AI session) for the project it registered, and run
+ * everything that executes commands in the project (version control, checks)
+ * in the plugin instance. Those keep the rights of the user OpenCode runs as,
+ * which may be a sandbox user: the daemon never runs a command itself, and
+ * takes nothing but the project's location from the plugin. The register
+ * result carries the editor token, which the plugin stores in the project.
+ */
+
+import { connect, createServer, type Server, type Socket } from "net";
+import { homedir } from "os";
+import { dirname, isAbsolute, join, relative } from "path";
+import { mkdirSync, realpathSync } from "fs";
+import type { OpencodeClient } from "./event-emitter.ts";
+import type { CommandRunner } from "./checks.ts";
+import type { Checkout, FinalizePlan, FinalizeResult, VcsBackend, VcsKind } from "./vcs.ts";
+import { ensureTokenFile, getTokenPath } from "./auth.ts";
+import { applySocketPermissions, prepareSocketPath } from "./socket.ts";
+
+/**
+ * Where the daemon keeps its socket and token
+ */
+export function getDaemonDir(): string {
+  return join(homedir(), ".local", "share", "codeforge");
+}
+
+/**
+ * Default path of the daemon's IPC socket
+ */
+export function getDefaultDaemonSocket(): string {
+  return join(getDaemonDir(), "daemon.sock");
+}
+
+/**
+ * Default file mode of the IPC socket: only the user running the daemon may
+ * register projects (daemon.socketMode/socketGroup open it to a sandbox user)
+ */
+export const DEFAULT_DAEMON_SOCKET_MODE = 0o600;
+
+// How long a plugin waits for the daemon to accept its registration
+const REGISTER_TIMEOUT_MS = 2000;
+
+/**
+ * What a plugin instance tells the daemon about its project
+ * Only where it is: the daemon loads the project's config itself.
+ */
+export interface PluginRegistration {
+  workingDirectory: string;
+}
+
+/**
+ * What a plugin instance runs for the daemon, as the user OpenCode runs as
+ */
+export interface PluginProcess {
+  vcs: VcsBackend;
+  run: CommandRunner;
+}
+
+/**
+ * Make a call in the plugin instance of a project (see PROCESS_METHODS)
+ */
+export type PluginCall = (method: string, params: Record<string, unknown>) => Promise<unknown>;
+
+/**
+ * The daemon's reply to a registration or tool call
+ */
+interface DaemonReply {
+  output?: string;
+  error?: string;
+  /** Editor token, on registration */
+  token?: string;
+}
+
+/**
+ * A tool as the daemon runs it (the plugin validated the args)
+ */
+export interface DaemonTool {
+  execute(args: any, context: any): Promise<string>;
+}
+
+/**
+ * Set up a project for a plugin instance that registered it
+ * @param client Reaches the project's most recently registered plugin instance
+ * @returns the project's tools, by name
+ */
+export type RegisterProject = (
+  registration: PluginRegistration,
+  client: OpencodeClient,
+  plugin: PluginCall
+) => Record<string, DaemonTool>;
+
+/**
+ * OpenCode client methods the daemon may call through a plugin instance
+ */
+const CLIENT_METHODS = ["app.log", "session.list", "session.prompt", "tui.showToast"] as const;
+type ClientMethod = (typeof CLIENT_METHODS)[number];
+
+/**
+ * PluginProcess methods the daemon may call through a plugin instance
+ */
+const PROCESS_METHODS = [
+  "run",
+  "vcs.getDiff",
+  "vcs.getCurrentChangeId",
+  "vcs.restorePaths",
+  "vcs.finalize",
+  "vcs.checkout",
+  "vcs.removeCheckout",
+] as const;
+
+/**
+ * Make sure a project may register with the daemon
+ * It must be an existing directory under one of the allowed roots (compared
+ * by real path, so symlinks can't lead out). Without roots any directory may,
+ * but only while the socket is owner-only: a shared socket would let another
+ * user have the daemon open any directory.
+ * @throws if it may not
+ */
+export function checkProjectAllowed(workingDirectory: string, roots: string[], socketShared: boolean): void {
+  if (!isAbsolute(workingDirectory)) {
+    throw new Error(`Not an absolute path: ${workingDirectory}`);
+  }
+  if (roots.length === 0) {
+    if (socketShared) {
+      throw new Error("The daemon's socket is shared: list the project roots that may register in daemon.projects");
+    }
+    return;
+  }
+
+  const real = realpathSync(workingDirectory);
+  const allowed = roots.some((root) => {
+    let realRoot: string;
+    try {
+      realRoot = realpathSync(root);
+    } catch {
+      return false;
+    }
+    const rel = relative(realRoot, real);
+    return rel === "" || (!rel.startsWith("..") && !isAbsolute(rel));
+  });
+  if (!allowed) {
+    throw new Error(`${workingDirectory} is not under any of daemon.projects`);
+  }
+}
+
+/**
+ * Version control of a project, run by its plugin instance
+ */
+class RemoteVcs implements VcsBackend {
+  constructor(readonly kind: VcsKind, private call: PluginCall) {}
+
+  async getDiff(changeId?: string): Promise<string> {
+    return (await this.call("vcs.getDiff", { changeId })) as string;
+  }
+
+  async getCurrentChangeId(): Promise<string> {
+    return (await this.call("vcs.getCurrentChangeId", {})) as string;
+  }
+
+  async restorePaths(changeId: string, paths: string[]): Promise<void> {
+    await this.call("vcs.restorePaths", { changeId, paths });
+  }
+
+  async finalize(plan: FinalizePlan): Promise<FinalizeResult> {
+    return (await this.call("vcs.finalize", { plan })) as FinalizeResult;
+  }
+
+  async checkout(changeId: string): Promise<Checkout> {
+    const { id, dir } = (await this.call("vcs.checkout", { changeId })) as { id: string; dir: string };
+    return {
+      dir,
+      remove: async () => {
+        await this.call("vcs.removeCheckout", { id });
+      },
+    };
+  }
+}
+
+/**
+ * Version control and command runner of a registered project, both running in
+ * its plugin instance
+ * @param kind The project's backend, as the daemon resolves it from its config
+ */
+export function remoteProcess(kind: VcsKind, call: PluginCall): PluginProcess {
+  return {
+    vcs: new RemoteVcs(kind, call),
+    run: async (command, cwd, timeoutSeconds) =>
+      (await call("run", { command, cwd, timeoutSeconds })) as Awaited<ReturnType<CommandRunner>>,
+  };
+}
+
+/**
+ * Read newline-delimited JSON messages from a socket
+ */
+function onMessages(socket: Socket, handle: (message: any) => void): void {
+  let buffer = "";
+  socket.setEncoding("utf-8");
+  socket.on("data", (chunk: string) => {
+    buffer += chunk;
+    let newline: number;
+    while ((newline = buffer.indexOf("\n")) !== -1) {
+      const line = buffer.slice(0, newline);
+      buffer = buffer.slice(newline + 1);
+      if (!line.trim()) continue;
+      try {
+        handle(JSON.parse(line));
+      } catch (error) {
+        const errorMsg = error instanceof Error ? error.message : String(error);
+        console.warn(`[codeforge] Ignoring bad daemon message: ${errorMsg}`);
+      }
+    }
+  });
+}
+
+function send(socket: Socket, message: object): void {
+  socket.write(JSON.stringify(message) + "\n");
+}
+
+/**
+ * An OpenCode client whose calls are made by a plugin instance
+ * Results carry only `data` (and `error`), which is all the daemon reads.
+ */
+function remoteClient(call: (method: ClientMethod, params: unknown) => Promise<unknown>): OpencodeClient {
+  const wrap = (method: ClientMethod) => async (params: unknown) => ({ data: await call(method, params) });
+  return {
+    app: { log: wrap("app.log") },
+    session: {
+      list: wrap("session.list"),
+      prompt: wrap("session.prompt"),
+    },
+    tui: { showToast: wrap("tui.showToast") },
+  } as unknown as OpencodeClient;
+}
+
+/**
+ * The daemon's end of the IPC socket
+ */
+export interface PluginListener {
+  server: Server;
+  /** OpenCode client of a project, reaching its most recently registered plugin instance */
+  clientFor(workingDirectory: string): OpencodeClient;
+}
+
+/**
+ * Accept plugin instances on the daemon's IPC socket
+ * @param options File mode and group of the socket (default: owner only), and
+ *   the editor token handed to each registered project
+ */
+export async function listenForPlugins(
+  path: string,
+  register: RegisterProject,
+  options: { mode?: number; gid?: number; token?: string } = {}
+): Promise<PluginListener> {
+  mkdirSync(dirname(path), { recursive: true });
+  if (!(await prepareSocketPath(path))) {
+    throw new Error(`address already in use: ${path}`);
+  }
+
+  // Plugin instances per project, most recently registered last
+  const instances = new Map<string, Socket[]>();
+  const clients = new Map<string, OpencodeClient>();
+  const tools = new Map<string, Record<string, DaemonTool>>();
+  let nextRequestId = 0;
+  const pending = new Map<string, { socket: Socket; resolve: (data: unknown) => void; reject: (error: Error) => void }>();
+
+  const pluginFor = (workingDirectory: string) => (method: string, params: unknown): Promise<unknown> => {
+    const socket = instances.get(workingDirectory)?.at(-1);
+    if (!socket) {
+      return Promise.reject(new Error(`No plugin instance connected for ${workingDirectory}`));
+    }
+    const id = `c${++nextRequestId}`;
+    return new Promise((resolve, reject) => {
+      pending.set(id, { socket, resolve, reject });
+      send(socket, { type: "client", id, method, params });
+    });
+  };
+
+  const clientFor = (workingDirectory: string): OpencodeClient => {
+    let client = clients.get(workingDirectory);
+    if (!client) {
+      client = remoteClient(pluginFor(workingDirectory));
+      clients.set(workingDirectory, client);
+    }
+    return client;
+  };
+
+  const server = createServer((socket) => {
+    let workingDirectory: string | undefined;
+
+    onMessages(socket, (message) => {
+      if (message.type === "register") {
+        try {
+          if (typeof message.workingDirectory !== "string") {
+            throw new Error("Missing workingDirectory");
+          }
+          const registration: PluginRegistration = { workingDirectory: message.workingDirectory };
+          tools.set(
+            registration.workingDirectory,
+            register(registration, clientFor(registration.workingDirectory), pluginFor(registration.workingDirectory))
+          );
+          workingDirectory = registration.workingDirectory;
+          instances.set(workingDirectory, [...(instances.get(workingDirectory) ?? []), socket]);
+          console.log(`[codeforge-daemon] Registered ${workingDirectory}`);
+          send(socket, { type: "result", id: message.id, token: options.token });
+        } catch (error) {
+          send(socket, { type: "result", id: message.id, error: error instanceof Error ? error.message : String(error) });
+        }
+      } else if (message.type === "call") {
+        const tool = workingDirectory ? tools.get(workingDirectory)?.[message.tool] : undefined;
+        if (!tool) {
+          send(socket, { type: "result", id: message.id, error: `Unknown tool or unregistered project: ${message.tool}` });
+          return;
+        }
+        tool.execute(message.args, message.context ?? {}).then(
+          (output) => send(socket, { type: "result", id: message.id, output }),
+          (error) => send(socket, { type: "result", id: message.id, error: error instanceof Error ? error.message : String(error) })
+        );
+      } else if (message.type === "client_result") {
+        const request = pending.get(message.id);
+        pending.delete(message.id);
+        if (message.error !== undefined) {
+          request?.reject(new Error(message.error));
+        } else {
+          request?.resolve(message.data);
+        }
+      }
+    });
+
+    socket.on("error", () => {
+      // Handled by close
+    });
+    socket.on("close", () => {
+      // Client calls it will never answer
+      for (const [id, request] of pending) {
+        if (request.socket === socket) {
+          pending.delete(id);
+          request.reject(new Error("Plugin instance disconnected"));
+        }
+      }
+      if (!workingDirectory) return;
+      const remaining = (instances.get(workingDirectory) ?? []).filter((s) => s !== socket);
+      if (remaining.length > 0) {
+        instances.set(workingDirectory, remaining);
+      } else {
+        instances.delete(workingDirectory);
+      }
+      console.log(`[codeforge-daemon] Plugin instance for ${workingDirectory} disconnected`);
+    });
+  });
+
+  await new Promise<void>((resolve, reject) => {
+    server.once("error", reject);
+    server.listen(path, () => {
+      server.off("error", reject);
+      resolve();
+    });
+  });
+  applySocketPermissions(path, options.mode ?? DEFAULT_DAEMON_SOCKET_MODE, options.gid);
+  return { server, clientFor };
+}
+
+/**
+ * A plugin instance's link to the daemon
+ * Reconnects (and registers again) on the next tool call after losing it.
+ */
+export class DaemonConnection {
+  private socket: Socket | null = null;
+  private nextRequestId = 0;
+  private pending = new Map<string, (reply: DaemonReply) => void>();
+  // Temporary checkouts made for the daemon, until it removes them
+  private checkouts = new Map<string, Checkout>();
+  private nextCheckoutId = 0;
+  // Permission problems are reported once, not on every tool call
+  private warnedAccess = false;
+
+  /**
+   * @param local Runs the project's version control and commands for the daemon
+   */
+  constructor(
+    private path: string,
+    private registration: PluginRegistration,
+    private client: OpencodeClient,
+    private local: PluginProcess
+  ) {}
+
+  get connected(): boolean {
+    return this.socket !== null;
+  }
+
+  /**
+   * Connect and register the project
+   * @returns whether the daemon accepted the registration
+   */
+  async connect(): Promise<boolean> {
+    if (this.socket) return true;
+
+    const socket = await new Promise<Socket | null>((resolve) => {
+      const candidate = connect(this.path);
+      candidate.once("connect", () => resolve(candidate));
+      candidate.once("error", (error: Error & { code?: string }) => {
+        if (error.code === "EACCES" && !this.warnedAccess) {
+          this.warnedAccess = true;
+          console.warn(
+            `[codeforge] No permission to connect to the CodeForge daemon at ${this.path}; running tools in-process ` +
+              `(the daemon's daemon.socketMode/socketGroup must let this user in)`
+          );
+        }
+        resolve(null);
+      });
+    });
+    if (!socket) return false;
+
+    socket.on("error", () => {
+      // Handled by close
+    });
+    socket.on("close", () => {
+      if (this.socket === socket) this.socket = null;
+      // Calls in flight may or may not have run: report them as failed
+      for (const reply of this.pending.values()) {
+        reply({ error: "Lost the connection to the CodeForge daemon" });
+      }
+      this.pending.clear();
+    });
+    onMessages(socket, (message) => this.handle(socket, message));
+    this.socket = socket;
+
+    const reply = await Promise.race([
+      this.request({ type: "register", ...this.registration }),
+      new Promise<{ error: string }>((resolve) =>
+        setTimeout(() => resolve({ error: "timed out" }), REGISTER_TIMEOUT_MS)
+      ),
+    ]);
+    if (reply.error !== undefined) {
+      console.warn(`[codeforge] Daemon refused ${this.registration.workingDirectory}: ${reply.error}`);
+      this.close();
+      return false;
+    }
+    // Editors opened in the project read the daemon's token from there
+    if (reply.token) {
+      ensureTokenFile(getTokenPath(this.registration.workingDirectory), reply.token);
+    }
+    return true;
+  }
+
+  /**
+   * Run a tool in the daemon
+   * @param context The call's session and message (feedback goes back to the session)
+   * @returns its output, or undefined if the daemon can't be reached
+   */
+  async call(tool: string, args: unknown, context: { sessionID?: string; messageID?: string; agent?: string } = {}): Promise<string | undefined> {
+    if (!(await this.connect())) return undefined;
+    const reply = await this.request({ type: "call", tool, args, context });
+    if (reply.error !== undefined) {
+      return JSON.stringify({ success: false, error: reply.error });
+    }
+    return reply.output;
+  }
+
+  close(): void {
+    this.socket?.end();
+    this.socket = null;
+  }
+
+  private request(message: object): Promise<DaemonReply> {
+    const socket = this.socket;
+    if (!socket) {
+      return Promise.resolve({ error: "Not connected to the CodeForge daemon" });
+    }
+    const id = `p${++this.nextRequestId}`;
+    return new Promise((resolve) => {
+      this.pending.set(id, (reply) => {
+        this.pending.delete(id);
+        resolve(reply);
+      });
+      send(socket, { ...message, id });
+    });
+  }
+
+  private handle(socket: Socket, message: any): void {
+    if (message.type === "result") {
+      this.pending.get(message.id)?.(message);
+    } else if (message.type === "client") {
+      const call = (PROCESS_METHODS as readonly string[]).includes(message.method)
+        ? this.callLocal(message.method, message.params ?? {})
+        : this.callClient(message.method, message.params);
+      call.then(
+        (data) => send(socket, { type: "client_result", id: message.id, data }),
+        (error) => send(socket, { type: "client_result", id: message.id, error: error instanceof Error ? error.message : String(error) })
+      );
+    }
+  }
+
+  /**
+   * Make an OpenCode client call on the daemon's behalf
+   */
+  private async callClient(method: string, params: unknown): Promise<unknown> {
+    if (!(CLIENT_METHODS as readonly string[]).includes(method)) {
+      throw new Error(`Unsupported client method: ${method}`);
+    }
+    const [namespace, name] = method.split(".") as [string, string];
+    const result = await (this.client as any)[namespace][name](params);
+    if (result?.error) {
+      throw new Error(typeof result.error === "string" ? result.error : JSON.stringify(result.error));
+    }
+    return result?.data;
+  }
+
+  /**
+   * Run version control or a command in this process on the daemon's behalf
+   */
+  private async callLocal(method: string, params: any): Promise<unknown> {
+    const { vcs, run } = this.local;
+    switch (method) {
+      case "run":
+        return run(params.command, params.cwd, params.timeoutSeconds);
+      case "vcs.getDiff":
+        return vcs.getDiff(params.changeId);
+      case "vcs.getCurrentChangeId":
+        return vcs.getCurrentChangeId();
+      case "vcs.restorePaths":
+        await vcs.restorePaths(params.changeId, params.paths);
+        return null;
+      case "vcs.finalize":
+        return vcs.finalize(params.plan);
+      case "vcs.checkout": {
+        const checkout = await vcs.checkout(params.changeId);
+        const id = `k${++this.nextCheckoutId}`;
+        this.checkouts.set(id, checkout);
+        return { id, dir: checkout.dir };
+      }
+      case "vcs.removeCheckout": {
+        const checkout = this.checkouts.get(params.id);
+        this.checkouts.delete(params.id);
+        await checkout?.remove();
+        return null;
+      }
+      default:
+        throw new Error(`Unsupported client method: ${method}`);
+    }
+  }
+}
+
+/**
+ * Register a project with the daemon
+ * @param local Runs the project's version control and commands for the daemon
+ * @returns the connection, or null if no daemon is listening
+ */
+export async function connectToDaemon(
+  path: string,
+  registration: PluginRegistration,
+  client: OpencodeClient,
+  local: PluginProcess
+): Promise<DaemonConnection | null> {
+  const connection = new DaemonConnection(path, registration, client, local);
+  return (await connection.connect()) ? connection : null;
+}
+
+/**
+ * Run tools in the daemon, or in-process while it can't be reached
+ * A call the daemon received but never answered is not run again.
+ */
+export function forwardTools<T extends Record<string, DaemonTool>>(tools: T, daemon: DaemonConnection): T {
+  const forwarded: Record<string, DaemonTool> = {};
+  for (const [name, definition] of Object.entries(tools)) {
+    forwarded[name] = {
+      ...definition,
+      async execute(args, context) {
+        // The abort signal stays behind
+        const { sessionID, messageID, agent } = context ?? {};
+        const output = await daemon.call(name, args, { sessionID, messageID, agent });
+        return output ?? definition.execute(args, context);
+      },
+    };
+  }
+  return forwarded as T;
+}
@@ -3,7 +3,7 @@
  * Parses output from `jj diff` or `git diff` into structured hunks
  */
 
-import type { Hunk } from "./types.ts";
+import type { Hunk, FileChange, FileChangeKind } from "./types.ts";
 
 /**
  * Parsed file diff containing all hunks for a single file
@@ -15,6 +15,18 @@ export interface FileDiff {
   newPath: string;
   /** All hunks in this file */
   hunks: ParsedHunk[];
+  /** What happened to the file as a whole (absent = plain content modification) */
+  changeKind?: FileChangeKind;
+  /** Rename/copy similarity percentage */
+  similarity?: number;
+  /** File mode before the change (e.g. "100644") */
+  oldMode?: string;
+  /** File mode after the change (e.g. "100755") */
+  newMode?: string;
+  /** Whether the file content is binary (no textual hunks) */
+  binary?: boolean;
+  /** Raw extended header lines (diff --git, rename from, new mode, ...) */
+  header?: string;
 }
 
 /**
@@ -79,10 +91,17 @@ export function parseDiff(diffText: string): FileDiff[] {
         oldPath: paths.oldPath,
         newPath: paths.newPath,
         hunks: [],
+        header: line,
       };
       continue;
     }
 
+    // Extended git headers between "diff --git" and the first hunk
+    if (currentFile && !currentHunkMeta && parseExtendedHeader(currentFile, line)) {
+      currentFile.header += "\n" + line;
+      continue;
+    }
+
     // --- a/path or --- /dev/null
     if (line.startsWith("--- ")) {
       if (currentFile) {
@@ -134,6 +153,76 @@ export function parseDiff(diffText: string): FileDiff[] {
   return files;
 }
 
+/**
+ * Apply a git extended header line (rename from, new file mode, ...) to a file diff
+ * Returns false if the line is not an extended header.
+ */
+function parseExtendedHeader(file: FileDiff, line: string): boolean {
+  let match: RegExpMatchArray | null;
+
+  if ((match = line.match(/^new file mode (\d+)$/))) {
+    file.changeKind = "add";
+    file.newMode = match[1]!;
+  } else if ((match = line.match(/^deleted file mode (\d+)$/))) {
+    file.changeKind = "delete";
+    file.oldMode = match[1]!;
+  } else if ((match = line.match(/^old mode (\d+)$/))) {
+    file.oldMode = match[1]!;
+    file.changeKind ??= "mode";
+  } else if ((match = line.match(/^new mode (\d+)$/))) {
+    file.newMode = match[1]!;
+    file.changeKind ??= "mode";
+  } else if ((match = line.match(/^(?:similarity|dissimilarity) index (\d+)%$/))) {
+    file.similarity = line.startsWith("dis") ? 100 - parseInt(match[1]!, 10) : parseInt(match[1]!, 10);
+  } else if ((match = line.match(/^(rename|copy) from (.+)$/))) {
+    file.changeKind = match[1] === "rename" ? "rename" : "copy";
+    file.oldPath = match[2]!;
+  } else if ((match = line.match(/^(rename|copy) to (.+)$/))) {
+    file.changeKind = match[1] === "rename" ? "rename" : "copy";
+    file.newPath = match[2]!;
+  } else if (line.startsWith("Binary files ") || line === "GIT binary patch") {
+    file.binary = true;
+    file.changeKind ??= "binary";
+  } else if (line.startsWith("index ")) {
+    // index <hash>..<hash> [mode] - mode is unchanged if present here
+    const mode = line.match(/^index \S+ (\d+)$/);
+    if (mode) {
+      file.oldMode ??= mode[1]!;
+      file.newMode ??= mode[1]!;
+    }
+  } else {
+    return false;
+  }
+
+  return true;
+}
+
+/**
+ * Get the path a file diff is reviewed under (newPath, or oldPath for deletions)
+ */
+export function fileDiffPath(fileDiff: FileDiff): string {
+  return fileDiff.newPath && fileDiff.newPath !== "/dev/null" ? fileDiff.newPath : fileDiff.oldPath;
+}
+
+/**
+ * Whether a file diff needs a file-level hunk to be reviewable
+ * (renames, copies, mode changes, binary changes, and empty adds/deletes)
+ */
+export function needsFileLevelHunk(fileDiff: FileDiff): boolean {
+  switch (fileDiff.changeKind) {
+    case undefined:
+      return false;
+    case "rename":
+    case "copy":
+    case "mode":
+    case "binary":
+      return true;
+    case "add":
+    case "delete":
+      return fileDiff.binary === true || fileDiff.hunks.length === 0;
+  }
+}
+
 /**
  * Extract file paths from a diff header line
  */
@@ -182,16 +271,29 @@ export function fileDiffsToHunks(fileDiffs: FileDiff[], suggestionId: string): H
   const hunks: Hunk[] = [];
 
   for (const fileDiff of fileDiffs) {
+    const path = fileDiffPath(fileDiff);
+
+    // File-level hunk first, so a rename is reviewed before edits to the renamed file
+    if (needsFileLevelHunk(fileDiff)) {
+      hunks.push({
+        id: `${suggestionId}:${path}:file`,
+        file: path,
+        diff: fileDiff.header ?? `diff --git a/${fileDiff.oldPath} b/${fileDiff.newPath}`,
+        fileChange: toFileChange(fileDiff),
+      });
+    }
+
     for (let i = 0; i < fileDiff.hunks.length; i++) {
       const parsedHunk = fileDiff.hunks[i]!;
       const { original } = extractHunkContent(parsedHunk.content);
       
       hunks.push({
-        id: `${suggestionId}:${fileDiff.newPath}:${i}`,
-        file: fileDiff.newPath,
+        id: `${suggestionId}:${path}:${i}`,
+        file: path,
         diff: parsedHunk.content,
         originalLines: original,
         originalStartLine: parsedHunk.oldStart,
+        fileMode: fileDiff.changeKind === "delete" ? fileDiff.oldMode : fileDiff.newMode ?? fileDiff.oldMode,
       });
     }
   }
@@ -199,6 +301,75 @@ export function fileDiffsToHunks(fileDiffs: FileDiff[], suggestionId: string): H
   return hunks;
 }
 
+/**
+ * Build the file-level change metadata for a file diff
+ */
+function toFileChange(fileDiff: FileDiff): FileChange {
+  return {
+    kind: fileDiff.changeKind ?? "binary",
+    oldPath: fileDiff.oldPath,
+    newPath: fileDiff.newPath,
+    similarity: fileDiff.similarity,
+    oldMode: fileDiff.oldMode,
+    newMode: fileDiff.newMode,
+    binary: fileDiff.binary,
+  };
+}
+
+/**
+ * Parse a file-level hunk diff (extended git headers) back into a FileChange
+ * Returns null if the diff is a regular @@ hunk.
+ */
+export function parseFileChange(diff: string): FileChange | null {
+  if (!diff.startsWith("diff --git ")) {
+    return null;
+  }
+  const fileDiff = parseDiff(diff)[0];
+  if (!fileDiff || !fileDiff.changeKind) {
+    return null;
+  }
+  return toFileChange(fileDiff);
+}
+
+/**
+ * Render a FileChange as extended git headers (the diff of a file-level hunk)
+ */
+export function formatFileChange(change: FileChange): string {
+  const oldPath = change.kind === "add" ? change.newPath : change.oldPath;
+  const newPath = change.kind === "delete" ? change.oldPath : change.newPath;
+  const lines = [`diff --git a/${oldPath} b/${newPath}`];
+
+  switch (change.kind) {
+    case "add":
+      lines.push(`new file mode ${change.newMode ?? "100644"}`);
+      break;
+    case "delete":
+      lines.push(`deleted file mode ${change.oldMode ?? "100644"}`);
+      break;
+    case "rename":
+    case "copy":
+      if (change.oldMode && change.newMode && change.oldMode !== change.newMode) {
+        lines.push(`old mode ${change.oldMode}`, `new mode ${change.newMode}`);
+      }
+      if (change.similarity !== undefined) {
+        lines.push(`similarity index ${change.similarity}%`);
+      }
+      lines.push(`${change.kind} from ${change.oldPath}`, `${change.kind} to ${change.newPath}`);
+      break;
+    case "mode":
+      lines.push(`old mode ${change.oldMode ?? "100644"}`, `new mode ${change.newMode ?? "100644"}`);
+      break;
+    case "binary":
+      break;
+  }
+
+  if (change.binary) {
+    lines.push(`Binary files a/${oldPath} and b/${newPath} differ`);
+  }
+
+  return lines.join("\n");
+}
+
 /**
  * Filter options for selective publishing
  */
@@ -391,17 +562,23 @@ export function extractHunkContent(hunkDiff: string): {
 /**
  * Calculate the net line offset for a file based on previously applied hunks
  * Positive offset means the file has grown, negative means it has shrunk
+ * 
+ * hunkIds gives the ID of each hunk; without it IDs are derived from the hunk's
+ * position, which no longer holds once part of a hunk has been decided and the
+ * rest is pending under a new ID.
  */
 export function calculateLineOffset(
   hunks: ParsedHunk[],
   appliedHunkIds: Set<string>,
   filePath: string,
-  suggestionId: string
+  suggestionId: string,
+  hunkIds?: string[]
 ): number {
   let offset = 0;
 
   for (const hunk of hunks) {
-    const hunkId = `${suggestionId}:${filePath}:${hunks.indexOf(hunk)}`;
+    const index = hunks.indexOf(hunk);
+    const hunkId = hunkIds?.[index] ?? `${suggestionId}:${filePath}:${index}`;
     
     if (appliedHunkIds.has(hunkId)) {
       // This hunk was applied, calculate its net line change
@@ -441,3 +618,321 @@ export function adjustHunkLineNumbers(hunkDiff: string, lineOffset: number): str
   
   return lines.join("\n");
 }
+
+/**
+ * Body lines of a hunk (after the @@ header), with empty context lines written out as " "
+ */
+function hunkBody(hunkDiff: string): string[] {
+  return hunkDiff
+    .split("\n")
+    .slice(1)
+    .filter((line, i, all) => line !== "" || i < all.length - 1)
+    .map((line) => line === "" ? " " : line);
+}
+
+/**
+ * Split a hunk by a decision on a selection of its lines
+ * 
+ * selection is the hunk narrowed to the selected changes: unselected additions
+ * dropped and unselected removals turned into context. An accepted selection is
+ * in the file afterwards, so the rest of the hunk is expressed against the file
+ * with it applied; a rejected one leaves the file as it was.
+ * Returns the rest of the hunk (null if the selection covered every change), or
+ * undefined if selection isn't a selection of the hunk's changes.
+ */
+export function splitHunkSelection(
+  hunkDiff: string,
+  selection: string,
+  decision: "accept" | "reject"
+): { remaining: string | null } | undefined {
+  const header = parseHunkHeader(hunkDiff.split("\n")[0] ?? "");
+  if (!header || !parseHunkHeader(selection.split("\n")[0] ?? "")) return undefined;
+
+  const selected = hunkBody(selection).filter((line) => !line.startsWith("\\"));
+  const remaining: string[] = [];
+  let next = 0;
+  let picked = 0;
+  let left = 0;
+  let kept = false;
+
+  for (const line of hunkBody(hunkDiff)) {
+    // "\ No newline at end of file" belongs to the line before it
+    if (line.startsWith("\\")) {
+      if (kept) remaining.push(line);
+      continue;
+    }
+
+    const text = line.slice(1);
+    let out: string | null;
+    if (line.startsWith("+")) {
+      if (selected[next] === line) {
+        next++;
+        picked++;
+        out = decision === "accept" ? ` ${text}` : null;
+      } else {
+        left++;
+        out = line;
+      }
+    } else if (line.startsWith("-")) {
+      if (selected[next] === line) {
+        picked++;
+        out = decision === "accept" ? null : ` ${text}`;
+      } else if (selected[next] === ` ${text}`) {
+        left++;
+        out = line;
+      } else {
+        return undefined;
+      }
+      next++;
+    } else {
+      if (selected[next] !== line) return undefined;
+      next++;
+      out = line;
+    }
+
+    kept = out !== null;
+    if (out !== null) remaining.push(out);
+  }
+
+  if (next !== selected.length || picked === 0) return undefined;
+  if (left === 0) return { remaining: null };
+
+  const oldCount = remaining.filter((line) => line.startsWith(" ") || line.startsWith("-")).length;
+  const newCount = remaining.filter((line) => line.startsWith(" ") || line.startsWith("+")).length;
+  const context = header.context ? ` ${header.context}` : "";
+  return {
+    remaining: [`@@ -${header.oldStart},${oldCount} +${header.newStart},${newCount} @@${context}`, ...remaining].join("\n"),
+  };
+}
+
+/**
+ * A body line of a hunk with any "\ No newline at end of file" marker that follows it
+ */
+interface HunkBodyLine {
+  line: string;
+  markers: string[];
+}
+
+function hunkBodyLines(hunkDiff: string): HunkBodyLine[] {
+  const lines: HunkBodyLine[] = [];
+  for (const line of hunkBody(hunkDiff)) {
+    if (line.startsWith("\\") && lines.length > 0) {
+      lines[lines.length - 1]!.markers.push(line);
+    } else {
+      lines.push({ line, markers: [] });
+    }
+  }
+  return lines;
+}
+
+const isChangeLine = (entry: HunkBodyLine) => entry.line.startsWith("+") || entry.line.startsWith("-");
+const isOldLine = (entry: HunkBodyLine) => entry.line.startsWith(" ") || entry.line.startsWith("-");
+const isNewLine = (entry: HunkBodyLine) => entry.line.startsWith(" ") || entry.line.startsWith("+");
+
+/**
+ * Write out body lines as a hunk starting at the given old/new lines
+ */
+function formatHunk(lines: HunkBodyLine[], oldStart: number, newStart: number, context?: string): string {
+  const oldCount = lines.filter(isOldLine).length;
+  const newCount = lines.filter(isNewLine).length;
+  return [
+    `@@ -${oldStart},${oldCount} +${newStart},${newCount} @@${context ? ` ${context}` : ""}`,
+    ...lines.flatMap((entry) => [entry.line, ...entry.markers]),
+  ].join("\n");
+}
+
+/**
+ * Split a hunk into smaller hunks, like `s` in `git add -p`
+ * 
+ * Without atLine, the hunk is cut at every run of context lines between two
+ * groups of changes. With it (1-based, counting the body lines after the @@
+ * header), the hunk is cut just before that line, which may fall inside a
+ * group of changes. Context lines at a cut are kept on both sides of it.
+ * Returns the pieces in order, or undefined if the hunk can't be cut there
+ * (a piece would have no changes).
+ */
+export function splitHunkDiff(hunkDiff: string, atLine?: number): string[] | undefined {
+  const header = parseHunkHeader(hunkDiff.split("\n")[0] ?? "");
+  if (!header) return undefined;
+
+  const lines = hunkBodyLines(hunkDiff);
+  const isContext = (i: number) => i >= 0 && i < lines.length && !isChangeLine(lines[i]!);
+
+  // Inclusive [start, end] ranges of body lines, one per piece
+  const ranges: Array<[number, number]> = [];
+  if (atLine === undefined) {
+    const groups: Array<[number, number]> = [];
+    lines.forEach((entry, i) => {
+      if (!isChangeLine(entry)) return;
+      const last = groups[groups.length - 1];
+      if (last && last[1] === i - 1) {
+        last[1] = i;
+      } else {
+        groups.push([i, i]);
+      }
+    });
+    groups.forEach((_, g) => {
+      const start = g === 0 ? 0 : groups[g - 1]![1] + 1;
+      const end = g === groups.length - 1 ? lines.length - 1 : groups[g + 1]![0] - 1;
+      ranges.push([start, end]);
+    });
+  } else {
+    const cut = atLine - 1;
+    if (!Number.isInteger(atLine) || cut <= 0 || cut >= lines.length) return undefined;
+    let end = cut - 1;
+    while (isContext(end + 1)) end++;
+    let start = cut;
+    while (isContext(start - 1)) start--;
+    ranges.push([0, end], [start, lines.length - 1]);
+  }
+
+  if (ranges.length < 2) return undefined;
+
+  const pieces: string[] = [];
+  for (const [start, end] of ranges) {
+    const piece = lines.slice(start, end + 1);
+    if (!piece.some(isChangeLine)) return undefined;
+    const before = lines.slice(0, start);
+    pieces.push(formatHunk(
+      piece,
+      header.oldStart + before.filter(isOldLine).length,
+      header.newStart + before.filter(isNewLine).length,
+      header.context
+    ));
+  }
+  return pieces;
+}
+
+/**
+ * Join two hunks of the same file into one
+ * 
+ * second must start at or after first in the original file. Where they
+ * overlap, the shared lines must be context in both (as with the pieces of a
+ * split); where they don't touch, gap holds the unchanged lines between them.
+ * Returns the joined hunk, or undefined if the hunks overlap in their changes
+ * or gap doesn't fill the space between them.
+ */
+export function mergeHunkDiffs(first: string, second: string, gap: string[] = []): string | undefined {
+  const a = parseHunkHeader(first.split("\n")[0] ?? "");
+  const b = parseHunkHeader(second.split("\n")[0] ?? "");
+  if (!a || !b || b.oldStart < a.oldStart) return undefined;
+
+  const firstLines = hunkBodyLines(first);
+  let secondLines = hunkBodyLines(second);
+  const between = b.oldStart - (a.oldStart + a.oldCount);
+
+  if (between < 0) {
+    const overlap = -between;
+    const tail = firstLines.filter(isOldLine).slice(-overlap);
+    const head = secondLines.slice(0, overlap);
+    if (
+      head.length < overlap ||
+      tail.length < overlap ||
+      head.some((entry, i) => isChangeLine(entry) || entry.line !== tail[i]!.line) ||
+      tail.some(isChangeLine)
+    ) {
+      return undefined;
+    }
+    secondLines = secondLines.slice(overlap);
+  } else if (gap.length !== between) {
+    return undefined;
+  }
+
+  const gapLines = between > 0 ? gap.map((line) => ({ line: ` ${line}`, markers: [] })) : [];
+  return formatHunk([...firstLines, ...gapLines, ...secondLines], a.oldStart, a.newStart, a.context);
+}
+
+/**
+ * Outcome of matching a freshly computed set of hunks against the previous one
+ * Matched hunks carry the ID (and version) of the hunk they correspond to.
+ */
+export interface HunkMatchResult {
+  /** Same change as a previous hunk (its @@ header may have moved) */
+  unchanged: Hunk[];
+  /** A different change over the same original lines as a previous hunk */
+  changed: Hunk[];
+  /** No counterpart in the previous hunks */
+  added: Hunk[];
+  /** Previous hunks with no counterpart in the new ones */
+  removed: Hunk[];
+}
+
+/**
+ * Identity of a hunk's change: the file plus its +/- lines, ignoring position and context
+ */
+function changeKey(hunk: Hunk): string {
+  if (hunk.fileChange) {
+    return `${hunk.file}\0${hunk.diff}`;
+  }
+  const changed = hunk.diff.split("\n").filter((line) => line.startsWith("+") || line.startsWith("-"));
+  return `${hunk.file}\0${changed.join("\n")}`;
+}
+
+/**
+ * Original-file line range a hunk covers (pure insertions cover the line they follow)
+ */
+function originalRange(hunk: Hunk): { start: number; end: number } | null {
+  if (hunk.fileChange) return null;
+  const header = parseHunkHeader(hunk.diff.split("\n")[0] ?? "");
+  if (!header) return null;
+  return { start: header.oldStart, end: header.oldStart + Math.max(header.oldCount, 1) };
+}
+
+/**
+ * Pick the candidate whose original position is nearest to the hunk's
+ */
+function closestHunk(hunk: Hunk, candidates: Hunk[]): Hunk | undefined {
+  const line = hunk.originalStartLine ?? 0;
+  let best: Hunk | undefined;
+  for (const candidate of candidates) {
+    if (!best || Math.abs((candidate.originalStartLine ?? 0) - line) < Math.abs((best.originalStartLine ?? 0) - line)) {
+      best = candidate;
+    }
+  }
+  return best;
+}
+
+/**
+ * Match hunks re-computed from an evolving change against the previous hunks
+ * 
+ * Hunks with identical +/- lines in the same file are the same change. Of the
+ * rest, a new hunk whose original-file range overlaps a previous hunk's range
+ * replaces it. The parent revision doesn't move while a change evolves, so
+ * original line numbers are comparable across both sets.
+ */
+export function matchHunks(previous: Hunk[], next: Hunk[]): HunkMatchResult {
+  const result: HunkMatchResult = { unchanged: [], changed: [], added: [], removed: [] };
+  const remaining = new Set(previous);
+  const unmatched: Hunk[] = [];
+
+  for (const hunk of next) {
+    const key = changeKey(hunk);
+    const match = closestHunk(hunk, [...remaining].filter((p) => changeKey(p) === key));
+    if (match) {
+      remaining.delete(match);
+      result.unchanged.push({ ...hunk, id: match.id, version: match.version });
+    } else {
+      unmatched.push(hunk);
+    }
+  }
+
+  for (const hunk of unmatched) {
+    const range = originalRange(hunk);
+    const candidates = range
+      ? [...remaining].filter((p) => {
+          const other = p.file === hunk.file ? originalRange(p) : null;
+          return other !== null && other.start < range.end && range.start < other.end;
+        })
+      : [];
+    const match = closestHunk(hunk, candidates);
+    if (match) {
+      remaining.delete(match);
+      result.changed.push({ ...hunk, id: match.id, version: match.version });
+    } else {
+      result.added.push(hunk);
+    }
+  }
+
+  result.removed = [...remaining];
+  return result;
+}
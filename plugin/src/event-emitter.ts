@@ -10,6 +10,10 @@
  * - level: "info" 
  * - message: JSON stringified event
  * - extra: { event: true, eventType: "<type>" }
+ *
+ * Given a store, events that change review state are also added to its event
+ * log and carry their sequence number ("seq"), so editors that reconnect can
+ * catch up on what they missed.
  */
 
 import type { createOpencodeClient } from "@opencode-ai/sdk";
@@ -19,76 +23,62 @@ import type {
   SuggestionErrorEvent,
   SuggestionStatusEvent,
   SuggestionHunkAppliedEvent,
+  SuggestionHunkRevertedEvent,
+  SuggestionCommentEvent,
+  SuggestionRevisionRequestedEvent,
+  SuggestionHunkRevisedEvent,
+  SuggestionUpdatedEvent,
+  SuggestionHunksChangedEvent,
+  SuggestionCheckEvent,
   SuggestionListEvent,
   Suggestion,
+  Hunk,
+  ReviewComment,
+  HunkVersion,
+  CheckResult,
 } from "./types.ts";
+import type { SuggestionStore } from "./suggestion-store.ts";
 import { broadcast } from "./http-server.ts";
 
 export type OpencodeClient = ReturnType<typeof createOpencodeClient>;
 
 const SERVICE_NAME = "codeforge";
 
-// Store for AI notifications (feedback received)
-interface AINotification {
-  timestamp: number;
-  suggestionId: string;
-  hunkId: string;
-  file: string;
-  action: "accepted" | "rejected" | "modified";
-  comment?: string;
-}
-
-// Recent notifications for AI to query
-const aiNotifications: AINotification[] = [];
-const MAX_NOTIFICATIONS = 100;
+// Progress and errors only matter to whoever is connected when they happen
+const UNSEQUENCED_EVENTS = new Set<SuggestionEvent["type"]>([
+  "suggestion.status",
+  "suggestion.error",
+  "suggestion.list",
+]);
 
 /**
  * Event emitter that sends events via OpenCode's log API
  */
 export class SuggestionEventEmitter {
   private client: OpencodeClient;
-
-  constructor(client: OpencodeClient) {
-    this.client = client;
-  }
-
-  /**
-   * Add a notification for the AI about feedback received
-   */
-  notifyAI(notification: Omit<AINotification, "timestamp">): void {
-    aiNotifications.push({
-      ...notification,
-      timestamp: Date.now(),
-    });
-    // Keep only recent notifications
-    while (aiNotifications.length > MAX_NOTIFICATIONS) {
-      aiNotifications.shift();
-    }
-  }
+  private store?: SuggestionStore;
+  private workingDirectory?: string;
 
   /**
-   * Get pending AI notifications (and optionally clear them)
+   * @param options.store Store whose event log numbers and keeps the events
+   * @param options.workingDirectory Only broadcast to clients subscribed to this directory
    */
-  static getAINotifications(clear: boolean = false): AINotification[] {
-    const notifications = [...aiNotifications];
-    if (clear) {
-      aiNotifications.length = 0;
-    }
-    return notifications;
-  }
-
-  /**
-   * Clear all AI notifications
-   */
-  static clearAINotifications(): void {
-    aiNotifications.length = 0;
+  constructor(client: OpencodeClient, options: { store?: SuggestionStore; workingDirectory?: string } = {}) {
+    this.client = client;
+    this.store = options.store;
+    this.workingDirectory = options.workingDirectory;
   }
 
   /**
    * Emit a suggestion event
    * @param workingDirectory If specified, only broadcast to clients subscribed to this directory
+   *   (defaults to the emitter's)
    */
-  async emit(event: SuggestionEvent, workingDirectory?: string): Promise<void> {
+  async emit(event: SuggestionEvent, workingDirectory = this.workingDirectory): Promise<void> {
+    if (this.store && !UNSEQUENCED_EVENTS.has(event.type)) {
+      event = { ...event, seq: this.store.appendEvent(event) } as SuggestionEvent;
+    }
+
     // Broadcast to WebSocket clients (filtered by working directory if specified)
     broadcast(event, workingDirectory);
     
@@ -171,13 +161,130 @@ export class SuggestionEventEmitter {
   async emitHunkApplied(
     suggestionId: string,
     hunkId: string,
-    action: SuggestionHunkAppliedEvent["action"]
+    action: SuggestionHunkAppliedEvent["action"],
+    remainingHunk?: Hunk
   ): Promise<void> {
     const event: SuggestionHunkAppliedEvent = {
       type: "suggestion.hunk_applied",
       suggestionId,
       hunkId,
       action,
+      remainingHunk,
+    };
+    await this.emit(event);
+  }
+
+  /**
+   * Emit suggestion.hunk_reverted event
+   */
+  async emitHunkReverted(
+    suggestionId: string,
+    hunk: Hunk,
+    previousAction: SuggestionHunkRevertedEvent["previousAction"],
+    replaces?: string
+  ): Promise<void> {
+    const event: SuggestionHunkRevertedEvent = {
+      type: "suggestion.hunk_reverted",
+      suggestionId,
+      hunkId: hunk.id,
+      previousAction,
+      hunk,
+      replaces,
+    };
+    await this.emit(event);
+  }
+
+  /**
+   * Emit suggestion.revision_requested event
+   */
+  async emitRevisionRequested(
+    suggestionId: string,
+    hunkId: string,
+    instruction: string,
+    stateVersion?: number
+  ): Promise<void> {
+    const event: SuggestionRevisionRequestedEvent = {
+      type: "suggestion.revision_requested",
+      suggestionId,
+      hunkId,
+      instruction,
+      stateVersion,
+    };
+    await this.emit(event);
+  }
+
+  /**
+   * Emit suggestion.hunk_revised event
+   */
+  async emitHunkRevised(suggestionId: string, hunk: Hunk, previous: HunkVersion): Promise<void> {
+    const event: SuggestionHunkRevisedEvent = {
+      type: "suggestion.hunk_revised",
+      suggestionId,
+      hunkId: hunk.id,
+      hunk,
+      previous,
+    };
+    await this.emit(event);
+  }
+
+  /**
+   * Emit suggestion.updated event
+   */
+  async emitUpdated(
+    suggestion: Suggestion,
+    changes: { added: string[]; changed: string[]; removed: string[] }
+  ): Promise<void> {
+    const event: SuggestionUpdatedEvent = {
+      type: "suggestion.updated",
+      suggestion: {
+        id: suggestion.id,
+        jjChangeId: suggestion.jjChangeId,
+        description: suggestion.description,
+        files: suggestion.files,
+        hunks: suggestion.hunks,
+        workingDirectory: suggestion.workingDirectory,
+      },
+      added: changes.added,
+      changed: changes.changed,
+      removed: changes.removed,
+    };
+    await this.emit(event, suggestion.workingDirectory);
+  }
+
+  /**
+   * Emit suggestion.hunks_changed event (pending hunks were split or merged)
+   */
+  async emitHunksChanged(suggestionId: string, replaced: string[], hunks: Hunk[]): Promise<void> {
+    const event: SuggestionHunksChangedEvent = {
+      type: "suggestion.hunks_changed",
+      suggestionId,
+      replaced,
+      hunks,
+    };
+    await this.emit(event);
+  }
+
+  /**
+   * Emit suggestion.comment event
+   */
+  async emitComment(comment: ReviewComment): Promise<void> {
+    const event: SuggestionCommentEvent = {
+      type: "suggestion.comment",
+      suggestionId: comment.suggestionId,
+      hunkId: comment.hunkId,
+      comment,
+    };
+    await this.emit(event);
+  }
+
+  /**
+   * Emit suggestion.check event
+   */
+  async emitCheck(check: CheckResult): Promise<void> {
+    const event: SuggestionCheckEvent = {
+      type: "suggestion.check",
+      suggestionId: check.suggestionId,
+      check,
     };
     await this.emit(event);
   }
@@ -215,6 +322,13 @@ export function parseLogEvent(logMessage: string, extra?: Record<string, unknown
       "suggestion.error", 
       "suggestion.status",
       "suggestion.hunk_applied",
+      "suggestion.hunk_reverted",
+      "suggestion.revision_requested",
+      "suggestion.hunk_revised",
+      "suggestion.updated",
+      "suggestion.hunks_changed",
+      "suggestion.comment",
+      "suggestion.check",
       "suggestion.list",
     ];
     
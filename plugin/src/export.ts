@@ -0,0 +1,250 @@
+/**
+ * Preference dataset export
+ *
+ * Turns review decisions into chosen/rejected pairs for preference tuning:
+ * the reviewer's edit over the AI's hunk, an accepted hunk over leaving the
+ * code alone (and the reverse for rejections), and the version the reviewer
+ * accepted over earlier ones they asked to change. Secrets are redacted and
+ * duplicate pairs dropped before the records are written as JSONL.
+ */
+
+import { createHash } from "crypto";
+import { existsSync, readFileSync } from "fs";
+import { join } from "path";
+import { extractHunkContent } from "./diff-parser.ts";
+import type { SuggestionStore } from "./suggestion-store.ts";
+import type { ExportQuery, FeedbackLogEntry, HunkVersion, PreferenceRecord } from "./types.ts";
+
+/**
+ * A review decision with what the export needs from its suggestion and hunk
+ */
+export interface ExportEntry {
+  timestamp: number;
+  suggestionId: string;
+  hunkId: string;
+  action: FeedbackLogEntry["action"];
+  file: string;
+  originalDiff?: string;
+  modifiedDiff?: string;
+  comment?: string;
+  /** Suggestion description (absent once the suggestion is removed) */
+  description?: string;
+  workingDirectory?: string;
+  /** Where the hunk started in the file the AI changed */
+  originalStartLine?: number;
+  /** File-level hunks (renames, deletions, binaries) have no text to compare */
+  fileLevel: boolean;
+  /** Versions the reviewer asked to change before deciding, oldest first */
+  versions: HunkVersion[];
+}
+
+// File lines on each side of a hunk unless the query says otherwise
+const DEFAULT_CONTEXT_LINES = 20;
+
+const REDACTED = "[REDACTED]";
+
+// Credentials that have a recognizable shape of their own
+const SECRET_PATTERNS: RegExp[] = [
+  /-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----/g,
+  /\b(?:AKIA|ASIA)[0-9A-Z]{16}\b/g,
+  /\bgh[pousr]_[A-Za-z0-9]{36,}\b/g,
+  /\bgithub_pat_[A-Za-z0-9_]{22,}\b/g,
+  /\bxox[abprs]-[A-Za-z0-9-]{10,}\b/g,
+  /\bsk-[A-Za-z0-9_-]{20,}\b/g,
+  /\bAIza[0-9A-Za-z_-]{35}\b/g,
+  /\beyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}\b/g,
+];
+
+// Values assigned to something named like a secret, e.g. `api_key = "..."`
+const SECRET_ASSIGNMENT =
+  /((?:api[_-]?key|secret|token|passw(?:or)?d|credentials?)\w*["']?\s*[:=]\s*["']?)([^\s"',;()]{8,})(?![\w(])/gi;
+
+// Bearer and basic credentials in headers or URLs
+const SECRET_CREDENTIALS = [/(\bBearer\s+)([A-Za-z0-9._~+/-]{16,}=*)/g, /(\/\/[^/\s:@]+:)([^/\s@]+)(?=@)/g];
+
+/**
+ * Replace anything that looks like a credential with [REDACTED]
+ */
+export function redactSecrets(text: string): string {
+  let redacted = text;
+  for (const pattern of SECRET_PATTERNS) {
+    redacted = redacted.replace(pattern, REDACTED);
+  }
+  for (const pattern of [SECRET_ASSIGNMENT, ...SECRET_CREDENTIALS]) {
+    redacted = redacted.replace(pattern, (match, prefix: string, value: string) =>
+      value === REDACTED ? match : `${prefix}${REDACTED}`
+    );
+  }
+  return redacted;
+}
+
+/**
+ * Find a block of lines in a file, the occurrence nearest the given line
+ * @returns 0-based index of its first line, or undefined if it isn't there
+ */
+function findBlock(fileLines: string[], block: string[], nearLine?: number): number | undefined {
+  if (block.length === 0) return undefined;
+  let best: number | undefined;
+  for (let i = 0; i + block.length <= fileLines.length; i++) {
+    if (block.every((line, j) => fileLines[i + j] === line)) {
+      if (nearLine === undefined) return i;
+      if (best === undefined || Math.abs(i + 1 - nearLine) < Math.abs(best + 1 - nearLine)) {
+        best = i;
+      }
+    }
+  }
+  return best;
+}
+
+/**
+ * Lines around a hunk in the file as it is now
+ * The file holds whatever the decision left there (the AI's lines, the
+ * reviewer's or the original ones); if later changes moved that block out of
+ * recognition there is no window.
+ */
+function contextWindow(
+  entry: ExportEntry,
+  current: string[],
+  contextLines: number,
+  readFile: (file: string) => string | undefined
+): { before: string[]; after: string[] } {
+  const none = { before: [], after: [] };
+  if (contextLines <= 0) return none;
+
+  const content = readFile(entry.file);
+  if (content === undefined) return none;
+
+  const fileLines = content.split("\n");
+  const start = findBlock(fileLines, current, entry.originalStartLine);
+  if (start === undefined) return none;
+
+  const end = start + current.length;
+  return {
+    before: fileLines.slice(Math.max(0, start - contextLines), start),
+    after: fileLines.slice(end, end + contextLines),
+  };
+}
+
+/**
+ * Redact a block of lines as a whole, so secrets spanning lines are caught
+ */
+function redactLines(lines: string[]): string[] {
+  return lines.length === 0 ? [] : redactSecrets(lines.join("\n")).split("\n");
+}
+
+/**
+ * Read project files, or none without a project directory
+ */
+function projectFileReader(projectDir?: string): (file: string) => string | undefined {
+  return (file) => {
+    if (!projectDir) return undefined;
+    const path = join(projectDir, file);
+    try {
+      return existsSync(path) ? readFileSync(path, "utf-8") : undefined;
+    } catch {
+      return undefined;
+    }
+  };
+}
+
+/**
+ * Build the preference pairs for review decisions
+ * Pairs whose completions are the same are skipped, as are file-level hunks.
+ * @param projectDir Where the context windows are read from (none without it)
+ * @param readFile Reads a file of the project by its relative path (tests pass their own)
+ */
+export function buildPreferenceRecords(
+  entries: ExportEntry[],
+  query: ExportQuery = {},
+  projectDir?: string,
+  readFile: (file: string) => string | undefined = projectFileReader(projectDir)
+): PreferenceRecord[] {
+  const contextLines = query.contextLines ?? DEFAULT_CONTEXT_LINES;
+  const records: PreferenceRecord[] = [];
+  const seen = new Set<string>();
+
+  for (const entry of entries) {
+    if (entry.fileLevel || !entry.originalDiff?.startsWith("@@")) continue;
+
+    const proposed = extractHunkContent(entry.originalDiff);
+    const modified = entry.action === "modify" && entry.modifiedDiff ? extractHunkContent(entry.modifiedDiff).modified : undefined;
+    // What the decision left in the file
+    const kept = entry.action === "reject" ? proposed.original : modified ?? proposed.modified;
+    const context = contextWindow(entry, kept, contextLines, readFile);
+
+    const pairs: Array<{ source: PreferenceRecord["source"]; chosen: string[]; rejected: string[]; instruction?: string }> = [];
+    if (entry.action === "modify" && modified) {
+      pairs.push({ source: "modified", chosen: modified, rejected: proposed.modified, instruction: entry.comment });
+    } else if (entry.action === "accept") {
+      pairs.push({ source: "accepted", chosen: proposed.modified, rejected: proposed.original, instruction: entry.comment });
+    } else if (entry.action === "reject") {
+      pairs.push({ source: "rejected", chosen: proposed.original, rejected: proposed.modified, instruction: entry.comment });
+    }
+    if (entry.action !== "reject") {
+      for (const version of entry.versions) {
+        if (!version.diff.startsWith("@@")) continue;
+        pairs.push({ source: "revised", chosen: kept, rejected: extractHunkContent(version.diff).modified, instruction: version.instruction });
+      }
+    }
+
+    for (const pair of pairs) {
+      const chosen = redactSecrets(pair.chosen.join("\n"));
+      const rejected = redactSecrets(pair.rejected.join("\n"));
+      if (chosen === rejected) continue;
+
+      const prompt: PreferenceRecord["prompt"] = {
+        file: entry.file,
+        description: entry.description === undefined ? undefined : redactSecrets(entry.description),
+        instruction: pair.instruction === undefined ? undefined : redactSecrets(pair.instruction),
+        contextBefore: redactLines(context.before),
+        original: redactLines(proposed.original),
+        contextAfter: redactLines(context.after),
+      };
+      const id = createHash("sha256")
+        .update(JSON.stringify([prompt, chosen, rejected]))
+        .digest("hex")
+        .slice(0, 16);
+      if (seen.has(id)) continue;
+      seen.add(id);
+
+      records.push({
+        id,
+        source: pair.source,
+        prompt,
+        chosen,
+        rejected,
+        suggestionId: entry.suggestionId,
+        hunkId: entry.hunkId,
+        timestamp: entry.timestamp,
+        workingDirectory: entry.workingDirectory,
+      });
+    }
+  }
+
+  return records;
+}
+
+/**
+ * Preference records of several projects, dropping duplicates across them
+ * @param projects Project directory and its store
+ */
+export function exportProjects(projects: Iterable<[string, SuggestionStore]>, query: ExportQuery = {}): PreferenceRecord[] {
+  const records: PreferenceRecord[] = [];
+  const seen = new Set<string>();
+  for (const [projectDir, store] of projects) {
+    const entries = store.getExportEntries(query.since, query.until);
+    for (const record of buildPreferenceRecords(entries, query, projectDir)) {
+      if (seen.has(record.id)) continue;
+      seen.add(record.id);
+      records.push(record);
+    }
+  }
+  return records;
+}
+
+/**
+ * One JSON record per line
+ */
+export function toJsonl(records: PreferenceRecord[]): string {
+  return records.map((record) => JSON.stringify(record) + "\n").join("");
+}
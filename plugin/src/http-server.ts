@@ -7,63 +7,161 @@
  * 
  * WebSocket Protocol:
  * 
- * Client -> Server:
- *   {"type": "feedback", "suggestionId": "...", "hunkId": "...", "action": "accept|reject|modify", "modifiedDiff"?: "...", "comment"?: "...", "workingDirectory": "..."}
+ * Client -> Server (every command carries an "id", echoed in its response):
+ *   {"type": "feedback", "suggestionId": "...", "hunkId": "...", "action": "accept|reject|modify|request_changes", "modifiedDiff"?: "...", "comment"?: "...", "partial"?: true, "workingDirectory": "..."}
+ *   {"type": "feedback", "suggestionId": "...", "hunkId"?: "...", "action": "undo", "workingDirectory": "..."}
+ *   {"type": "feedback_batch", "suggestionId": "...", "decisions": [{"hunkId": "...", "action": "accept|reject|modify", ...}], "workingDirectory": "..."}
+ *   {"type": "comment", "suggestionId": "...", "hunkId": "...", "body": "...", "workingDirectory": "..."}
+ *   {"type": "split", "suggestionId": "...", "hunkId": "...", "line"?: N, "workingDirectory": "..."}
+ *   {"type": "merge", "suggestionId": "...", "hunkIds": ["...", "..."], "workingDirectory": "..."}
  *   {"type": "complete", "suggestionId": "...", "action": "finalize|discard", "workingDirectory": "..."}
+ *   {"type": "run_checks", "suggestionId": "...", "workingDirectory": "..."}
  *   {"type": "list", "workingDirectory": "..."}
  *   {"type": "get", "suggestionId": "...", "workingDirectory": "..."}
+ *   {"type": "search", "workingDirectory": "...", "query"?: "...", "file"?: "...", "action"?: "...", "since"?: ..., "until"?: ..., "jjChangeId"?: "...", "limit"?: N}
+ *   {"type": "stats", "workingDirectory": "...", "since"?: ..., "until"?: ..., "interval"?: "day|week|month", "limit"?: N}
+ *   {"type": "projects"}
+ *   {"type": "subscribe", "workingDirectory": "...", "protocolVersion"?: N, "clientName"?: "...", "sinceSeq"?: N}
+ *   {"type": "presence", "suggestionId"?: "...", "hunkId"?: "..."}
+ * 
+ * Messages are validated against protocol.schema.json (generated from the
+ * ClientCommand type); invalid ones get {"success": false, "code":
+ * "invalid_message", "errors": [{"path": "$.hunkId", "message": "..."}]}.
+ * subscribe agrees on the highest protocol version both sides speak and
+ * returns it as "protocolVersion" ("code": "unsupported_version" if none).
+ * 
+ * Events that change review state carry "seq", their number in the working
+ * directory's event log. subscribe returns the latest "seq"; with "sinceSeq"
+ * it is followed by the events after that ("replayed": N), or by a
+ * suggestion.snapshot when they are no longer all kept ("snapshot": true).
+ * 
+ * Feedback may carry "stateVersion" (the Hunk.stateVersion the reviewer saw).
+ * If the hunk changed or was decided since, it is refused with
+ * {"success": false, "code": "conflict", ...} (HTTP 409).
+ * 
+ * Transport:
+ *   TCP on host:port, or a Unix socket (config.unix) whose file mode and group
+ *   decide who may connect. The API is the same on both.
+ * 
+ * Authentication:
+ *   When a token is configured, every REST route and the /ws upgrade require
+ *   `Authorization: Bearer <token>` or `?token=<token>`. Failures get a 401
+ *   with {"success": false, "code": "unauthorized", "error": "..."}.
  * 
  * Server -> Client:
+ *   {"type": "connected", "message": "...", "protocolVersion": N}
  *   {"type": "suggestion.ready", "suggestion": {...}}
  *   {"type": "suggestion.hunk_applied", "suggestionId": "...", "hunkId": "...", "action": "accepted|rejected|modified"}
+ *   {"type": "suggestion.hunk_reverted", "suggestionId": "...", "hunkId": "...", "previousAction": "accepted|rejected|modified", "hunk": {...}}
+ *   {"type": "suggestion.revision_requested", "suggestionId": "...", "hunkId": "...", "instruction": "..."}
+ *   {"type": "suggestion.hunk_revised", "suggestionId": "...", "hunkId": "...", "hunk": {...}, "previous": {...}}
+ *   {"type": "suggestion.updated", "suggestion": {...}, "added": [...], "changed": [...], "removed": [...]}
+ *   {"type": "suggestion.hunks_changed", "suggestionId": "...", "replaced": [...], "hunks": [...]}
+ *   {"type": "suggestion.comment", "suggestionId": "...", "hunkId": "...", "comment": {...}}
+ *   {"type": "suggestion.check", "suggestionId": "...", "check": {"name": "...", "status": "running|passed|failed|error", "diagnostics": [...], ...}}
  *   {"type": "suggestion.status", "status": "...", "message": "...", "suggestionId"?: "..."}
  *   {"type": "suggestion.error", "code": "...", "message": "...", "suggestionId"?: "...", "hunkId"?: "..."}
+ *   {"type": "suggestion.presence", "clients": [{"clientId": "...", "name"?: "...", "suggestionId"?: "...", "hunkId"?: "..."}]}
+ *   {"type": "suggestion.snapshot", "seq": N, "suggestions": [{...}]}
  *   {"type": "suggestion.list", "suggestions": [...]}
- *   {"type": "response", "id": "...", "success": true|false, "data"?: {...}, "error"?: "..."}
+ *   {"type": "response", "id": "...", "success": true|false, "error"?: "...", "code"?: "...", ...}
  */
 
 import type { ServerWebSocket } from "bun";
 import type { createOpencodeClient } from "@opencode-ai/sdk";
 import { SuggestionStore } from "./suggestion-store.ts";
 import { SuggestionEventEmitter } from "./event-emitter.ts";
-import type { HunkFeedback, SuggestionComplete, FeedbackResult } from "./types.ts";
+import { startChecks, type CheckEnvironment } from "./checks.ts";
+import { retryDueNotifications, sendNotification } from "./notifications.ts";
+import { computeReviewStats } from "./stats.ts";
+import { exportProjects, toJsonl } from "./export.ts";
+import { applySocketPermissions, DEFAULT_SOCKET_MODE } from "./socket.ts";
+import { ensureTokenFile, extractRequestToken, getTokenPath, tokensMatch, unauthorizedResponse } from "./auth.ts";
+import { reverseHunk, reverseFileChange } from "./patch-applier.ts";
+import { splitHunkSelection, parseHunkHeader, extractHunkContent } from "./diff-parser.ts";
+import { PROTOCOL_VERSION, MIN_PROTOCOL_VERSION, negotiateVersion, validateMessage, describeErrors } from "./protocol.ts";
+import type { Hunk, Suggestion, ReviewerPresence, SuggestionPresenceEvent, BatchDecision, HunkFeedback, HunkFeedbackBatch, HunkUndo, HunkComment, HunkSplit, HunkMerge, HunksChangedResult, ReviewComment, SuggestionComplete, FeedbackResult, FeedbackBatchResult, UndoResult, HistoryQuery, StatsQuery, ExportQuery, ProjectSummary } from "./types.ts";
 
 type OpencodeClient = ReturnType<typeof createOpencodeClient>;
 
 export interface HttpServerConfig {
   port: number;
   host?: string;
+  /** Shared secret required on every request (auth disabled if omitted) */
+  token?: string;
+  /** Listen on this Unix socket instead of host/port */
+  unix?: string;
+  /** File mode of the socket (default: 660) */
+  socketMode?: number;
+  /** Group the socket is handed to (default: the server's) */
+  socketGroup?: number;
 }
 
 export interface HttpServerDeps {
   stores: Map<string, SuggestionStore>;
   emitters: Map<string, SuggestionEventEmitter>;
-  client: OpencodeClient;
+  /** Check pipeline per working directory, with the VCS and runner it uses */
+  checks: Map<string, CheckEnvironment>;
+  /** OpenCode client, or one per working directory when projects belong to different instances (daemon) */
+  client: OpencodeClient | ((workingDirectory: string) => OpencodeClient);
 }
 
 // WebSocket client data
 interface WSClientData {
   id: string;
   workingDirectory?: string;
+  /** Name given on subscribe */
+  name?: string;
+  /** Protocol version agreed on subscribe */
+  protocolVersion?: number;
+  /** What the editor is looking at (presence) */
+  suggestionId?: string;
+  hunkId?: string;
 }
 
+// Most missed events replayed on subscribe; editors further behind get a snapshot
+const MAX_REPLAY_EVENTS = 500;
+
+// How often undelivered AI notifications are checked for a due retry
+const NOTIFICATION_RETRY_INTERVAL_MS = 5_000;
+
 // Connected WebSocket clients
 const wsClients = new Set<ServerWebSocket<WSClientData>>();
 
+/**
+ * Normalize a working directory path
+ * Converts relative paths (relative to home, as editors send them) to absolute paths and normalizes them
+ */
+function normalizeWorkingDirectory(workingDir: string): string {
+  if (!workingDir || workingDir.trim() === "") {
+    return process.env.HOME || process.env.USERPROFILE || "/tmp";
+  }
+
+  // If it's already absolute, return it normalized
+  if (workingDir.startsWith("/")) {
+    return workingDir.replace(/\/+$/, ""); // Remove trailing slashes
+  }
+
+  // It's relative, convert to absolute
+  const homeDir = process.env.HOME || process.env.USERPROFILE || "";
+  const absolutePath = `${homeDir}/${workingDir}`.replace(/\/+$/, "");
+  return absolutePath;
+}
+
 /**
  * Broadcast a message to all connected WebSocket clients
  * If workingDirectory is specified, only send to clients subscribed to that directory
  */
 export function broadcast(message: object, workingDirectory?: string): void {
   const data = JSON.stringify(message);
+  const normalizedFilter = workingDirectory ? normalizeWorkingDirectory(workingDirectory) : undefined;
   for (const client of wsClients) {
     try {
       // If workingDirectory filter is specified, only send to matching clients
-      if (workingDirectory) {
-        // Normalize both for comparison
-        const normalizedFilter = workingDirectory.replace(/\/+$/, "");
-        const normalizedClient = (client.data.workingDirectory || "").replace(/\/+$/, "");
-        if (normalizedClient === normalizedFilter) {
+      if (normalizedFilter) {
+        // Relative and absolute spellings of the same directory match
+        const subscribed = client.data.workingDirectory;
+        if (subscribed && normalizeWorkingDirectory(subscribed) === normalizedFilter) {
           client.send(data);
         }
       } else {
@@ -76,6 +174,125 @@ export function broadcast(message: object, workingDirectory?: string): void {
   }
 }
 
+/**
+ * Who is connected to a working directory and what they are looking at
+ */
+export function getPresence(workingDirectory: string): ReviewerPresence[] {
+  const normalizedFilter = workingDirectory.replace(/\/+$/, "");
+  return [...wsClients]
+    .filter((client) => (client.data.workingDirectory || "").replace(/\/+$/, "") === normalizedFilter)
+    .map((client) => ({
+      clientId: client.data.id,
+      name: client.data.name,
+      suggestionId: client.data.suggestionId,
+      hunkId: client.data.hunkId,
+    }));
+}
+
+/**
+ * Send the presence of a working directory to its subscribers
+ */
+function broadcastPresence(workingDirectory: string | undefined): void {
+  if (!workingDirectory) return;
+  const event: SuggestionPresenceEvent = { type: "suggestion.presence", clients: getPresence(workingDirectory) };
+  broadcast(event, workingDirectory);
+}
+
+/**
+ * Read a text field of a WebSocket message or a query parameter (undefined if empty)
+ */
+function queryText(get: (key: string) => unknown, key: string): string | undefined {
+  const value = get(key);
+  return value === undefined || value === null || value === "" ? undefined : String(value);
+}
+
+/**
+ * Read a date: a millisecond timestamp or anything Date.parse understands
+ * Returns an error message if it is neither.
+ */
+function queryTime(get: (key: string) => unknown, key: string): number | undefined | string {
+  const value = queryText(get, key);
+  if (value === undefined) return undefined;
+  const ms = /^\d+$/.test(value) ? Number(value) : Date.parse(value);
+  return Number.isNaN(ms) ? `Invalid ${key}: ${value}` : ms;
+}
+
+/**
+ * Read an integer (undefined if absent, an error message if invalid)
+ */
+function queryInteger(get: (key: string) => unknown, key: string): number | undefined | string {
+  const text = queryText(get, key);
+  const value = text === undefined ? undefined : Number(text);
+  if (value !== undefined && !Number.isInteger(value)) {
+    return `Invalid ${key}: ${text}`;
+  }
+  return value;
+}
+
+/**
+ * Build a history query from WebSocket message fields or /history query parameters
+ * Dates may be millisecond timestamps or anything Date.parse understands.
+ * Returns an error message if a filter is invalid.
+ */
+function parseHistoryQuery(get: (key: string) => unknown): HistoryQuery | string {
+  const action = queryText(get, "action");
+  if (action !== undefined && !["accept", "reject", "modify"].includes(action)) {
+    return "Invalid action. Must be: accept, reject, or modify";
+  }
+
+  const since = queryTime(get, "since");
+  if (typeof since === "string") return since;
+  const until = queryTime(get, "until");
+  if (typeof until === "string") return until;
+  const limit = queryInteger(get, "limit");
+  if (typeof limit === "string") return limit;
+
+  return {
+    query: queryText(get, "query"),
+    file: queryText(get, "file"),
+    action: action as HistoryQuery["action"],
+    since,
+    until,
+    jjChangeId: queryText(get, "jjChangeId"),
+    limit,
+  };
+}
+
+/**
+ * Build a stats query from WebSocket message fields or /stats query parameters
+ * Returns an error message if a filter is invalid.
+ */
+function parseStatsQuery(get: (key: string) => unknown): StatsQuery | string {
+  const interval = queryText(get, "interval");
+  if (interval !== undefined && !["day", "week", "month"].includes(interval)) {
+    return "Invalid interval. Must be: day, week, or month";
+  }
+
+  const since = queryTime(get, "since");
+  if (typeof since === "string") return since;
+  const until = queryTime(get, "until");
+  if (typeof until === "string") return until;
+  const limit = queryInteger(get, "limit");
+  if (typeof limit === "string") return limit;
+
+  return { since, until, interval: interval as StatsQuery["interval"], limit };
+}
+
+/**
+ * Build an export query from /export query parameters
+ * Returns an error message if a filter is invalid.
+ */
+function parseExportQuery(get: (key: string) => unknown): ExportQuery | string {
+  const since = queryTime(get, "since");
+  if (typeof since === "string") return since;
+  const until = queryTime(get, "until");
+  if (typeof until === "string") return until;
+  const contextLines = queryInteger(get, "contextLines");
+  if (typeof contextLines === "string") return contextLines;
+
+  return { since, until, contextLines };
+}
+
 /**
  * Create and start the HTTP + WebSocket server
  */
@@ -83,26 +300,13 @@ export function createHttpServer(
   config: HttpServerConfig,
   deps: HttpServerDeps
 ): ReturnType<typeof Bun.serve> {
-  const { stores, emitters, client } = deps;
+  const { stores, emitters, checks, client } = deps;
 
   /**
-   * Normalize a working directory path
-   * Converts relative paths to absolute paths and normalizes them
+   * The OpenCode instance a project belongs to
    */
-  function normalizeWorkingDirectory(workingDir: string): string {
-    if (!workingDir || workingDir.trim() === "") {
-      return process.env.HOME || process.env.USERPROFILE || "/tmp";
-    }
-
-    // If it's already absolute, return it normalized
-    if (workingDir.startsWith("/")) {
-      return workingDir.replace(/\/+$/, ""); // Remove trailing slashes
-    }
-
-    // It's relative, convert to absolute
-    const homeDir = process.env.HOME || process.env.USERPROFILE || "";
-    const absolutePath = `${homeDir}/${workingDir}`.replace(/\/+$/, "");
-    return absolutePath;
+  function clientFor(workingDirectory: string): OpencodeClient {
+    return typeof client === "function" ? client(normalizeWorkingDirectory(workingDirectory)) : client;
   }
 
   /**
@@ -131,10 +335,15 @@ export function createHttpServer(
           dbPath: dbPath,
           feedbackLogPath: `${normalized}/.opencode/suggestion-feedback.jsonl`,
         });
-        emitter = new SuggestionEventEmitter(client);
+        emitter = new SuggestionEventEmitter(clientFor(normalized), { store, workingDirectory: normalized });
         
         stores.set(normalized, store);
         emitters.set(normalized, emitter);
+
+        // Share the server token with editors opened in this project
+        if (config.token) {
+          ensureTokenFile(getTokenPath(normalized), config.token);
+        }
         
         console.log(`[HTTP Server] Successfully ${dbExists ? "loaded existing" : "created new"} store for: ${normalized}`);
       } catch (error) {
@@ -148,45 +357,77 @@ export function createHttpServer(
   }
 
   /**
-   * Notify the AI about feedback by injecting a message into the current session
+   * Every project with a store (all registered ones under the daemon) and its
+   * pending suggestions; projects whose database is unreachable are left out
    */
-  async function notifyAI(message: string): Promise<void> {
-    try {
-      const sessions = await client.session.list();
-      if (!sessions.data || sessions.data.length === 0) {
-        return;
-      }
-      
-      // Find the main session (not a subagent) - subagent titles contain "@"
-      // Also prefer sessions without a parentID (top-level sessions)
-      const mainSession = sessions.data.find(s => 
-        s.title && !s.title.includes("@") && !s.parentID
-      ) || sessions.data.find(s => 
-        s.title && !s.title.includes("@")
-      ) || sessions.data[sessions.data.length - 1]; // fallback to oldest
-      
-      if (!mainSession?.id) {
-        return;
-      }
+  function listProjects(): ProjectSummary[] {
+    return [...stores]
+      .filter(([, store]) => store.isDbHealthy())
+      .map(([workingDirectory, store]) => ({
+        workingDirectory,
+        suggestions: store.listSuggestions(workingDirectory).suggestions,
+      }))
+      .sort((a, b) => a.workingDirectory.localeCompare(b.workingDirectory));
+  }
 
-      // Inject message into the conversation
-      await client.session.prompt({
-        path: { id: mainSession.id },
-        body: {
-          noReply: true,
-          parts: [{ type: "text", text: message }],
-        },
-      });
-      
-      // Also show a toast notification
-      await client.tui.showToast({
-        body: { message, variant: "info" },
+  /**
+   * A suggestion with its hunk states, threads and checks (get, snapshots)
+   */
+  function suggestionDetails(store: SuggestionStore, suggestion: Suggestion): Record<string, unknown> {
+    // Convert Map to object
+    const hunkStates: Record<string, unknown> = {};
+    for (const [key, value] of suggestion.hunkStates) {
+      hunkStates[key] = value;
+    }
+    return {
+      ...suggestion,
+      hunkStates,
+      remainingCount: store.getRemainingCount(suggestion.id),
+      threads: store.getThreads(suggestion.id),
+      checks: store.getChecks(suggestion.id),
+    };
+  }
+
+  /**
+   * Tell the AI about feedback, in the session that published the suggestion
+   * It is stored first: what can't be delivered now is retried, and can be
+   * pulled with the get_review_feedback tool.
+   */
+  async function notifyAI(
+    body: { workingDirectory: string; suggestionId: string; hunkId?: string },
+    message: string
+  ): Promise<void> {
+    const result = getStoreAndEmitter(body.workingDirectory);
+    if (!result) return;
+    try {
+      await sendNotification(result.store, clientFor(body.workingDirectory), {
+        suggestionId: body.suggestionId,
+        hunkId: body.hunkId,
+        message,
       });
-    } catch {
-      // Silently fail - notification is best-effort
+    } catch (error) {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.warn(`[HTTP Server] Failed to store notification for ${body.suggestionId}:`, errorMsg);
     }
   }
 
+  // Retry notifications that didn't reach the AI (started once the server is up)
+  let retryingNotifications = false;
+  async function retryNotifications(): Promise<void> {
+    if (retryingNotifications) return;
+    retryingNotifications = true;
+    for (const [workingDirectory, store] of stores) {
+      if (!store.isDbHealthy()) continue;
+      try {
+        await retryDueNotifications(store, clientFor(workingDirectory));
+      } catch (error) {
+        const errorMsg = error instanceof Error ? error.message : String(error);
+        console.warn(`[HTTP Server] Failed to retry notifications for ${workingDirectory}:`, errorMsg);
+      }
+    }
+    retryingNotifications = false;
+  }
+
   /**
    * Process feedback (shared between HTTP and WebSocket)
    * 
@@ -215,20 +456,78 @@ export function createHttpServer(
 
       const hunk = store.getHunk(body.suggestionId, body.hunkId);
       if (!hunk) {
+        // Another editor may have got there first
+        const decision = store.getDecision(body.suggestionId, body.hunkId);
+        if (decision) {
+          const remainingHunks = store.getRemainingCount(body.suggestionId);
+          return { success: false, applied: false, remainingHunks, code: "conflict", error: `Hunk ${body.hunkId} was already decided (${decision.action})` };
+        }
         return { success: false, applied: false, remainingHunks: 0, error: `Hunk not found: ${body.hunkId}` };
       }
 
+      // Optimistic locking: refuse feedback based on a state that has moved on
+      if (body.stateVersion !== undefined && body.stateVersion !== hunk.stateVersion) {
+        return {
+          success: false,
+          applied: false,
+          remainingHunks: store.getRemainingCount(body.suggestionId),
+          code: "conflict",
+          error: `Hunk ${body.hunkId} changed since you saw it (state version ${body.stateVersion}, now ${hunk.stateVersion})`,
+          currentHunk: hunk,
+        };
+      }
+
+      // Not a decision: leave the hunk up for review and ask the AI for a new version
+      if (body.action === "request_changes") {
+        if (!body.comment) {
+          return { success: false, applied: false, remainingHunks: 0, error: "request_changes requires a comment with the instruction" };
+        }
+        store.updateHunkState(body.suggestionId, body.hunkId, body, false);
+        const stateVersion = store.getHunk(body.suggestionId, body.hunkId)?.stateVersion;
+        await emitter.emitRevisionRequested(body.suggestionId, body.hunkId, body.comment, stateVersion);
+
+        const remaining = store.getRemainingCount(body.suggestionId);
+        await notifyAI(
+          body,
+          `[Suggestion Feedback] User requested changes to hunk ${body.hunkId} in ${hunk.file} (version ${hunk.version ?? 1}): "${body.comment}". ` +
+          `Publish a new version with the revise_hunk tool.`
+        );
+
+        return { success: true, applied: false, remainingHunks: remaining };
+      }
+
+      // Partial decision: work out what is left of the hunk after the selected lines
+      let remainingDiff: string | null = null;
+      if (body.partial) {
+        if (body.action !== "modify" && body.action !== "reject") {
+          return { success: false, applied: false, remainingHunks: 0, error: "Only modify and reject can decide part of a hunk" };
+        }
+        if (!body.modifiedDiff || hunk.fileChange) {
+          return { success: false, applied: false, remainingHunks: 0, error: "A partial decision needs the selected lines of a content hunk as modifiedDiff" };
+        }
+        const split = splitHunkSelection(hunk.diff, body.modifiedDiff, body.action === "modify" ? "accept" : "reject");
+        if (!split) {
+          return { success: false, applied: false, remainingHunks: 0, error: "modifiedDiff is not a selection of the hunk's lines" };
+        }
+        remainingDiff = split.remaining;
+      }
+
       // Determine the result based on action
       // Note: actual file changes are applied client-side, we just track state here
       const applied = body.action === "accept" || body.action === "modify";
       const reverted = body.action === "reject";
 
       // Update the store
-      store.updateHunkState(body.suggestionId, body.hunkId, body, applied || reverted);
+      let remainingHunk: Hunk | undefined;
+      if (remainingDiff) {
+        remainingHunk = store.decidePartially(body.suggestionId, body.hunkId, body, applied || reverted, remainingDiff);
+      } else {
+        store.updateHunkState(body.suggestionId, body.hunkId, body, applied || reverted);
+      }
 
       // Emit events (these will be broadcast to WebSocket clients)
       const action = body.action === "accept" ? "accepted" : body.action === "reject" ? "rejected" : "modified";
-      await emitter.emitHunkApplied(body.suggestionId, body.hunkId, action);
+      await emitter.emitHunkApplied(body.suggestionId, body.hunkId, action, remainingHunk);
 
       const remaining = store.getRemainingCount(body.suggestionId);
       await emitter.emitStatus(remaining === 0 ? "applied" : "partial", `${remaining} hunks remaining`, body.suggestionId);
@@ -237,9 +536,10 @@ export function createHttpServer(
       const actionVerb = body.action === "accept" ? "accepted" : body.action === "reject" ? "rejected" : "modified";
       const commentPart = body.comment ? ` Comment: "${body.comment}"` : "";
       const modifyInfo = body.action === "modify" && body.modifiedDiff ? `\nModified diff:\n${body.modifiedDiff}` : "";
-      await notifyAI(`[Suggestion Feedback] User ${actionVerb} hunk in ${hunk.file}. ${remaining} hunks remaining.${commentPart}${modifyInfo}`);
+      const partInfo = remainingHunk ? " some lines of a" : "";
+      await notifyAI(body, `[Suggestion Feedback] User ${actionVerb}${partInfo} hunk in ${hunk.file}. ${remaining} hunks remaining.${commentPart}${modifyInfo}`);
 
-      return { success: true, applied, reverted, remainingHunks: remaining };
+      return { success: true, applied, reverted, remainingHunks: remaining, remainingHunk };
     } catch (error) {
       const errorMsg = error instanceof Error ? error.message : String(error);
       console.error(`[HTTP Server] Error processing feedback:`, errorMsg);
@@ -247,6 +547,375 @@ export function createHttpServer(
     }
   }
 
+  /**
+   * Process several decisions on one suggestion (shared between HTTP and WebSocket)
+   * 
+   * All or nothing: every hunk is checked before any is decided, and the
+   * decisions are recorded in one transaction. Editors get the usual
+   * hunk_applied events, but a single status event, and the AI a single
+   * notification summing up the batch.
+   */
+  async function processFeedbackBatch(body: HunkFeedbackBatch & { workingDirectory: string }): Promise<FeedbackBatchResult> {
+    try {
+      const result = getStoreAndEmitter(body.workingDirectory);
+      if (!result) {
+        return { success: false, remainingHunks: 0, error: `No store found for working directory: ${body.workingDirectory}` };
+      }
+
+      const { store, emitter } = result;
+
+      if (!store.isDbHealthy()) {
+        return { success: false, remainingHunks: 0, error: `Database is not accessible. Path: ${store.getDbPath()}` };
+      }
+
+      const suggestion = store.getSuggestion(body.suggestionId);
+      if (!suggestion) {
+        return { success: false, remainingHunks: 0, error: `Suggestion not found: ${body.suggestionId}` };
+      }
+
+      if (body.decisions.length === 0) {
+        return { success: false, remainingHunks: store.getRemainingCount(body.suggestionId), error: "No decisions in the batch" };
+      }
+
+      // Check every hunk before deciding any
+      const seen = new Set<string>();
+      const checked: Array<{ decision: BatchDecision; hunk: Hunk }> = [];
+      for (const decision of body.decisions) {
+        const refuse = (error: string, extra: Partial<FeedbackBatchResult> = {}): FeedbackBatchResult => ({
+          success: false,
+          remainingHunks: store.getRemainingCount(body.suggestionId),
+          hunkId: decision.hunkId,
+          error,
+          ...extra,
+        });
+
+        if (seen.has(decision.hunkId)) {
+          return refuse(`Hunk ${decision.hunkId} is decided twice in the batch`);
+        }
+        seen.add(decision.hunkId);
+
+        const hunk = store.getHunk(body.suggestionId, decision.hunkId);
+        if (!hunk) {
+          const decided = store.getDecision(body.suggestionId, decision.hunkId);
+          if (decided) {
+            return refuse(`Hunk ${decision.hunkId} was already decided (${decided.action})`, { code: "conflict" });
+          }
+          return refuse(`Hunk not found: ${decision.hunkId}`);
+        }
+        if (decision.stateVersion !== undefined && decision.stateVersion !== hunk.stateVersion) {
+          return refuse(
+            `Hunk ${decision.hunkId} changed since you saw it (state version ${decision.stateVersion}, now ${hunk.stateVersion})`,
+            { code: "conflict", currentHunk: hunk }
+          );
+        }
+        if (decision.action === "modify" && !decision.modifiedDiff) {
+          return refuse(`modify needs a modifiedDiff (hunk ${decision.hunkId})`);
+        }
+        checked.push({ decision, hunk });
+      }
+
+      store.decideHunks(body.suggestionId, body.decisions.map((decision) => ({
+        feedback: { ...decision, suggestionId: body.suggestionId },
+        applied: true,
+      })));
+
+      // Emit events (these will be broadcast to WebSocket clients)
+      const verbs = { accept: "accepted", reject: "rejected", modify: "modified" } as const;
+      for (const decision of body.decisions) {
+        await emitter.emitHunkApplied(body.suggestionId, decision.hunkId, verbs[decision.action]);
+      }
+
+      const remaining = store.getRemainingCount(body.suggestionId);
+      await emitter.emitStatus(
+        remaining === 0 ? "applied" : "partial",
+        `${body.decisions.length} hunks reviewed, ${remaining} hunks remaining`,
+        body.suggestionId
+      );
+
+      // One notification for the whole batch
+      const summary = (["accept", "reject", "modify"] as const)
+        .map((action) => {
+          const files = checked.flatMap(({ decision, hunk }) => (decision.action === action ? [hunk.file] : []));
+          if (files.length === 0) return undefined;
+          return `${verbs[action]} ${files.length} ${files.length === 1 ? "hunk" : "hunks"} (${[...new Set(files)].join(", ")})`;
+        })
+        .filter(Boolean)
+        .join(", ");
+      const details = checked
+        .map(({ decision, hunk }) => {
+          const comment = decision.comment ? ` Comment: "${decision.comment}"` : "";
+          const modifyInfo = decision.action === "modify" ? `\nModified diff:\n${decision.modifiedDiff}` : "";
+          return comment || modifyInfo ? `\n- ${hunk.id} (${hunk.file}):${comment}${modifyInfo}` : "";
+        })
+        .join("");
+      await notifyAI(body, `[Suggestion Feedback] User ${summary}. ${remaining} hunks remaining.${details}`);
+
+      return { success: true, decided: body.decisions.map((d) => d.hunkId), remainingHunks: remaining };
+    } catch (error) {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.error(`[HTTP Server] Error processing feedback batch:`, errorMsg);
+      return { success: false, remainingHunks: 0, error: `Database error: ${errorMsg}` };
+    }
+  }
+
+  /**
+   * Process undo of a review decision (shared between HTTP and WebSocket)
+   * 
+   * Like feedback, the editor reverts its own files: the result carries the
+   * reversed diff for it to apply.
+   */
+  async function processUndo(body: HunkUndo & { workingDirectory: string }): Promise<UndoResult> {
+    try {
+      const result = getStoreAndEmitter(body.workingDirectory);
+      if (!result) {
+        return { success: false, remainingHunks: 0, error: `No store found for working directory: ${body.workingDirectory}` };
+      }
+
+      const { store, emitter } = result;
+
+      // Check database health
+      if (!store.isDbHealthy()) {
+        return { success: false, remainingHunks: 0, error: `Database is not accessible. Path: ${store.getDbPath()}` };
+      }
+
+      if (!store.getSuggestion(body.suggestionId)) {
+        return { success: false, remainingHunks: 0, error: `Suggestion not found: ${body.suggestionId}` };
+      }
+
+      const undone = store.undoHunkState(body.suggestionId, body.hunkId);
+      if (!undone) {
+        return { success: false, remainingHunks: 0, error: `No decision found to undo for ${body.hunkId ?? body.suggestionId}` };
+      }
+
+      const { hunk, entry, replaces } = undone;
+      const previousAction = entry.action === "accept" ? "accepted" : entry.action === "reject" ? "rejected" : "modified";
+
+      // Rejections never touched the editor's files; accepts and modifies need reversing
+      let revertDiff: string | undefined;
+      let revertFileChange: UndoResult["revertFileChange"];
+      if (entry.action !== "reject") {
+        revertDiff = reverseHunk(entry.modifiedDiff ?? hunk.diff);
+        if (hunk.fileChange) {
+          revertFileChange = reverseFileChange(hunk.fileChange);
+        }
+      }
+
+      await emitter.emitHunkReverted(body.suggestionId, hunk, previousAction, replaces);
+
+      const remaining = store.getRemainingCount(body.suggestionId);
+      await emitter.emitStatus("partial", `${remaining} hunks remaining`, body.suggestionId);
+
+      // Notify the AI
+      await notifyAI(body, `[Suggestion Feedback] User undid their ${previousAction} decision on a hunk in ${hunk.file}. It is back up for review. ${remaining} hunks remaining.`);
+
+      return { success: true, hunk, previousAction, revertDiff, revertFileChange, remainingHunks: remaining };
+    } catch (error) {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.error(`[HTTP Server] Error processing undo:`, errorMsg);
+      return { success: false, remainingHunks: 0, error: `Database error: ${errorMsg}` };
+    }
+  }
+
+  /**
+   * Process a reviewer comment on a hunk (shared between HTTP and WebSocket)
+   * The comment starts or continues the hunk's thread; the AI is asked to
+   * answer through the reply_to_review tool.
+   */
+  async function processComment(
+    body: HunkComment & { workingDirectory: string }
+  ): Promise<{ success: boolean; comment?: ReviewComment; error?: string }> {
+    try {
+      const result = getStoreAndEmitter(body.workingDirectory);
+      if (!result) {
+        return { success: false, error: `No store found for working directory: ${body.workingDirectory}` };
+      }
+
+      const { store, emitter } = result;
+
+      // Check database health
+      if (!store.isDbHealthy()) {
+        return { success: false, error: `Database is not accessible. Path: ${store.getDbPath()}` };
+      }
+
+      const suggestion = store.getSuggestion(body.suggestionId);
+      if (!suggestion) {
+        return { success: false, error: `Suggestion not found: ${body.suggestionId}` };
+      }
+      if (!store.hasHunk(body.suggestionId, body.hunkId)) {
+        return { success: false, error: `Hunk not found: ${body.hunkId}` };
+      }
+
+      const comment = store.addComment({
+        suggestionId: body.suggestionId,
+        hunkId: body.hunkId,
+        author: "reviewer",
+        body: body.body,
+      });
+      await emitter.emitComment(comment);
+
+      const file = suggestion.hunks.find((h) => h.id === body.hunkId)?.file ?? body.hunkId.split(":")[1];
+      await notifyAI(
+        body,
+        `[Review Comment] On hunk ${body.hunkId} (${file}) of suggestion ${body.suggestionId}: "${body.body}". ` +
+        `Answer with the reply_to_review tool.`
+      );
+
+      return { success: true, comment };
+    } catch (error) {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.error(`[HTTP Server] Error processing comment:`, errorMsg);
+      return { success: false, error: `Database error: ${errorMsg}` };
+    }
+  }
+
+  /**
+   * Process a split of a pending hunk (WebSocket)
+   */
+  async function processSplit(body: HunkSplit & { workingDirectory: string }): Promise<HunksChangedResult> {
+    try {
+      const result = getStoreAndEmitter(body.workingDirectory);
+      if (!result) {
+        return { success: false, error: `No store found for working directory: ${body.workingDirectory}` };
+      }
+
+      const { store, emitter } = result;
+
+      // Check database health
+      if (!store.isDbHealthy()) {
+        return { success: false, error: `Database is not accessible. Path: ${store.getDbPath()}` };
+      }
+
+      if (!store.getSuggestion(body.suggestionId)) {
+        return { success: false, error: `Suggestion not found: ${body.suggestionId}` };
+      }
+      const hunk = store.getHunk(body.suggestionId, body.hunkId);
+      if (!hunk) {
+        return { success: false, error: `Hunk not found: ${body.hunkId}` };
+      }
+
+      const hunks = store.splitHunk(body.suggestionId, body.hunkId, body.line);
+      if (!hunks) {
+        const reason = hunk.fileChange
+          ? "file-level hunks can't be split"
+          : store.getHunkState(body.suggestionId, body.hunkId)?.state === "revision_requested"
+            ? "it is awaiting a revision"
+            : body.line === undefined
+              ? "it has a single group of changes; pick a line to split at"
+              : `splitting at line ${body.line} would leave a part without changes`;
+        return { success: false, error: `Cannot split ${body.hunkId}: ${reason}` };
+      }
+
+      const replaced = [body.hunkId];
+      await emitter.emitHunksChanged(body.suggestionId, replaced, hunks);
+      await notifyAI(
+        body,
+        `[Suggestion Review] User split hunk ${body.hunkId} (${hunk.file}) into ${hunks.length} hunks: ${hunks.map((h) => h.id).join(", ")}.`
+      );
+
+      return { success: true, replaced, hunks };
+    } catch (error) {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.error(`[HTTP Server] Error processing split:`, errorMsg);
+      return { success: false, error: `Database error: ${errorMsg}` };
+    }
+  }
+
+  /**
+   * Read the unchanged lines between two hunks of a file from the working copy
+   * The working copy holds the suggested change, so each hunk's new side must
+   * be found where its header says; otherwise the gap can't be trusted.
+   */
+  async function readGapLines(workingDirectory: string, first: Hunk, second: Hunk): Promise<string[] | string> {
+    const a = parseHunkHeader(first.diff.split("\n")[0] ?? "");
+    const b = parseHunkHeader(second.diff.split("\n")[0] ?? "");
+    if (!a || !b) return "invalid hunk header";
+
+    const gapStart = a.newStart + a.newCount;
+    if (b.newStart <= gapStart) return [];
+
+    const file = Bun.file(`${normalizeWorkingDirectory(workingDirectory)}/${first.file}`);
+    if (!(await file.exists())) return `${first.file} is not in the working copy`;
+    const lines = (await file.text()).split("\n");
+
+    const matches = (start: number, expected: string[]) =>
+      expected.every((line, i) => lines[start - 1 + i] === line);
+    if (
+      b.newStart - gapStart !== b.oldStart - (a.oldStart + a.oldCount) ||
+      !matches(a.newStart, extractHunkContent(first.diff).modified) ||
+      !matches(b.newStart, extractHunkContent(second.diff).modified)
+    ) {
+      return `${first.file} in the working copy no longer matches the hunks`;
+    }
+
+    return lines.slice(gapStart - 1, b.newStart - 1);
+  }
+
+  /**
+   * Process a merge of two pending hunks (WebSocket)
+   */
+  async function processMerge(body: HunkMerge & { workingDirectory: string }): Promise<HunksChangedResult> {
+    try {
+      const result = getStoreAndEmitter(body.workingDirectory);
+      if (!result) {
+        return { success: false, error: `No store found for working directory: ${body.workingDirectory}` };
+      }
+
+      const { store, emitter } = result;
+
+      // Check database health
+      if (!store.isDbHealthy()) {
+        return { success: false, error: `Database is not accessible. Path: ${store.getDbPath()}` };
+      }
+
+      const suggestion = store.getSuggestion(body.suggestionId);
+      if (!suggestion) {
+        return { success: false, error: `Suggestion not found: ${body.suggestionId}` };
+      }
+
+      const pair = body.hunkIds.map((id) => suggestion.hunks.find((h) => h.id === id));
+      const missing = body.hunkIds.find((_, i) => !pair[i]);
+      if (missing) {
+        return { success: false, error: `Hunk not found: ${missing}` };
+      }
+      // In file order
+      const oldStart = (hunk: Hunk) => parseHunkHeader(hunk.diff.split("\n")[0] ?? "")?.oldStart ?? 0;
+      const [first, second] = (pair as [Hunk, Hunk]).sort((a, b) => oldStart(a) - oldStart(b));
+      if (first.file !== second.file) {
+        return { success: false, error: "Only hunks of the same file can be merged" };
+      }
+      if (first.fileChange || second.fileChange) {
+        return { success: false, error: "File-level hunks can't be merged" };
+      }
+      const awaiting = [first, second].find((h) => suggestion.hunkStates.get(h.id)?.state === "revision_requested");
+      if (awaiting) {
+        return { success: false, error: `Cannot merge: ${awaiting.id} is awaiting a revision` };
+      }
+
+      const gap = await readGapLines(body.workingDirectory, first, second);
+      if (typeof gap === "string") {
+        return { success: false, error: `Cannot merge: ${gap}` };
+      }
+
+      const hunk = store.mergeHunks(body.suggestionId, [first.id, second.id], gap);
+      if (!hunk) {
+        return { success: false, error: "Cannot merge: the hunks aren't adjacent or change overlapping lines" };
+      }
+
+      const replaced = [first.id, second.id];
+      await emitter.emitHunksChanged(body.suggestionId, replaced, [hunk]);
+      await notifyAI(
+        body,
+        `[Suggestion Review] User merged hunks ${first.id} and ${second.id} (${first.file}) into ${hunk.id}.`
+      );
+
+      return { success: true, replaced, hunks: [hunk] };
+    } catch (error) {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.error(`[HTTP Server] Error processing merge:`, errorMsg);
+      return { success: false, error: `Database error: ${errorMsg}` };
+    }
+  }
+
   /**
    * Process complete request (shared between HTTP and WebSocket)
    */
@@ -271,11 +940,11 @@ export function createHttpServer(
 
       if (body.action === "finalize") {
         await emitter.emitStatus("applied", "Suggestion finalized", body.suggestionId);
-        store.removeSuggestion(body.suggestionId);
+        store.archiveSuggestion(body.suggestionId);
         return { success: true, action: "finalized" };
       } else {
         store.discardSuggestion(body.suggestionId);
-        store.removeSuggestion(body.suggestionId);
+        store.archiveSuggestion(body.suggestionId);
         await emitter.emitStatus("applied", "Suggestion discarded", body.suggestionId);
         return { success: true, action: "discarded" };
       }
@@ -286,6 +955,44 @@ export function createHttpServer(
     }
   }
 
+  /**
+   * Process a request to run the check pipeline (shared between HTTP and WebSocket)
+   * The pipeline runs in the background; results stream as suggestion.check events.
+   */
+  function processRunChecks(body: { suggestionId: string; workingDirectory: string }): { success: boolean; checks?: string[]; error?: string } {
+    try {
+      const result = getStoreAndEmitter(body.workingDirectory);
+      if (!result) {
+        return { success: false, error: `No store found for working directory: ${body.workingDirectory}` };
+      }
+
+      const { store, emitter } = result;
+
+      if (!store.isDbHealthy()) {
+        return { success: false, error: `Database is not accessible. Path: ${store.getDbPath()}` };
+      }
+
+      if (!store.getSuggestion(body.suggestionId)) {
+        return { success: false, error: `Suggestion not found: ${body.suggestionId}` };
+      }
+
+      const cwd = normalizeWorkingDirectory(body.workingDirectory);
+      const environment = checks.get(cwd);
+      if (!environment || environment.checks.length === 0) {
+        return { success: false, error: "No checks configured for this project" };
+      }
+
+      if (!startChecks({ ...environment, suggestionId: body.suggestionId, store, emitter })) {
+        return { success: false, error: `Checks are already running for ${body.suggestionId}` };
+      }
+      return { success: true, checks: environment.checks.map((c) => c.name) };
+    } catch (error) {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.error(`[HTTP Server] Error starting checks:`, errorMsg);
+      return { success: false, error: `Database error: ${errorMsg}` };
+    }
+  }
+
   /**
    * Handle WebSocket message
    */
@@ -295,11 +1002,26 @@ export function createHttpServer(
     try {
       parsed = JSON.parse(message);
     } catch {
-      ws.send(JSON.stringify({ type: "error", error: "Invalid JSON" }));
+      ws.send(JSON.stringify({ type: "response", success: false, code: "invalid_message", error: "Invalid JSON" }));
       return;
     }
 
-    const { type, id } = parsed;
+    // Check the command against the protocol schema before dispatching it
+    const errors = validateMessage("ClientCommand", parsed);
+    const id = typeof parsed === "object" && parsed !== null && typeof parsed.id === "string" ? parsed.id : undefined;
+    if (errors.length > 0) {
+      ws.send(JSON.stringify({
+        type: "response",
+        id,
+        success: false,
+        code: "invalid_message",
+        error: `Invalid message: ${describeErrors(errors)}`,
+        errors,
+      }));
+      return;
+    }
+
+    const { type } = parsed;
 
     // Helper to send response
     const respond = (data: object) => {
@@ -309,26 +1031,47 @@ export function createHttpServer(
     try {
       switch (type) {
         case "feedback": {
+          if (parsed.action === "undo") {
+            if (!parsed.suggestionId || !parsed.workingDirectory) {
+              respond({ success: false, error: "Missing required fields: suggestionId, workingDirectory" });
+              return;
+            }
+            const result = await processUndo({
+              suggestionId: parsed.suggestionId as string,
+              hunkId: parsed.hunkId as string | undefined,
+              workingDirectory: parsed.workingDirectory as string,
+            });
+            respond({ ...result, undone: result.success });
+            break;
+          }
           if (!parsed.suggestionId || !parsed.hunkId || !parsed.action || !parsed.workingDirectory) {
             respond({ success: false, error: "Missing required fields: suggestionId, hunkId, action, workingDirectory" });
             return;
           }
-          if (!["accept", "reject", "modify"].includes(parsed.action as string)) {
-            respond({ success: false, error: "Invalid action. Must be: accept, reject, or modify" });
+          if (!["accept", "reject", "modify", "request_changes"].includes(parsed.action as string)) {
+            respond({ success: false, error: "Invalid action. Must be: accept, reject, modify, request_changes, or undo" });
             return;
           }
           const result = await processFeedback({
             suggestionId: parsed.suggestionId as string,
             hunkId: parsed.hunkId as string,
-            action: parsed.action as "accept" | "reject" | "modify",
+            action: parsed.action as HunkFeedback["action"],
             modifiedDiff: parsed.modifiedDiff as string | undefined,
             comment: parsed.comment as string | undefined,
+            partial: parsed.partial === true,
+            stateVersion: typeof parsed.stateVersion === "number" ? parsed.stateVersion : undefined,
             workingDirectory: parsed.workingDirectory as string,
           });
           respond(result);
           break;
         }
 
+        case "feedback_batch": {
+          const result = await processFeedbackBatch(parsed as unknown as HunkFeedbackBatch & { workingDirectory: string });
+          respond(result);
+          break;
+        }
+
         case "complete": {
           if (!parsed.suggestionId || !parsed.action || !parsed.workingDirectory) {
             respond({ success: false, error: "Missing required fields: suggestionId, action, workingDirectory" });
@@ -347,6 +1090,71 @@ export function createHttpServer(
           break;
         }
 
+        case "run_checks": {
+          if (!parsed.suggestionId || !parsed.workingDirectory) {
+            respond({ success: false, error: "Missing required fields: suggestionId, workingDirectory" });
+            return;
+          }
+          respond(processRunChecks({
+            suggestionId: parsed.suggestionId as string,
+            workingDirectory: parsed.workingDirectory as string,
+          }));
+          break;
+        }
+
+        case "comment": {
+          if (!parsed.suggestionId || !parsed.hunkId || !parsed.body || !parsed.workingDirectory) {
+            respond({ success: false, error: "Missing required fields: suggestionId, hunkId, body, workingDirectory" });
+            return;
+          }
+          const result = await processComment({
+            suggestionId: parsed.suggestionId as string,
+            hunkId: parsed.hunkId as string,
+            body: parsed.body as string,
+            workingDirectory: parsed.workingDirectory as string,
+          });
+          respond(result);
+          break;
+        }
+
+        case "split": {
+          if (!parsed.suggestionId || !parsed.hunkId || !parsed.workingDirectory) {
+            respond({ success: false, error: "Missing required fields: suggestionId, hunkId, workingDirectory" });
+            return;
+          }
+          if (parsed.line !== undefined && typeof parsed.line !== "number") {
+            respond({ success: false, error: "Invalid line: must be a number" });
+            return;
+          }
+          const result = await processSplit({
+            suggestionId: parsed.suggestionId as string,
+            hunkId: parsed.hunkId as string,
+            line: parsed.line as number | undefined,
+            workingDirectory: parsed.workingDirectory as string,
+          });
+          respond(result);
+          break;
+        }
+
+        case "merge": {
+          const hunkIds = parsed.hunkIds;
+          if (!parsed.suggestionId || !parsed.workingDirectory || !Array.isArray(hunkIds)) {
+            respond({ success: false, error: "Missing required fields: suggestionId, hunkIds, workingDirectory" });
+            return;
+          }
+          if (hunkIds.length !== 2 || hunkIds[0] === hunkIds[1] || !hunkIds.every((id) => typeof id === "string")) {
+            respond({ success: false, error: "Invalid hunkIds: must be two different hunk IDs" });
+            return;
+          }
+          const result = await processMerge({
+            suggestionId: parsed.suggestionId as string,
+            hunkIds: hunkIds as [string, string],
+            workingDirectory: parsed.workingDirectory as string,
+          });
+          respond(result);
+          break;
+        }
+
         case "list": {
           try {
             if (!parsed.workingDirectory) {
@@ -369,7 +1177,18 @@ export function createHttpServer(
             respond({ success: true, ...listResult });
           } catch (error) {
             const errorMsg = error instanceof Error ? error.message : String(error);
-            console.error(`[HTTP Server] Error listing suggestions (WebSocket):`, errorMsg);
+            console.error(`[HTTP Server] Error listing suggestions (WebSocket):`, errorMsg);
+            respond({ success: false, error: `Database error: ${errorMsg}` });
+          }
+          break;
+        }
+
+        case "projects": {
+          try {
+            respond({ success: true, projects: listProjects() });
+          } catch (error) {
+            const errorMsg = error instanceof Error ? error.message : String(error);
+            console.error(`[HTTP Server] Error listing projects (WebSocket):`, errorMsg);
             respond({ success: false, error: `Database error: ${errorMsg}` });
           }
           break;
@@ -377,8 +1196,26 @@ export function createHttpServer(
 
         case "subscribe": {
           // Client wants to subscribe to a specific working directory
+          const protocolVersion = negotiateVersion(parsed.protocolVersion as number | undefined);
+          if (protocolVersion === undefined) {
+            respond({
+              success: false,
+              code: "unsupported_version",
+              error: `Protocol version ${parsed.protocolVersion} is not supported (server speaks ${MIN_PROTOCOL_VERSION}-${PROTOCOL_VERSION})`,
+              protocolVersion: PROTOCOL_VERSION,
+            });
+            return;
+          }
+          ws.data.protocolVersion = protocolVersion;
           const wd = parsed.workingDirectory as string | undefined;
+          const previous = ws.data.workingDirectory;
           ws.data.workingDirectory = wd;
+          ws.data.name = typeof parsed.clientName === "string" ? parsed.clientName : undefined;
+          if (previous !== wd) {
+            ws.data.suggestionId = undefined;
+            ws.data.hunkId = undefined;
+            broadcastPresence(previous);
+          }
           // Send filtered list
           const storeResult = getStoreAndEmitter(wd || "");
           if (!storeResult) {
@@ -389,7 +1226,46 @@ export function createHttpServer(
           // Use normalized working directory for filtering
           const normalizedWd = normalizeWorkingDirectory(wd || "");
           const listResult = store.listSuggestions(normalizedWd);
-          respond({ success: true, subscribed: wd, ...listResult });
+          const seq = store.getLatestSeq();
+          // A reconnecting editor gets the events it missed, or a snapshot if it missed too many
+          const missed = typeof parsed.sinceSeq === "number"
+            ? store.getEventsSince(parsed.sinceSeq, MAX_REPLAY_EVENTS)
+            : [];
+          respond({
+            success: true,
+            subscribed: wd,
+            clientId: ws.data.id,
+            protocolVersion,
+            seq,
+            replayed: missed?.length ?? 0,
+            snapshot: missed === undefined,
+            ...listResult,
+          });
+          if (missed) {
+            for (const event of missed) {
+              ws.send(JSON.stringify(event));
+            }
+          } else {
+            const suggestions = listResult.suggestions
+              .map((brief) => store.getSuggestion(brief.id))
+              .filter((suggestion): suggestion is Suggestion => suggestion !== undefined)
+              .map((suggestion) => suggestionDetails(store, suggestion));
+            ws.send(JSON.stringify({ type: "suggestion.snapshot", seq, suggestions }));
+          }
+          broadcastPresence(wd);
+          break;
+        }
+
+        case "presence": {
+          // The editor moved to another suggestion or hunk (neither: it looks at nothing)
+          if (!ws.data.workingDirectory) {
+            respond({ success: false, error: "Subscribe to a working directory first" });
+            return;
+          }
+          ws.data.suggestionId = typeof parsed.suggestionId === "string" ? parsed.suggestionId : undefined;
+          ws.data.hunkId = typeof parsed.hunkId === "string" ? parsed.hunkId : undefined;
+          respond({ success: true });
+          broadcastPresence(ws.data.workingDirectory);
           break;
         }
 
@@ -414,19 +1290,7 @@ export function createHttpServer(
               respond({ success: false, error: `Suggestion not found: ${parsed.suggestionId}` });
               return;
             }
-            // Convert Map to object
-            const hunkStates: Record<string, unknown> = {};
-            for (const [key, value] of suggestion.hunkStates) {
-              hunkStates[key] = value;
-            }
-            respond({
-              success: true,
-              suggestion: {
-                ...suggestion,
-                hunkStates,
-                remainingCount: store.getRemainingCount(parsed.suggestionId as string),
-              },
-            });
+            respond({ success: true, suggestion: suggestionDetails(store, suggestion) });
           } catch (error) {
             const errorMsg = error instanceof Error ? error.message : String(error);
             console.error(`[HTTP Server] Error getting suggestion (WebSocket):`, errorMsg);
@@ -435,6 +1299,69 @@ export function createHttpServer(
           break;
         }
 
+        case "search": {
+          try {
+            if (!parsed.workingDirectory) {
+              respond({ success: false, error: "Missing required field: workingDirectory" });
+              return;
+            }
+            const query = parseHistoryQuery((key) => parsed[key]);
+            if (typeof query === "string") {
+              respond({ success: false, error: query });
+              return;
+            }
+            const storeResult = getStoreAndEmitter(parsed.workingDirectory as string);
+            if (!storeResult) {
+              respond({ success: false, error: `No store found for working directory: ${parsed.workingDirectory}` });
+              return;
+            }
+            const { store } = storeResult;
+            if (!store.isDbHealthy()) {
+              respond({ success: false, error: `Database is not accessible. Path: ${store.getDbPath()}` });
+              return;
+            }
+            const normalizedWd = normalizeWorkingDirectory(parsed.workingDirectory as string);
+            const searchResult = store.searchHistory({ ...query, workingDirectory: normalizedWd });
+            respond({ success: true, ...searchResult });
+          } catch (error) {
+            const errorMsg = error instanceof Error ? error.message : String(error);
+            console.error(`[HTTP Server] Error searching history (WebSocket):`, errorMsg);
+            respond({ success: false, error: `Database error: ${errorMsg}` });
+          }
+          break;
+        }
+
+        case "stats": {
+          try {
+            if (!parsed.workingDirectory) {
+              respond({ success: false, error: "Missing required field: workingDirectory" });
+              return;
+            }
+            const query = parseStatsQuery((key) => parsed[key]);
+            if (typeof query === "string") {
+              respond({ success: false, error: query });
+              return;
+            }
+            const storeResult = getStoreAndEmitter(parsed.workingDirectory as string);
+            if (!storeResult) {
+              respond({ success: false, error: `No store found for working directory: ${parsed.workingDirectory}` });
+              return;
+            }
+            const { store } = storeResult;
+            if (!store.isDbHealthy()) {
+              respond({ success: false, error: `Database is not accessible. Path: ${store.getDbPath()}` });
+              return;
+            }
+            const stats = computeReviewStats(store.getReviewRecords(query.since, query.until), query);
+            respond({ success: true, stats });
+          } catch (error) {
+            const errorMsg = error instanceof Error ? error.message : String(error);
+            console.error(`[HTTP Server] Error computing stats (WebSocket):`, errorMsg);
+            respond({ success: false, error: `Database error: ${errorMsg}` });
+          }
+          break;
+        }
+
         default:
           respond({ success: false, error: `Unknown message type: ${type}` });
       }
@@ -444,14 +1371,34 @@ export function createHttpServer(
   }
 
   const server = Bun.serve<WSClientData>({
-    port: config.port,
-    hostname: config.host ?? "127.0.0.1",
+    ...(config.unix
+      ? { unix: config.unix }
+      : { port: config.port, hostname: config.host ?? "127.0.0.1" }),
 
     async fetch(req, server) {
       const url = new URL(req.url);
       const path = url.pathname;
       const method = req.method;
 
+      // CORS headers - no wildcard origin, so browser pages cannot read responses
+      const corsHeaders = {
+        "Access-Control-Allow-Methods": "GET, POST, OPTIONS",
+        "Access-Control-Allow-Headers": "Content-Type, Authorization",
+      };
+
+      // Handle preflight
+      if (method === "OPTIONS") {
+        return new Response(null, { headers: corsHeaders });
+      }
+
+      // Require the shared token on every other request (including /ws upgrade)
+      if (config.token) {
+        const provided = extractRequestToken(req, url);
+        if (!tokensMatch(provided, config.token)) {
+          return unauthorizedResponse(corsHeaders, provided !== null);
+        }
+      }
+
       // WebSocket upgrade
       if (path === "/ws") {
         const clientId = crypto.randomUUID();
@@ -464,18 +1411,6 @@ export function createHttpServer(
         return new Response("WebSocket upgrade failed", { status: 400 });
       }
 
-      // CORS headers for local development
-      const corsHeaders = {
-        "Access-Control-Allow-Origin": "*",
-        "Access-Control-Allow-Methods": "GET, POST, OPTIONS",
-        "Access-Control-Allow-Headers": "Content-Type",
-      };
-
-      // Handle preflight
-      if (method === "OPTIONS") {
-        return new Response(null, { headers: corsHeaders });
-      }
-
       try {
         // Health check
         if (path === "/health" && method === "GET") {
@@ -548,6 +1483,151 @@ export function createHttpServer(
           }
         }
 
+        // Every project served, with its pending suggestions
+        if (path === "/projects" && method === "GET") {
+          try {
+            return Response.json({ projects: listProjects() }, { headers: corsHeaders });
+          } catch (error) {
+            const errorMsg = error instanceof Error ? error.message : String(error);
+            console.error(`[HTTP Server] Error listing projects:`, errorMsg);
+            return Response.json(
+              { error: `Database error: ${errorMsg}` },
+              { status: 503, headers: corsHeaders }
+            );
+          }
+        }
+
+        // Who is reviewing in a working directory
+        if (path === "/presence" && method === "GET") {
+          const workingDir = url.searchParams.get("workingDirectory");
+          if (!workingDir) {
+            return Response.json(
+              { error: "Missing required query parameter: workingDirectory" },
+              { status: 400, headers: corsHeaders }
+            );
+          }
+          return Response.json({ clients: getPresence(workingDir) }, { headers: corsHeaders });
+        }
+
+        // Search suggestion history (active and archived)
+        if (path === "/history" && method === "GET") {
+          try {
+            const workingDir = url.searchParams.get("workingDirectory");
+            if (!workingDir) {
+              return Response.json(
+                { error: "Missing required query parameter: workingDirectory" },
+                { status: 400, headers: corsHeaders }
+              );
+            }
+            const query = parseHistoryQuery((key) => url.searchParams.get(key) ?? undefined);
+            if (typeof query === "string") {
+              return Response.json({ error: query }, { status: 400, headers: corsHeaders });
+            }
+            const storeResult = getStoreAndEmitter(workingDir);
+            if (!storeResult) {
+              return Response.json(
+                { error: `No store found for working directory: ${workingDir}` },
+                { status: 404, headers: corsHeaders }
+              );
+            }
+            const { store } = storeResult;
+            if (!store.isDbHealthy()) {
+              return Response.json(
+                { error: `Database is not accessible. Path: ${store.getDbPath()}` },
+                { status: 503, headers: corsHeaders }
+              );
+            }
+            const normalizedWd = normalizeWorkingDirectory(workingDir);
+            const result = store.searchHistory({ ...query, workingDirectory: normalizedWd });
+            return Response.json(result, { headers: corsHeaders });
+          } catch (error) {
+            const errorMsg = error instanceof Error ? error.message : String(error);
+            console.error(`[HTTP Server] Error searching history:`, errorMsg);
+            return Response.json(
+              { error: `Database error: ${errorMsg}` },
+              { status: 503, headers: corsHeaders }
+            );
+          }
+        }
+
+        // Preference dataset (JSONL), for one project or every open one
+        if (path === "/export" && method === "GET") {
+          try {
+            const query = parseExportQuery((key) => url.searchParams.get(key) ?? undefined);
+            if (typeof query === "string") {
+              return Response.json({ error: query }, { status: 400, headers: corsHeaders });
+            }
+
+            const workingDir = url.searchParams.get("workingDirectory");
+            let projects: Array<[string, SuggestionStore]>;
+            if (workingDir) {
+              const storeResult = getStoreAndEmitter(workingDir);
+              if (!storeResult) {
+                return Response.json(
+                  { error: `No store found for working directory: ${workingDir}` },
+                  { status: 404, headers: corsHeaders }
+                );
+              }
+              projects = [[normalizeWorkingDirectory(workingDir), storeResult.store]];
+            } else {
+              projects = [...stores];
+            }
+
+            const records = exportProjects(projects.filter(([, store]) => store.isDbHealthy()), query);
+
+            return new Response(toJsonl(records), {
+              headers: { ...corsHeaders, "Content-Type": "application/x-ndjson" },
+            });
+          } catch (error) {
+            const errorMsg = error instanceof Error ? error.message : String(error);
+            console.error(`[HTTP Server] Error exporting preference dataset:`, errorMsg);
+            return Response.json(
+              { error: `Database error: ${errorMsg}` },
+              { status: 503, headers: corsHeaders }
+            );
+          }
+        }
+
+        // Review statistics
+        if (path === "/stats" && method === "GET") {
+          try {
+            const workingDir = url.searchParams.get("workingDirectory");
+            if (!workingDir) {
+              return Response.json(
+                { error: "Missing required query parameter: workingDirectory" },
+                { status: 400, headers: corsHeaders }
+              );
+            }
+            const query = parseStatsQuery((key) => url.searchParams.get(key) ?? undefined);
+            if (typeof query === "string") {
+              return Response.json({ error: query }, { status: 400, headers: corsHeaders });
+            }
+            const storeResult = getStoreAndEmitter(workingDir);
+            if (!storeResult) {
+              return Response.json(
+                { error: `No store found for working directory: ${workingDir}` },
+                { status: 404, headers: corsHeaders }
+              );
+            }
+            const { store } = storeResult;
+            if (!store.isDbHealthy()) {
+              return Response.json(
+                { error: `Database is not accessible. Path: ${store.getDbPath()}` },
+                { status: 503, headers: corsHeaders }
+              );
+            }
+            const stats = computeReviewStats(store.getReviewRecords(query.since, query.until), query);
+            return Response.json(stats, { headers: corsHeaders });
+          } catch (error) {
+            const errorMsg = error instanceof Error ? error.message : String(error);
+            console.error(`[HTTP Server] Error computing stats:`, errorMsg);
+            return Response.json(
+              { error: `Database error: ${errorMsg}` },
+              { status: 503, headers: corsHeaders }
+            );
+          }
+        }
+
         // Get specific suggestion
         if (path.startsWith("/suggestions/") && method === "GET") {
           try {
@@ -583,19 +1663,7 @@ export function createHttpServer(
               );
             }
 
-            const hunkStates: Record<string, unknown> = {};
-            for (const [key, value] of suggestion.hunkStates) {
-              hunkStates[key] = value;
-            }
-
-            return Response.json(
-              {
-                ...suggestion,
-                hunkStates,
-                remainingCount: store.getRemainingCount(suggestionId),
-              },
-              { headers: corsHeaders }
-            );
+            return Response.json(suggestionDetails(store, suggestion), { headers: corsHeaders });
           } catch (error) {
             const errorMsg = error instanceof Error ? error.message : String(error);
             console.error(`[HTTP Server] Error getting suggestion:`, errorMsg);
@@ -608,7 +1676,23 @@ export function createHttpServer(
 
         // Submit hunk feedback (HTTP)
         if (path === "/feedback" && method === "POST") {
-          const body = await req.json() as HunkFeedback & { workingDirectory: string };
+          const body = await req.json() as (HunkFeedback | (HunkUndo & { action: "undo" })) & { workingDirectory: string };
+
+          if (body.action === "undo") {
+            if (!body.suggestionId || !body.workingDirectory) {
+              return Response.json(
+                { error: "Missing required fields: suggestionId, workingDirectory" },
+                { status: 400, headers: corsHeaders }
+              );
+            }
+
+            const result = await processUndo(body);
+            if (!result.success) {
+              const notFound = result.error?.includes("not found") || result.error?.includes("No decision");
+              return Response.json(result, { status: notFound ? 404 : 500, headers: corsHeaders });
+            }
+            return Response.json(result, { headers: corsHeaders });
+          }
           
           if (!body.suggestionId || !body.hunkId || !body.action || !body.workingDirectory) {
             return Response.json(
@@ -617,16 +1701,51 @@ export function createHttpServer(
             );
           }
 
-          if (!["accept", "reject", "modify"].includes(body.action)) {
+          if (!["accept", "reject", "modify", "request_changes"].includes(body.action)) {
+            return Response.json(
+              { error: "Invalid action. Must be: accept, reject, modify, request_changes, or undo" },
+              { status: 400, headers: corsHeaders }
+            );
+          }
+
+          if (body.action === "request_changes" && !body.comment) {
             return Response.json(
-              { error: "Invalid action. Must be: accept, reject, or modify" },
+              { error: "request_changes requires a comment with the instruction" },
               { status: 400, headers: corsHeaders }
             );
           }
 
           const result = await processFeedback(body);
           if (!result.success) {
-            return Response.json(result, { status: result.error?.includes("not found") ? 404 : 500, headers: corsHeaders });
+            const status = result.code === "conflict" ? 409 : result.error?.includes("not found") ? 404 : 500;
+            return Response.json(result, { status, headers: corsHeaders });
+          }
+          return Response.json(result, { headers: corsHeaders });
+        }
+
+        // Decide several hunks at once (HTTP)
+        if (path === "/feedback/batch" && method === "POST") {
+          const body = await req.json() as HunkFeedbackBatch & { workingDirectory: string };
+
+          if (!body.suggestionId || !Array.isArray(body.decisions) || !body.workingDirectory) {
+            return Response.json(
+              { error: "Missing required fields: suggestionId, decisions, workingDirectory" },
+              { status: 400, headers: corsHeaders }
+            );
+          }
+
+          const invalid = body.decisions.find((d) => !d?.hunkId || !["accept", "reject", "modify"].includes(d.action));
+          if (invalid) {
+            return Response.json(
+              { error: "Every decision needs a hunkId and an action: accept, reject or modify" },
+              { status: 400, headers: corsHeaders }
+            );
+          }
+
+          const result = await processFeedbackBatch(body);
+          if (!result.success) {
+            const status = result.code === "conflict" ? 409 : result.error?.includes("not found") ? 404 : result.error?.startsWith("Database") ? 500 : 400;
+            return Response.json(result, { status, headers: corsHeaders });
           }
           return Response.json(result, { headers: corsHeaders });
         }
@@ -656,6 +1775,47 @@ export function createHttpServer(
           return Response.json(result, { headers: corsHeaders });
         }
 
+        // Run the check pipeline (HTTP)
+        if (path === "/checks" && method === "POST") {
+          const body = await req.json() as { suggestionId: string; workingDirectory: string };
+
+          if (!body.suggestionId || !body.workingDirectory) {
+            return Response.json(
+              { error: "Missing required fields: suggestionId, workingDirectory" },
+              { status: 400, headers: corsHeaders }
+            );
+          }
+
+          const result = processRunChecks(body);
+          if (!result.success) {
+            const status = result.error?.includes("not found") ? 404
+              : result.error?.includes("already running") ? 409
+              : result.error?.includes("Database") ? 503
+              : 400;
+            return Response.json(result, { status, headers: corsHeaders });
+          }
+          return Response.json(result, { status: 202, headers: corsHeaders });
+        }
+
+        // Comment on a hunk (HTTP)
+        if (path === "/comment" && method === "POST") {
+          const body = await req.json() as HunkComment & { workingDirectory: string };
+
+          if (!body.suggestionId || !body.hunkId || !body.body || !body.workingDirectory) {
+            return Response.json(
+              { error: "Missing required fields: suggestionId, hunkId, body, workingDirectory" },
+              { status: 400, headers: corsHeaders }
+            );
+          }
+
+          const result = await processComment(body);
+          if (!result.success) {
+            const notFound = result.error?.includes("not found");
+            return Response.json(result, { status: notFound ? 404 : 500, headers: corsHeaders });
+          }
+          return Response.json(result, { headers: corsHeaders });
+        }
+
         // 404 for unknown routes
         return Response.json(
           { error: "Not found" },
@@ -678,6 +1838,7 @@ export function createHttpServer(
         ws.send(JSON.stringify({ 
           type: "connected", 
           message: "Connected. Send {type: 'subscribe', workingDirectory: '/path'} to filter suggestions.",
+          protocolVersion: PROTOCOL_VERSION,
         }));
       },
 
@@ -687,9 +1848,30 @@ export function createHttpServer(
 
       close(ws) {
         wsClients.delete(ws);
+        broadcastPresence(ws.data.workingDirectory);
       },
     },
   });
 
+  const notificationRetryTimer = setInterval(retryNotifications, NOTIFICATION_RETRY_INTERVAL_MS);
+  notificationRetryTimer.unref?.();
+
+  // Stopping the server also stops the retries, so they don't outlive the stores
+  const stopServer = server.stop.bind(server);
+  server.stop = (...args: Parameters<typeof stopServer>) => {
+    clearInterval(notificationRetryTimer);
+    return stopServer(...args);
+  };
+
+  if (config.unix) {
+    try {
+      applySocketPermissions(config.unix, config.socketMode ?? DEFAULT_SOCKET_MODE, config.socketGroup);
+    } catch (error) {
+      // Don't keep serving on a socket more open (or closed) than configured
+      server.stop(true);
+      throw error;
+    }
+  }
+
   return server;
 }
@@ -2,25 +2,34 @@
  * CodeForge - OpenCode Plugin
  * 
  * Enables AI to publish code suggestions for hunk-by-hunk review.
- * Works with jj (Jujutsu) for version control.
+ * Works with jj (Jujutsu) or plain git for version control.
  * 
  * The plugin exposes:
- * 1. Tools for the AI to call (publish_suggestion, suggestion_status, etc.)
- * 2. HTTP server for the editor to send feedback directly (POST /feedback, etc.)
+ * 1. Tools for the AI to call (publish_suggestion, update_suggestion, suggestion_status, run_checks, revise_hunk, reply_to_review, get_review_feedback, etc.)
+ * 2. HTTP server for the editor to send feedback directly (POST /feedback, etc.),
+ *    or registration with the CodeForge daemon, which serves editors for all projects
  * 3. Events emitted via OpenCode's log API (suggestion.ready, suggestion.hunk_applied, etc.)
  */
 
 import { tool, type Plugin } from "@opencode-ai/plugin";
 import { existsSync, readFileSync } from "fs";
 import { homedir } from "os";
-import { join } from "path";
+import { join, resolve } from "path";
 // Server type from Bun.serve()
 import { SuggestionStore, generateSuggestionId } from "./suggestion-store.ts";
 import { SuggestionEventEmitter } from "./event-emitter.ts";
-import { parseDiff, fileDiffsToHunks, filterFileDiffs, type FilterOptions, calculateLineOffset, adjustHunkLineNumbers } from "./diff-parser.ts";
-import { applyHunkToFile, applyModifiedHunk, reverseHunk } from "./patch-applier.ts";
-import { createHttpServer } from "./http-server.ts";
-import type { HunkFeedback, PublishSuggestionResult, FeedbackResult } from "./types.ts";
+import { parseDiff, fileDiffsToHunks, filterFileDiffs, type FilterOptions, calculateLineOffset, adjustHunkLineNumbers, parseHunkHeader, extractHunkContent, splitHunkSelection } from "./diff-parser.ts";
+import { applyHunkToFile, applyModifiedHunk, reverseHunk, DRIFT_TOLERANT_OPTIONS } from "./patch-applier.ts";
+import type { ApplyResult } from "./patch-applier.ts";
+import { createHttpServer, type HttpServerDeps } from "./http-server.ts";
+import { createVcs, resolveVcsKind, vcsErrorCode, formatCommitMessage, formatRefName, type VcsBackend, type VcsKind, type FinalizeResult } from "./vcs.ts";
+import { runChecks, startChecks, runCargoCheck, runCommand, type CheckConfig, type CheckEnvironment, type CommandRunner } from "./checks.ts";
+import { describeRustFiles } from "./rust-items.ts";
+import { ensureTokenFile, getTokenPath, loadOrCreateToken } from "./auth.ts";
+import { DEFAULT_SOCKET_MODE, parseSocketMode, prepareSocketPath, resolveGroupId, resolveSocketPath } from "./socket.ts";
+import { DEFAULT_DAEMON_SOCKET_MODE, connectToDaemon, forwardTools, getDefaultDaemonSocket, type DaemonConnection, type PluginProcess } from "./daemon.ts";
+import type { OpencodeClient } from "./event-emitter.ts";
+import type { Hunk, HunkFeedback, PublishSuggestionResult, UpdateSuggestionResult, FeedbackResult } from "./types.ts";
 
 /**
  * CodeForge configuration schema
@@ -36,17 +45,97 @@ export interface CodeForgeConfig {
     enabled?: boolean;
     port?: number;
     host?: string;
+    /** Require a shared token on HTTP/WebSocket requests (default: true) */
+    auth?: boolean;
+    /** Listen on this Unix socket instead of host/port; relative paths are relative to the project */
+    socket?: string;
+    /** Octal file mode of the socket (default: "660") */
+    socketMode?: string;
+    /** Group (name or ID) the socket is handed to, e.g. one shared with the developer */
+    socketGroup?: string;
+  };
+  vcs?: {
+    /** "jj", "git", or "auto" to detect from .jj / .git (default: "auto") */
+    backend?: VcsKind | "auto";
+    /** git only: ref the working tree is diffed against, or a stash entry (default: "HEAD") */
+    baseRef?: string;
+    /** Bookmark (jj) or branch (git) for finalized suggestions; {id}, {short_id} and {slug} are expanded (default: "codeforge/{id}") */
+    branch?: string;
+    /** Push the bookmark/branch when finalizing (default: false) */
+    push?: boolean;
+    /** Remote to push to (default: jj's default remote, or "origin" for git) */
+    remote?: string;
+  };
+  daemon?: {
+    /** Register with the CodeForge daemon instead of serving editors in-process (default: false) */
+    enabled?: boolean;
+    /** The daemon's IPC socket (default: ~/.local/share/codeforge/daemon.sock) */
+    socket?: string;
+    /** Octal file mode of the IPC socket (default: "600"); e.g. "660" with socketGroup when OpenCode runs as another user */
+    socketMode?: string;
+    /** Group (name or ID) the IPC socket is handed to, shared with the users OpenCode runs as */
+    socketGroup?: string;
+    /** Directories whose projects (and subdirectories) may register; required when the socket is shared. Global config only */
+    projects?: string[];
+  };
+  checks?: {
+    /** Commands run against a suggestion's change, in order; a project pipeline replaces the global one */
+    pipeline?: CheckConfig[];
+    /** Run the pipeline whenever a suggestion is published or updated (default: true) */
+    onPublish?: boolean;
+    /** Run `cargo check` before a suggestion is sent for review and attach its diagnostics to the hunks (default: if the project has a Cargo.toml) */
+    cargo?: boolean;
   };
 }
 
 /**
  * Load and merge configuration from all sources
  */
-export function loadConfig(projectDir: string): { enabled: boolean; port: number; host: string } {
+export function loadConfig(projectDir: string): {
+  enabled: boolean;
+  port: number;
+  host: string;
+  auth: boolean;
+  socket?: string;
+  socketMode: number;
+  socketGroup?: number;
+  vcs: VcsKind | "auto";
+  baseRef?: string;
+  branch: string;
+  push: boolean;
+  remote?: string;
+  checks: CheckConfig[];
+  checksOnPublish: boolean;
+  cargoCheck?: boolean;
+  /** The daemon's IPC socket, when the project is handed to the daemon */
+  daemon?: string;
+  /** File mode and group of the daemon's IPC socket (used by the daemon) */
+  daemonSocketMode: number;
+  daemonSocketGroup?: number;
+  /** Roots of the projects that may register with the daemon (used by the daemon) */
+  daemonProjects: string[];
+} {
   // Defaults
   let enabled = true;
   let port = 4097;
   let host = "127.0.0.1";
+  let auth = true;
+  let socket: string | undefined;
+  let socketMode = "660";
+  let socketGroup: string | undefined;
+  let vcs: VcsKind | "auto" = "auto";
+  let baseRef: string | undefined;
+  let branch = "codeforge/{id}";
+  let push = false;
+  let remote: string | undefined;
+  let checks: CheckConfig[] = [];
+  let checksOnPublish = true;
+  let cargoCheck: boolean | undefined;
+  let daemon = false;
+  let daemonSocket: string | undefined;
+  let daemonSocketMode = "600";
+  let daemonSocketGroup: string | undefined;
+  let daemonProjects: string[] = [];
 
   // Helper to load JSON config file
   const loadJsonConfig = (path: string): CodeForgeConfig | null => {
@@ -68,6 +157,30 @@ export function loadConfig(projectDir: string): { enabled: boolean; port: number
     if (globalConfig.server.enabled !== undefined) enabled = globalConfig.server.enabled;
     if (globalConfig.server.port !== undefined) port = globalConfig.server.port;
     if (globalConfig.server.host !== undefined) host = globalConfig.server.host;
+    if (globalConfig.server.auth !== undefined) auth = globalConfig.server.auth;
+    if (globalConfig.server.socket !== undefined) socket = globalConfig.server.socket;
+    if (globalConfig.server.socketMode !== undefined) socketMode = globalConfig.server.socketMode;
+    if (globalConfig.server.socketGroup !== undefined) socketGroup = globalConfig.server.socketGroup;
+  }
+  if (globalConfig?.vcs) {
+    if (globalConfig.vcs.backend !== undefined) vcs = globalConfig.vcs.backend;
+    if (globalConfig.vcs.baseRef !== undefined) baseRef = globalConfig.vcs.baseRef;
+    if (globalConfig.vcs.branch !== undefined) branch = globalConfig.vcs.branch;
+    if (globalConfig.vcs.push !== undefined) push = globalConfig.vcs.push;
+    if (globalConfig.vcs.remote !== undefined) remote = globalConfig.vcs.remote;
+  }
+  if (globalConfig?.daemon) {
+    if (globalConfig.daemon.enabled !== undefined) daemon = globalConfig.daemon.enabled;
+    if (globalConfig.daemon.socket !== undefined) daemonSocket = globalConfig.daemon.socket;
+    if (globalConfig.daemon.socketMode !== undefined) daemonSocketMode = globalConfig.daemon.socketMode;
+    if (globalConfig.daemon.socketGroup !== undefined) daemonSocketGroup = globalConfig.daemon.socketGroup;
+    // Not read from project config: a project must not be able to allow itself
+    if (globalConfig.daemon.projects !== undefined) daemonProjects = globalConfig.daemon.projects;
+  }
+  if (globalConfig?.checks) {
+    if (globalConfig.checks.pipeline !== undefined) checks = globalConfig.checks.pipeline;
+    if (globalConfig.checks.onPublish !== undefined) checksOnPublish = globalConfig.checks.onPublish;
+    if (globalConfig.checks.cargo !== undefined) cargoCheck = globalConfig.checks.cargo;
   }
 
   // 2. Project config: .opencode/codeforge.json
@@ -77,6 +190,28 @@ export function loadConfig(projectDir: string): { enabled: boolean; port: number
     if (projectConfig.server.enabled !== undefined) enabled = projectConfig.server.enabled;
     if (projectConfig.server.port !== undefined) port = projectConfig.server.port;
     if (projectConfig.server.host !== undefined) host = projectConfig.server.host;
+    if (projectConfig.server.auth !== undefined) auth = projectConfig.server.auth;
+    if (projectConfig.server.socket !== undefined) socket = projectConfig.server.socket;
+    if (projectConfig.server.socketMode !== undefined) socketMode = projectConfig.server.socketMode;
+    if (projectConfig.server.socketGroup !== undefined) socketGroup = projectConfig.server.socketGroup;
+  }
+  if (projectConfig?.vcs) {
+    if (projectConfig.vcs.backend !== undefined) vcs = projectConfig.vcs.backend;
+    if (projectConfig.vcs.baseRef !== undefined) baseRef = projectConfig.vcs.baseRef;
+    if (projectConfig.vcs.branch !== undefined) branch = projectConfig.vcs.branch;
+    if (projectConfig.vcs.push !== undefined) push = projectConfig.vcs.push;
+    if (projectConfig.vcs.remote !== undefined) remote = projectConfig.vcs.remote;
+  }
+  if (projectConfig?.daemon) {
+    if (projectConfig.daemon.enabled !== undefined) daemon = projectConfig.daemon.enabled;
+    if (projectConfig.daemon.socket !== undefined) daemonSocket = projectConfig.daemon.socket;
+    if (projectConfig.daemon.socketMode !== undefined) daemonSocketMode = projectConfig.daemon.socketMode;
+    if (projectConfig.daemon.socketGroup !== undefined) daemonSocketGroup = projectConfig.daemon.socketGroup;
+  }
+  if (projectConfig?.checks) {
+    if (projectConfig.checks.pipeline !== undefined) checks = projectConfig.checks.pipeline;
+    if (projectConfig.checks.onPublish !== undefined) checksOnPublish = projectConfig.checks.onPublish;
+    if (projectConfig.checks.cargo !== undefined) cargoCheck = projectConfig.checks.cargo;
   }
 
   // 3. Environment variables (highest precedence)
@@ -90,67 +225,191 @@ export function loadConfig(projectDir: string): { enabled: boolean; port: number
   if (process.env.CODEFORGE_SERVER_HOST !== undefined) {
     host = process.env.CODEFORGE_SERVER_HOST;
   }
+  if (process.env.CODEFORGE_SERVER_AUTH !== undefined) {
+    auth = process.env.CODEFORGE_SERVER_AUTH !== "false";
+  }
+  if (process.env.CODEFORGE_SERVER_SOCKET !== undefined) {
+    socket = process.env.CODEFORGE_SERVER_SOCKET || undefined;
+  }
+  if (process.env.CODEFORGE_SERVER_SOCKET_MODE !== undefined) {
+    socketMode = process.env.CODEFORGE_SERVER_SOCKET_MODE;
+  }
+  if (process.env.CODEFORGE_SERVER_SOCKET_GROUP !== undefined) {
+    socketGroup = process.env.CODEFORGE_SERVER_SOCKET_GROUP || undefined;
+  }
+  const envVcs = process.env.CODEFORGE_VCS;
+  if (envVcs === "jj" || envVcs === "git" || envVcs === "auto") {
+    vcs = envVcs;
+  }
+  if (process.env.CODEFORGE_VCS_BASE_REF !== undefined) {
+    baseRef = process.env.CODEFORGE_VCS_BASE_REF;
+  }
+  if (process.env.CODEFORGE_VCS_BRANCH !== undefined) {
+    branch = process.env.CODEFORGE_VCS_BRANCH;
+  }
+  if (process.env.CODEFORGE_VCS_PUSH !== undefined) {
+    push = process.env.CODEFORGE_VCS_PUSH === "true";
+  }
+  if (process.env.CODEFORGE_VCS_REMOTE !== undefined) {
+    remote = process.env.CODEFORGE_VCS_REMOTE;
+  }
+  if (process.env.CODEFORGE_CHECKS_ON_PUBLISH !== undefined) {
+    checksOnPublish = process.env.CODEFORGE_CHECKS_ON_PUBLISH !== "false";
+  }
+  if (process.env.CODEFORGE_CARGO_CHECK !== undefined) {
+    cargoCheck = process.env.CODEFORGE_CARGO_CHECK !== "false";
+  }
+  if (process.env.CODEFORGE_DAEMON !== undefined) {
+    daemon = process.env.CODEFORGE_DAEMON !== "false";
+  }
+  if (process.env.CODEFORGE_DAEMON_SOCKET !== undefined) {
+    daemonSocket = process.env.CODEFORGE_DAEMON_SOCKET;
+  }
+  if (process.env.CODEFORGE_DAEMON_SOCKET_MODE !== undefined) {
+    daemonSocketMode = process.env.CODEFORGE_DAEMON_SOCKET_MODE;
+  }
+  if (process.env.CODEFORGE_DAEMON_SOCKET_GROUP !== undefined) {
+    daemonSocketGroup = process.env.CODEFORGE_DAEMON_SOCKET_GROUP || undefined;
+  }
+  if (process.env.CODEFORGE_DAEMON_PROJECTS !== undefined) {
+    daemonProjects = process.env.CODEFORGE_DAEMON_PROJECTS.split(":");
+  }
+
+  // Drop malformed entries rather than failing on them later
+  checks = (Array.isArray(checks) ? checks : []).filter((check) => {
+    const valid = typeof check?.name === "string" && typeof check?.command === "string";
+    if (!valid) console.warn(`[codeforge] Ignoring invalid check:`, check);
+    return valid;
+  });
 
-  return { enabled, port, host };
+  // Socket settings that can't be used fall back to the defaults
+  const socketPermissions = (mode: string, group: string | undefined, defaultMode: number) => {
+    let parsed = parseSocketMode(String(mode));
+    if (parsed === undefined) {
+      console.warn(`[codeforge] Ignoring invalid socket mode: ${mode}`);
+      parsed = defaultMode;
+    }
+    const gid = group === undefined ? undefined : resolveGroupId(String(group));
+    if (group !== undefined && gid === undefined) {
+      console.warn(`[codeforge] Ignoring unknown socket group: ${group}`);
+    }
+    return { mode: parsed, gid };
+  };
+  const serverPermissions = socketPermissions(socketMode, socketGroup, DEFAULT_SOCKET_MODE);
+  const daemonPermissions = socketPermissions(daemonSocketMode, daemonSocketGroup, DEFAULT_DAEMON_SOCKET_MODE);
+
+  return {
+    enabled,
+    port,
+    host,
+    auth,
+    socket: socket ? resolveSocketPath(socket, projectDir) : undefined,
+    socketMode: serverPermissions.mode,
+    socketGroup: serverPermissions.gid,
+    vcs,
+    baseRef,
+    branch,
+    push,
+    remote,
+    checks,
+    checksOnPublish,
+    cargoCheck,
+    daemon: daemon ? (daemonSocket || getDefaultDaemonSocket()) : undefined,
+    daemonSocketMode: daemonPermissions.mode,
+    daemonSocketGroup: daemonPermissions.gid,
+    daemonProjects: (Array.isArray(daemonProjects) ? daemonProjects : [])
+      .filter((root) => typeof root === "string" && root !== "")
+      .map((root) => resolve(root.replace(/^~(?=$|\/)/, homedir()))),
+  };
 }
 
+/**
+ * Configuration of a project, as loaded by loadConfig
+ */
+export type ProjectConfig = ReturnType<typeof loadConfig>;
+
 // Global state (persists across tool calls within a session)
 // Map of working directory -> store to support multiple projects
 const stores = new Map<string, SuggestionStore>();
 const emitters = new Map<string, SuggestionEventEmitter>();
+const checkEnvironments = new Map<string, CheckEnvironment>();
 let httpServer: ReturnType<typeof Bun.serve> | null = null;
+// Shared secret of the running server (undefined when auth is disabled)
+let serverToken: string | undefined;
 
 /**
- * Get diff from jj for a specific change (in git/unified diff format)
+ * File and line-range filters shared by publish_suggestion and update_suggestion
  */
-async function getJjDiff($: any, changeId?: string): Promise<string> {
-  try {
-    if (changeId) {
-      const result = await $`jj diff -r ${changeId} --git`.text();
-      return result;
-    } else {
-      const result = await $`jj diff --git`.text();
-      return result;
-    }
-  } catch (error) {
-    throw new Error(`Failed to get jj diff: ${error instanceof Error ? error.message : String(error)}`);
-  }
+interface PublishFilterArgs {
+  files?: string[];
+  exclude_files?: string[];
+  line_ranges?: Array<{ file: string; start_line: number; end_line: number }>;
 }
 
 /**
- * Get current jj change ID
+ * Parse a VCS diff into suggestion hunks, applying the publish filters
+ * Files under .opencode/ are always excluded. Hunks of Rust files are labelled
+ * by the items they touch, read from the working copy in rootDir.
  */
-async function getCurrentChangeId($: any): Promise<string> {
-  try {
-    console.log("[codeforge] Running: jj log -r @ --no-graph -T commit_id");
-    const result = await $`jj log -r @ --no-graph -T commit_id`.text();
-    console.log("[codeforge] Result:", result);
-    console.log("[codeforge] Result length:", result.length);
-    return result.trim();
-  } catch (error) {
-    console.error("[codeforge] Error:", error);
-    throw new Error(`Failed to get current change ID: ${error instanceof Error ? error.message : String(error)}`);
-  }
+function diffToHunks(diffText: string, suggestionId: string, args: PublishFilterArgs, rootDir: string): { hunks: Hunk[]; files: string[] } {
+  // Parse the diff into file diffs
+  let fileDiffs = parseDiff(diffText);
+  
+  // Always exclude .opencode directory files
+  const defaultExcludeFiles = [".opencode/**"];
+  const excludeFiles = args.exclude_files 
+    ? [...defaultExcludeFiles, ...args.exclude_files]
+    : defaultExcludeFiles;
+  
+  // Apply filters (always apply default exclude)
+  const filterOptions: FilterOptions = {
+    includeFiles: args.files,
+    excludeFiles,
+    lineRanges: args.line_ranges?.map(r => ({
+      file: r.file,
+      startLine: r.start_line,
+      endLine: r.end_line,
+    })),
+  };
+  fileDiffs = filterFileDiffs(fileDiffs, filterOptions);
+  
+  // Convert to hunks
+  const hunks = describeRustFiles(fileDiffsToHunks(fileDiffs, suggestionId), rootDir);
+  const files = [...new Set(fileDiffs.map(fd => fd.newPath !== "/dev/null" ? fd.newPath : fd.oldPath))];
+
+  return { hunks, files };
 }
 
 /**
- * The main plugin export
+ * A project's store, emitter, version control and command runner, shared by its tools
  */
-export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
-  // Validate and normalize directory parameter
-  let workingDir = directory;
-  if (!directory || directory.trim() === "" || directory === "/") {
-    // Use home directory as fallback
-    workingDir = process.env.HOME || process.env.USERPROFILE || "/tmp";
-    console.warn(`[codeforge] Invalid working directory "${directory}", using fallback: ${workingDir}`);
-  }
+export interface ProjectContext {
+  workingDir: string;
+  config: ProjectConfig;
+  vcs: VcsBackend;
+  run: CommandRunner;
+  store: SuggestionStore;
+  emitter: SuggestionEventEmitter;
+}
 
-  // Set the shell working directory to the project directory
-  const shell = $.cwd(workingDir);
+/**
+ * Version control and command runner of a project, in this process
+ * @param shell Shell bound to the project directory
+ */
+export function localProcess(workingDir: string, config: ProjectConfig, shell: any): PluginProcess {
+  // Version control backend that suggestions are read from and finalized into
+  const vcs = createVcs(resolveVcsKind(config.vcs, workingDir), shell, workingDir, { baseRef: config.baseRef });
+  return { vcs, run: runCommand };
+}
+
+/**
+ * Open a project's store and emitter, reusing them if it is already open
+ * @param host Where the project's version control and commands run (the
+ *   plugin instance that registered it, when the daemon opens it)
+ */
+export function openProject(workingDir: string, config: ProjectConfig, client: OpencodeClient, host: PluginProcess): ProjectContext {
+  const { vcs, run } = host;
 
-  // Load configuration from files and environment
-  const config = loadConfig(workingDir);
-  
   // Initialize or get existing store for this working directory
   let store = stores.get(workingDir);
   if (!store) {
@@ -174,32 +433,98 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
   // Initialize or get existing emitter for this working directory
   let emitter = emitters.get(workingDir);
   if (!emitter) {
-    emitter = new SuggestionEventEmitter(client);
+    emitter = new SuggestionEventEmitter(client, { store, workingDirectory: workingDir });
     emitters.set(workingDir, emitter);
   }
+  checkEnvironments.set(workingDir, { checks: config.checks, vcs, run });
+
+  return { workingDir, config, vcs, run, store, emitter };
+}
+
+/**
+ * Start the HTTP + WebSocket server editors connect to
+ * @param client OpenCode client, or one per project when they belong to different instances (daemon)
+ */
+export async function serveEditors(
+  config: ProjectConfig,
+  token: string | undefined,
+  client: HttpServerDeps["client"]
+): Promise<ReturnType<typeof Bun.serve>> {
+  // A socket file left behind by a crashed server would block the new one
+  if (config.socket && !(await prepareSocketPath(config.socket))) {
+    throw new Error(`address already in use: ${config.socket}`);
+  }
+  return createHttpServer(
+    {
+      port: config.port,
+      host: config.host,
+      token,
+      unix: config.socket,
+      socketMode: config.socketMode,
+      socketGroup: config.socketGroup,
+    },
+    { stores, emitters, checks: checkEnvironments, client }
+  );
+}
+
+/**
+ * The main plugin export
+ */
+export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
+  // Validate and normalize directory parameter
+  let workingDir = directory;
+  if (!directory || directory.trim() === "" || directory === "/") {
+    // Use home directory as fallback
+    workingDir = process.env.HOME || process.env.USERPROFILE || "/tmp";
+    console.warn(`[codeforge] Invalid working directory "${directory}", using fallback: ${workingDir}`);
+  }
+
+  // Load configuration from files and environment
+  const config = loadConfig(workingDir);
+
+  // Open the project's store, with a shell bound to the project directory
+  const local = localProcess(workingDir, config, $.cwd(workingDir));
+  const project = openProject(workingDir, config, client, local);
+  const { store } = project;
+  const hooks = createProjectHooks(project);
+
+  // Hand the project to a running daemon, which then serves its editors
+  let daemon: DaemonConnection | null = null;
+  if (config.daemon) {
+    daemon = await connectToDaemon(config.daemon, { workingDirectory: workingDir }, client, local);
+    if (daemon) {
+      console.log(`[codeforge] Registered with the daemon at ${config.daemon}`);
+    } else {
+      console.log(`[codeforge] No daemon listening on ${config.daemon}, serving editors in-process`);
+    }
+  }
 
   // Start the HTTP server for direct editor communication (if enabled)
-  if (config.enabled) {
+  if (config.enabled && !daemon) {
+    const address = config.socket ?? `${config.host}:${config.port}`;
     // Check if server is already running (plugin may be loaded multiple times)
     if (httpServer) {
-      console.log(`[codeforge] HTTP server already running on ${config.host}:${config.port}`);
+      console.log(`[codeforge] HTTP server already running on ${address}`);
+      // Make the running server's token available to editors in this project
+      if (serverToken) {
+        ensureTokenFile(getTokenPath(workingDir), serverToken);
+      }
     } else {
       try {
-        httpServer = createHttpServer(
-          { port: config.port, host: config.host },
-          { stores, emitters, client }
-        );
-        console.log(`[codeforge] HTTP server started on ${config.host}:${config.port}`);
+        const token = config.auth ? loadOrCreateToken(getTokenPath(workingDir)) : undefined;
+        httpServer = await serveEditors(config, token, client);
+        serverToken = token;
+        console.log(`[codeforge] HTTP server started on ${address}`);
       } catch (error) {
         const errorMsg = error instanceof Error ? error.message : String(error);
         
         // Check if it's a port-in-use error - this is common and not a real error
         if (errorMsg.includes("EADDRINUSE") || errorMsg.includes("address already in use") || errorMsg.includes("Is port")) {
-          console.log(`[codeforge] Port ${config.port} is already in use. HTTP server not started.`);
+          console.log(`[codeforge] ${address} is already in use. HTTP server not started.`);
           console.log(`[codeforge] Plugin will continue without HTTP server - editor integration will not work.`);
         } else {
           // Other errors are more serious, log as error
-          console.error(`[codeforge] Failed to start HTTP server on port ${config.port}: ${errorMsg}`);
+          console.error(`[codeforge] Failed to start HTTP server on ${address}: ${errorMsg}`);
         }
         
         // Don't throw - allow plugin to load without HTTP server
@@ -207,21 +532,81 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
         httpServer = null;
       }
     }
-  } else {
+  } else if (!daemon) {
     console.log("[codeforge] HTTP server disabled via config");
     httpServer = null;
   }
 
+  return {
+    tool: daemon ? forwardTools(hooks.tool, daemon) : hooks.tool,
+
+    // Cleanup function called when plugin is unloaded
+    cleanup: async () => {
+      daemon?.close();
+      if (httpServer) {
+        httpServer.stop();
+        httpServer = null;
+        serverToken = undefined;
+        console.log("[codeforge] HTTP server stopped");
+      }
+      if (store) {
+        store.close();
+        console.log("[codeforge] Database connection closed");
+      }
+    },
+  };
+};
+
+/**
+ * The AI's tools for a project
+ * The plugin runs them in-process; the daemon runs them for registered projects.
+ */
+export function createProjectHooks(project: ProjectContext) {
+  const { workingDir, config, vcs, run, store, emitter } = project;
+
+  /**
+   * Run the check pipeline in the background if it is configured to follow publishing
+   */
+  const checkOnPublish = (suggestionId: string): boolean => {
+    if (!config.checksOnPublish || config.checks.length === 0) return false;
+    return startChecks({ suggestionId, checks: config.checks, vcs, run, store, emitter });
+  };
+
+  // Rust projects get compiler diagnostics on their hunks unless turned off
+  const cargoCheck = config.cargoCheck ?? existsSync(join(workingDir, "Cargo.toml"));
+
+  /**
+   * Attach what `cargo check` reports inside a suggestion's hunks
+   * Failing to run it only costs the diagnostics, never the suggestion.
+   */
+  const attachCompilerDiagnostics = async (suggestionId: string): Promise<void> => {
+    const suggestion = store.getSuggestion(suggestionId);
+    if (!cargoCheck || !suggestion) return;
+
+    await emitter.emitStatus("testing", "Running cargo check", suggestionId);
+    try {
+      const checkout = await vcs.checkout(suggestion.jjChangeId);
+      try {
+        store.setHunkDiagnostics(suggestionId, await runCargoCheck(checkout.dir, suggestion.hunks, undefined, run));
+      } finally {
+        await checkout.remove();
+      }
+    } catch (error) {
+      const errorMsg = error instanceof Error ? error.message : String(error);
+      console.warn(`[codeforge] cargo check failed for ${suggestionId}:`, errorMsg);
+    }
+  };
+
   return {
     tool: {
       /**
-       * Publish current jj change as a suggestion for user review
+       * Publish the current change as a suggestion for user review
        */
       publish_suggestion: tool({
-        description: "Publish current jj change as a suggestion for user review. Call this when you have made changes and want the user to review them hunk-by-hunk. Supports selective publishing by filtering files or line ranges.",
+        description: "Publish the current change (the jj change, or the git working tree) as a suggestion for user review. Call this when you have made changes and want the user to review them hunk-by-hunk. Supports selective publishing by filtering files or line ranges.",
         args: {
           description: tool.schema.string().describe("Human-readable description of the changes"),
-          change_id: tool.schema.string().optional().describe("jj change ID to publish (defaults to current change)"),
+          change_id: tool.schema.string().optional().describe("jj change ID, or git base ref / stash@{N}, to publish (defaults to current change)"),
           files: tool.schema.array(tool.schema.string()).optional().describe("File paths or glob patterns to include (e.g., ['src/**/*.ts', 'lib/utils.ts']). If omitted, includes all changed files."),
           exclude_files: tool.schema.array(tool.schema.string()).optional().describe("File paths or glob patterns to exclude (e.g., ['**/*.test.ts', 'docs/**'])"),
           line_ranges: tool.schema.array(
@@ -233,7 +618,7 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
           ).optional().describe("Filter hunks to only include those overlapping with specified line ranges"),
           hunk_descriptions: tool.schema.record(tool.schema.string(), tool.schema.string()).optional().describe("Map of hunk IDs to short one-line descriptions. If provided, these will be shown in the editor instead of hunk IDs. Format: {\"suggestion-id:file:0\": \"Add error handling\", \"suggestion-id:file:1\": \"Fix typo\"}"),
         },
-        async execute(args): Promise<string> {
+        async execute(args, context): Promise<string> {
           try {
             // Check database health
             if (!store.isDbHealthy()) {
@@ -244,10 +629,10 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
             }
 
             // Get the change ID
-            const changeId = args.change_id ?? await getCurrentChangeId(shell);
+            const changeId = args.change_id ?? await vcs.getCurrentChangeId();
 
             // Get the diff
-            const diffText = await getJjDiff(shell, changeId);
+            const diffText = await vcs.getDiff(changeId);
 
             if (!diffText.trim()) {
               return JSON.stringify({
@@ -256,32 +641,12 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
               });
             }
 
-            // Parse the diff into file diffs
-            let fileDiffs = parseDiff(diffText);
-            
-            // Always exclude .opencode directory files
-            const defaultExcludeFiles = [".opencode/**"];
-            const excludeFiles = args.exclude_files 
-              ? [...defaultExcludeFiles, ...args.exclude_files]
-              : defaultExcludeFiles;
-            
-            // Apply filters (always apply default exclude)
+            // Parse, filter and split the diff into hunks
             const hasFilters = args.files || args.exclude_files || args.line_ranges;
-            const filterOptions: FilterOptions = {
-              includeFiles: args.files,
-              excludeFiles,
-              lineRanges: args.line_ranges?.map(r => ({
-                file: r.file,
-                startLine: r.start_line,
-                endLine: r.end_line,
-              })),
-            };
-            fileDiffs = filterFileDiffs(fileDiffs, filterOptions);
-            
-            // Convert to hunks
             const suggestionId = generateSuggestionId();
-            let hunks = fileDiffsToHunks(fileDiffs, suggestionId);
-            const files = [...new Set(fileDiffs.map(fd => fd.newPath !== "/dev/null" ? fd.newPath : fd.oldPath))];
+            const parsed = diffToHunks(diffText, suggestionId, args, workingDir);
+            const files = parsed.files;
+            let hunks = parsed.hunks;
             
             if (hunks.length === 0) {
               const filterMsg = hasFilters ? " (after applying filters)" : "";
@@ -312,10 +677,13 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
               files,
               hunks,
               workingDirectory: relativeWorkingDir,
+              sessionId: context?.sessionID,
             });
 
-            // Emit the ready event
-            await emitter.emitReady(suggestion);
+            // Emit the ready event, with compiler diagnostics on the hunks
+            await attachCompilerDiagnostics(suggestionId);
+            await emitter.emitReady(store.getSuggestion(suggestionId) ?? suggestion);
+            const checking = checkOnPublish(suggestionId);
 
             const result: PublishSuggestionResult = {
               suggestionId,
@@ -327,11 +695,96 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
             return JSON.stringify({
               success: true,
               ...result,
+              checking,
               message: `Published suggestion with ${hunks.length} hunks in ${files.length} files${filterInfo}`,
             });
           } catch (error) {
             const errorMessage = error instanceof Error ? error.message : String(error);
-            await emitter.emitError("jj_error", errorMessage);
+            await emitter.emitError(vcsErrorCode(vcs.kind), errorMessage);
+            return JSON.stringify({
+              success: false,
+              error: errorMessage,
+            });
+          }
+        },
+      }),
+
+      /**
+       * Re-publish an existing suggestion after the change evolved
+       */
+      update_suggestion: tool({
+        description: "Update a suggestion you already published after making further edits to the same change, instead of publishing a new one. The diff is recomputed and matched against the existing hunks: unchanged hunks keep their review state, changed hunks go back up for review (as a new version), hunks no longer in the change are dropped and new ones are added.",
+        args: {
+          suggestion_id: tool.schema.string().describe("The suggestion ID returned by publish_suggestion"),
+          description: tool.schema.string().optional().describe("New description (keeps the current one if omitted)"),
+          change_id: tool.schema.string().optional().describe("jj change ID, or git base ref / stash@{N}, to diff (defaults to current change)"),
+          files: tool.schema.array(tool.schema.string()).optional().describe("File paths or glob patterns to include, as for publish_suggestion"),
+          exclude_files: tool.schema.array(tool.schema.string()).optional().describe("File paths or glob patterns to exclude, as for publish_suggestion"),
+          line_ranges: tool.schema.array(
+            tool.schema.object({
+              file: tool.schema.string().describe("File path to apply line range filter"),
+              start_line: tool.schema.number().describe("Start line number (inclusive)"),
+              end_line: tool.schema.number().describe("End line number (inclusive)"),
+            })
+          ).optional().describe("Filter hunks to only include those overlapping with specified line ranges"),
+        },
+        async execute(args): Promise<string> {
+          try {
+            // Check database health
+            if (!store.isDbHealthy()) {
+              return JSON.stringify({
+                success: false,
+                error: `Database is not accessible. Path: ${store.getDbPath()}`,
+              });
+            }
+
+            if (!store.getSuggestion(args.suggestion_id)) {
+              return JSON.stringify({
+                success: false,
+                error: `Suggestion not found: ${args.suggestion_id}`,
+              });
+            }
+
+            const changeId = args.change_id ?? await vcs.getCurrentChangeId();
+            const diffText = await vcs.getDiff(changeId);
+
+            // An empty diff is fine here: everything still pending gets removed
+            const { hunks } = diffToHunks(diffText, args.suggestion_id, args, workingDir);
+
+            const updated = store.updateSuggestion(args.suggestion_id, {
+              jjChangeId: changeId,
+              description: args.description,
+              hunks,
+            });
+            if (!updated) {
+              return JSON.stringify({
+                success: false,
+                error: `Suggestion not found: ${args.suggestion_id}`,
+              });
+            }
+
+            await attachCompilerDiagnostics(args.suggestion_id);
+            await emitter.emitUpdated(store.getSuggestion(args.suggestion_id) ?? updated.suggestion, updated);
+            const checking = checkOnPublish(args.suggestion_id);
+
+            const result: UpdateSuggestionResult = {
+              suggestionId: args.suggestion_id,
+              added: updated.added,
+              changed: updated.changed,
+              removed: updated.removed,
+              hunkCount: updated.suggestion.hunks.length,
+              files: updated.suggestion.files,
+            };
+
+            return JSON.stringify({
+              success: true,
+              ...result,
+              checking,
+              message: `Updated suggestion: ${updated.added.length} added, ${updated.changed.length} changed, ${updated.removed.length} removed`,
+            });
+          } catch (error) {
+            const errorMessage = error instanceof Error ? error.message : String(error);
+            await emitter.emitError(vcsErrorCode(vcs.kind), errorMessage, args.suggestion_id);
             return JSON.stringify({
               success: false,
               error: errorMessage,
@@ -351,6 +804,7 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
           action: tool.schema.enum(["accept", "reject", "modify"]).describe("The action to take"),
           modified_diff: tool.schema.string().optional().describe("If action is 'modify', the user's edited diff"),
           comment: tool.schema.string().optional().describe("Optional feedback comment"),
+          partial: tool.schema.boolean().optional().describe("Decide only the lines in modified_diff (the hunk narrowed to them); the rest stays pending as a new hunk"),
         },
         async execute(args): Promise<string> {
           try {
@@ -385,15 +839,41 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
               action: args.action,
               modifiedDiff: args.modified_diff,
               comment: args.comment,
+              partial: args.partial,
             };
 
+            // Partial decision: work out what is left of the hunk after the selected lines
+            let remainingDiff: string | null = null;
+            if (args.partial) {
+              if (args.action !== "modify" && args.action !== "reject") {
+                return JSON.stringify({
+                  success: false,
+                  error: "Only modify and reject can decide part of a hunk",
+                  applied: false,
+                });
+              }
+              const split = args.modified_diff && !hunk.fileChange
+                ? splitHunkSelection(hunk.diff, args.modified_diff, args.action === "modify" ? "accept" : "reject")
+                : undefined;
+              if (!split) {
+                return JSON.stringify({
+                  success: false,
+                  error: "modified_diff must be the hunk narrowed to the selected lines",
+                  applied: false,
+                });
+              }
+              remainingDiff = split.remaining;
+            }
+
             let applied = false;
             let reverted = false;
+            let applyResult: ApplyResult | undefined;
 
             const filePath = `${workingDir}/${hunk.file}`;
 
             // Calculate line offset based on previously applied hunks in this file
-            const fileHunks = suggestion.hunks.filter(h => h.file === hunk.file);
+            // File-level hunks (renames, mode changes, ...) don't shift line numbers
+            const fileHunks = suggestion.hunks.filter(h => h.file === hunk.file && !h.fileChange);
             const appliedHunkIds = new Set<string>();
             for (const [hunkId, state] of suggestion.hunkStates) {
               if (state.reviewed && state.applied) {
@@ -407,7 +887,13 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
             const parsedHunks = fileDiffs[0]?.hunks || [];
             
             // Calculate the line offset for this hunk
-            const lineOffset = calculateLineOffset(parsedHunks, appliedHunkIds, hunk.file, args.suggestion_id);
+            const lineOffset = calculateLineOffset(
+              parsedHunks,
+              appliedHunkIds,
+              hunk.file,
+              args.suggestion_id,
+              fileHunks.map(h => h.id)
+            );
             
             // Adjust the hunk diff if there's an offset
             let adjustedDiff = hunk.diff;
@@ -415,13 +901,59 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
               adjustedDiff = adjustHunkLineNumbers(hunk.diff, lineOffset);
             }
 
-            if (args.action === "accept") {
+            const applyOptions = { ...DRIFT_TOLERANT_OPTIONS, rootDir: workingDir };
+
+            if (hunk.fileChange && args.action === "modify") {
+              return JSON.stringify({
+                success: false,
+                error: "File-level hunks can only be accepted or rejected",
+                applied: false,
+              });
+            }
+
+            if (remainingDiff && args.action === "modify") {
+              // Accepted lines plus the pending rest make up the whole hunk, already in working copy
+              applied = true;
+            } else if (remainingDiff && args.action === "reject") {
+              // Take the hunk out, then put back the lines that are still pending
+              const revertResult = await applyHunkToFile(filePath, reverseHunk(adjustedDiff), applyOptions);
+              if (!revertResult.success) {
+                await emitter.emitError(
+                  "apply_failed",
+                  revertResult.error ?? "Failed to revert rejected lines",
+                  args.suggestion_id,
+                  args.hunk_id
+                );
+                return JSON.stringify({
+                  success: false,
+                  error: revertResult.error,
+                  applied: false,
+                });
+              }
+
+              applyResult = await applyHunkToFile(filePath, adjustHunkLineNumbers(remainingDiff, lineOffset), applyOptions);
+              if (!applyResult.success) {
+                await emitter.emitError(
+                  "apply_failed",
+                  applyResult.error ?? "Failed to keep the rest of the hunk",
+                  args.suggestion_id,
+                  args.hunk_id
+                );
+                return JSON.stringify({
+                  success: false,
+                  error: applyResult.error,
+                  applied: false,
+                });
+              }
+
+              reverted = true;
+            } else if (args.action === "accept") {
               // Accept: hunk is already in working copy, nothing to do
               // (AI made the change, user approved it)
               applied = true;
             } else if (args.action === "modify") {
               // Modify: revert original, apply modified version
-              const revertResult = await applyHunkToFile(filePath, reverseHunk(adjustedDiff));
+              const revertResult = await applyHunkToFile(filePath, reverseHunk(adjustedDiff), applyOptions);
               if (!revertResult.success) {
                 await emitter.emitError(
                   "apply_failed",
@@ -436,7 +968,10 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
                 });
               }
 
-              const applyResult = await applyModifiedHunk(filePath, args.modified_diff!);
+              applyResult = await applyModifiedHunk(filePath, args.modified_diff!, {
+                ...applyOptions,
+                base: hunk.originalLines,
+              });
               if (!applyResult.success) {
                 await emitter.emitError(
                   "apply_failed",
@@ -452,19 +987,35 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
               }
 
               applied = true;
+            } else if (args.action === "reject" && hunk.fileChange?.binary) {
+              // Binary content can't be reverted from a diff - restore the paths from the parent revision
+              const paths = [...new Set([hunk.fileChange.oldPath, hunk.fileChange.newPath])];
+              try {
+                await vcs.restorePaths(suggestion.jjChangeId, paths);
+              } catch (error) {
+                const errorMsg = error instanceof Error ? error.message : String(error);
+                await emitter.emitError(vcsErrorCode(vcs.kind), errorMsg, args.suggestion_id, args.hunk_id);
+                return JSON.stringify({
+                  success: false,
+                  error: `Failed to restore binary file: ${errorMsg}`,
+                  applied: false,
+                });
+              }
+
+              reverted = true;
             } else if (args.action === "reject") {
               // Reject: revert the hunk (undo the AI's change)
-              const revertResult = await applyHunkToFile(filePath, reverseHunk(adjustedDiff));
-              if (!revertResult.success) {
+              applyResult = await applyHunkToFile(filePath, reverseHunk(adjustedDiff), applyOptions);
+              if (!applyResult.success) {
                 await emitter.emitError(
                   "apply_failed",
-                  revertResult.error ?? "Failed to revert rejected hunk",
+                  applyResult.error ?? "Failed to revert rejected hunk",
                   args.suggestion_id,
                   args.hunk_id
                 );
                 return JSON.stringify({
                   success: false,
-                  error: revertResult.error,
+                  error: applyResult.error,
                   applied: false,
                 });
               }
@@ -472,14 +1023,29 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
               reverted = true;
             }
 
+            // Let the editor know the file now contains conflict markers
+            if (applyResult?.status === "conflicted") {
+              await emitter.emitError(
+                "merge_conflict",
+                `${applyResult.conflicts ?? 1} conflict(s) written to ${hunk.file}`,
+                args.suggestion_id,
+                args.hunk_id
+              );
+            }
+
             // Update the store
-            store.updateHunkState(args.suggestion_id, args.hunk_id, feedback, applied || reverted);
+            let remainingHunk: Hunk | undefined;
+            if (remainingDiff) {
+              remainingHunk = store.decidePartially(args.suggestion_id, args.hunk_id, feedback, applied || reverted, remainingDiff);
+            } else {
+              store.updateHunkState(args.suggestion_id, args.hunk_id, feedback, applied || reverted);
+            }
 
             // Emit the hunk applied event
             const action = args.action === "accept" ? "accepted"
               : args.action === "reject" ? "rejected"
               : "modified";
-            await emitter.emitHunkApplied(args.suggestion_id, args.hunk_id, action);
+            await emitter.emitHunkApplied(args.suggestion_id, args.hunk_id, action, remainingHunk);
 
             // Emit status update
             const remaining = store.getRemainingCount(args.suggestion_id);
@@ -494,6 +1060,8 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
               applied,
               reverted,
               remainingHunks: remaining,
+              applyStatus: applyResult?.status,
+              remainingHunk,
             };
 
             return JSON.stringify(result);
@@ -531,6 +1099,238 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
         },
       }),
 
+      /**
+       * Run the check pipeline against a suggestion
+       */
+      run_checks: tool({
+        description: "Run the configured check pipeline (tests, linters; see checks.pipeline in codeforge.json) against a suggestion's change (in the working copy when it holds the change, else in a temporary checkout of it). Results are shown to the user next to the hunks they concern and returned here.",
+        args: {
+          suggestion_id: tool.schema.string().describe("The suggestion ID"),
+        },
+        async execute(args): Promise<string> {
+          try {
+            if (config.checks.length === 0) {
+              return JSON.stringify({
+                success: false,
+                error: "No checks configured. Add checks.pipeline to .opencode/codeforge.json",
+              });
+            }
+
+            if (!store.getSuggestion(args.suggestion_id)) {
+              return JSON.stringify({
+                success: false,
+                error: `Suggestion not found: ${args.suggestion_id}`,
+              });
+            }
+
+            const results = await runChecks({
+              suggestionId: args.suggestion_id,
+              checks: config.checks,
+              vcs,
+              run,
+              store,
+              emitter,
+            });
+
+            const failed = results.filter((r) => r.status !== "passed");
+            return JSON.stringify({
+              success: true,
+              passed: failed.length === 0,
+              checks: results.map((r) => ({
+                name: r.name,
+                status: r.status,
+                exitCode: r.exitCode,
+                diagnostics: r.diagnostics,
+                // The end of the output is where failures are summarized
+                output: r.status === "passed" ? undefined : r.output?.slice(-2000),
+              })),
+              message: failed.length === 0
+                ? `All ${results.length} checks passed`
+                : `${failed.length} of ${results.length} checks failed`,
+            });
+          } catch (error) {
+            return JSON.stringify({
+              success: false,
+              error: error instanceof Error ? error.message : String(error),
+            });
+          }
+        },
+      }),
+
+      /**
+       * Publish a new version of a hunk the reviewer requested changes to
+       */
+      revise_hunk: tool({
+        description: "Publish a revised version of a single hunk after the user requested changes to it. Make the change in the working copy first, then pass the new unified diff hunk (starting with @@) for the same file. The hunk keeps its ID and gets a new version number.",
+        args: {
+          suggestion_id: tool.schema.string().describe("The suggestion ID"),
+          hunk_id: tool.schema.string().describe("The hunk ID changes were requested on"),
+          diff: tool.schema.string().describe("The replacement hunk in unified diff format, starting with the @@ header"),
+          note: tool.schema.string().optional().describe("Short explanation of what changed, added to the hunk's review thread"),
+        },
+        async execute(args): Promise<string> {
+          try {
+            // Check database health
+            if (!store.isDbHealthy()) {
+              return JSON.stringify({
+                success: false,
+                error: `Database is not accessible. Path: ${store.getDbPath()}`,
+              });
+            }
+
+            const hunk = store.getHunk(args.suggestion_id, args.hunk_id);
+            if (!hunk) {
+              return JSON.stringify({
+                success: false,
+                error: `Hunk not found: ${args.hunk_id} (it may already have been reviewed)`,
+              });
+            }
+            if (hunk.fileChange) {
+              return JSON.stringify({
+                success: false,
+                error: "File-level hunks (renames, deletes, mode changes) can't be revised",
+              });
+            }
+
+            const diff = args.diff.replace(/\n+$/, "");
+            const header = parseHunkHeader(diff.split("\n")[0] ?? "");
+            if (!header) {
+              return JSON.stringify({
+                success: false,
+                error: "diff must be a single hunk starting with an @@ -start,count +start,count @@ header",
+              });
+            }
+
+            const revised = store.reviseHunk(args.suggestion_id, args.hunk_id, {
+              diff,
+              originalStartLine: header.oldStart,
+              originalLines: extractHunkContent(diff).original,
+            });
+            if (!revised) {
+              return JSON.stringify({
+                success: false,
+                error: `Hunk not found: ${args.hunk_id}`,
+              });
+            }
+
+            await emitter.emitHunkRevised(args.suggestion_id, revised.hunk, revised.previous);
+
+            if (args.note) {
+              const comment = store.addComment({
+                suggestionId: args.suggestion_id,
+                hunkId: args.hunk_id,
+                author: "ai",
+                body: args.note,
+              });
+              await emitter.emitComment(comment);
+            }
+
+            return JSON.stringify({
+              success: true,
+              hunkId: args.hunk_id,
+              version: revised.hunk.version,
+              previousVersion: revised.previous.version,
+            });
+          } catch (error) {
+            return JSON.stringify({
+              success: false,
+              error: error instanceof Error ? error.message : String(error),
+            });
+          }
+        },
+      }),
+
+      /**
+       * Answer a reviewer's comment on a hunk
+       */
+      reply_to_review: tool({
+        description: "Reply to a reviewer's comment on a suggestion hunk. Use this when you receive a [Review Comment] to explain the change or answer the question; the reply appears in the hunk's thread in the editor.",
+        args: {
+          suggestion_id: tool.schema.string().describe("The suggestion ID"),
+          hunk_id: tool.schema.string().describe("The hunk ID the comment was made on"),
+          message: tool.schema.string().describe("Your reply"),
+        },
+        async execute(args): Promise<string> {
+          try {
+            // Check database health
+            if (!store.isDbHealthy()) {
+              return JSON.stringify({
+                success: false,
+                error: `Database is not accessible. Path: ${store.getDbPath()}`,
+              });
+            }
+
+            if (!store.hasHunk(args.suggestion_id, args.hunk_id)) {
+              return JSON.stringify({
+                success: false,
+                error: `Hunk not found: ${args.hunk_id} in suggestion ${args.suggestion_id}`,
+              });
+            }
+
+            const comment = store.addComment({
+              suggestionId: args.suggestion_id,
+              hunkId: args.hunk_id,
+              author: "ai",
+              body: args.message,
+            });
+            await emitter.emitComment(comment);
+
+            return JSON.stringify({
+              success: true,
+              thread: store.getThread(args.suggestion_id, args.hunk_id),
+            });
+          } catch (error) {
+            return JSON.stringify({
+              success: false,
+              error: error instanceof Error ? error.message : String(error),
+            });
+          }
+        },
+      }),
+
+      /**
+       * Pull review feedback that hasn't reached the AI
+       */
+      get_review_feedback: tool({
+        description: "Get review feedback (decisions, comments, change requests) that wasn't delivered to your session, e.g. because the notification failed. Returned feedback counts as delivered. Call this before continuing work on a suggestion if you may have missed feedback.",
+        args: {
+          suggestion_id: tool.schema.string().optional().describe("Only feedback on this suggestion"),
+          include_delivered: tool.schema.boolean().optional().describe("Also return feedback that was already delivered"),
+          limit: tool.schema.number().optional().describe("Most recent entries to return (default: 50)"),
+        },
+        async execute(args): Promise<string> {
+          try {
+            const notifications = store.getNotifications({
+              undelivered: !args.include_delivered,
+              suggestionId: args.suggestion_id,
+              limit: args.limit ?? 50,
+            });
+            for (const notification of notifications) {
+              if (notification.deliveredAt === undefined) {
+                store.markNotificationDelivered(notification.id, "tool");
+              }
+            }
+
+            return JSON.stringify({
+              success: true,
+              count: notifications.length,
+              feedback: notifications.map((n) => ({
+                suggestionId: n.suggestionId,
+                hunkId: n.hunkId,
+                message: n.message,
+                createdAt: n.createdAt,
+                delivered: n.deliveredAt !== undefined,
+              })),
+            });
+          } catch (error) {
+            return JSON.stringify({
+              success: false,
+              error: error instanceof Error ? error.message : String(error),
+            });
+          }
+        },
+      }),
+
       /**
        * List all pending suggestions
        */
@@ -569,10 +1369,11 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
        * Finalize or discard a suggestion
        */
       complete_suggestion: tool({
-        description: "Finalize or discard a suggestion. Use 'finalize' after all hunks are reviewed to sync changes, or 'discard' to abandon the suggestion.",
+        description: "Finalize or discard a suggestion. Use 'finalize' after all hunks are reviewed: it commits exactly the accepted and modified hunks as a new change (jj) or commit (git) on the suggestion's parent, points a bookmark/branch at it and optionally pushes it. Use 'discard' to abandon the suggestion.",
         args: {
           suggestion_id: tool.schema.string().describe("The suggestion ID"),
-          action: tool.schema.enum(["finalize", "discard"]).describe("'finalize' to sync changes, 'discard' to abandon"),
+          action: tool.schema.enum(["finalize", "discard"]).describe("'finalize' to commit the accepted hunks, 'discard' to abandon"),
+          push: tool.schema.boolean().optional().describe("Push the bookmark/branch after committing (defaults to vcs.push in codeforge.json)"),
         },
         async execute(args): Promise<string> {
           try {
@@ -593,32 +1394,63 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
             }
 
             if (args.action === "finalize") {
-              // Sync with shared repo
+              if (suggestion.hunks.length > 0) {
+                return JSON.stringify({
+                  success: false,
+                  error: `${suggestion.hunks.length} hunks are still pending review`,
+                });
+              }
+
+              const { accepted, rejected } = store.getDecidedHunks(args.suggestion_id);
+              if (accepted.length === 0) {
+                return JSON.stringify({
+                  success: false,
+                  error: "No accepted hunks to commit - discard the suggestion instead",
+                });
+              }
+
+              // Build a clean commit from the accepted hunks only
+              let finalized: FinalizeResult;
               try {
-                await shell`jj git push`.text();
-                await emitter.emitStatus("applied", "Changes synced to remote", args.suggestion_id);
+                finalized = await vcs.finalize({
+                  changeId: suggestion.jjChangeId,
+                  message: formatCommitMessage(suggestion.id, suggestion.description, accepted, rejected),
+                  hunks: accepted,
+                  name: formatRefName(config.branch, suggestion.id, suggestion.description),
+                  push: args.push ?? config.push,
+                  remote: config.remote,
+                });
               } catch (error) {
                 await emitter.emitError(
                   "sync_failed",
-                  `Failed to sync: ${error instanceof Error ? error.message : String(error)}`,
+                  `Failed to finalize: ${error instanceof Error ? error.message : String(error)}`,
                   args.suggestion_id
                 );
                 return JSON.stringify({
                   success: false,
-                  error: "Failed to sync changes to remote",
+                  error: `Failed to finalize changes with ${vcs.kind}: ${error instanceof Error ? error.message : String(error)}`,
                 });
               }
 
-              store.removeSuggestion(args.suggestion_id);
+              const message = finalized.pushed
+                ? `Committed ${accepted.length} hunks to ${finalized.name} and pushed`
+                : `Committed ${accepted.length} hunks to ${finalized.name}`;
+              await emitter.emitStatus("applied", message, args.suggestion_id);
+
+              store.archiveSuggestion(args.suggestion_id);
               return JSON.stringify({
                 success: true,
                 action: "finalized",
-                message: "Suggestion finalized and synced",
+                commitId: finalized.commitId,
+                name: finalized.name,
+                pushed: finalized.pushed,
+                rejected: rejected.map(h => h.id),
+                message: `Suggestion finalized: ${message}`,
               });
             } else {
               // Discard
               store.discardSuggestion(args.suggestion_id);
-              store.removeSuggestion(args.suggestion_id);
+              store.archiveSuggestion(args.suggestion_id);
               
               await emitter.emitStatus("applied", "Suggestion discarded", args.suggestion_id);
 
@@ -681,7 +1513,7 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
             // Group hunks by file and adjust line numbers
             const files = new Set(suggestion.hunks.map(h => h.file));
             for (const file of files) {
-              const fileHunks = suggestion.hunks.filter(h => h.file === file);
+              const fileHunks = suggestion.hunks.filter(h => h.file === file && !h.fileChange);
               
               // Parse all hunks to get their metadata
               const fileDiffs = parseDiff(`diff --git a/${file} b/${file}\n` + 
@@ -694,7 +1526,13 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
                 const hunkId = hunk.id;
                 
                 // Calculate line offset for this hunk
-                const lineOffset = calculateLineOffset(parsedHunks, appliedHunkIds, file, args.suggestion_id);
+                const lineOffset = calculateLineOffset(
+                  parsedHunks,
+                  appliedHunkIds,
+                  file,
+                  args.suggestion_id,
+                  fileHunks.map(h => h.id)
+                );
                 
                 // Adjust the hunk diff if there's an offset
                 if (lineOffset !== 0) {
@@ -723,6 +1561,7 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
                 createdAt: suggestion.createdAt,
                 hunkStates,
                 remainingCount: store.getRemainingCount(args.suggestion_id),
+                threads: store.getThreads(args.suggestion_id),
               },
             });
           } catch (error) {
@@ -734,21 +1573,8 @@ export const CodeForgePlugin: Plugin = async ({ client, directory, $ }) => {
         },
       }),
     },
-
-    // Cleanup function called when plugin is unloaded
-    cleanup: async () => {
-      if (httpServer) {
-        httpServer.stop();
-        httpServer = null;
-        console.log("[codeforge] HTTP server stopped");
-      }
-      if (store) {
-        store.close();
-        console.log("[codeforge] Database connection closed");
-      }
-    },
   };
-};
+}
 
 // Default export for OpenCode plugin loading
 export default CodeForgePlugin;
@@ -0,0 +1,122 @@
+/**
+ * Delivery of review feedback to the AI
+ *
+ * Every notification is stored first, then injected into the session that
+ * published the suggestion. Failed deliveries are retried with backoff; what
+ * never arrives can still be pulled with the get_review_feedback tool.
+ */
+
+import type { OpencodeClient } from "./event-emitter.ts";
+import type { SuggestionStore } from "./suggestion-store.ts";
+import type { AINotification } from "./types.ts";
+
+/** Attempts before a notification is left for get_review_feedback */
+export const MAX_DELIVERY_ATTEMPTS = 6;
+
+/** Delay before the first retry; doubles with every failed attempt */
+const RETRY_BASE_MS = 5_000;
+
+/**
+ * When to try again after a failed attempt (undefined: give up)
+ * @param attempts Failed attempts so far, including this one
+ */
+export function nextAttemptAt(attempts: number, now = Date.now()): number | undefined {
+  if (attempts >= MAX_DELIVERY_ATTEMPTS) return undefined;
+  return now + RETRY_BASE_MS * 2 ** (attempts - 1);
+}
+
+/**
+ * Main session of the OpenCode instance, for suggestions published before
+ * sessions were recorded
+ */
+async function findMainSession(client: OpencodeClient): Promise<string | undefined> {
+  const sessions = await client.session.list();
+  if (!sessions.data || sessions.data.length === 0) {
+    return undefined;
+  }
+
+  // Find the main session (not a subagent) - subagent titles contain "@"
+  // Also prefer sessions without a parentID (top-level sessions)
+  const mainSession = sessions.data.find(s =>
+    s.title && !s.title.includes("@") && !s.parentID
+  ) || sessions.data.find(s =>
+    s.title && !s.title.includes("@")
+  ) || sessions.data[sessions.data.length - 1]; // fallback to oldest
+
+  return mainSession?.id;
+}
+
+/**
+ * Try to inject a notification into its session
+ * @returns whether it was delivered; failures are recorded for a retry
+ */
+export async function deliverNotification(
+  store: SuggestionStore,
+  client: OpencodeClient,
+  notification: AINotification
+): Promise<boolean> {
+  try {
+    const sessionId = notification.sessionId ?? await findMainSession(client);
+    if (!sessionId) {
+      throw new Error("No session to deliver to");
+    }
+
+    const result = await client.session.prompt({
+      path: { id: sessionId },
+      body: {
+        noReply: true,
+        parts: [{ type: "text", text: notification.message }],
+      },
+    });
+    if (result?.error) {
+      throw new Error(typeof result.error === "string" ? result.error : JSON.stringify(result.error));
+    }
+    store.markNotificationDelivered(notification.id, "session");
+  } catch (error) {
+    const errorMsg = error instanceof Error ? error.message : String(error);
+    store.recordNotificationFailure(notification.id, errorMsg, nextAttemptAt(notification.attempts + 1));
+    return false;
+  }
+
+  // Also show a toast notification (best-effort)
+  try {
+    await client.tui.showToast({
+      body: { message: notification.message, variant: "info" },
+    });
+  } catch {
+    // The AI has it; the toast is only for the user
+  }
+  return true;
+}
+
+/**
+ * Store feedback for the AI and try to deliver it right away
+ */
+export async function sendNotification(
+  store: SuggestionStore,
+  client: OpencodeClient,
+  params: { suggestionId: string; hunkId?: string; message: string }
+): Promise<AINotification> {
+  // Due for a retry only if this attempt never finishes, so the two don't overlap
+  const notification = store.addNotification({ ...params, nextAttemptAt: nextAttemptAt(1) });
+  await deliverNotification(store, client, notification);
+  return store.getNotification(notification.id) ?? notification;
+}
+
+/**
+ * Retry the deliveries that are due
+ * @returns how many were delivered
+ */
+export async function retryDueNotifications(
+  store: SuggestionStore,
+  client: OpencodeClient,
+  now = Date.now()
+): Promise<number> {
+  let delivered = 0;
+  for (const notification of store.getDueNotifications(now)) {
+    if (await deliverNotification(store, client, notification)) {
+      delivered++;
+    }
+  }
+  return delivered;
+}
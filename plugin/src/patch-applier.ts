@@ -5,23 +5,75 @@
  * to the working copy files.
  */
 
-import { parseHunkHeader } from "./diff-parser.ts";
+import { existsSync } from "fs";
+import { chmod, copyFile, mkdir, rename, unlink, writeFile } from "fs/promises";
+import { dirname, join } from "path";
+import { parseHunkHeader, parseFileChange, formatFileChange } from "./diff-parser.ts";
+import type { ApplyStatus, FileChange } from "./types.ts";
+
+export type { ApplyStatus };
 
 export interface ApplyResult {
   success: boolean;
   error?: string;
   /** The new file content after applying the patch */
   newContent?: string;
+  /** How the hunk was applied (set when success is true) */
+  status?: ApplyStatus;
+  /** Lines between the header position and where the hunk was applied */
+  offset?: number;
+  /** Number of leading/trailing context lines that were ignored */
+  fuzz?: number;
+  /** Number of conflict regions written (only for "conflicted") */
+  conflicts?: number;
+}
+
+/**
+ * Options controlling how tolerant applyHunk is of drifted files
+ * All fallbacks are disabled by default, so hunks must match exactly.
+ */
+export interface ApplyOptions {
+  /** How many lines around the header position to search for matching context */
+  maxOffset?: number;
+  /** How many leading/trailing context lines may be ignored when matching */
+  maxFuzz?: number;
+  /** Fall back to a three-way merge, writing conflict markers when it can't merge */
+  merge?: boolean;
+  /** Merge base for the three-way fallback (e.g. Hunk.originalLines); defaults to the hunk's old side */
+  base?: string[];
+  /** Repository root, used to resolve the paths of file-level hunks (renames, copies) */
+  rootDir?: string;
 }
 
+/**
+ * Options for applying hunks to a working copy that keeps being edited
+ */
+export const DRIFT_TOLERANT_OPTIONS: ApplyOptions = {
+  maxOffset: 200,
+  maxFuzz: 2,
+  merge: true,
+};
+
+/** Conflict marker labels */
+const CONFLICT_START = "<<<<<<< working copy";
+const CONFLICT_SEPARATOR = "=======";
+const CONFLICT_END = ">>>>>>> suggestion";
+
+type Change = { type: "context" | "add" | "remove"; content: string };
+
 /**
  * Apply a unified diff hunk to file content
  * 
+ * Tries, in order: the exact header position, nearby offsets, ignoring outer
+ * context lines (fuzz), and finally a three-way merge. Only the first step is
+ * enabled unless options allow the others.
+ * 
  * @param originalContent - The original file content
  * @param hunkDiff - The unified diff hunk (including @@ header)
+ * @param options - Drift tolerance (see ApplyOptions)
  * @returns Result with new content or error
  */
-export function applyHunk(originalContent: string, hunkDiff: string): ApplyResult {
+export function applyHunk(originalContent: string, hunkDiff: string, options: ApplyOptions = {}): ApplyResult {
   const lines = originalContent.split("\n");
   const hunkLines = hunkDiff.split("\n");
 
@@ -37,8 +89,60 @@ export function applyHunk(originalContent: string, hunkDiff: string): ApplyResul
   }
 
   // Extract the changes from the hunk
-  const changes: Array<{ type: "context" | "add" | "remove"; content: string }> = [];
-  
+  const changes = parseChanges(hunkLines);
+
+  // Start position is 0-indexed (header.oldStart is 1-indexed)
+  const startIndex = header.oldStart - 1;
+
+  // Verify context lines match (with some fuzz tolerance)
+  const verifyResult = verifyContext(lines, changes, startIndex);
+  if (verifyResult.success) {
+    return {
+      success: true,
+      newContent: buildContent(lines, changes, startIndex),
+      status: "clean",
+      offset: 0,
+      fuzz: 0,
+    };
+  }
+
+  // Search nearby offsets, then progressively ignore outer context lines
+  const maxOffset = options.maxOffset ?? 0;
+  const maxFuzz = options.maxFuzz ?? 0;
+  for (let fuzz = 0; fuzz <= maxFuzz; fuzz++) {
+    const trimmed = trimContext(changes, fuzz);
+    if (!trimmed) continue;
+
+    const expected = startIndex + trimmed.leading;
+    const position = findMatch(lines, trimmed.changes, expected, maxOffset);
+    if (position !== null) {
+      return {
+        success: true,
+        newContent: buildContent(lines, trimmed.changes, position),
+        status: fuzz === 0 ? "offset" : "fuzzed",
+        offset: position - expected,
+        fuzz,
+      };
+    }
+  }
+
+  // Last resort: three-way merge against the base
+  if (options.merge) {
+    const merged = mergeHunk(lines, changes, startIndex, options.base, maxOffset);
+    if (merged) {
+      return merged;
+    }
+  }
+
+  return { success: false, error: verifyResult.error };
+}
+
+/**
+ * Parse the body of a hunk (everything after the @@ header) into changes
+ */
+function parseChanges(hunkLines: string[]): Change[] {
+  const changes: Change[] = [];
+
   for (let i = 1; i < hunkLines.length; i++) {
     const line = hunkLines[i];
     if (line === undefined) continue;
@@ -58,17 +162,13 @@ export function applyHunk(originalContent: string, hunkDiff: string): ApplyResul
     }
   }
 
-  // Apply the changes
-  // Start position is 0-indexed (header.oldStart is 1-indexed)
-  const startIndex = header.oldStart - 1;
-
-  // Verify context lines match (with some fuzz tolerance)
-  const verifyResult = verifyContext(lines, changes, startIndex);
-  if (!verifyResult.success) {
-    return { success: false, error: verifyResult.error };
-  }
+  return changes;
+}
 
-  // Build the new content
+/**
+ * Build the new file content by applying changes at a verified position
+ */
+function buildContent(lines: string[], changes: Change[], startIndex: number): string {
   const result: string[] = [];
 
   // Add lines before the hunk
@@ -95,7 +195,192 @@ export function applyHunk(originalContent: string, hunkDiff: string): ApplyResul
     result.push(lines[i]!);
   }
 
-  return { success: true, newContent: result.join("\n") };
+  return result.join("\n");
+}
+
+/**
+ * Drop up to `fuzz` context lines from each end of the hunk (like patch's fuzz factor)
+ * Returns null when this fuzz level would not change anything or would leave
+ * nothing of the old side to anchor on.
+ */
+function trimContext(changes: Change[], fuzz: number): { changes: Change[]; leading: number } | null {
+  let leadingContext = 0;
+  while (leadingContext < changes.length && changes[leadingContext]!.type === "context") {
+    leadingContext++;
+  }
+  let trailingContext = 0;
+  while (
+    trailingContext < changes.length - leadingContext &&
+    changes[changes.length - 1 - trailingContext]!.type === "context"
+  ) {
+    trailingContext++;
+  }
+
+  const leading = Math.min(fuzz, leadingContext);
+  const trailing = Math.min(fuzz, trailingContext);
+  if (fuzz > 0 && leading === Math.min(fuzz - 1, leadingContext) && trailing === Math.min(fuzz - 1, trailingContext)) {
+    // Same as the previous fuzz level
+    return null;
+  }
+
+  const trimmed = changes.slice(leading, changes.length - trailing);
+  if (!trimmed.some((c) => c.type !== "add")) {
+    // Nothing left to verify against - would match anywhere
+    return null;
+  }
+
+  return { changes: trimmed, leading };
+}
+
+/**
+ * Find the closest position to `expected` where the hunk's old side matches
+ */
+function findMatch(lines: string[], changes: Change[], expected: number, maxOffset: number): number | null {
+  for (let distance = 0; distance <= maxOffset; distance++) {
+    for (const position of distance === 0 ? [expected] : [expected - distance, expected + distance]) {
+      if (position < 0 || position > lines.length) continue;
+      if (verifyContext(lines, changes, position).success) {
+        return position;
+      }
+    }
+  }
+  return null;
+}
+
+/**
+ * Three-way merge a hunk into drifted file content
+ * 
+ * base   = what the hunk was generated against (Hunk.originalLines or its old side)
+ * ours   = the region of the current file that corresponds to base
+ * theirs = the hunk's new side
+ */
+function mergeHunk(
+  lines: string[],
+  changes: Change[],
+  startIndex: number,
+  base: string[] | undefined,
+  maxOffset: number
+): ApplyResult | null {
+  const baseLines = base ?? changes.filter((c) => c.type !== "add").map((c) => c.content);
+  const theirs = changes.filter((c) => c.type !== "remove").map((c) => c.content);
+  if (baseLines.length === 0) {
+    return null;
+  }
+
+  // Locate the region of the file that corresponds to the base
+  const margin = Math.max(maxOffset, baseLines.length);
+  const windowStart = Math.max(0, startIndex - margin);
+  const windowEnd = Math.min(lines.length, startIndex + baseLines.length + margin);
+  const window = lines.slice(windowStart, windowEnd);
+  const matches = longestCommonSubsequence(baseLines, window);
+  if (matches.length === 0) {
+    return null;
+  }
+
+  const [firstBase, firstFile] = matches[0]!;
+  const [lastBase, lastFile] = matches[matches.length - 1]!;
+  const regionStart = Math.max(0, windowStart + firstFile - firstBase);
+  const regionEnd = Math.min(lines.length, windowStart + lastFile + (baseLines.length - lastBase));
+  const ours = lines.slice(regionStart, regionEnd);
+
+  const merged = diff3Merge(baseLines, ours, theirs);
+  const newLines = [...lines.slice(0, regionStart), ...merged.lines, ...lines.slice(regionEnd)];
+
+  return {
+    success: true,
+    newContent: newLines.join("\n"),
+    status: merged.conflicts > 0 ? "conflicted" : "fuzzed",
+    offset: regionStart - startIndex,
+    conflicts: merged.conflicts,
+  };
+}
+
+/**
+ * Longest common subsequence between two line lists
+ * Returns matched index pairs [aIndex, bIndex] in increasing order
+ */
+function longestCommonSubsequence(a: string[], b: string[]): Array<[number, number]> {
+  const rows = a.length + 1;
+  const cols = b.length + 1;
+  const table = new Array<number>(rows * cols).fill(0);
+
+  for (let i = a.length - 1; i >= 0; i--) {
+    for (let j = b.length - 1; j >= 0; j--) {
+      table[i * cols + j] = normalizeWhitespace(a[i]!) === normalizeWhitespace(b[j]!)
+        ? table[(i + 1) * cols + j + 1]! + 1
+        : Math.max(table[(i + 1) * cols + j]!, table[i * cols + j + 1]!);
+    }
+  }
+
+  const pairs: Array<[number, number]> = [];
+  let i = 0;
+  let j = 0;
+  while (i < a.length && j < b.length) {
+    if (normalizeWhitespace(a[i]!) === normalizeWhitespace(b[j]!)) {
+      pairs.push([i, j]);
+      i++;
+      j++;
+    } else if (table[(i + 1) * cols + j]! >= table[i * cols + j + 1]!) {
+      i++;
+    } else {
+      j++;
+    }
+  }
+  return pairs;
+}
+
+/**
+ * Line-based diff3 merge
+ * Unchanged-on-one-side chunks take the other side; chunks changed differently
+ * on both sides become conflict regions.
+ */
+export function diff3Merge(base: string[], ours: string[], theirs: string[]): { lines: string[]; conflicts: number } {
+  const oursByBase = new Map(longestCommonSubsequence(base, ours));
+  const theirsByBase = new Map(longestCommonSubsequence(base, theirs));
+
+  const result: string[] = [];
+  let conflicts = 0;
+  let b = 0;
+  let o = 0;
+  let t = 0;
+
+  const sameLines = (x: string[], y: string[]) =>
+    x.length === y.length && x.every((line, i) => normalizeWhitespace(line) === normalizeWhitespace(y[i]!));
+
+  while (true) {
+    // Next base line that is unchanged on both sides
+    let k = b;
+    while (k < base.length && !(oursByBase.has(k) && theirsByBase.has(k))) {
+      k++;
+    }
+    const oEnd = k < base.length ? oursByBase.get(k)! : ours.length;
+    const tEnd = k < base.length ? theirsByBase.get(k)! : theirs.length;
+
+    const baseChunk = base.slice(b, k);
+    const oursChunk = ours.slice(o, oEnd);
+    const theirsChunk = theirs.slice(t, tEnd);
+
+    if (sameLines(oursChunk, baseChunk)) {
+      result.push(...theirsChunk);
+    } else if (sameLines(theirsChunk, baseChunk) || sameLines(oursChunk, theirsChunk)) {
+      result.push(...oursChunk);
+    } else {
+      result.push(CONFLICT_START, ...oursChunk, CONFLICT_SEPARATOR, ...theirsChunk, CONFLICT_END);
+      conflicts++;
+    }
+
+    if (k >= base.length) {
+      break;
+    }
+
+    // Stable line - keep the working copy's version
+    result.push(ours[oEnd]!);
+    b = k + 1;
+    o = oEnd + 1;
+    t = tEnd + 1;
+  }
+
+  return { lines: result, conflicts };
 }
 
 /**
@@ -103,7 +388,7 @@ export function applyHunk(originalContent: string, hunkDiff: string): ApplyResul
  */
 function verifyContext(
   originalLines: string[],
-  changes: Array<{ type: "context" | "add" | "remove"; content: string }>,
+  changes: Change[],
   startIndex: number
 ): { success: boolean; error?: string } {
   let originalIndex = startIndex;
@@ -146,9 +431,20 @@ function normalizeWhitespace(line: string): string {
  */
 export async function applyHunkToFile(
   filePath: string,
-  hunkDiff: string
+  hunkDiff: string,
+  options: ApplyOptions = {}
 ): Promise<ApplyResult> {
   try {
+    // File-level hunks (rename, delete, mode change, ...) operate on the file system
+    const fileChange = parseFileChange(hunkDiff);
+    if (fileChange) {
+      const rootDir = options.rootDir ?? rootDirFor(filePath, fileChange);
+      if (!rootDir) {
+        return { success: false, error: `Cannot resolve repository root for ${filePath}` };
+      }
+      return applyFileChange(rootDir, fileChange);
+    }
+
     // Read the file
     const file = Bun.file(filePath);
     const exists = await file.exists();
@@ -165,7 +461,7 @@ export async function applyHunkToFile(
           .join("\n");
         
         await Bun.write(filePath, content);
-        return { success: true, newContent: content };
+        return { success: true, newContent: content, status: "clean" };
       }
       
       return { success: false, error: `File not found: ${filePath}` };
@@ -174,12 +470,19 @@ export async function applyHunkToFile(
     const originalContent = await file.text();
 
     // Apply the hunk
-    const result = applyHunk(originalContent, hunkDiff);
+    const result = applyHunk(originalContent, hunkDiff, options);
     
     if (!result.success) {
       return result;
     }
 
+    // A hunk that empties the file to +0,0 deletes it
+    const header = parseHunkHeader(hunkDiff.split("\n")[0] ?? "");
+    if (header?.newStart === 0 && header.newCount === 0 && result.newContent!.trim() === "") {
+      await unlink(filePath);
+      return result;
+    }
+
     // Write the new content
     await Bun.write(filePath, result.newContent!);
 
@@ -197,16 +500,142 @@ export async function applyHunkToFile(
  */
 export async function applyModifiedHunk(
   filePath: string,
-  modifiedDiff: string
+  modifiedDiff: string,
+  options: ApplyOptions = {}
 ): Promise<ApplyResult> {
   // Modified diff is applied the same way as original
-  return applyHunkToFile(filePath, modifiedDiff);
+  return applyHunkToFile(filePath, modifiedDiff, options);
+}
+
+/**
+ * Derive the repository root from an absolute file path and the change's paths
+ */
+function rootDirFor(filePath: string, change: FileChange): string | null {
+  for (const path of [change.newPath, change.oldPath]) {
+    if (filePath.endsWith("/" + path)) {
+      return filePath.slice(0, -(path.length + 1));
+    }
+  }
+  return null;
+}
+
+/**
+ * Convert a git file mode ("100755") into permission bits
+ * Returns null for modes that aren't regular files (symlinks, submodules)
+ */
+function permissionBits(mode: string | undefined): number | null {
+  if (!mode || !mode.startsWith("100")) {
+    return null;
+  }
+  return parseInt(mode.slice(-3), 8);
+}
+
+/**
+ * Apply a file-level change (rename, copy, delete, mode change, empty add)
+ * Binary content can't be reconstructed from a diff and is reported as an error.
+ */
+export async function applyFileChange(rootDir: string, change: FileChange): Promise<ApplyResult> {
+  const oldPath = join(rootDir, change.oldPath);
+  const newPath = join(rootDir, change.newPath);
+
+  if (change.binary && change.kind !== "delete" && change.kind !== "rename") {
+    return {
+      success: false,
+      error: `Binary change to ${change.newPath} cannot be applied from a diff; restore it through version control`,
+    };
+  }
+
+  try {
+    switch (change.kind) {
+      case "add":
+        if (existsSync(newPath)) {
+          return { success: false, error: `File already exists: ${change.newPath}` };
+        }
+        await mkdir(dirname(newPath), { recursive: true });
+        await writeFile(newPath, "");
+        break;
+
+      case "delete":
+        if (!existsSync(oldPath)) {
+          return { success: false, error: `File not found: ${change.oldPath}` };
+        }
+        await unlink(oldPath);
+        return { success: true, status: "clean" };
+
+      case "rename":
+      case "copy":
+        if (!existsSync(oldPath)) {
+          return { success: false, error: `File not found: ${change.oldPath}` };
+        }
+        if (existsSync(newPath)) {
+          return { success: false, error: `File already exists: ${change.newPath}` };
+        }
+        await mkdir(dirname(newPath), { recursive: true });
+        if (change.kind === "rename") {
+          await rename(oldPath, newPath);
+        } else {
+          await copyFile(oldPath, newPath);
+        }
+        break;
+
+      case "mode":
+        if (!existsSync(newPath)) {
+          return { success: false, error: `File not found: ${change.newPath}` };
+        }
+        break;
+
+      case "binary":
+        // Handled above
+        break;
+    }
+
+    const bits = permissionBits(change.newMode);
+    if (bits !== null && (change.kind === "add" || change.oldMode !== change.newMode)) {
+      await chmod(newPath, bits);
+    }
+
+    return { success: true, status: "clean" };
+  } catch (error) {
+    return {
+      success: false,
+      error: `Failed to apply file change: ${error instanceof Error ? error.message : String(error)}`,
+    };
+  }
+}
+
+/**
+ * Reverse a file-level change (for rejecting or undoing it)
+ */
+export function reverseFileChange(change: FileChange): FileChange {
+  switch (change.kind) {
+    case "add":
+    case "copy":
+      // Undoing an add or copy removes the new file
+      return { kind: "delete", oldPath: change.newPath, newPath: change.newPath, oldMode: change.newMode, binary: change.binary };
+    case "delete":
+      return { kind: "add", oldPath: change.oldPath, newPath: change.oldPath, newMode: change.oldMode, binary: change.binary };
+    case "rename":
+    case "mode":
+    case "binary":
+      return {
+        ...change,
+        oldPath: change.newPath,
+        newPath: change.oldPath,
+        oldMode: change.newMode,
+        newMode: change.oldMode,
+      };
+  }
 }
 
 /**
  * Create a reverse hunk (for undoing an applied hunk)
  */
 export function reverseHunk(hunkDiff: string): string {
+  const fileChange = parseFileChange(hunkDiff);
+  if (fileChange) {
+    return formatFileChange(reverseFileChange(fileChange));
+  }
+
   const lines = hunkDiff.split("\n");
   const result: string[] = [];
 
@@ -0,0 +1,356 @@
+/**
+ * JSON Schema generation for the WebSocket protocol
+ *
+ * Reads the interfaces and type aliases in types.ts and writes them out as a
+ * JSON Schema, one $defs entry per type. Only the TypeScript the protocol
+ * uses is understood: string/number literal unions, arrays, tuples, inline
+ * object types, Array/Map/Record, interface extends and indexed access
+ * (Foo["bar"]). Interfaces are flattened, so extends never shows up in the
+ * schema.
+ *
+ * Run with `bun run schema` after changing types.ts; the schema goes to
+ * src/protocol.schema.json and the nvim plugin's copy.
+ */
+
+import { readFileSync, writeFileSync } from "fs";
+import { join } from "path";
+
+export type JsonSchema = { [key: string]: unknown };
+
+type TypeNode =
+  | { kind: "keyword"; name: string }
+  | { kind: "literal"; value: string | number | boolean }
+  | { kind: "ref"; name: string }
+  | { kind: "array"; items: TypeNode }
+  | { kind: "tuple"; items: TypeNode[] }
+  | { kind: "map"; values: TypeNode }
+  | { kind: "object"; members: Member[] }
+  | { kind: "union"; types: TypeNode[] }
+  | { kind: "index"; target: TypeNode; property: string };
+
+interface Member {
+  name: string;
+  optional: boolean;
+  type: TypeNode;
+  doc?: string;
+}
+
+interface Declaration {
+  name: string;
+  doc?: string;
+  extends: string[];
+  type: TypeNode;
+}
+
+const KEYWORDS = new Set(["string", "number", "boolean", "unknown", "any", "object", "null"]);
+
+/**
+ * Split TypeScript source into tokens, keeping doc comments (other comments are dropped)
+ */
+function tokenize(source: string): string[] {
+  const tokens: string[] = [];
+  const pattern = /\/\*\*[\s\S]*?\*\/|\/\*[\s\S]*?\*\/|\/\/[^\n]*|"(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'|-?\d+(?:\.\d+)?|[A-Za-z_$][\w$]*|\S/g;
+  for (const match of source.matchAll(pattern)) {
+    const token = match[0];
+    if (token.startsWith("/**")) {
+      tokens.push(token);
+    } else if (!token.startsWith("//") && !token.startsWith("/*")) {
+      tokens.push(token);
+    }
+  }
+  return tokens;
+}
+
+/**
+ * First paragraph of a doc comment, on one line
+ */
+function docText(comment: string): string | undefined {
+  const text = comment
+    .replace(/^\/\*\*|\*\/$/g, "")
+    .split("\n")
+    .map((line) => line.replace(/^\s*\*\s?/, "").trim());
+  const first: string[] = [];
+  for (const line of text) {
+    if (line === "") {
+      if (first.length > 0) break;
+      continue;
+    }
+    first.push(line);
+  }
+  return first.length > 0 ? first.join(" ") : undefined;
+}
+
+class Parser {
+  private pos = 0;
+
+  constructor(private tokens: string[]) {}
+
+  private peek(offset = 0): string | undefined {
+    return this.tokens[this.pos + offset];
+  }
+
+  private next(): string {
+    const token = this.tokens[this.pos++];
+    if (token === undefined) throw new Error("Unexpected end of types");
+    return token;
+  }
+
+  private expect(token: string): void {
+    const got = this.next();
+    if (got !== token) throw new Error(`Expected "${token}", got "${got}"`);
+  }
+
+  /** Skip doc comments, returning the last one */
+  private docs(): string | undefined {
+    let doc: string | undefined;
+    while (this.peek()?.startsWith("/**")) {
+      doc = docText(this.next());
+    }
+    return doc;
+  }
+
+  /** Skip a statement we don't model (imports, consts, functions) */
+  private skipStatement(): void {
+    let depth = 0;
+    while (this.peek() !== undefined) {
+      const token = this.next();
+      if (token === "{" || token === "(" || token === "[") depth++;
+      if (token === "}" || token === ")" || token === "]") {
+        depth--;
+        if (depth === 0 && token === "}" && this.peek() !== ";") return;
+      }
+      if (token === ";" && depth === 0) return;
+    }
+  }
+
+  declarations(): Declaration[] {
+    const declarations: Declaration[] = [];
+    while (this.peek() !== undefined) {
+      const doc = this.docs();
+      if (this.peek() === undefined) break;
+      if (this.peek() === "export") this.next();
+
+      const keyword = this.peek();
+      if (keyword === "interface") {
+        this.next();
+        const name = this.next();
+        const bases: string[] = [];
+        if (this.peek() === "extends") {
+          this.next();
+          bases.push(this.next());
+          while (this.peek() === ",") {
+            this.next();
+            bases.push(this.next());
+          }
+        }
+        declarations.push({ name, doc, extends: bases, type: this.objectType() });
+      } else if (keyword === "type") {
+        this.next();
+        const name = this.next();
+        this.expect("=");
+        const type = this.type();
+        if (this.peek() === ";") this.next();
+        declarations.push({ name, doc, extends: [], type });
+      } else {
+        this.skipStatement();
+      }
+    }
+    return declarations;
+  }
+
+  private objectType(): TypeNode {
+    this.expect("{");
+    const members: Member[] = [];
+    for (;;) {
+      const doc = this.docs();
+      if (this.peek() === "}") break;
+      const name = this.next().replace(/^["']|["']$/g, "");
+      const optional = this.peek() === "?";
+      if (optional) this.next();
+      this.expect(":");
+      members.push({ name, optional, type: this.type(), doc });
+      if (this.peek() === ";" || this.peek() === ",") this.next();
+    }
+    this.expect("}");
+    return { kind: "object", members };
+  }
+
+  type(): TypeNode {
+    if (this.peek() === "|") this.next();
+    const types = [this.postfix()];
+    while (this.peek() === "|") {
+      this.next();
+      types.push(this.postfix());
+    }
+    return types.length === 1 ? types[0]! : { kind: "union", types };
+  }
+
+  private postfix(): TypeNode {
+    let type = this.primary();
+    while (this.peek() === "[") {
+      this.next();
+      if (this.peek() === "]") {
+        this.next();
+        type = { kind: "array", items: type };
+      } else {
+        const property = this.next().replace(/^["']|["']$/g, "");
+        this.expect("]");
+        type = { kind: "index", target: type, property };
+      }
+    }
+    return type;
+  }
+
+  private typeArguments(): TypeNode[] {
+    this.expect("<");
+    const args = [this.type()];
+    while (this.peek() === ",") {
+      this.next();
+      args.push(this.type());
+    }
+    this.expect(">");
+    return args;
+  }
+
+  private primary(): TypeNode {
+    const token = this.peek();
+    if (token === "{") return this.objectType();
+    if (token === "(") {
+      this.next();
+      const inner = this.type();
+      this.expect(")");
+      return inner;
+    }
+    if (token === "[") {
+      this.next();
+      const items: TypeNode[] = [];
+      while (this.peek() !== "]") {
+        items.push(this.type());
+        if (this.peek() === ",") this.next();
+      }
+      this.expect("]");
+      return { kind: "tuple", items };
+    }
+
+    const word = this.next();
+    if (/^["']/.test(word)) return { kind: "literal", value: word.slice(1, -1) };
+    if (/^-?\d/.test(word)) return { kind: "literal", value: Number(word) };
+    if (word === "true" || word === "false") return { kind: "literal", value: word === "true" };
+    if (KEYWORDS.has(word)) return { kind: "keyword", name: word };
+    if (word === "Array") return { kind: "array", items: this.typeArguments()[0]! };
+    if (word === "Map" || word === "Record") return { kind: "map", values: this.typeArguments()[1]! };
+    return { kind: "ref", name: word };
+  }
+}
+
+/**
+ * Generate the protocol schema from the source of types.ts
+ */
+export function generateSchema(source: string): JsonSchema {
+  const declarations = new Map<string, Declaration>();
+  for (const declaration of new Parser(tokenize(source)).declarations()) {
+    declarations.set(declaration.name, declaration);
+  }
+
+  /** Own and inherited members of an interface, later ones overriding */
+  const membersOf = (name: string): Member[] => {
+    const declaration = declarations.get(name);
+    if (!declaration) throw new Error(`Unknown type: ${name}`);
+    if (declaration.type.kind !== "object") throw new Error(`${name} is not an object type`);
+    const members = new Map<string, Member>();
+    for (const base of declaration.extends) {
+      for (const member of membersOf(base)) members.set(member.name, member);
+    }
+    for (const member of declaration.type.members) members.set(member.name, member);
+    return [...members.values()];
+  };
+
+  /** Resolve Foo["bar"] to the type of the property */
+  const resolveIndex = (target: TypeNode, property: string): TypeNode => {
+    const members = target.kind === "ref" ? membersOf(target.name) : target.kind === "object" ? target.members : [];
+    const member = members.find((m) => m.name === property);
+    if (!member) throw new Error(`No property ${property} to index`);
+    return member.type;
+  };
+
+  const objectSchema = (members: Member[]): JsonSchema => {
+    const properties: JsonSchema = {};
+    const required: string[] = [];
+    for (const member of members) {
+      const schema = toSchema(member.type);
+      properties[member.name] = member.doc ? { description: member.doc, ...schema } : schema;
+      if (!member.optional) required.push(member.name);
+    }
+    return required.length > 0 ? { type: "object", properties, required } : { type: "object", properties };
+  };
+
+  const toSchema = (node: TypeNode): JsonSchema => {
+    switch (node.kind) {
+      case "keyword":
+        if (node.name === "unknown" || node.name === "any") return {};
+        return { type: node.name };
+      case "literal":
+        return { const: node.value };
+      case "ref":
+        if (!declarations.has(node.name)) throw new Error(`Unknown type: ${node.name}`);
+        return { $ref: `#/$defs/${node.name}` };
+      case "array":
+        return { type: "array", items: toSchema(node.items) };
+      case "tuple":
+        return {
+          type: "array",
+          prefixItems: node.items.map(toSchema),
+          minItems: node.items.length,
+          maxItems: node.items.length,
+        };
+      case "map":
+        return { type: "object", additionalProperties: toSchema(node.values) };
+      case "object":
+        return objectSchema(node.members);
+      case "index":
+        return toSchema(resolveIndex(node.target, node.property));
+      case "union": {
+        if (node.types.every((t) => t.kind === "literal" && typeof t.value === "string")) {
+          return { type: "string", enum: node.types.map((t) => (t as { value: string }).value) };
+        }
+        return { anyOf: node.types.map(toSchema) };
+      }
+    }
+  };
+
+  const defs: JsonSchema = {};
+  for (const declaration of declarations.values()) {
+    const schema = declaration.type.kind === "object"
+      ? objectSchema(membersOf(declaration.name))
+      : toSchema(declaration.type);
+    defs[declaration.name] = declaration.doc ? { description: declaration.doc, ...schema } : schema;
+  }
+
+  return {
+    $schema: "https://json-schema.org/draft/2020-12/schema",
+    $id: "codeforge-protocol",
+    description: "CodeForge WebSocket protocol, generated from plugin/src/types.ts",
+    $defs: defs,
+  };
+}
+
+/**
+ * Where the generated schema is written: the server's copy and the nvim plugin's
+ */
+export const SCHEMA_PATHS = [
+  join(import.meta.dir, "protocol.schema.json"),
+  join(import.meta.dir, "../../nvim/lua/codeforge/protocol.schema.json"),
+];
+
+export function renderSchema(): string {
+  const source = readFileSync(join(import.meta.dir, "types.ts"), "utf-8");
+  return JSON.stringify(generateSchema(source), null, 2) + "\n";
+}
+
+if (import.meta.main) {
+  const rendered = renderSchema();
+  for (const path of SCHEMA_PATHS) {
+    writeFileSync(path, rendered);
+    console.log(`Wrote ${path}`);
+  }
+}
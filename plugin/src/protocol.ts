@@ -0,0 +1,175 @@
+/**
+ * WebSocket protocol version and message validation
+ *
+ * Messages are checked against protocol.schema.json (generated from types.ts
+ * by protocol-schema.ts). The validator covers the JSON Schema keywords the
+ * generator emits: $ref, type, const, enum, properties, required,
+ * additionalProperties, items, prefixItems, minItems, maxItems and anyOf.
+ * Unknown properties are allowed so either side can add fields.
+ */
+
+import schemaJson from "./protocol.schema.json";
+import type { ProtocolError } from "./types.ts";
+import type { JsonSchema } from "./protocol-schema.ts";
+
+/** Highest protocol version this server speaks */
+export const PROTOCOL_VERSION = 1;
+/** Oldest protocol version this server still accepts on subscribe */
+export const MIN_PROTOCOL_VERSION = 1;
+
+const schema = schemaJson as JsonSchema;
+const defs = schema.$defs as Record<string, JsonSchema>;
+
+/**
+ * Agree on a version with a client: the highest both speak
+ * Returns undefined if the client is too old.
+ */
+export function negotiateVersion(clientVersion: number | undefined): number | undefined {
+  const version = Math.min(clientVersion ?? 1, PROTOCOL_VERSION);
+  return version >= MIN_PROTOCOL_VERSION ? version : undefined;
+}
+
+function resolve(node: JsonSchema): JsonSchema {
+  const ref = node.$ref;
+  if (typeof ref !== "string") return node;
+  const def = defs[ref.replace("#/$defs/", "")];
+  if (!def) throw new Error(`Unknown schema reference: ${ref}`);
+  return resolve(def);
+}
+
+function typeOf(value: unknown): string {
+  if (value === null) return "null";
+  if (Array.isArray(value)) return "array";
+  if (typeof value === "number" && Number.isInteger(value)) return "integer";
+  return typeof value;
+}
+
+function matchesType(value: unknown, type: string): boolean {
+  const actual = typeOf(value);
+  return actual === type || (type === "number" && actual === "integer");
+}
+
+/**
+ * The const properties of an object schema (e.g. type: "feedback")
+ * anyOf branches are picked by these, so a message is checked against the
+ * shape its type says rather than every alternative.
+ */
+function constProperties(node: JsonSchema): Array<[string, unknown]> {
+  const properties = (node.properties ?? {}) as Record<string, JsonSchema>;
+  return Object.entries(properties)
+    .filter(([, property]) => "const" in property)
+    .map(([name, property]) => [name, property.const]);
+}
+
+/** Object branches of an anyOf, with nested unions (via $ref) flattened */
+function branches(node: JsonSchema): JsonSchema[] {
+  const resolved = resolve(node);
+  if (Array.isArray(resolved.anyOf)) {
+    return (resolved.anyOf as JsonSchema[]).flatMap(branches);
+  }
+  return [resolved];
+}
+
+function check(node: JsonSchema, value: unknown, path: string, errors: ProtocolError[]): void {
+  const resolved = resolve(node);
+
+  if (Array.isArray(resolved.anyOf)) {
+    const options = branches(resolved);
+    const isObject = typeOf(value) === "object";
+    const candidates = isObject
+      ? options.filter((option) =>
+          constProperties(option).every(([name, expected]) => (value as Record<string, unknown>)[name] === expected)
+        )
+      : options;
+
+    if (candidates.length === 0) {
+      // Name the discriminating field that matched nothing
+      const name = options.map(constProperties).find((props) => props.length > 0)?.[0]?.[0];
+      const got = name ? (value as Record<string, unknown>)[name] : undefined;
+      errors.push(name
+        ? { path: `${path}.${name}`, message: `unknown value ${JSON.stringify(got)}` }
+        : { path, message: "does not match any allowed shape" });
+      return;
+    }
+
+    // Valid if any candidate accepts it; otherwise report the closest one
+    let best: ProtocolError[] | undefined;
+    for (const candidate of candidates) {
+      const candidateErrors: ProtocolError[] = [];
+      check(candidate, value, path, candidateErrors);
+      if (candidateErrors.length === 0) return;
+      if (!best || candidateErrors.length < best.length) best = candidateErrors;
+    }
+    errors.push(...best!);
+    return;
+  }
+
+  if ("const" in resolved && value !== resolved.const) {
+    errors.push({ path, message: `must be ${JSON.stringify(resolved.const)}` });
+    return;
+  }
+
+  if (Array.isArray(resolved.enum) && !resolved.enum.includes(value)) {
+    errors.push({ path, message: `must be one of ${resolved.enum.map((v) => JSON.stringify(v)).join(", ")}` });
+    return;
+  }
+
+  const type = resolved.type as string | undefined;
+  if (type && !matchesType(value, type)) {
+    errors.push({ path, message: `must be ${type === "object" || type === "array" ? "an" : "a"} ${type}` });
+    return;
+  }
+
+  if (type === "object") {
+    const record = value as Record<string, unknown>;
+    const properties = (resolved.properties ?? {}) as Record<string, JsonSchema>;
+    for (const name of (resolved.required ?? []) as string[]) {
+      if (record[name] === undefined) {
+        errors.push({ path: `${path}.${name}`, message: "is required" });
+      }
+    }
+    for (const [name, property] of Object.entries(properties)) {
+      if (record[name] !== undefined) {
+        check(property, record[name], `${path}.${name}`, errors);
+      }
+    }
+    const additional = resolved.additionalProperties as JsonSchema | undefined;
+    if (additional && typeof additional === "object") {
+      for (const [name, item] of Object.entries(record)) {
+        if (!(name in properties)) check(additional, item, `${path}.${name}`, errors);
+      }
+    }
+  }
+
+  if (type === "array") {
+    const items = value as unknown[];
+    const prefix = (resolved.prefixItems ?? []) as JsonSchema[];
+    if (typeof resolved.minItems === "number" && items.length < resolved.minItems) {
+      errors.push({ path, message: `must have at least ${resolved.minItems} items` });
+    }
+    if (typeof resolved.maxItems === "number" && items.length > resolved.maxItems) {
+      errors.push({ path, message: `must have at most ${resolved.maxItems} items` });
+    }
+    items.forEach((item, i) => {
+      const itemSchema = prefix[i] ?? (resolved.items as JsonSchema | undefined);
+      if (itemSchema) check(itemSchema, item, `${path}[${i}]`, errors);
+    });
+  }
+}
+
+/**
+ * Check a message against a type of the protocol schema
+ * @returns what is wrong with it (empty if valid)
+ */
+export function validateMessage(typeName: string, value: unknown): ProtocolError[] {
+  const errors: ProtocolError[] = [];
+  check({ $ref: `#/$defs/${typeName}` }, value, "$", errors);
+  return errors;
+}
+
+/**
+ * One-line summary of validation errors for the response's error field
+ */
+export function describeErrors(errors: ProtocolError[]): string {
+  return errors.map((e) => `${e.path} ${e.message}`).join("; ");
+}
@@ -0,0 +1,491 @@
+/**
+ * Rust item outline and item-aware hunk descriptions
+ *
+ * A small scanner rather than a full grammar: it blanks out comments, strings
+ * and char literals, tracks braces, and reads the header of every item at
+ * module, impl and trait level. That is enough to tell which items a hunk
+ * touches and whether it changes their signature.
+ */
+
+import { existsSync, readFileSync } from "fs";
+import { join } from "path";
+import { parseHunkHeader } from "./diff-parser.ts";
+import type { Hunk } from "./types.ts";
+
+export type RustItemKind =
+  | "fn" | "struct" | "enum" | "union" | "trait" | "impl" | "mod"
+  | "use" | "const" | "static" | "type" | "macro" | "extern";
+
+export interface RustItem {
+  kind: RustItemKind;
+  /** e.g. "fn new", "impl Display for MeshGrid", "use block" */
+  label: string;
+  /** First line, including doc comments and attributes (1-indexed) */
+  startLine: number;
+  /** Line of the opening brace, or of the `;` ending a bodiless item */
+  signatureEndLine: number;
+  endLine: number;
+  children: RustItem[];
+}
+
+// Items whose body holds further items
+const CONTAINERS = new Set<RustItemKind>(["impl", "trait", "mod", "extern"]);
+// Items whose braces belong to the item itself and which end at `;`
+const BODILESS = new Set<RustItemKind>(["use", "const", "static", "type"]);
+
+// Hunk descriptions list at most this many items
+const MAX_DESCRIBED_ITEMS = 3;
+
+/**
+ * Replace comments, string and char literals with spaces, keeping newlines
+ * so offsets and line numbers stay the same
+ */
+export function maskRustSource(source: string): string {
+  const out = source.split("");
+  const n = source.length;
+  const blank = (from: number, to: number) => {
+    for (let k = from; k < to && k < n; k++) {
+      if (out[k] !== "\n") out[k] = " ";
+    }
+  };
+  const isIdent = (c: string | undefined) => c !== undefined && /[A-Za-z0-9_]/.test(c);
+  const rawString = /b?r(#*)"/y;
+
+  let i = 0;
+  while (i < n) {
+    const c = source[i]!;
+    const next = source[i + 1];
+
+    if (c === "/" && next === "/") {
+      let end = source.indexOf("\n", i);
+      if (end < 0) end = n;
+      blank(i, end);
+      i = end;
+      continue;
+    }
+
+    if (c === "/" && next === "*") {
+      // Block comments nest in Rust
+      let depth = 1;
+      let j = i + 2;
+      while (j < n && depth > 0) {
+        if (source[j] === "/" && source[j + 1] === "*") {
+          depth++;
+          j += 2;
+        } else if (source[j] === "*" && source[j + 1] === "/") {
+          depth--;
+          j += 2;
+        } else {
+          j++;
+        }
+      }
+      blank(i, j);
+      i = j;
+      continue;
+    }
+
+    if ((c === "r" || c === "b") && !isIdent(source[i - 1])) {
+      rawString.lastIndex = i;
+      const raw = rawString.exec(source);
+      if (raw) {
+        const close = source.indexOf(`"${raw[1]}`, i + raw[0].length);
+        const end = close < 0 ? n : close + 1 + raw[1]!.length;
+        blank(i, end);
+        i = end;
+        continue;
+      }
+    }
+
+    if (c === '"') {
+      let j = i + 1;
+      while (j < n && source[j] !== '"') {
+        j += source[j] === "\\" ? 2 : 1;
+      }
+      blank(i, j + 1);
+      i = j + 1;
+      continue;
+    }
+
+    if (c === "'") {
+      // A char literal, unless it is a lifetime or label ('a, 'static)
+      if (next === "\\") {
+        let j = i + 2;
+        while (j < n && source[j] !== "'" && source[j] !== "\n") j++;
+        blank(i, j + 1);
+        i = j + 1;
+        continue;
+      }
+      const width = next !== undefined && next.codePointAt(0)! > 0xffff ? 2 : 1;
+      if (next !== undefined && next !== "\n" && source[i + 1 + width] === "'") {
+        blank(i, i + 2 + width);
+        i += 2 + width;
+        continue;
+      }
+    }
+
+    i++;
+  }
+
+  return out.join("");
+}
+
+/**
+ * Drop leading attributes (`#[...]`, `#![...]`) from an item header
+ */
+function stripAttributes(text: string): string {
+  let rest = text.trimStart();
+  while (rest.startsWith("#")) {
+    const open = rest.indexOf("[");
+    if (open < 0) break;
+    let depth = 0;
+    let end = open;
+    for (; end < rest.length; end++) {
+      if (rest[end] === "[") depth++;
+      else if (rest[end] === "]" && --depth === 0) break;
+    }
+    rest = rest.slice(end + 1).trimStart();
+  }
+  return rest;
+}
+
+/**
+ * Skip a balanced `<...>` at the start of text
+ */
+function skipGenerics(text: string): string {
+  if (!text.startsWith("<")) return text;
+  let depth = 0;
+  for (let i = 0; i < text.length; i++) {
+    if (text[i] === "<") depth++;
+    // `->` inside Fn bounds is not a closing bracket
+    else if (text[i] === ">" && text[i - 1] !== "-" && --depth === 0) {
+      return text.slice(i + 1).trimStart();
+    }
+  }
+  return text;
+}
+
+/**
+ * Read the kind and label of an item from its header (the text before its
+ * body or `;`). Returns null if it isn't an item we know.
+ */
+function describeHeader(header: string): { kind: RustItemKind; label: string } | null {
+  const text = stripAttributes(header)
+    .replace(/\s+/g, " ")
+    .replace(/^pub(?:\s*\([^)]*\))? /, "")
+    .trim();
+
+  const fn = text.match(/^(?:(?:default|const|async|unsafe|safe|extern)\s+)*fn\s+(?:r#)?(\w+)/);
+  if (fn) return { kind: "fn", label: `fn ${fn[1]}` };
+
+  const trait = text.match(/^(?:(?:unsafe|auto)\s+)*trait\s+(\w+)/);
+  if (trait) return { kind: "trait", label: `trait ${trait[1]}` };
+
+  const impl = text.match(/^(?:unsafe\s+)?impl\b\s*(.*)$/);
+  if (impl) {
+    const target = skipGenerics(impl[1]!).split(/\bwhere\b/)[0]!.trim();
+    return { kind: "impl", label: target ? `impl ${target}` : "impl" };
+  }
+
+  const named = text.match(/^(struct|enum|union|mod|type|const|static)\s+(?:mut\s+)?(?:r#)?(\w+)/);
+  if (named) return { kind: named[1] as RustItemKind, label: `${named[1]} ${named[2]}` };
+
+  if (/^use\b/.test(text)) return { kind: "use", label: "use block" };
+  if (/^extern crate\b/.test(text)) return { kind: "use", label: "use block" };
+  if (/^(?:unsafe\s+)?extern\b/.test(text)) return { kind: "extern", label: "extern block" };
+
+  const macroRules = text.match(/^macro_rules!\s*(\w+)/);
+  if (macroRules) return { kind: "macro", label: `macro_rules! ${macroRules[1]}` };
+
+  const macro = text.match(/^([\w:]+)\s*!/);
+  if (macro) return { kind: "macro", label: `${macro[1]}!` };
+
+  return null;
+}
+
+/**
+ * Outline the items of a Rust source file
+ * Nested items are listed under impl, trait, mod and extern blocks; items
+ * inside function bodies are not. Consecutive `use` declarations are merged
+ * into one "use block" item.
+ */
+export function parseRustItems(source: string): RustItem[] {
+  const masked = maskRustSource(source);
+
+  interface Frame {
+    item?: RustItem;
+    itemLevel: boolean;
+    children: RustItem[];
+  }
+  const root: Frame = { itemLevel: true, children: [] };
+  const stack: Frame[] = [root];
+
+  // Item header being read at item level
+  let header: { start: number; startLine: number; depth: number } | null = null;
+  let line = 1;
+
+  for (let i = 0; i < masked.length; i++) {
+    const c = masked[i]!;
+    if (c === "\n") {
+      line++;
+      continue;
+    }
+    const frame = stack[stack.length - 1]!;
+
+    if (header) {
+      if (c === "(" || c === "[") {
+        header.depth++;
+      } else if (c === ")" || c === "]") {
+        header.depth--;
+        // An inner attribute belongs to the enclosing item, not the next one
+        if (header.depth === 0 && c === "]" && masked.slice(header.start, i).trimStart().startsWith("#!")) {
+          header = null;
+        }
+      } else if (c === "{") {
+        const described = header.depth > 0 ? null : describeHeader(masked.slice(header.start, i));
+        if (header.depth > 0 || (described && BODILESS.has(described.kind))) {
+          header.depth++;
+        } else {
+          const item: RustItem | undefined = described
+            ? { ...described, startLine: header.startLine, signatureEndLine: line, endLine: line, children: [] }
+            : undefined;
+          if (item) frame.children.push(item);
+          stack.push({ item, itemLevel: !!item && CONTAINERS.has(item.kind), children: item?.children ?? [] });
+          header = null;
+        }
+      } else if (c === "}") {
+        header.depth--;
+      } else if (c === ";" && header.depth === 0) {
+        const described = describeHeader(masked.slice(header.start, i));
+        if (described) {
+          frame.children.push({ ...described, startLine: header.startLine, signatureEndLine: line, endLine: line, children: [] });
+        }
+        header = null;
+      }
+      continue;
+    }
+
+    if (frame.itemLevel) {
+      if (c === "}") {
+        if (stack.length > 1) closeFrame(stack.pop()!, line);
+      } else if (c !== ";" && !/\s/.test(c)) {
+        header = { start: i, startLine: line, depth: 0 };
+        i--; // read this character again as part of the header
+      }
+    } else if (c === "{") {
+      stack.push({ itemLevel: false, children: [] });
+    } else if (c === "}" && stack.length > 1) {
+      closeFrame(stack.pop()!, line);
+    }
+  }
+
+  const lines = source.split("\n");
+  finishItems(root.children, lines);
+  return root.children;
+}
+
+function closeFrame(frame: { item?: RustItem }, line: number): void {
+  if (frame.item) frame.item.endLine = line;
+}
+
+/**
+ * Merge consecutive use declarations and pull doc comments into the items below them
+ */
+function finishItems(items: RustItem[], lines: string[]): void {
+  for (let i = items.length - 1; i > 0; i--) {
+    const prev = items[i - 1]!;
+    const item = items[i]!;
+    if (prev.kind === "use" && item.kind === "use") {
+      prev.endLine = item.endLine;
+      prev.signatureEndLine = item.signatureEndLine;
+      items.splice(i, 1);
+    }
+  }
+
+  for (const item of items) {
+    while (item.startLine > 1 && /^\s*\/\/\/(?!\/)/.test(lines[item.startLine - 2] ?? "")) {
+      item.startLine--;
+    }
+    finishItems(item.children, lines);
+  }
+}
+
+/**
+ * The items enclosing a line, outermost first
+ */
+export function itemPathAt(items: RustItem[], line: number): RustItem[] {
+  const path: RustItem[] = [];
+  let level = items;
+  for (;;) {
+    const item = level.find((it) => line >= it.startLine && line <= it.endLine);
+    if (!item) return path;
+    path.push(item);
+    level = item.children;
+  }
+}
+
+/**
+ * Find an item by its labels, outermost first
+ */
+function findByLabels(items: RustItem[], labels: string[]): RustItem | undefined {
+  let level = items;
+  let found: RustItem | undefined;
+  for (const label of labels) {
+    found = level.find((it) => it.label === label);
+    if (!found) return undefined;
+    level = found.children;
+  }
+  return found;
+}
+
+/**
+ * Rebuild the old version of a file from its new version and all of its hunks
+ * Returns null if the hunks don't match the new source (e.g. the working copy
+ * doesn't hold the change), along with the old line numbers each hunk removes
+ * and the new line numbers it adds.
+ */
+function reconstructOld(
+  newLines: string[],
+  hunks: Hunk[]
+): { oldLines: string[]; changes: Map<string, { removed: number[]; added: number[] }> } | null {
+  const oldLines: string[] = [];
+  const changes = new Map<string, { removed: number[]; added: number[] }>();
+  let newPos = 1;
+
+  for (const hunk of hunks) {
+    const [headerLine, ...body] = hunk.diff.split("\n");
+    const header = parseHunkHeader(headerLine ?? "");
+    if (!header) return null;
+
+    // With no new lines, newStart is the line the hunk comes after
+    const copyUntil = header.newCount === 0 ? header.newStart : header.newStart - 1;
+    if (copyUntil < newPos - 1 || copyUntil > newLines.length) return null;
+    while (newPos <= copyUntil) oldLines.push(newLines[newPos++ - 1]!);
+
+    const change = { removed: [] as number[], added: [] as number[] };
+    for (const line of body) {
+      const marker = line[0];
+      const content = line.slice(1);
+      if (marker === " " || marker === "+") {
+        if (newLines[newPos - 1] !== content) return null;
+        if (marker === " ") oldLines.push(content);
+        else change.added.push(newPos);
+        newPos++;
+      } else if (marker === "-") {
+        oldLines.push(content);
+        change.removed.push(oldLines.length);
+      } else if (line === "" && body[body.length - 1] === line) {
+        // Trailing newline of the diff text
+      } else if (marker !== "\\") {
+        return null;
+      }
+    }
+    changes.set(hunk.id, change);
+  }
+
+  while (newPos <= newLines.length) oldLines.push(newLines[newPos++ - 1]!);
+  return { oldLines, changes };
+}
+
+/**
+ * Label the hunks of one Rust file by the items they touch
+ *
+ * Sets `item` (the outermost item, for grouping) and, unless the hunk already
+ * has one, a description such as "fn calculate_trivial_connection (signature
+ * changed)". Hunks must all be from the same file, in file order; if they don't
+ * match newSource they are returned unchanged.
+ */
+export function describeRustHunks(hunks: Hunk[], newSource: string): Hunk[] {
+  const contentHunks = hunks.filter((h) => !h.fileChange);
+  const newLines = newSource.split("\n");
+  const rebuilt = reconstructOld(newLines, contentHunks);
+  if (!rebuilt) return hunks;
+
+  const oldItems = parseRustItems(rebuilt.oldLines.join("\n"));
+  const newItems = parseRustItems(newSource);
+
+  return hunks.map((hunk) => {
+    const change = rebuilt.changes.get(hunk.id);
+    if (!change) return hunk;
+
+    // Touched items by label path, in the order first seen
+    const touched = new Map<string, { labels: string[]; status?: string }>();
+    const visit = (items: RustItem[], otherItems: RustItem[], line: number, missing: string) => {
+      const path = itemPathAt(items, line);
+      if (path.length === 0) return;
+
+      // Stop at the first item the other side doesn't have: the whole item was added or removed
+      let depth = path.length;
+      for (let d = 1; d <= path.length; d++) {
+        if (!findByLabels(otherItems, path.slice(0, d).map((it) => it.label))) {
+          depth = d;
+          break;
+        }
+      }
+      const labels = path.slice(0, depth).map((it) => it.label);
+      const item = path[depth - 1]!;
+      const key = labels.join("\u0000");
+      const existsOnOtherSide = !!findByLabels(otherItems, labels);
+
+      const entry = touched.get(key) ?? { labels };
+      if (!existsOnOtherSide) {
+        entry.status = missing;
+      } else if (line <= item.signatureEndLine && item.kind === "fn" && !entry.status) {
+        entry.status = "signature changed";
+      }
+      touched.set(key, entry);
+    };
+
+    // Blank lines between items say nothing about them
+    for (const line of change.removed) {
+      if (rebuilt.oldLines[line - 1]!.trim()) visit(oldItems, newItems, line, "removed");
+    }
+    for (const line of change.added) {
+      if (newLines[line - 1]!.trim()) visit(newItems, oldItems, line, "added");
+    }
+
+    const entries = [...touched.values()];
+    if (entries.length === 0) return hunk;
+
+    const parts = entries.map((e) => {
+      const label = e.labels[e.labels.length - 1]!;
+      return e.status ? `${label} (${e.status})` : label;
+    });
+    const shown = parts.slice(0, MAX_DESCRIBED_ITEMS);
+    if (parts.length > MAX_DESCRIBED_ITEMS) shown.push(`and ${parts.length - MAX_DESCRIBED_ITEMS} more`);
+
+    return {
+      ...hunk,
+      item: entries[0]!.labels[0],
+      description: hunk.description ?? shown.join(", "),
+    };
+  });
+}
+
+/**
+ * Label the hunks of every Rust file from the sources in rootDir
+ * Hunks of other files pass through unchanged.
+ */
+export function describeRustFiles(hunks: Hunk[], rootDir: string): Hunk[] {
+  const byFile = new Map<string, Hunk[]>();
+  for (const hunk of hunks) {
+    if (!hunk.file.endsWith(".rs")) continue;
+    byFile.set(hunk.file, [...(byFile.get(hunk.file) ?? []), hunk]);
+  }
+
+  const described = new Map<string, Hunk>();
+  for (const [file, fileHunks] of byFile) {
+    const path = join(rootDir, file);
+    let source = "";
+    try {
+      // A deleted file has no new source; its hunks rebuild the old one alone
+      if (existsSync(path)) source = readFileSync(path, "utf-8");
+    } catch {
+      continue;
+    }
+    for (const hunk of describeRustHunks(fileHunks, source)) {
+      described.set(hunk.id, hunk);
+    }
+  }
+
+  return hunks.map((hunk) => described.get(hunk.id) ?? hunk);
+}
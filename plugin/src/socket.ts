@@ -0,0 +1,94 @@
+/**
+ * Unix domain socket transport for the HTTP + WebSocket server
+ *
+ * Instead of a TCP port (shared by every project and reachable by every local
+ * user), the server can listen on a socket file. Access is then governed by
+ * the file's mode and group: e.g. mode 660 with a group the sandbox user and
+ * the developer share.
+ */
+
+import { chmodSync, chownSync, existsSync, lstatSync, readFileSync, unlinkSync } from "fs";
+import { connect } from "net";
+import { isAbsolute, join } from "path";
+
+/**
+ * Default file mode for the socket: owner and group may connect
+ * (matches the token file, which is group-readable for the same reason)
+ */
+export const DEFAULT_SOCKET_MODE = 0o660;
+
+/**
+ * Resolve a configured socket path; relative paths are relative to the project
+ */
+export function resolveSocketPath(path: string, projectDir: string): string {
+  return isAbsolute(path) ? path : join(projectDir, path);
+}
+
+/**
+ * Parse an octal file mode such as "660" or "0o660"
+ * Returns undefined if it isn't one.
+ */
+export function parseSocketMode(mode: string): number | undefined {
+  const digits = mode.trim().replace(/^0o/i, "");
+  if (!/^[0-7]{3,4}$/.test(digits)) return undefined;
+  return parseInt(digits, 8);
+}
+
+/**
+ * Look up a group ID by name (or take a numeric ID as is)
+ * @param groupFile Group database to read (for tests)
+ */
+export function resolveGroupId(group: string, groupFile = "/etc/group"): number | undefined {
+  if (/^\d+$/.test(group)) return Number(group);
+  try {
+    for (const line of readFileSync(groupFile, "utf-8").split("\n")) {
+      const [name, , gid] = line.split(":");
+      if (name === group && gid !== undefined && /^\d+$/.test(gid)) {
+        return Number(gid);
+      }
+    }
+  } catch {
+    // No group database: only numeric IDs work
+  }
+  return undefined;
+}
+
+/**
+ * Check whether a server is accepting connections on a socket
+ */
+function isSocketLive(path: string): Promise<boolean> {
+  return new Promise((resolve) => {
+    const probe = connect(path);
+    probe.once("connect", () => {
+      probe.destroy();
+      resolve(true);
+    });
+    probe.once("error", () => resolve(false));
+  });
+}
+
+/**
+ * Make room for the server's socket
+ * A socket file left behind by a server that is gone is removed; one that
+ * still accepts connections, or a path that isn't a socket, is left alone.
+ * @returns whether the path is free to listen on
+ */
+export async function prepareSocketPath(path: string): Promise<boolean> {
+  if (!existsSync(path)) return true;
+  if (!lstatSync(path).isSocket()) return false;
+  if (await isSocketLive(path)) return false;
+  unlinkSync(path);
+  return true;
+}
+
+/**
+ * Restrict who may connect to the socket
+ * @param gid Group to hand the socket to (keeps the current group if omitted)
+ */
+export function applySocketPermissions(path: string, mode: number, gid?: number): void {
+  if (gid !== undefined) {
+    // -1 keeps the owner
+    chownSync(path, -1, gid);
+  }
+  chmodSync(path, mode);
+}
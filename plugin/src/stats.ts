@@ -0,0 +1,160 @@
+/**
+ * Review statistics
+ *
+ * How the reviewer has received the AI's suggestions, computed from the
+ * decisions in feedback_log: acceptance over time and by file, directory,
+ * extension and suggestion size, the time from publishing a suggestion to
+ * deciding its hunks, and how much reviewers changed the hunks they modified.
+ */
+
+import { dirname, extname } from "path";
+import { extractHunkContent } from "./diff-parser.ts";
+import type { AcceptanceStats, FeedbackLogEntry, GroupedAcceptanceStats, ReviewStats, StatsQuery } from "./types.ts";
+
+/**
+ * A review decision, with what the stats need to know about its suggestion
+ */
+export interface ReviewRecord {
+  timestamp: number;
+  suggestionId: string;
+  action: FeedbackLogEntry["action"];
+  file: string;
+  originalDiff?: string;
+  modifiedDiff?: string;
+  /** When the suggestion was published (absent once it is removed) */
+  suggestionCreatedAt?: number;
+  /** Hunks in the suggestion, decided and still pending */
+  suggestionHunks: number;
+}
+
+// Files and directories listed unless the query says otherwise
+const DEFAULT_LIST_LIMIT = 20;
+
+// Upper bound (inclusive) and label of each suggestion size bucket
+const SIZE_BUCKETS: Array<[number, string]> = [[1, "1"], [3, "2-3"], [7, "4-7"], [15, "8-15"], [Infinity, "16+"]];
+
+const DAY_MS = 24 * 60 * 60 * 1000;
+
+function acceptance(records: ReviewRecord[]): AcceptanceStats {
+  const count = (action: ReviewRecord["action"]) => records.filter((r) => r.action === action).length;
+  const accepted = count("accept");
+  const modified = count("modify");
+  const total = records.length;
+  return {
+    total,
+    accepted,
+    rejected: count("reject"),
+    modified,
+    acceptanceRate: total === 0 ? 0 : (accepted + modified) / total,
+  };
+}
+
+function groupBy(records: ReviewRecord[], keyOf: (record: ReviewRecord) => string): GroupedAcceptanceStats[] {
+  const groups = new Map<string, ReviewRecord[]>();
+  for (const record of records) {
+    const key = keyOf(record);
+    const group = groups.get(key);
+    if (group) {
+      group.push(record);
+    } else {
+      groups.set(key, [record]);
+    }
+  }
+  return [...groups].map(([key, group]) => ({ key, ...acceptance(group) }));
+}
+
+// Busiest first, then alphabetically
+function busiest(groups: GroupedAcceptanceStats[], limit: number): GroupedAcceptanceStats[] {
+  return groups.sort((a, b) => b.total - a.total || a.key.localeCompare(b.key)).slice(0, limit);
+}
+
+/**
+ * First day (UTC, YYYY-MM-DD) of the period a timestamp falls in
+ * Weeks start on Monday.
+ */
+export function periodStart(timestamp: number, interval: NonNullable<StatsQuery["interval"]>): string {
+  const date = new Date(timestamp);
+  if (interval === "month") {
+    date.setUTCDate(1);
+  } else if (interval === "week") {
+    const sinceMonday = (date.getUTCDay() + 6) % 7;
+    date.setTime(date.getTime() - sinceMonday * DAY_MS);
+  }
+  return date.toISOString().slice(0, 10);
+}
+
+/**
+ * Size bucket of a suggestion with this many hunks
+ */
+export function sizeBucket(hunks: number): string {
+  return SIZE_BUCKETS.find(([max]) => hunks <= max)![1];
+}
+
+function median(values: number[]): number | undefined {
+  if (values.length === 0) return undefined;
+  const sorted = [...values].sort((a, b) => a - b);
+  const middle = Math.floor(sorted.length / 2);
+  return sorted.length % 2 === 1 ? sorted[middle] : (sorted[middle - 1]! + sorted[middle]!) / 2;
+}
+
+/**
+ * How many lines the reviewer added or removed relative to the AI's version
+ * Compares the new side of both diffs: lines in only one of them, by longest
+ * common subsequence.
+ */
+export function modificationSize(originalDiff: string, modifiedDiff: string): number {
+  const a = extractHunkContent(originalDiff).modified;
+  const b = extractHunkContent(modifiedDiff).modified;
+
+  // Longest common subsequence, one row at a time
+  let previous = new Array<number>(b.length + 1).fill(0);
+  for (const line of a) {
+    const current = [0];
+    for (let j = 0; j < b.length; j++) {
+      current.push(line === b[j] ? previous[j]! + 1 : Math.max(previous[j + 1]!, current[j]!));
+    }
+    previous = current;
+  }
+  return a.length + b.length - 2 * previous[b.length]!;
+}
+
+/**
+ * Statistics over review decisions
+ * @param records Decisions, already narrowed to the query's time range
+ */
+export function computeReviewStats(records: ReviewRecord[], query: StatsQuery = {}): ReviewStats {
+  const interval = query.interval ?? "week";
+  const limit = query.limit ?? DEFAULT_LIST_LIMIT;
+
+  const overTime = groupBy(records, (r) => periodStart(r.timestamp, interval))
+    .sort((a, b) => a.key.localeCompare(b.key));
+  const bySuggestionSize = groupBy(records, (r) => sizeBucket(r.suggestionHunks))
+    .sort((a, b) => SIZE_BUCKETS.findIndex(([, label]) => label === a.key) - SIZE_BUCKETS.findIndex(([, label]) => label === b.key));
+
+  const waits = records
+    .filter((r) => r.suggestionCreatedAt !== undefined)
+    .map((r) => Math.max(0, r.timestamp - r.suggestionCreatedAt!));
+
+  const modified = records.filter((r) => r.action === "modify");
+  const sizes = modified
+    .filter((r) => r.originalDiff && r.modifiedDiff)
+    .map((r) => modificationSize(r.originalDiff!, r.modifiedDiff!));
+  const totalLinesChanged = sizes.reduce((sum, size) => sum + size, 0);
+
+  return {
+    overall: acceptance(records),
+    overTime,
+    byFile: busiest(groupBy(records, (r) => r.file), limit),
+    byDirectory: busiest(groupBy(records, (r) => dirname(r.file)), limit),
+    byExtension: busiest(groupBy(records, (r) => extname(r.file) || "(none)"), Infinity),
+    bySuggestionSize,
+    timeToReview: { medianMs: median(waits), samples: waits.length },
+    modifications: {
+      count: modified.length,
+      rate: records.length === 0 ? 0 : modified.length / records.length,
+      medianLinesChanged: median(sizes),
+      meanLinesChanged: sizes.length === 0 ? undefined : totalLinesChanged / sizes.length,
+      totalLinesChanged,
+    },
+  };
+}
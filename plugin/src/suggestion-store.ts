@@ -4,6 +4,8 @@
  */
 
 import { Database } from "bun:sqlite";
+import { appendFileSync, mkdirSync } from "fs";
+import { dirname } from "path";
 import type {
   Suggestion,
   Hunk,
@@ -11,7 +13,172 @@ import type {
   HunkFeedback,
   FeedbackLogEntry,
   ListSuggestionsResult,
+  HistoryQuery,
+  HistoryEntry,
+  HistorySearchResult,
+  ReviewComment,
+  HunkVersion,
+  CheckResult,
+  CheckStatus,
+  CheckDiagnostic,
+  SuggestionEvent,
+  AINotification,
 } from "./types.ts";
+import { matchHunks, parseHunkHeader, extractHunkContent, splitHunkDiff, mergeHunkDiffs } from "./diff-parser.ts";
+import type { ExportEntry } from "./export.ts";
+import type { ReviewRecord } from "./stats.ts";
+
+/**
+ * Row shape of the feedback_log table
+ */
+interface FeedbackLogRow {
+  timestamp: number;
+  suggestion_id: string;
+  hunk_id: string;
+  action: string;
+  file: string;
+  original_diff: string | null;
+  modified_diff: string | null;
+  comment: string | null;
+  applied: number;
+}
+
+function feedbackLogRowToEntry(row: FeedbackLogRow): FeedbackLogEntry {
+  return {
+    timestamp: row.timestamp,
+    suggestionId: row.suggestion_id,
+    hunkId: row.hunk_id,
+    action: row.action as "accept" | "reject" | "modify",
+    file: row.file,
+    originalDiff: row.original_diff ?? undefined,
+    modifiedDiff: row.modified_diff ?? undefined,
+    comment: row.comment ?? undefined,
+    applied: row.applied === 1,
+  };
+}
+
+/**
+ * Row shape of the check_results table
+ */
+interface CheckResultRow {
+  suggestion_id: string;
+  name: string;
+  command: string;
+  status: string;
+  exit_code: number | null;
+  output: string | null;
+  diagnostics: string;
+  started_at: number;
+  finished_at: number | null;
+}
+
+function checkResultRowToResult(row: CheckResultRow): CheckResult {
+  return {
+    suggestionId: row.suggestion_id,
+    name: row.name,
+    command: row.command,
+    status: row.status as CheckStatus,
+    exitCode: row.exit_code ?? undefined,
+    output: row.output ?? undefined,
+    diagnostics: JSON.parse(row.diagnostics),
+    startedAt: row.started_at,
+    finishedAt: row.finished_at ?? undefined,
+  };
+}
+
+/**
+ * Row shape of the hunk_states columns that make up a HunkState
+ */
+interface HunkStateRow {
+  reviewed: number;
+  revision_requested: number;
+  instruction: string | null;
+}
+
+function hunkStateRowToState(row: HunkStateRow): HunkState {
+  const state: HunkState = { reviewed: row.reviewed === 1 };
+  if (row.revision_requested === 1) {
+    state.state = "revision_requested";
+    state.instruction = row.instruction ?? undefined;
+  }
+  return state;
+}
+
+/**
+ * Row shape of the review_comments table
+ */
+interface ReviewCommentRow {
+  id: number;
+  suggestion_id: string;
+  hunk_id: string;
+  author: string;
+  body: string;
+  created_at: number;
+}
+
+function reviewCommentRowToComment(row: ReviewCommentRow): ReviewComment {
+  return {
+    id: row.id,
+    suggestionId: row.suggestion_id,
+    hunkId: row.hunk_id,
+    author: row.author as ReviewComment["author"],
+    body: row.body,
+    createdAt: row.created_at,
+  };
+}
+
+/**
+ * Row shape of the notifications table
+ */
+interface NotificationRow {
+  id: number;
+  suggestion_id: string;
+  hunk_id: string | null;
+  session_id: string | null;
+  message: string;
+  created_at: number;
+  delivered_at: number | null;
+  delivered_via: string | null;
+  attempts: number;
+  last_error: string | null;
+}
+
+function notificationRowToNotification(row: NotificationRow): AINotification {
+  return {
+    id: row.id,
+    suggestionId: row.suggestion_id,
+    hunkId: row.hunk_id ?? undefined,
+    sessionId: row.session_id ?? undefined,
+    message: row.message,
+    createdAt: row.created_at,
+    deliveredAt: row.delivered_at ?? undefined,
+    deliveredVia: (row.delivered_via ?? undefined) as AINotification["deliveredVia"],
+    attempts: row.attempts,
+    lastError: row.last_error ?? undefined,
+  };
+}
+
+/**
+ * How many events the log keeps; editors further behind get a snapshot
+ */
+export const EVENT_LOG_SIZE = 1000;
+
+/**
+ * Turn free text into an FTS5 query
+ * Every word is quoted so punctuation common in code (`foo.bar`, `a-b`, `::`)
+ * can't be read as query syntax; a trailing `*` keeps prefix matching.
+ */
+export function toFtsQuery(text: string): string {
+  return text
+    .split(/\s+/)
+    .filter((word) => word !== "" && word !== "*")
+    .map((word) => {
+      const prefix = word.endsWith("*");
+      const term = prefix ? word.slice(0, -1) : word;
+      return `"${term.replace(/"/g, '""')}"${prefix ? "*" : ""}`;
+    })
+    .join(" ");
+}
 
 /**
  * SQLite-backed store for suggestions
@@ -20,6 +187,8 @@ import type {
 export class SuggestionStore {
   private db: Database;
   private feedbackLogPath?: string;
+  /** JSONL entries held back until the enclosing transaction commits */
+  private pendingFeedbackLog?: FeedbackLogEntry[];
   private dbPath: string;
   private isHealthy: boolean;
 
@@ -56,7 +225,7 @@ export class SuggestionStore {
       
       this.db = new Database(dbPath);
       this.initSchema();
-      this.cleanupCompletedSuggestions();
+      this.archiveCompletedSuggestions();
       this.isHealthy = true;
       
       if (dbExists) {
@@ -107,6 +276,7 @@ export class SuggestionStore {
           diff TEXT NOT NULL,
           original_start_line INTEGER,
           original_lines TEXT,
+          file_change TEXT,
           FOREIGN KEY (suggestion_id) REFERENCES suggestions(id) ON DELETE CASCADE
         );
 
@@ -131,9 +301,85 @@ export class SuggestionStore {
           applied INTEGER NOT NULL
         );
 
+        CREATE TABLE IF NOT EXISTS hunk_versions (
+          hunk_id TEXT NOT NULL,
+          suggestion_id TEXT NOT NULL,
+          version INTEGER NOT NULL,
+          diff TEXT NOT NULL,
+          original_start_line INTEGER,
+          original_lines TEXT,
+          instruction TEXT,
+          created_at INTEGER NOT NULL,
+          PRIMARY KEY (hunk_id, version)
+        );
+
+        CREATE TABLE IF NOT EXISTS review_comments (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          suggestion_id TEXT NOT NULL,
+          hunk_id TEXT NOT NULL,
+          author TEXT NOT NULL,
+          body TEXT NOT NULL,
+          created_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS check_results (
+          suggestion_id TEXT NOT NULL,
+          name TEXT NOT NULL,
+          command TEXT NOT NULL,
+          status TEXT NOT NULL,
+          exit_code INTEGER,
+          output TEXT,
+          diagnostics TEXT NOT NULL DEFAULT '[]',
+          started_at INTEGER NOT NULL,
+          finished_at INTEGER,
+          PRIMARY KEY (suggestion_id, name),
+          FOREIGN KEY (suggestion_id) REFERENCES suggestions(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS events (
+          seq INTEGER PRIMARY KEY AUTOINCREMENT,
+          type TEXT NOT NULL,
+          payload TEXT NOT NULL,
+          created_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS notifications (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          suggestion_id TEXT NOT NULL,
+          hunk_id TEXT,
+          session_id TEXT,
+          message TEXT NOT NULL,
+          created_at INTEGER NOT NULL,
+          delivered_at INTEGER,
+          delivered_via TEXT,
+          attempts INTEGER NOT NULL DEFAULT 0,
+          next_attempt_at INTEGER,
+          last_error TEXT
+        );
+
         CREATE INDEX IF NOT EXISTS idx_hunks_suggestion ON hunks(suggestion_id);
         CREATE INDEX IF NOT EXISTS idx_hunk_states_suggestion ON hunk_states(suggestion_id);
+        CREATE INDEX IF NOT EXISTS idx_review_comments_hunk ON review_comments(suggestion_id, hunk_id);
+        CREATE INDEX IF NOT EXISTS idx_notifications_undelivered ON notifications(delivered_at, next_attempt_at);
       `);
+
+      // Columns added after the initial schema (databases created by older versions)
+      this.addColumnIfMissing("hunks", "file_change", "TEXT");
+      this.addColumnIfMissing("feedback_log", "hunk_snapshot", "TEXT");
+      this.addColumnIfMissing("feedback_log", "undone_at", "INTEGER");
+      this.addColumnIfMissing("suggestions", "archived_at", "INTEGER");
+      this.addColumnIfMissing("hunks", "version", "INTEGER NOT NULL DEFAULT 1");
+      this.addColumnIfMissing("hunk_states", "revision_requested", "INTEGER NOT NULL DEFAULT 0");
+      this.addColumnIfMissing("hunk_states", "instruction", "TEXT");
+      this.addColumnIfMissing("feedback_log", "remainder_hunk_id", "TEXT");
+      this.addColumnIfMissing("hunks", "description", "TEXT");
+      this.addColumnIfMissing("hunks", "diagnostics", "TEXT");
+      this.addColumnIfMissing("hunks", "item", "TEXT");
+      this.addColumnIfMissing("hunk_states", "state_version", "INTEGER NOT NULL DEFAULT 1");
+      this.addColumnIfMissing("suggestions", "session_id", "TEXT");
+      this.addColumnIfMissing("hunks", "file_mode", "TEXT");
+
+      this.initHistoryIndex();
     } catch (error) {
       const errorMsg = error instanceof Error ? error.message : String(error);
       console.error(`[SuggestionStore] Failed to initialize database schema:`, errorMsg);
@@ -142,14 +388,95 @@ export class SuggestionStore {
   }
 
   /**
-   * Clean up completed suggestions on startup
+   * Add a column to an existing table if it isn't there yet
    */
-  private cleanupCompletedSuggestions(): void {
-    // Remove suggestions with no remaining hunks
+  private addColumnIfMissing(table: string, column: string, definition: string): void {
+    const columns = this.db.query(`PRAGMA table_info(${table})`).all() as Array<{ name: string }>;
+    if (!columns.some((c) => c.name === column)) {
+      this.db.exec(`ALTER TABLE ${table} ADD COLUMN ${column} ${definition}`);
+    }
+  }
+
+  /**
+   * Create the full-text index over suggestion history
+   * One row per suggestion (description), unreviewed hunk (diff) and feedback
+   * entry (diffs and comment). Triggers keep it in sync with the base tables.
+   */
+  private initHistoryIndex(): void {
+    const exists = this.db.query(
+      `SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'history_fts'`
+    ).get();
+
     this.db.exec(`
-      DELETE FROM suggestions 
-      WHERE id NOT IN (SELECT DISTINCT suggestion_id FROM hunks)
+      CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(
+        description, file, diff, comment,
+        kind UNINDEXED, ref_id UNINDEXED, suggestion_id UNINDEXED
+      );
+
+      CREATE TRIGGER IF NOT EXISTS history_suggestions_ai AFTER INSERT ON suggestions BEGIN
+        INSERT INTO history_fts (description, file, diff, comment, kind, ref_id, suggestion_id)
+        VALUES (new.description, '', '', '', 'suggestion', new.id, new.id);
+      END;
+
+      CREATE TRIGGER IF NOT EXISTS history_suggestions_au AFTER UPDATE OF description ON suggestions BEGIN
+        UPDATE history_fts SET description = new.description WHERE kind = 'suggestion' AND ref_id = new.id;
+      END;
+
+      CREATE TRIGGER IF NOT EXISTS history_suggestions_ad AFTER DELETE ON suggestions BEGIN
+        DELETE FROM history_fts WHERE kind = 'suggestion' AND ref_id = old.id;
+      END;
+
+      CREATE TRIGGER IF NOT EXISTS history_hunks_ai AFTER INSERT ON hunks BEGIN
+        INSERT INTO history_fts (description, file, diff, comment, kind, ref_id, suggestion_id)
+        VALUES ('', new.file, new.diff, '', 'hunk', new.id, new.suggestion_id);
+      END;
+
+      CREATE TRIGGER IF NOT EXISTS history_hunks_ad AFTER DELETE ON hunks BEGIN
+        DELETE FROM history_fts WHERE kind = 'hunk' AND ref_id = old.id;
+      END;
+
+      CREATE TRIGGER IF NOT EXISTS history_hunks_au AFTER UPDATE OF diff ON hunks BEGIN
+        UPDATE history_fts SET diff = new.diff WHERE kind = 'hunk' AND ref_id = new.id;
+      END;
+
+      CREATE TRIGGER IF NOT EXISTS history_feedback_ai AFTER INSERT ON feedback_log BEGIN
+        INSERT INTO history_fts (description, file, diff, comment, kind, ref_id, suggestion_id)
+        VALUES ('', new.file, COALESCE(new.original_diff, '') || char(10) || COALESCE(new.modified_diff, ''),
+                COALESCE(new.comment, ''), 'decision', CAST(new.id AS TEXT), new.suggestion_id);
+      END;
+
+      CREATE TRIGGER IF NOT EXISTS history_feedback_ad AFTER DELETE ON feedback_log BEGIN
+        DELETE FROM history_fts WHERE kind = 'decision' AND ref_id = CAST(old.id AS TEXT);
+      END;
     `);
+
+    // Index what was stored before the index existed
+    if (!exists) {
+      this.db.exec(`
+        INSERT INTO history_fts (description, file, diff, comment, kind, ref_id, suggestion_id)
+        SELECT description, '', '', '', 'suggestion', id, id FROM suggestions;
+
+        INSERT INTO history_fts (description, file, diff, comment, kind, ref_id, suggestion_id)
+        SELECT '', file, diff, '', 'hunk', id, suggestion_id FROM hunks;
+
+        INSERT INTO history_fts (description, file, diff, comment, kind, ref_id, suggestion_id)
+        SELECT '', file, COALESCE(original_diff, '') || char(10) || COALESCE(modified_diff, ''),
+               COALESCE(comment, ''), 'decision', CAST(id AS TEXT), suggestion_id FROM feedback_log;
+      `);
+    }
+  }
+
+  /**
+   * Archive completed suggestions on startup
+   * Suggestions with no remaining hunks drop out of the review list but stay
+   * searchable through searchHistory.
+   */
+  private archiveCompletedSuggestions(): void {
+    this.db.run(
+      `UPDATE suggestions SET archived_at = ?
+       WHERE archived_at IS NULL AND id NOT IN (SELECT DISTINCT suggestion_id FROM hunks)`,
+      [Date.now()]
+    );
   }
 
   /**
@@ -162,39 +489,53 @@ export class SuggestionStore {
     files: string[];
     hunks: Hunk[];
     workingDirectory: string;
+    /** OpenCode session publishing it; feedback is delivered there */
+    sessionId?: string;
   }): Suggestion {
     const createdAt = Date.now();
 
     // Insert suggestion
     this.db.run(
-      `INSERT INTO suggestions (id, jj_change_id, description, status, created_at, working_directory)
-       VALUES (?, ?, ?, 'pending', ?, ?)`,
-      [params.id, params.jjChangeId, params.description, createdAt, params.workingDirectory]
+      `INSERT INTO suggestions (id, jj_change_id, description, status, created_at, working_directory, session_id)
+       VALUES (?, ?, ?, 'pending', ?, ?, ?)`,
+      [params.id, params.jjChangeId, params.description, createdAt, params.workingDirectory, params.sessionId ?? null]
     );
 
     // Insert hunks and their states
-    const insertHunk = this.db.prepare(
-      `INSERT INTO hunks (id, suggestion_id, file, diff, original_start_line, original_lines)
-       VALUES (?, ?, ?, ?, ?, ?)`
-    );
-    const insertState = this.db.prepare(
-      `INSERT INTO hunk_states (hunk_id, suggestion_id, reviewed)
-       VALUES (?, ?, 0)`
-    );
-
     for (const hunk of params.hunks) {
-      insertHunk.run(
+      this.insertHunk(params.id, hunk);
+    }
+
+    return this.getSuggestion(params.id)!;
+  }
+
+  /**
+   * Insert a hunk with an unreviewed state
+   */
+  private insertHunk(suggestionId: string, hunk: Hunk): void {
+    this.db.run(
+      `INSERT INTO hunks (id, suggestion_id, file, diff, original_start_line, original_lines, file_change, version, description, diagnostics, item, file_mode)
+       VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+      [
         hunk.id,
-        params.id,
+        suggestionId,
         hunk.file,
         hunk.diff,
         hunk.originalStartLine ?? null,
-        hunk.originalLines ? JSON.stringify(hunk.originalLines) : null
-      );
-      insertState.run(hunk.id, params.id);
-    }
-
-    return this.getSuggestion(params.id)!;
+        hunk.originalLines ? JSON.stringify(hunk.originalLines) : null,
+        hunk.fileChange ? JSON.stringify(hunk.fileChange) : null,
+        hunk.version ?? 1,
+        hunk.description ?? null,
+        hunk.diagnostics?.length ? JSON.stringify(hunk.diagnostics) : null,
+        hunk.item ?? null,
+        hunk.fileMode ?? null,
+      ]
+    );
+    this.db.run(
+      `INSERT INTO hunk_states (hunk_id, suggestion_id, reviewed, state_version)
+       VALUES (?, ?, 0, ?)`,
+      [hunk.id, suggestionId, hunk.stateVersion ?? 1]
+    );
   }
 
   /**
@@ -203,7 +544,7 @@ export class SuggestionStore {
   getSuggestion(id: string): Suggestion | undefined {
     const row = this.db.query(
       `SELECT id, jj_change_id, description, status, created_at, working_directory
-       FROM suggestions WHERE id = ?`
+       FROM suggestions WHERE id = ? AND archived_at IS NULL`
     ).get(id) as {
       id: string;
       jj_change_id: string;
@@ -217,7 +558,8 @@ export class SuggestionStore {
 
     // Get hunks
     const hunkRows = this.db.query(
-      `SELECT id, file, diff, original_start_line, original_lines
+      `SELECT id, file, diff, original_start_line, original_lines, file_change, version, description, diagnostics, item, file_mode,
+              (SELECT state_version FROM hunk_states WHERE hunk_states.hunk_id = hunks.id) AS state_version
        FROM hunks WHERE suggestion_id = ?`
     ).all(id) as Array<{
       id: string;
@@ -225,6 +567,13 @@ export class SuggestionStore {
       diff: string;
       original_start_line: number | null;
       original_lines: string | null;
+      file_change: string | null;
+      version: number;
+      description: string | null;
+      diagnostics: string | null;
+      item: string | null;
+      file_mode: string | null;
+      state_version: number | null;
     }>;
 
     const hunks: Hunk[] = hunkRows.map((h) => ({
@@ -234,16 +583,23 @@ export class SuggestionStore {
       diff: h.diff,
       originalStartLine: h.original_start_line ?? undefined,
       originalLines: h.original_lines ? JSON.parse(h.original_lines) : undefined,
+      fileChange: h.file_change ? JSON.parse(h.file_change) : undefined,
+      fileMode: h.file_mode ?? undefined,
+      version: h.version,
+      description: h.description ?? undefined,
+      diagnostics: h.diagnostics ? JSON.parse(h.diagnostics) : undefined,
+      item: h.item ?? undefined,
+      stateVersion: h.state_version ?? undefined,
     }));
 
     // Get hunk states
     const stateRows = this.db.query(
-      `SELECT hunk_id, reviewed FROM hunk_states WHERE suggestion_id = ?`
-    ).all(id) as Array<{ hunk_id: string; reviewed: number }>;
+      `SELECT hunk_id, reviewed, revision_requested, instruction FROM hunk_states WHERE suggestion_id = ?`
+    ).all(id) as Array<HunkStateRow & { hunk_id: string }>;
 
     const hunkStates = new Map<string, HunkState>();
     for (const s of stateRows) {
-      hunkStates.set(s.hunk_id, { reviewed: s.reviewed === 1 });
+      hunkStates.set(s.hunk_id, hunkStateRowToState(s));
     }
 
     // Get unique files from hunks
@@ -267,7 +623,8 @@ export class SuggestionStore {
    */
   getHunk(suggestionId: string, hunkId: string): Hunk | undefined {
     const row = this.db.query(
-      `SELECT id, file, diff, original_start_line, original_lines
+      `SELECT id, file, diff, original_start_line, original_lines, file_change, version, description, diagnostics, item, file_mode,
+              (SELECT state_version FROM hunk_states WHERE hunk_states.hunk_id = hunks.id) AS state_version
        FROM hunks WHERE id = ? AND suggestion_id = ?`
     ).get(hunkId, suggestionId) as {
       id: string;
@@ -275,6 +632,13 @@ export class SuggestionStore {
       diff: string;
       original_start_line: number | null;
       original_lines: string | null;
+      file_change: string | null;
+      version: number;
+      description: string | null;
+      diagnostics: string | null;
+      item: string | null;
+      file_mode: string | null;
+      state_version: number | null;
     } | null;
 
     if (!row) return undefined;
@@ -285,19 +649,359 @@ export class SuggestionStore {
       diff: row.diff,
       originalStartLine: row.original_start_line ?? undefined,
       originalLines: row.original_lines ? JSON.parse(row.original_lines) : undefined,
+      fileChange: row.file_change ? JSON.parse(row.file_change) : undefined,
+      fileMode: row.file_mode ?? undefined,
+      version: row.version,
+      description: row.description ?? undefined,
+      diagnostics: row.diagnostics ? JSON.parse(row.diagnostics) : undefined,
+      item: row.item ?? undefined,
+      stateVersion: row.state_version ?? undefined,
     };
   }
 
+  /**
+   * Attach compiler diagnostics to a suggestion's hunks
+   * Replaces what every hunk had; hunks without a matching diagnostic get none.
+   */
+  setHunkDiagnostics(suggestionId: string, diagnostics: CheckDiagnostic[]): void {
+    const byHunk = new Map<string, CheckDiagnostic[]>();
+    for (const diagnostic of diagnostics) {
+      if (!diagnostic.hunkId) continue;
+      byHunk.set(diagnostic.hunkId, [...(byHunk.get(diagnostic.hunkId) ?? []), diagnostic]);
+    }
+
+    this.db.transaction(() => {
+      this.db.run(`UPDATE hunks SET diagnostics = NULL WHERE suggestion_id = ?`, [suggestionId]);
+      for (const [hunkId, hunkDiagnostics] of byHunk) {
+        this.db.run(
+          `UPDATE hunks SET diagnostics = ? WHERE id = ? AND suggestion_id = ?`,
+          [JSON.stringify(hunkDiagnostics), hunkId, suggestionId]
+        );
+      }
+    })();
+  }
+
   /**
    * Get hunk state
    */
   getHunkState(suggestionId: string, hunkId: string): HunkState | undefined {
     const row = this.db.query(
-      `SELECT reviewed FROM hunk_states WHERE hunk_id = ? AND suggestion_id = ?`
-    ).get(hunkId, suggestionId) as { reviewed: number } | null;
+      `SELECT reviewed, revision_requested, instruction FROM hunk_states WHERE hunk_id = ? AND suggestion_id = ?`
+    ).get(hunkId, suggestionId) as HunkStateRow | null;
 
     if (!row) return undefined;
-    return { reviewed: row.reviewed === 1 };
+    return hunkStateRowToState(row);
+  }
+
+  /**
+   * Record a change to a hunk or its review state (see Hunk.stateVersion)
+   */
+  private bumpStateVersion(suggestionId: string, hunkId: string): void {
+    this.db.run(
+      `UPDATE hunk_states SET state_version = state_version + 1 WHERE hunk_id = ? AND suggestion_id = ?`,
+      [hunkId, suggestionId]
+    );
+  }
+
+  /**
+   * Get the decision that took a hunk out of review, if it still stands
+   */
+  getDecision(suggestionId: string, hunkId: string): { action: FeedbackLogEntry["action"]; timestamp: number } | undefined {
+    const row = this.db.query(
+      `SELECT action, timestamp FROM feedback_log
+       WHERE suggestion_id = ? AND hunk_id = ? AND undone_at IS NULL
+       ORDER BY timestamp DESC, id DESC
+       LIMIT 1`
+    ).get(suggestionId, hunkId) as { action: FeedbackLogEntry["action"]; timestamp: number } | null;
+    return row ?? undefined;
+  }
+
+  /**
+   * Ask for a new version of a hunk (request_changes)
+   * The hunk stays up for review in the revision_requested state until the AI
+   * revises it or the reviewer decides it anyway.
+   */
+  requestRevision(suggestionId: string, hunkId: string, instruction: string): boolean {
+    const result = this.db.run(
+      `UPDATE hunk_states SET revision_requested = 1, instruction = ?, state_version = state_version + 1
+       WHERE hunk_id = ? AND suggestion_id = ? AND reviewed = 0`,
+      [instruction, hunkId, suggestionId]
+    );
+    return result.changes > 0;
+  }
+
+  /**
+   * Replace a hunk with a revised version, keeping the old one in hunk_versions
+   * Returns the new hunk and the version it replaced.
+   */
+  reviseHunk(
+    suggestionId: string,
+    hunkId: string,
+    revision: { diff: string; originalStartLine?: number; originalLines?: string[] }
+  ): { hunk: Hunk; previous: HunkVersion } | undefined {
+    const current = this.getHunk(suggestionId, hunkId);
+    if (!current) return undefined;
+
+    const state = this.getHunkState(suggestionId, hunkId);
+    const previous: HunkVersion = {
+      hunkId,
+      version: current.version ?? 1,
+      diff: current.diff,
+      originalLines: current.originalLines,
+      originalStartLine: current.originalStartLine,
+      instruction: state?.instruction,
+      createdAt: Date.now(),
+    };
+
+    this.db.transaction(() => {
+      this.insertHunkVersion(suggestionId, previous);
+      this.db.run(
+        `UPDATE hunks SET diff = ?, original_start_line = ?, original_lines = ?, version = ?
+         WHERE id = ? AND suggestion_id = ?`,
+        [
+          revision.diff,
+          revision.originalStartLine ?? current.originalStartLine ?? null,
+          revision.originalLines
+            ? JSON.stringify(revision.originalLines)
+            : current.originalLines ? JSON.stringify(current.originalLines) : null,
+          previous.version + 1,
+          hunkId,
+          suggestionId,
+        ]
+      );
+      this.db.run(
+        `UPDATE hunk_states SET revision_requested = 0, instruction = NULL, state_version = state_version + 1
+         WHERE hunk_id = ? AND suggestion_id = ?`,
+        [hunkId, suggestionId]
+      );
+    })();
+
+    return { hunk: this.getHunk(suggestionId, hunkId)!, previous };
+  }
+
+  /**
+   * Keep a superseded version of a hunk
+   */
+  private insertHunkVersion(suggestionId: string, version: HunkVersion): void {
+    this.db.run(
+      `INSERT INTO hunk_versions (hunk_id, suggestion_id, version, diff, original_start_line, original_lines, instruction, created_at)
+       VALUES (?, ?, ?, ?, ?, ?, ?, ?)`,
+      [
+        version.hunkId,
+        suggestionId,
+        version.version,
+        version.diff,
+        version.originalStartLine ?? null,
+        version.originalLines ? JSON.stringify(version.originalLines) : null,
+        version.instruction ?? null,
+        version.createdAt,
+      ]
+    );
+  }
+
+  /**
+   * Re-publish a suggestion from a fresh diff of its (evolved) change
+   * New hunks are matched to the previous ones with matchHunks. Unchanged hunks
+   * keep their review state (decided ones stay decided), changed ones get a new
+   * version and go back up for review (even if already decided), pending hunks that disappeared are
+   * dropped and new ones are added as pending.
+   * Returns the IDs affected, or undefined if the suggestion isn't active.
+   */
+  updateSuggestion(
+    suggestionId: string,
+    params: { jjChangeId: string; description?: string; hunks: Hunk[] }
+  ): { suggestion: Suggestion; added: string[]; changed: string[]; removed: string[] } | undefined {
+    const suggestion = this.getSuggestion(suggestionId);
+    if (!suggestion) return undefined;
+
+    // Reviews don't touch the change, so decided hunks (rejected ones too) are
+    // still in its diff as published; match them against what was published,
+    // not the reviewer's modification
+    const decided = this.db.query(
+      `SELECT id, hunk_id, file, original_diff, hunk_snapshot
+       FROM feedback_log
+       WHERE suggestion_id = ? AND undone_at IS NULL
+       ORDER BY timestamp, id`
+    ).all(suggestionId) as Array<{
+      id: number;
+      hunk_id: string;
+      file: string;
+      original_diff: string | null;
+      hunk_snapshot: string | null;
+    }>;
+    const decisions = new Map<string, { logId: number; hunk: Hunk }>();
+    for (const row of decided) {
+      const snapshot: Hunk = row.hunk_snapshot
+        ? JSON.parse(row.hunk_snapshot)
+        : { id: row.hunk_id, file: row.file, diff: row.original_diff ?? "" };
+      decisions.set(row.hunk_id, { logId: row.id, hunk: snapshot });
+    }
+
+    const pending = new Map(suggestion.hunks.map((h) => [h.id, h]));
+    const match = matchHunks(
+      [...suggestion.hunks, ...[...decisions.values()].map((d) => d.hunk)],
+      params.hunks
+    );
+
+    // New hunks get fresh IDs that no earlier hunk of this suggestion used
+    const used = this.usedHunkIds(suggestionId);
+    const added = match.added.map((hunk) => ({ ...hunk, id: this.freshHunkId(hunk.id, used) }));
+
+    const removed = match.removed.filter((h) => pending.has(h.id));
+    const now = Date.now();
+
+    this.db.transaction(() => {
+      // Same change, possibly moved: refresh the diff without touching its state
+      for (const hunk of match.unchanged) {
+        if (!pending.has(hunk.id)) continue;
+        this.db.run(
+          `UPDATE hunks SET diff = ?, original_start_line = ?, original_lines = ?
+           WHERE id = ? AND suggestion_id = ?`,
+          [
+            hunk.diff,
+            hunk.originalStartLine ?? null,
+            hunk.originalLines ? JSON.stringify(hunk.originalLines) : null,
+            hunk.id,
+            suggestionId,
+          ]
+        );
+      }
+
+      for (const hunk of match.changed) {
+        const current = pending.get(hunk.id);
+        if (current) {
+          const state = this.getHunkState(suggestionId, hunk.id);
+          const version = current.version ?? 1;
+          this.insertHunkVersion(suggestionId, {
+            hunkId: hunk.id,
+            version,
+            diff: current.diff,
+            originalLines: current.originalLines,
+            originalStartLine: current.originalStartLine,
+            instruction: state?.instruction,
+            createdAt: now,
+          });
+          this.db.run(
+            `UPDATE hunks SET diff = ?, original_start_line = ?, original_lines = ?, file_change = ?, file_mode = ?, version = ?
+             WHERE id = ? AND suggestion_id = ?`,
+            [
+              hunk.diff,
+              hunk.originalStartLine ?? null,
+              hunk.originalLines ? JSON.stringify(hunk.originalLines) : null,
+              hunk.fileChange ? JSON.stringify(hunk.fileChange) : null,
+              hunk.fileMode ?? null,
+              version + 1,
+              hunk.id,
+              suggestionId,
+            ]
+          );
+          this.db.run(
+            `UPDATE hunk_states SET revision_requested = 0, instruction = NULL, state_version = state_version + 1
+             WHERE hunk_id = ? AND suggestion_id = ?`,
+            [hunk.id, suggestionId]
+          );
+        } else {
+          // Already decided: the decision covered another version, so review it again
+          const decision = decisions.get(hunk.id)!;
+          const version = decision.hunk.version ?? 1;
+          this.insertHunkVersion(suggestionId, {
+            hunkId: hunk.id,
+            version,
+            diff: decision.hunk.diff,
+            originalLines: decision.hunk.originalLines,
+            originalStartLine: decision.hunk.originalStartLine,
+            createdAt: now,
+          });
+          this.insertHunk(suggestionId, {
+            ...hunk,
+            version: version + 1,
+            stateVersion: (decision.hunk.stateVersion ?? 1) + 1,
+          });
+          this.db.run(`UPDATE feedback_log SET undone_at = ? WHERE id = ?`, [now, decision.logId]);
+        }
+      }
+
+      for (const hunk of removed) {
+        this.db.run(`DELETE FROM hunk_states WHERE hunk_id = ?`, [hunk.id]);
+        this.db.run(`DELETE FROM hunks WHERE id = ?`, [hunk.id]);
+      }
+
+      for (const hunk of added) {
+        this.insertHunk(suggestionId, hunk);
+      }
+
+      const remainingCount = this.getRemainingCount(suggestionId);
+      const decidedCount = this.db.query(
+        `SELECT COUNT(*) as count FROM feedback_log WHERE suggestion_id = ? AND undone_at IS NULL`
+      ).get(suggestionId) as { count: number };
+      const status = remainingCount === 0 ? "complete" : decidedCount.count > 0 ? "partial" : "pending";
+      this.db.run(
+        `UPDATE suggestions SET jj_change_id = ?, description = ?, status = ? WHERE id = ?`,
+        [params.jjChangeId, params.description ?? suggestion.description, status, suggestionId]
+      );
+    })();
+
+    return {
+      suggestion: this.getSuggestion(suggestionId)!,
+      added: added.map((h) => h.id),
+      changed: match.changed.map((h) => h.id),
+      removed: removed.map((h) => h.id),
+    };
+  }
+
+  /**
+   * IDs any hunk of the suggestion has had (pending, decided or superseded)
+   */
+  private usedHunkIds(suggestionId: string): Set<string> {
+    return new Set(
+      (this.db.query(
+        `SELECT id FROM hunks WHERE suggestion_id = ?
+         UNION SELECT hunk_id FROM feedback_log WHERE suggestion_id = ?
+         UNION SELECT hunk_id FROM hunk_versions WHERE suggestion_id = ?`
+      ).all(suggestionId, suggestionId, suggestionId) as Array<{ id: string }>).map((r) => r.id)
+    );
+  }
+
+  /**
+   * Pick an ID in the same suggestion:file:N scheme that isn't in used (and claim it)
+   */
+  private freshHunkId(id: string, used: Set<string>): string {
+    const base = id.replace(/:\d+$/, "");
+    let fresh = id;
+    for (let i = 0; used.has(fresh); i++) {
+      fresh = `${base}:${i}`;
+    }
+    used.add(fresh);
+    return fresh;
+  }
+
+  /**
+   * Get the superseded versions of a hunk, oldest first
+   */
+  getHunkVersions(suggestionId: string, hunkId: string): HunkVersion[] {
+    const rows = this.db.query(
+      `SELECT hunk_id, version, diff, original_start_line, original_lines, instruction, created_at
+       FROM hunk_versions WHERE suggestion_id = ? AND hunk_id = ?
+       ORDER BY version`
+    ).all(suggestionId, hunkId) as Array<{
+      hunk_id: string;
+      version: number;
+      diff: string;
+      original_start_line: number | null;
+      original_lines: string | null;
+      instruction: string | null;
+      created_at: number;
+    }>;
+
+    return rows.map((row) => ({
+      hunkId: row.hunk_id,
+      version: row.version,
+      diff: row.diff,
+      originalStartLine: row.original_start_line ?? undefined,
+      originalLines: row.original_lines ? JSON.parse(row.original_lines) : undefined,
+      instruction: row.instruction ?? undefined,
+      createdAt: row.created_at,
+    }));
   }
 
   /**
@@ -312,7 +1016,12 @@ export class SuggestionStore {
     const hunk = this.getHunk(suggestionId, hunkId);
     if (!hunk) return false;
 
-    // Log feedback before removing
+    // Not a decision: the hunk stays up for review until the AI revises it
+    if (feedback.action === "request_changes") {
+      return this.requestRevision(suggestionId, hunkId, feedback.comment ?? "");
+    }
+
+    // Log feedback before removing (with a snapshot so the decision can be undone)
     this.logFeedback({
       timestamp: Date.now(),
       suggestionId,
@@ -323,7 +1032,7 @@ export class SuggestionStore {
       modifiedDiff: feedback.modifiedDiff,
       comment: feedback.comment,
       applied,
-    });
+    }, hunk);
 
     // Remove the hunk and its state
     this.db.run(`DELETE FROM hunk_states WHERE hunk_id = ?`, [hunkId]);
@@ -337,6 +1046,251 @@ export class SuggestionStore {
     return true;
   }
 
+  /**
+   * Decide a selection of a hunk's lines (feedback.partial)
+   * The decision is logged against the hunk like any other; the lines left out
+   * of the selection go back up for review as a new pending hunk.
+   * Returns that hunk, or undefined if the hunk doesn't exist.
+   */
+  decidePartially(
+    suggestionId: string,
+    hunkId: string,
+    feedback: HunkFeedback,
+    applied: boolean,
+    remainingDiff: string
+  ): Hunk | undefined {
+    const hunk = this.getHunk(suggestionId, hunkId);
+    if (!hunk) return undefined;
+
+    const header = parseHunkHeader(remainingDiff.split("\n")[0] ?? "");
+    const remainder: Hunk = {
+      id: this.freshHunkId(hunkId, this.usedHunkIds(suggestionId)),
+      file: hunk.file,
+      diff: remainingDiff,
+      originalLines: extractHunkContent(remainingDiff).original,
+      originalStartLine: header?.oldStart ?? hunk.originalStartLine,
+      description: hunk.description,
+      item: hunk.item,
+      fileMode: hunk.fileMode,
+    };
+
+    this.withFeedbackLog(() => this.db.transaction(() => {
+      this.updateHunkState(suggestionId, hunkId, feedback, applied);
+      this.db.run(
+        `UPDATE feedback_log SET remainder_hunk_id = ? WHERE id = (SELECT MAX(id) FROM feedback_log WHERE suggestion_id = ? AND hunk_id = ?)`,
+        [remainder.id, suggestionId, hunkId]
+      );
+      this.insertHunk(suggestionId, remainder);
+      this.db.run(`UPDATE suggestions SET status = 'partial' WHERE id = ?`, [suggestionId]);
+    })());
+
+    return this.getHunk(suggestionId, remainder.id);
+  }
+
+  /**
+   * Decide several hunks of a suggestion in one transaction
+   * Either every decision is recorded or none: if a hunk is gone the
+   * transaction is rolled back and this throws.
+   */
+  decideHunks(suggestionId: string, decisions: Array<{ feedback: HunkFeedback; applied: boolean }>): void {
+    this.withFeedbackLog(() => this.db.transaction(() => {
+      for (const { feedback, applied } of decisions) {
+        if (!this.updateHunkState(suggestionId, feedback.hunkId, feedback, applied)) {
+          throw new Error(`Hunk not found: ${feedback.hunkId}`);
+        }
+      }
+    })());
+  }
+
+  /**
+   * Run a transaction, appending its JSONL feedback entries only once it commits
+   * (nothing is written if it throws)
+   */
+  private withFeedbackLog(run: () => void): void {
+    if (this.pendingFeedbackLog) {
+      run();
+      return;
+    }
+
+    const pending: FeedbackLogEntry[] = [];
+    this.pendingFeedbackLog = pending;
+    try {
+      run();
+    } finally {
+      this.pendingFeedbackLog = undefined;
+    }
+    this.appendToFeedbackLog(pending);
+  }
+
+  /**
+   * Undo the review decision for a hunk, putting it back up for review
+   * If hunkId is omitted, the most recent decision in the suggestion is undone.
+   * Undoing a partial decision folds the rest of the hunk back in, so it is
+   * refused once that rest has been decided too.
+   * Returns the restored hunk, the decision that was undone and the ID of the
+   * pending rest it replaces (partial decisions).
+   */
+  undoHunkState(
+    suggestionId: string,
+    hunkId?: string
+  ): { hunk: Hunk; entry: FeedbackLogEntry; replaces?: string } | undefined {
+    const suggestion = this.db.query(
+      `SELECT id FROM suggestions WHERE id = ? AND archived_at IS NULL`
+    ).get(suggestionId);
+    if (!suggestion) return undefined;
+
+    const row = this.db.query(
+      `SELECT id, timestamp, suggestion_id, hunk_id, action, file, original_diff, modified_diff, comment, applied, hunk_snapshot, remainder_hunk_id
+       FROM feedback_log
+       WHERE suggestion_id = ? AND (? IS NULL OR hunk_id = ?) AND undone_at IS NULL
+       ORDER BY timestamp DESC, id DESC
+       LIMIT 1`
+    ).get(suggestionId, hunkId ?? null, hunkId ?? null) as (FeedbackLogRow & {
+      id: number;
+      hunk_snapshot: string | null;
+      remainder_hunk_id: string | null;
+    }) | null;
+
+    if (!row) return undefined;
+
+    // Already back up for review
+    if (this.getHunk(suggestionId, row.hunk_id)) return undefined;
+
+    // The rest of a partially decided hunk must still be pending to fold it back in
+    const replaces = row.remainder_hunk_id ?? undefined;
+    if (replaces && !this.getHunk(suggestionId, replaces)) return undefined;
+
+    const hunk: Hunk = row.hunk_snapshot
+      ? JSON.parse(row.hunk_snapshot)
+      : { id: row.hunk_id, file: row.file, diff: row.original_diff ?? "" };
+
+    this.db.transaction(() => {
+      if (replaces) {
+        this.db.run(`DELETE FROM hunk_states WHERE hunk_id = ?`, [replaces]);
+        this.db.run(`DELETE FROM hunks WHERE id = ?`, [replaces]);
+      }
+      this.insertHunk(suggestionId, { ...hunk, stateVersion: (hunk.stateVersion ?? 1) + 1 });
+      this.db.run(`UPDATE feedback_log SET undone_at = ? WHERE id = ?`, [Date.now(), row.id]);
+
+      // Back to partial if other decisions still stand, otherwise pending
+      const decided = this.db.query(
+        `SELECT COUNT(*) as count FROM feedback_log WHERE suggestion_id = ? AND undone_at IS NULL`
+      ).get(suggestionId) as { count: number };
+      this.db.run(`UPDATE suggestions SET status = ? WHERE id = ?`, [
+        decided.count > 0 ? "partial" : "pending",
+        suggestionId,
+      ]);
+    })();
+
+    return { hunk, entry: feedbackLogRowToEntry(row), replaces };
+  }
+
+  /**
+   * A pending hunk that can be split or merged: not file-level, not awaiting a revision
+   */
+  private getReshapeableHunk(suggestionId: string, hunkId: string): Hunk | undefined {
+    const hunk = this.getHunk(suggestionId, hunkId);
+    if (!hunk || hunk.fileChange) return undefined;
+    if (this.getHunkState(suggestionId, hunkId)?.state === "revision_requested") return undefined;
+    return hunk;
+  }
+
+  /**
+   * Split a pending hunk into smaller ones (see splitHunkDiff)
+   * The first piece keeps the hunk's ID, version, state and thread; the others
+   * are added as pending hunks under fresh IDs. Every piece keeps the hunk's
+   * description.
+   * Returns the pieces in order, or undefined if the hunk can't be split (there).
+   */
+  splitHunk(suggestionId: string, hunkId: string, atLine?: number): Hunk[] | undefined {
+    const hunk = this.getReshapeableHunk(suggestionId, hunkId);
+    if (!hunk) return undefined;
+
+    const diffs = splitHunkDiff(hunk.diff, atLine);
+    if (!diffs) return undefined;
+
+    const used = this.usedHunkIds(suggestionId);
+    const pieces: Hunk[] = diffs.map((diff, i) => ({
+      id: i === 0 ? hunkId : this.freshHunkId(hunkId, used),
+      file: hunk.file,
+      diff,
+      originalLines: extractHunkContent(diff).original,
+      originalStartLine: parseHunkHeader(diff.split("\n")[0] ?? "")?.oldStart,
+      description: hunk.description,
+      item: hunk.item,
+      fileMode: hunk.fileMode,
+      version: i === 0 ? hunk.version : undefined,
+    }));
+
+    this.db.transaction(() => {
+      const [first, ...rest] = pieces;
+      this.db.run(
+        `UPDATE hunks SET diff = ?, original_start_line = ?, original_lines = ? WHERE id = ? AND suggestion_id = ?`,
+        [first!.diff, first!.originalStartLine ?? null, JSON.stringify(first!.originalLines), hunkId, suggestionId]
+      );
+      this.bumpStateVersion(suggestionId, hunkId);
+      for (const piece of rest) {
+        this.insertHunk(suggestionId, piece);
+      }
+    })();
+
+    return pieces.map((piece) => this.getHunk(suggestionId, piece.id)!);
+  }
+
+  /**
+   * Join two pending hunks of the same file into one (see mergeHunkDiffs)
+   * The hunks must be next to each other in the file: no other hunk, pending or
+   * decided, in between. The one that comes first keeps its ID, version and
+   * state and takes over the other's thread; the other is removed. gap holds
+   * the unchanged lines between the two when they don't touch.
+   * Returns the merged hunk, or undefined if the hunks can't be merged.
+   */
+  mergeHunks(suggestionId: string, hunkIds: [string, string], gap: string[] = []): Hunk | undefined {
+    const hunks = hunkIds.map((id) => this.getReshapeableHunk(suggestionId, id));
+    if (hunkIds[0] === hunkIds[1] || !hunks[0] || !hunks[1] || hunks[0].file !== hunks[1].file) return undefined;
+
+    const oldStart = (hunk: Hunk) => parseHunkHeader(hunk.diff.split("\n")[0] ?? "")?.oldStart ?? 0;
+    const [first, second] = (hunks as [Hunk, Hunk]).sort((a, b) => oldStart(a) - oldStart(b));
+
+    // Nothing else in the file, pending or already decided, may lie between them
+    const decided = this.db.query(
+      `SELECT original_diff FROM feedback_log
+       WHERE suggestion_id = ? AND file = ? AND undone_at IS NULL AND original_diff IS NOT NULL`
+    ).all(suggestionId, first.file) as Array<{ original_diff: string }>;
+    const others = [
+      ...this.getSuggestion(suggestionId)!.hunks.filter((h) => h.file === first.file && !hunkIds.includes(h.id)),
+      ...decided.map((row) => ({ id: "", file: first.file, diff: row.original_diff })),
+    ];
+    if (others.some((h) => oldStart(h) >= oldStart(first) && oldStart(h) <= oldStart(second))) return undefined;
+
+    const diff = mergeHunkDiffs(first.diff, second.diff, gap);
+    if (!diff) return undefined;
+
+    const descriptions = [...new Set([first.description, second.description].filter((d): d is string => !!d))];
+
+    this.db.transaction(() => {
+      this.db.run(
+        `UPDATE hunks SET diff = ?, original_lines = ?, description = ? WHERE id = ? AND suggestion_id = ?`,
+        [
+          diff,
+          JSON.stringify(extractHunkContent(diff).original),
+          descriptions.length > 0 ? descriptions.join("; ") : null,
+          first.id,
+          suggestionId,
+        ]
+      );
+      this.bumpStateVersion(suggestionId, first.id);
+      this.db.run(
+        `UPDATE review_comments SET hunk_id = ? WHERE hunk_id = ? AND suggestion_id = ?`,
+        [first.id, second.id, suggestionId]
+      );
+      this.db.run(`DELETE FROM hunk_states WHERE hunk_id = ?`, [second.id]);
+      this.db.run(`DELETE FROM hunks WHERE id = ?`, [second.id]);
+    })();
+
+    return this.getHunk(suggestionId, first.id);
+  }
+
   /**
    * Get count of remaining (pending) hunks
    */
@@ -347,6 +1301,39 @@ export class SuggestionStore {
     return row?.count ?? 0;
   }
 
+  /**
+   * The decided hunks of a suggestion, in the order they were decided
+   * Accepted hunks carry the diff that was accepted (the modified diff for
+   * modify, which includes accepted selections of a hunk's lines); rejected
+   * hunks carry the diff that was rejected. Undone decisions are left out.
+   */
+  getDecidedHunks(suggestionId: string): { accepted: Hunk[]; rejected: Hunk[] } {
+    const rows = this.db.query(
+      `SELECT hunk_id, action, file, original_diff, modified_diff, hunk_snapshot
+       FROM feedback_log
+       WHERE suggestion_id = ? AND undone_at IS NULL
+       ORDER BY timestamp, id`
+    ).all(suggestionId) as Array<{
+      hunk_id: string;
+      action: string;
+      file: string;
+      original_diff: string | null;
+      modified_diff: string | null;
+      hunk_snapshot: string | null;
+    }>;
+
+    const accepted: Hunk[] = [];
+    const rejected: Hunk[] = [];
+    for (const row of rows) {
+      const snapshot: Hunk = row.hunk_snapshot
+        ? JSON.parse(row.hunk_snapshot)
+        : { id: row.hunk_id, file: row.file, diff: row.original_diff ?? "" };
+      const hunk = { ...snapshot, diff: row.modified_diff ?? snapshot.diff };
+      (row.action === "reject" ? rejected : accepted).push(hunk);
+    }
+    return { accepted, rejected };
+  }
+
   /**
    * List all suggestions, optionally filtered by working directory
    * Only returns suggestions with pending hunks
@@ -357,6 +1344,7 @@ export class SuggestionStore {
              COUNT(h.id) as hunk_count
       FROM suggestions s
       LEFT JOIN hunks h ON h.suggestion_id = s.id
+      WHERE s.archived_at IS NULL
       GROUP BY s.id
       HAVING hunk_count > 0
     `;
@@ -368,9 +1356,10 @@ export class SuggestionStore {
                COUNT(h.id) as hunk_count
         FROM suggestions s
         LEFT JOIN hunks h ON h.suggestion_id = s.id
-        WHERE s.working_directory = ? 
+        WHERE s.archived_at IS NULL
+          AND (s.working_directory = ? 
            OR s.working_directory LIKE '%/' || ?
-           OR ? LIKE '%/' || s.working_directory
+           OR ? LIKE '%/' || s.working_directory)
         GROUP BY s.id
         HAVING hunk_count > 0
       `;
@@ -421,6 +1410,136 @@ export class SuggestionStore {
     return result.changes > 0;
   }
 
+  /**
+   * Archive a suggestion once it has been finalized or discarded
+   * It disappears from getSuggestion/listSuggestions but its hunks and
+   * feedback stay searchable through searchHistory.
+   */
+  archiveSuggestion(suggestionId: string): boolean {
+    const result = this.db.run(
+      `UPDATE suggestions SET archived_at = ? WHERE id = ? AND archived_at IS NULL`,
+      [Date.now(), suggestionId]
+    );
+    return result.changes > 0;
+  }
+
+  /**
+   * Search active and archived suggestions, hunks and review decisions
+   * With a text query results are ranked by relevance, otherwise newest first.
+   */
+  searchHistory(params: HistoryQuery = {}): HistorySearchResult {
+    const ftsQuery = params.query ? toFtsQuery(params.query) : "";
+
+    const matches = ftsQuery
+      ? `SELECT kind, ref_id, suggestion_id,
+                snippet(history_fts, -1, '[', ']', '...', 12) AS snippet,
+                bm25(history_fts) AS rank
+         FROM history_fts WHERE history_fts MATCH ?`
+      : `SELECT kind, ref_id, suggestion_id, NULL AS snippet, 0 AS rank FROM history_fts`;
+    const args: Array<string | number> = ftsQuery ? [ftsQuery] : [];
+    const where: string[] = [];
+
+    if (params.file) {
+      where.push(`(COALESCE(l.file, h.file) = ? OR COALESCE(l.file, h.file) LIKE ? || '/%')`);
+      args.push(params.file, params.file.replace(/\/+$/, ""));
+    }
+    if (params.action) {
+      where.push(`l.action = ?`);
+      args.push(params.action);
+    }
+    if (params.since !== undefined) {
+      where.push(`COALESCE(l.timestamp, s.created_at) >= ?`);
+      args.push(params.since);
+    }
+    if (params.until !== undefined) {
+      where.push(`COALESCE(l.timestamp, s.created_at) <= ?`);
+      args.push(params.until);
+    }
+    if (params.jjChangeId) {
+      where.push(`s.jj_change_id LIKE ? || '%'`);
+      args.push(params.jjChangeId);
+    }
+    if (params.workingDirectory) {
+      where.push(`(s.working_directory = ?
+        OR s.working_directory LIKE '%/' || ?
+        OR ? LIKE '%/' || s.working_directory)`);
+      args.push(params.workingDirectory, params.workingDirectory, params.workingDirectory);
+    }
+
+    const limit = Math.min(Math.max(params.limit ?? 50, 1), 500);
+    args.push(limit);
+
+    const rows = this.db.query(
+      `SELECT m.kind, m.ref_id, m.snippet,
+              s.id AS suggestion_id, s.jj_change_id, s.description, s.status, s.created_at,
+              s.archived_at, s.working_directory,
+              l.hunk_id AS log_hunk_id, l.action, l.file AS log_file, l.timestamp,
+              l.original_diff, l.modified_diff, l.comment, l.undone_at,
+              h.file AS hunk_file, h.diff AS hunk_diff
+       FROM (${matches}) m
+       JOIN suggestions s ON s.id = m.suggestion_id
+       LEFT JOIN feedback_log l ON m.kind = 'decision' AND l.id = CAST(m.ref_id AS INTEGER)
+       LEFT JOIN hunks h ON m.kind = 'hunk' AND h.id = m.ref_id
+       ${where.length > 0 ? `WHERE ${where.join(" AND ")}` : ""}
+       ORDER BY ${ftsQuery ? "m.rank, " : ""}COALESCE(l.timestamp, s.created_at) DESC
+       LIMIT ?`
+    ).all(...args) as Array<{
+      kind: HistoryEntry["kind"];
+      ref_id: string;
+      snippet: string | null;
+      suggestion_id: string;
+      jj_change_id: string;
+      description: string;
+      status: string;
+      created_at: number;
+      archived_at: number | null;
+      working_directory: string;
+      log_hunk_id: string | null;
+      action: string | null;
+      log_file: string | null;
+      timestamp: number | null;
+      original_diff: string | null;
+      modified_diff: string | null;
+      comment: string | null;
+      undone_at: number | null;
+      hunk_file: string | null;
+      hunk_diff: string | null;
+    }>;
+
+    const entries: HistoryEntry[] = rows.map((row) => {
+      const entry: HistoryEntry = {
+        kind: row.kind,
+        suggestionId: row.suggestion_id,
+        jjChangeId: row.jj_change_id,
+        description: row.description,
+        status: row.status as Suggestion["status"],
+        workingDirectory: row.working_directory,
+        createdAt: row.created_at,
+        archivedAt: row.archived_at ?? undefined,
+        timestamp: row.timestamp ?? row.created_at,
+        snippet: row.snippet ?? undefined,
+      };
+
+      if (row.kind === "decision") {
+        entry.hunkId = row.log_hunk_id ?? undefined;
+        entry.file = row.log_file ?? undefined;
+        entry.action = (row.action ?? undefined) as HistoryEntry["action"];
+        entry.diff = row.original_diff ?? undefined;
+        entry.modifiedDiff = row.modified_diff ?? undefined;
+        entry.comment = row.comment ?? undefined;
+        entry.undone = row.undone_at !== null;
+      } else if (row.kind === "hunk") {
+        entry.hunkId = row.ref_id;
+        entry.file = row.hunk_file ?? undefined;
+        entry.diff = row.hunk_diff ?? undefined;
+      }
+
+      return entry;
+    });
+
+    return { entries };
+  }
+
   /**
    * Remove a suggestion from the store
    */
@@ -431,12 +1550,242 @@ export class SuggestionStore {
   }
 
   /**
-   * Log feedback entry
+   * Check whether a hunk belongs to a suggestion, pending or already reviewed
+   */
+  hasHunk(suggestionId: string, hunkId: string): boolean {
+    const row = this.db.query(
+      `SELECT 1 FROM hunks WHERE id = ? AND suggestion_id = ?
+       UNION ALL
+       SELECT 1 FROM feedback_log WHERE hunk_id = ? AND suggestion_id = ?
+       LIMIT 1`
+    ).get(hunkId, suggestionId, hunkId, suggestionId);
+    return row !== null;
+  }
+
+  /**
+   * Append a message to a hunk's review thread
+   */
+  addComment(params: {
+    suggestionId: string;
+    hunkId: string;
+    author: ReviewComment["author"];
+    body: string;
+  }): ReviewComment {
+    const createdAt = Date.now();
+    const result = this.db.run(
+      `INSERT INTO review_comments (suggestion_id, hunk_id, author, body, created_at)
+       VALUES (?, ?, ?, ?, ?)`,
+      [params.suggestionId, params.hunkId, params.author, params.body, createdAt]
+    );
+
+    return {
+      id: Number(result.lastInsertRowid),
+      suggestionId: params.suggestionId,
+      hunkId: params.hunkId,
+      author: params.author,
+      body: params.body,
+      createdAt,
+    };
+  }
+
+  /**
+   * Get the review thread for a hunk, oldest first
+   */
+  getThread(suggestionId: string, hunkId: string): ReviewComment[] {
+    const rows = this.db.query(
+      `SELECT id, suggestion_id, hunk_id, author, body, created_at
+       FROM review_comments WHERE suggestion_id = ? AND hunk_id = ?
+       ORDER BY created_at, id`
+    ).all(suggestionId, hunkId) as ReviewCommentRow[];
+
+    return rows.map(reviewCommentRowToComment);
+  }
+
+  /**
+   * Get all review threads of a suggestion, keyed by hunk ID
+   */
+  getThreads(suggestionId: string): Record<string, ReviewComment[]> {
+    const rows = this.db.query(
+      `SELECT id, suggestion_id, hunk_id, author, body, created_at
+       FROM review_comments WHERE suggestion_id = ?
+       ORDER BY created_at, id`
+    ).all(suggestionId) as ReviewCommentRow[];
+
+    const threads: Record<string, ReviewComment[]> = {};
+    for (const row of rows) {
+      (threads[row.hunk_id] ??= []).push(reviewCommentRowToComment(row));
+    }
+    return threads;
+  }
+
+  /**
+   * Store the result of a check, replacing the previous result of the same name
    */
-  private logFeedback(entry: FeedbackLogEntry): void {
+  saveCheck(check: CheckResult): void {
     this.db.run(
-      `INSERT INTO feedback_log (timestamp, suggestion_id, hunk_id, action, file, original_diff, modified_diff, comment, applied)
+      `INSERT OR REPLACE INTO check_results
+         (suggestion_id, name, command, status, exit_code, output, diagnostics, started_at, finished_at)
        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+      [
+        check.suggestionId,
+        check.name,
+        check.command,
+        check.status,
+        check.exitCode ?? null,
+        check.output ?? null,
+        JSON.stringify(check.diagnostics),
+        check.startedAt,
+        check.finishedAt ?? null,
+      ]
+    );
+  }
+
+  /**
+   * Forget the check results of a suggestion (before a new pipeline run)
+   */
+  clearChecks(suggestionId: string): void {
+    this.db.run(`DELETE FROM check_results WHERE suggestion_id = ?`, [suggestionId]);
+  }
+
+  /**
+   * Get the latest check results of a suggestion, in the order they started
+   */
+  getChecks(suggestionId: string): CheckResult[] {
+    const rows = this.db.query(
+      `SELECT suggestion_id, name, command, status, exit_code, output, diagnostics, started_at, finished_at
+       FROM check_results WHERE suggestion_id = ?
+       ORDER BY started_at, rowid`
+    ).all(suggestionId) as CheckResultRow[];
+
+    return rows.map(checkResultRowToResult);
+  }
+
+  /**
+   * Add an event to the log, dropping the oldest beyond EVENT_LOG_SIZE
+   * @returns the event's sequence number
+   */
+  appendEvent(event: SuggestionEvent): number {
+    const { seq: _, ...payload } = event as SuggestionEvent & { seq?: number };
+    const result = this.db.run(
+      `INSERT INTO events (type, payload, created_at) VALUES (?, ?, ?)`,
+      [event.type, JSON.stringify(payload), Date.now()]
+    );
+    const seq = Number(result.lastInsertRowid);
+    this.db.run(`DELETE FROM events WHERE seq <= ?`, [seq - EVENT_LOG_SIZE]);
+    return seq;
+  }
+
+  /**
+   * Sequence number of the last event logged (0 if none)
+   * AUTOINCREMENT never reuses numbers, so this survives pruning and clear().
+   */
+  getLatestSeq(): number {
+    const row = this.db.query(`SELECT seq FROM sqlite_sequence WHERE name = 'events'`).get() as { seq: number } | null;
+    return row?.seq ?? 0;
+  }
+
+  /**
+   * Events logged after seq, oldest first, each with its seq
+   * Returns undefined if they can't all be replayed: some were pruned, there
+   * are more than limit, or seq is from another database (ahead of the log).
+   */
+  getEventsSince(seq: number, limit: number): SuggestionEvent[] | undefined {
+    const latest = this.getLatestSeq();
+    if (seq === latest) return [];
+    if (seq > latest || latest - seq > limit) return undefined;
+
+    const oldest = this.db.query(`SELECT MIN(seq) AS seq FROM events`).get() as { seq: number | null };
+    if (oldest.seq === null || oldest.seq > seq + 1) return undefined;
+
+    const rows = this.db.query(
+      `SELECT seq, payload FROM events WHERE seq > ? ORDER BY seq`
+    ).all(seq) as Array<{ seq: number; payload: string }>;
+    return rows.map((row) => ({ ...JSON.parse(row.payload), seq: row.seq }));
+  }
+
+  /**
+   * Store a notification for the AI, addressed to the session that published the suggestion
+   * It is due for delivery at nextAttemptAt (default: right away), so one whose
+   * first attempt never ran (e.g. the process died) is still retried.
+   */
+  addNotification(params: { suggestionId: string; hunkId?: string; message: string; nextAttemptAt?: number }): AINotification {
+    const session = this.db.query(`SELECT session_id FROM suggestions WHERE id = ?`).get(params.suggestionId) as
+      { session_id: string | null } | null;
+    const createdAt = Date.now();
+    const result = this.db.run(
+      `INSERT INTO notifications (suggestion_id, hunk_id, session_id, message, created_at, next_attempt_at)
+       VALUES (?, ?, ?, ?, ?, ?)`,
+      [params.suggestionId, params.hunkId ?? null, session?.session_id ?? null, params.message, createdAt, params.nextAttemptAt ?? createdAt]
+    );
+    return this.getNotification(Number(result.lastInsertRowid))!;
+  }
+
+  getNotification(id: number): AINotification | undefined {
+    const row = this.db.query(`SELECT * FROM notifications WHERE id = ?`).get(id) as NotificationRow | null;
+    return row ? notificationRowToNotification(row) : undefined;
+  }
+
+  /**
+   * Notifications, oldest first
+   * @param options.undelivered Only those that haven't reached the AI
+   */
+  getNotifications(options: { undelivered?: boolean; suggestionId?: string; limit?: number } = {}): AINotification[] {
+    const conditions: string[] = [];
+    const values: Array<string | number> = [];
+    if (options.undelivered) {
+      conditions.push("delivered_at IS NULL");
+    }
+    if (options.suggestionId !== undefined) {
+      conditions.push("suggestion_id = ?");
+      values.push(options.suggestionId);
+    }
+    const where = conditions.length > 0 ? `WHERE ${conditions.join(" AND ")}` : "";
+    // The most recent `limit`, in the order they were created
+    const rows = this.db.query(
+      `SELECT * FROM (SELECT * FROM notifications ${where} ORDER BY id DESC LIMIT ?) ORDER BY id`
+    ).all(...values, options.limit ?? -1) as NotificationRow[];
+    return rows.map(notificationRowToNotification);
+  }
+
+  /**
+   * Undelivered notifications whose next attempt is due
+   */
+  getDueNotifications(now: number): AINotification[] {
+    const rows = this.db.query(
+      `SELECT * FROM notifications
+       WHERE delivered_at IS NULL AND next_attempt_at IS NOT NULL AND next_attempt_at <= ?
+       ORDER BY id`
+    ).all(now) as NotificationRow[];
+    return rows.map(notificationRowToNotification);
+  }
+
+  markNotificationDelivered(id: number, via: NonNullable<AINotification["deliveredVia"]>): void {
+    this.db.run(
+      `UPDATE notifications SET delivered_at = ?, delivered_via = ?, next_attempt_at = NULL
+       WHERE id = ? AND delivered_at IS NULL`,
+      [Date.now(), via, id]
+    );
+  }
+
+  /**
+   * Record a failed delivery attempt
+   * @param nextAttemptAt When to try again (never, if omitted)
+   */
+  recordNotificationFailure(id: number, error: string, nextAttemptAt?: number): void {
+    this.db.run(
+      `UPDATE notifications SET attempts = attempts + 1, last_error = ?, next_attempt_at = ?
+       WHERE id = ? AND delivered_at IS NULL`,
+      [error, nextAttemptAt ?? null, id]
+    );
+  }
+
+  /**
+   * Log feedback entry
+   */
+  private logFeedback(entry: FeedbackLogEntry, hunk?: Hunk): void {
+    this.db.run(
+      `INSERT INTO feedback_log (timestamp, suggestion_id, hunk_id, action, file, original_diff, modified_diff, comment, applied, hunk_snapshot)
+       VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
       [
         entry.timestamp,
         entry.suggestionId,
@@ -447,71 +1796,140 @@ export class SuggestionStore {
         entry.modifiedDiff ?? null,
         entry.comment ?? null,
         entry.applied ? 1 : 0,
+        hunk ? JSON.stringify(hunk) : null,
       ]
     );
 
     // Also append to JSONL file if configured
-    if (this.feedbackLogPath) {
-      this.appendToFeedbackLog(entry);
+    if (this.pendingFeedbackLog) {
+      this.pendingFeedbackLog.push(entry);
+    } else {
+      this.appendToFeedbackLog([entry]);
     }
   }
 
   /**
-   * Append feedback entry to JSONL file
+   * Append feedback entries to JSONL file
+   * Synchronous and in one write, so concurrent decisions can't drop each other's lines
    */
-  private async appendToFeedbackLog(entry: FeedbackLogEntry): Promise<void> {
-    if (!this.feedbackLogPath) return;
+  private appendToFeedbackLog(entries: FeedbackLogEntry[]): void {
+    if (!this.feedbackLogPath || entries.length === 0) return;
 
     try {
-      const line = JSON.stringify(entry) + "\n";
-      const file = Bun.file(this.feedbackLogPath);
-      const existing = (await file.exists()) ? await file.text() : "";
-      await Bun.write(this.feedbackLogPath, existing + line);
+      mkdirSync(dirname(this.feedbackLogPath), { recursive: true });
+      appendFileSync(this.feedbackLogPath, entries.map((entry) => JSON.stringify(entry) + "\n").join(""));
     } catch (error) {
       console.error("Failed to write feedback log:", error);
     }
   }
 
   /**
-   * Get all feedback entries (for testing/debugging)
+   * Review decisions for the stats, oldest first (undone decisions left out)
+   * Each carries when its suggestion was published and how many hunks it has,
+   * decided or still pending.
    */
-  getFeedbackLog(): FeedbackLogEntry[] {
+  getReviewRecords(since?: number, until?: number): ReviewRecord[] {
     const rows = this.db.query(
-      `SELECT timestamp, suggestion_id, hunk_id, action, file, original_diff, modified_diff, comment, applied
-       FROM feedback_log ORDER BY timestamp`
-    ).all() as Array<{
+      `SELECT f.timestamp, f.suggestion_id, f.action, f.file, f.original_diff, f.modified_diff,
+              s.created_at AS suggestion_created_at,
+              (SELECT COUNT(*) FROM feedback_log g WHERE g.suggestion_id = f.suggestion_id AND g.undone_at IS NULL)
+                + (SELECT COUNT(*) FROM hunks h WHERE h.suggestion_id = f.suggestion_id) AS suggestion_hunks
+       FROM feedback_log f LEFT JOIN suggestions s ON s.id = f.suggestion_id
+       WHERE f.undone_at IS NULL AND f.timestamp >= ? AND f.timestamp <= ?
+       ORDER BY f.timestamp, f.id`
+    ).all(since ?? 0, until ?? Number.MAX_SAFE_INTEGER) as Array<{
       timestamp: number;
       suggestion_id: string;
-      hunk_id: string;
-      action: string;
+      action: ReviewRecord["action"];
       file: string;
       original_diff: string | null;
       modified_diff: string | null;
-      comment: string | null;
-      applied: number;
+      suggestion_created_at: number | null;
+      suggestion_hunks: number;
     }>;
 
     return rows.map((row) => ({
       timestamp: row.timestamp,
       suggestionId: row.suggestion_id,
-      hunkId: row.hunk_id,
-      action: row.action as "accept" | "reject" | "modify",
+      action: row.action,
       file: row.file,
       originalDiff: row.original_diff ?? undefined,
       modifiedDiff: row.modified_diff ?? undefined,
-      comment: row.comment ?? undefined,
-      applied: row.applied === 1,
+      suggestionCreatedAt: row.suggestion_created_at ?? undefined,
+      suggestionHunks: row.suggestion_hunks,
     }));
   }
 
+  /**
+   * Review decisions for the preference dataset, oldest first (undone decisions left out)
+   */
+  getExportEntries(since?: number, until?: number): ExportEntry[] {
+    const rows = this.db.query(
+      `SELECT f.timestamp, f.suggestion_id, f.hunk_id, f.action, f.file, f.original_diff, f.modified_diff, f.comment,
+              f.hunk_snapshot, s.description, s.working_directory
+       FROM feedback_log f LEFT JOIN suggestions s ON s.id = f.suggestion_id
+       WHERE f.undone_at IS NULL AND f.timestamp >= ? AND f.timestamp <= ?
+       ORDER BY f.timestamp, f.id`
+    ).all(since ?? 0, until ?? Number.MAX_SAFE_INTEGER) as Array<{
+      timestamp: number;
+      suggestion_id: string;
+      hunk_id: string;
+      action: ExportEntry["action"];
+      file: string;
+      original_diff: string | null;
+      modified_diff: string | null;
+      comment: string | null;
+      hunk_snapshot: string | null;
+      description: string | null;
+      working_directory: string | null;
+    }>;
+
+    return rows.map((row) => {
+      const snapshot: Partial<Hunk> = row.hunk_snapshot ? JSON.parse(row.hunk_snapshot) : {};
+      return {
+        timestamp: row.timestamp,
+        suggestionId: row.suggestion_id,
+        hunkId: row.hunk_id,
+        action: row.action,
+        file: row.file,
+        originalDiff: row.original_diff ?? undefined,
+        modifiedDiff: row.modified_diff ?? undefined,
+        comment: row.comment ?? undefined,
+        description: row.description ?? undefined,
+        workingDirectory: row.working_directory ?? undefined,
+        originalStartLine: snapshot.originalStartLine,
+        fileLevel: snapshot.fileChange !== undefined,
+        versions: this.getHunkVersions(row.suggestion_id, row.hunk_id),
+      };
+    });
+  }
+
+  /**
+   * Get all feedback entries (for testing/debugging)
+   */
+  getFeedbackLog(): FeedbackLogEntry[] {
+    const rows = this.db.query(
+      `SELECT timestamp, suggestion_id, hunk_id, action, file, original_diff, modified_diff, comment, applied
+       FROM feedback_log ORDER BY timestamp`
+    ).all() as FeedbackLogRow[];
+
+    return rows.map(feedbackLogRowToEntry);
+  }
+
   /**
    * Clear all suggestions (for testing)
    */
   clear(): void {
     this.db.exec(`DELETE FROM hunk_states`);
+    this.db.exec(`DELETE FROM hunk_versions`);
     this.db.exec(`DELETE FROM hunks`);
     this.db.exec(`DELETE FROM suggestions`);
     this.db.exec(`DELETE FROM feedback_log`);
+    this.db.exec(`DELETE FROM review_comments`);
+    this.db.exec(`DELETE FROM check_results`);
+    this.db.exec(`DELETE FROM history_fts`);
+    this.db.exec(`DELETE FROM events`);
+    this.db.exec(`DELETE FROM notifications`);
   }
 
   /**
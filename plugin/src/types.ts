@@ -23,6 +23,60 @@ export interface Hunk {
   originalStartLine?: number;
   /** Short one-line description of what this hunk changes */
   description?: string;
+  /** Outermost item the hunk touches, for grouping (Rust files, e.g. "impl MeshGrid") */
+  item?: string;
+  /**
+   * Version of the hunk's review state, bumped whenever the hunk or its state
+   * changes (revision requested, revised, split, merged, restored by undo).
+   * Feedback may carry the version it was based on; stale feedback is refused.
+   */
+  stateVersion?: number;
+  /** Set for file-level hunks (rename, delete, mode change, binary) instead of an @@ hunk */
+  fileChange?: FileChange;
+  /** Git file mode of the hunk's file (e.g. "100755"), so whole-file adds and deletes keep it */
+  fileMode?: string;
+  /** Revision of this hunk, starting at 1 and bumped each time the AI revises it */
+  version?: number;
+  /** Compiler errors and warnings in the hunk's proposed lines (cargo check when published) */
+  diagnostics?: CheckDiagnostic[];
+}
+
+/**
+ * A superseded version of a hunk (kept when the AI publishes a revision)
+ */
+export interface HunkVersion {
+  hunkId: string;
+  version: number;
+  diff: string;
+  originalLines?: string[];
+  originalStartLine?: number;
+  /** What the reviewer asked to change about this version */
+  instruction?: string;
+  createdAt: number;
+}
+
+/**
+ * What happened to a file as a whole
+ */
+export type FileChangeKind = "add" | "delete" | "rename" | "copy" | "mode" | "binary";
+
+/**
+ * A file-level change that can't be expressed as an @@ hunk
+ */
+export interface FileChange {
+  kind: FileChangeKind;
+  /** Path before the change */
+  oldPath: string;
+  /** Path after the change */
+  newPath: string;
+  /** Rename/copy similarity percentage */
+  similarity?: number;
+  /** Git file mode before the change (e.g. "100644") */
+  oldMode?: string;
+  /** Git file mode after the change (e.g. "100755") */
+  newMode?: string;
+  /** Whether the file content is binary */
+  binary?: boolean;
 }
 
 /**
@@ -31,7 +85,7 @@ export interface Hunk {
 export interface Suggestion {
   /** Unique suggestion ID (UUID) */
   id: string;
-  /** jj change ID for this suggestion */
+  /** jj change ID for this suggestion (git: base commit id or stash@{N}) */
   jjChangeId: string;
   /** Human-readable description of changes */
   description: string;
@@ -54,6 +108,10 @@ export type SuggestionStatus = "pending" | "partial" | "complete" | "discarded";
 export interface HunkState {
   reviewed: boolean;
   action?: "accepted" | "rejected" | "modified";
+  /** Set while the reviewer waits for the AI to revise the hunk (request_changes) */
+  state?: "revision_requested";
+  /** The reviewer's instruction for the revision */
+  instruction?: string;
   modifiedDiff?: string;
   comment?: string;
   appliedAt?: number;
@@ -63,7 +121,17 @@ export interface HunkState {
 // Event Types (Sandbox -> User Editor via SSE)
 // ============================================
 
-export interface SuggestionReadyEvent {
+/**
+ * Position of an event in its working directory's event log
+ * Events that change review state are numbered and kept so an editor that
+ * reconnects can ask for the ones it missed (subscribe sinceSeq).
+ */
+export interface SequencedEvent {
+  /** Absent on events that are not kept (status, error, list) */
+  seq?: number;
+}
+
+export interface SuggestionReadyEvent extends SequencedEvent {
   type: "suggestion.ready";
   suggestion: {
     id: string;
@@ -77,7 +145,8 @@ export interface SuggestionReadyEvent {
 
 export interface SuggestionErrorEvent {
   type: "suggestion.error";
-  code: "experiment_failed" | "sync_failed" | "jj_error" | "apply_failed" | "unknown";
+  /** jj_error: the jj backend failed; vcs_error: another backend (git) failed */
+  code: "experiment_failed" | "sync_failed" | "jj_error" | "vcs_error" | "apply_failed" | "merge_conflict" | "unknown";
   message: string;
   suggestionId?: string;
   hunkId?: string;
@@ -90,11 +159,79 @@ export interface SuggestionStatusEvent {
   message: string;
 }
 
-export interface SuggestionHunkAppliedEvent {
+export interface SuggestionHunkAppliedEvent extends SequencedEvent {
   type: "suggestion.hunk_applied";
   suggestionId: string;
   hunkId: string;
   action: "accepted" | "modified" | "rejected";
+  /** Partial decision: the rest of the hunk, back up for review under a new ID */
+  remainingHunk?: Hunk;
+}
+
+export interface SuggestionHunkRevertedEvent extends SequencedEvent {
+  type: "suggestion.hunk_reverted";
+  suggestionId: string;
+  hunkId: string;
+  /** The decision that was undone */
+  previousAction: "accepted" | "modified" | "rejected";
+  /** The hunk, back up for review */
+  hunk: Hunk;
+  /** Undoing a partial decision: the pending rest of the hunk, now part of it again */
+  replaces?: string;
+}
+
+export interface SuggestionRevisionRequestedEvent extends SequencedEvent {
+  type: "suggestion.revision_requested";
+  suggestionId: string;
+  hunkId: string;
+  instruction: string;
+  /** The hunk's new state version */
+  stateVersion?: number;
+}
+
+export interface SuggestionHunkRevisedEvent extends SequencedEvent {
+  type: "suggestion.hunk_revised";
+  suggestionId: string;
+  hunkId: string;
+  /** The new version of the hunk */
+  hunk: Hunk;
+  /** The version it replaces */
+  previous: HunkVersion;
+}
+
+export interface SuggestionUpdatedEvent extends SequencedEvent {
+  type: "suggestion.updated";
+  /** The suggestion as it stands after the update (full details, like get) */
+  suggestion: SuggestionReadyEvent["suggestion"];
+  /** New hunks, pending review */
+  added: string[];
+  /** Hunks whose change differs from the reviewed or pending version; pending again */
+  changed: string[];
+  /** Pending hunks that are no longer part of the change */
+  removed: string[];
+}
+
+export interface SuggestionHunksChangedEvent extends SequencedEvent {
+  type: "suggestion.hunks_changed";
+  suggestionId: string;
+  /** Pending hunks that were split or merged, in list order */
+  replaced: string[];
+  /** The hunks that take their place, in list order (pending unless they kept a replaced hunk's ID) */
+  hunks: Hunk[];
+}
+
+export interface SuggestionCommentEvent extends SequencedEvent {
+  type: "suggestion.comment";
+  suggestionId: string;
+  hunkId: string;
+  comment: ReviewComment;
+}
+
+export interface SuggestionCheckEvent extends SequencedEvent {
+  type: "suggestion.check";
+  suggestionId: string;
+  /** Sent when the check starts (status "running") and again when it finishes */
+  check: CheckResult;
 }
 
 export interface SuggestionListEvent {
@@ -110,11 +247,60 @@ export interface SuggestionListEvent {
   }>;
 }
 
+/**
+ * An editor connected to the server and what it is looking at
+ */
+export interface ReviewerPresence {
+  /** Connection ID assigned by the server */
+  clientId: string;
+  /** Name the editor gave on subscribe (e.g. the user name) */
+  name?: string;
+  suggestionId?: string;
+  hunkId?: string;
+}
+
+/**
+ * Who is reviewing in a working directory; sent to its WebSocket subscribers
+ * whenever an editor subscribes, moves or disconnects (not logged)
+ */
+export interface SuggestionPresenceEvent {
+  type: "suggestion.presence";
+  clients: ReviewerPresence[];
+}
+
+/**
+ * A suggestion with everything an editor shows about it (get, snapshots)
+ */
+export interface SuggestionDetails extends Suggestion {
+  remainingCount: number;
+  threads: Record<string, ReviewComment[]>;
+  checks: CheckResult[];
+}
+
+/**
+ * The whole state of a working directory, sent to one editor in place of a
+ * replay when it missed more events than the server keeps (not logged)
+ */
+export interface SuggestionSnapshotEvent {
+  type: "suggestion.snapshot";
+  /** Last event the snapshot includes; later events follow as usual */
+  seq: number;
+  /** Every suggestion of the working directory, in full */
+  suggestions: SuggestionDetails[];
+}
+
 export type SuggestionEvent =
   | SuggestionReadyEvent
   | SuggestionErrorEvent
   | SuggestionStatusEvent
   | SuggestionHunkAppliedEvent
+  | SuggestionHunkRevertedEvent
+  | SuggestionRevisionRequestedEvent
+  | SuggestionHunkRevisedEvent
+  | SuggestionUpdatedEvent
+  | SuggestionHunksChangedEvent
+  | SuggestionCommentEvent
+  | SuggestionCheckEvent
   | SuggestionListEvent;
 
 // ============================================
@@ -123,10 +309,142 @@ export type SuggestionEvent =
 
 export interface HunkFeedback {
   suggestionId: string;
+  hunkId: string;
+  /** request_changes asks the AI for a new version instead of deciding the hunk */
+  action: "accept" | "reject" | "modify" | "request_changes";
+  modifiedDiff?: string;
+  /** Optional remark; required for request_changes, where it is the instruction to the AI */
+  comment?: string;
+  /**
+   * Decide only some of the hunk's lines (modify to accept them, reject to reject them)
+   * modifiedDiff is then the hunk narrowed to those lines; the rest stays pending as a new hunk.
+   */
+  partial?: boolean;
+  /** Hunk.stateVersion the reviewer saw; the feedback is refused as a conflict if it moved on */
+  stateVersion?: number;
+}
+
+/**
+ * One hunk's decision in a HunkFeedbackBatch
+ */
+export interface BatchDecision {
   hunkId: string;
   action: "accept" | "reject" | "modify";
   modifiedDiff?: string;
   comment?: string;
+  /** Hunk.stateVersion the reviewer saw */
+  stateVersion?: number;
+}
+
+/**
+ * Decide several hunks of a suggestion at once ({"type": "feedback_batch"} / POST /feedback/batch)
+ * All decisions are recorded or none: a conflict or error on any hunk refuses the whole batch.
+ */
+export interface HunkFeedbackBatch {
+  suggestionId: string;
+  decisions: BatchDecision[];
+}
+
+/**
+ * Undo a review decision ({"action": "undo"} on the feedback endpoints)
+ * If hunkId is omitted, the most recent decision in the suggestion is undone.
+ */
+export interface HunkUndo {
+  suggestionId: string;
+  hunkId?: string;
+}
+
+/**
+ * A reviewer question or remark on a hunk ({"type": "comment"} / POST /comment)
+ * Unlike HunkFeedback.comment, this doesn't decide the hunk; the AI answers
+ * with the reply_to_review tool.
+ */
+export interface HunkComment {
+  suggestionId: string;
+  hunkId: string;
+  body: string;
+}
+
+/**
+ * Split a pending hunk before reviewing it ({"type": "split"})
+ * Without a line, the hunk is cut at every run of context between its groups
+ * of changes; with one (1-based, counting the lines after the @@ header), it is
+ * cut just before that line.
+ */
+export interface HunkSplit {
+  suggestionId: string;
+  hunkId: string;
+  line?: number;
+}
+
+/**
+ * Join two pending hunks of the same file into one ({"type": "merge"})
+ */
+export interface HunkMerge {
+  suggestionId: string;
+  hunkIds: [string, string];
+}
+
+/**
+ * Outcome of a split or merge
+ */
+export interface HunksChangedResult {
+  success: boolean;
+  /** The hunks that were replaced */
+  replaced?: string[];
+  /** The hunks that replaced them, in list order */
+  hunks?: Hunk[];
+  error?: string;
+}
+
+/**
+ * One message in a hunk's review thread
+ */
+export interface ReviewComment {
+  id: number;
+  suggestionId: string;
+  hunkId: string;
+  author: "reviewer" | "ai";
+  body: string;
+  createdAt: number;
+}
+
+/**
+ * Outcome of one command of the check pipeline (e.g. `cargo test`)
+ */
+export type CheckStatus = "running" | "passed" | "failed" | "error";
+
+/**
+ * A problem a check reported at a file position
+ */
+export interface CheckDiagnostic {
+  /** Path relative to the working directory */
+  file: string;
+  /** 1-indexed line in the working copy (the proposed file) */
+  line: number;
+  column?: number;
+  severity: "error" | "warning";
+  message: string;
+  /** Error code or lint name (e.g. "E0308", "unused_variables") */
+  code?: string;
+  /** Pending hunk whose proposed lines include this line */
+  hunkId?: string;
+}
+
+/**
+ * Result of running one check against a suggestion's change
+ */
+export interface CheckResult {
+  suggestionId: string;
+  name: string;
+  command: string;
+  status: CheckStatus;
+  exitCode?: number;
+  /** Tail of the combined stdout/stderr */
+  output?: string;
+  diagnostics: CheckDiagnostic[];
+  startedAt: number;
+  finishedAt?: number;
 }
 
 export interface SuggestionComplete {
@@ -134,6 +452,31 @@ export interface SuggestionComplete {
   action: "finalize" | "discard";
 }
 
+// ============================================
+// AI Notifications
+// ============================================
+
+/**
+ * Review feedback for the AI, kept until it reaches the session that
+ * published the suggestion (or the AI pulls it with get_review_feedback)
+ */
+export interface AINotification {
+  id: number;
+  suggestionId: string;
+  hunkId?: string;
+  /** Session the suggestion was published from (unknown for older suggestions) */
+  sessionId?: string;
+  message: string;
+  createdAt: number;
+  /** Unset while undelivered */
+  deliveredAt?: number;
+  /** "session": injected into the session; "tool": pulled with get_review_feedback */
+  deliveredVia?: "session" | "tool";
+  /** Failed attempts to inject it into the session */
+  attempts: number;
+  lastError?: string;
+}
+
 // ============================================
 // Feedback Log Entry (for JSONL logging)
 // ============================================
@@ -150,6 +493,174 @@ export interface FeedbackLogEntry {
   applied: boolean;
 }
 
+// ============================================
+// Suggestion History
+// ============================================
+
+/**
+ * Filters for searching suggestion history (active and archived)
+ * All fields are optional and combined with AND.
+ */
+export interface HistoryQuery {
+  /** Full-text query over descriptions, diffs and comments */
+  query?: string;
+  /** File path, or a directory prefix */
+  file?: string;
+  /** Only review decisions with this action */
+  action?: FeedbackLogEntry["action"];
+  /** Inclusive lower bound (ms since epoch) */
+  since?: number;
+  /** Inclusive upper bound (ms since epoch) */
+  until?: number;
+  /** jj change ID or a prefix of it */
+  jjChangeId?: string;
+  workingDirectory?: string;
+  /** Maximum results (default 50, capped at 500) */
+  limit?: number;
+}
+
+/**
+ * A single search hit
+ * - suggestion: matched the suggestion itself (description)
+ * - hunk: a hunk that was never reviewed (still pending, or left over when archived)
+ * - decision: an accept/reject/modify from the feedback log
+ */
+export interface HistoryEntry {
+  kind: "suggestion" | "hunk" | "decision";
+  suggestionId: string;
+  jjChangeId: string;
+  description: string;
+  status: SuggestionStatus;
+  workingDirectory: string;
+  createdAt: number;
+  /** Set once the suggestion was finalized, discarded or fully reviewed */
+  archivedAt?: number;
+  hunkId?: string;
+  file?: string;
+  action?: FeedbackLogEntry["action"];
+  /** When the decision was made (createdAt for suggestion and hunk hits) */
+  timestamp: number;
+  diff?: string;
+  modifiedDiff?: string;
+  comment?: string;
+  /** Decision was later undone */
+  undone?: boolean;
+  /** Highlighted excerpt around the full-text match */
+  snippet?: string;
+}
+
+export interface HistorySearchResult {
+  entries: HistoryEntry[];
+}
+
+// ============================================
+// Review Statistics
+// ============================================
+
+export interface StatsQuery {
+  /** Inclusive lower bound on decision time (ms since epoch) */
+  since?: number;
+  /** Inclusive upper bound on decision time (ms since epoch) */
+  until?: number;
+  /** Length of the periods in overTime (default week) */
+  interval?: "day" | "week" | "month";
+  /** Most files and directories listed (default 20, busiest first) */
+  limit?: number;
+}
+
+/**
+ * Decisions on a group of hunks
+ * Modified hunks count as accepted in the acceptance rate.
+ */
+export interface AcceptanceStats {
+  total: number;
+  accepted: number;
+  rejected: number;
+  modified: number;
+  /** (accepted + modified) / total, 0 when there are no decisions */
+  acceptanceRate: number;
+}
+
+export interface GroupedAcceptanceStats extends AcceptanceStats {
+  /** File, directory, extension, size bucket or period start (YYYY-MM-DD) */
+  key: string;
+}
+
+export interface ReviewStats {
+  overall: AcceptanceStats;
+  /** Oldest period first */
+  overTime: GroupedAcceptanceStats[];
+  byFile: GroupedAcceptanceStats[];
+  byDirectory: GroupedAcceptanceStats[];
+  byExtension: GroupedAcceptanceStats[];
+  /** Buckets of hunks per suggestion ("1", "2-3", "4-7", "8-15", "16+") */
+  bySuggestionSize: GroupedAcceptanceStats[];
+  /** From publishing a suggestion to deciding each of its hunks */
+  timeToReview: {
+    medianMs?: number;
+    samples: number;
+  };
+  /** How much reviewers changed the hunks they modified */
+  modifications: {
+    count: number;
+    /** modified / total */
+    rate: number;
+    /** Lines added or removed relative to the AI's version */
+    medianLinesChanged?: number;
+    meanLinesChanged?: number;
+    totalLinesChanged: number;
+  };
+}
+
+// ============================================
+// Preference Dataset
+// ============================================
+
+export interface ExportQuery {
+  /** Inclusive lower bound on decision time (ms since epoch) */
+  since?: number;
+  /** Inclusive upper bound on decision time (ms since epoch) */
+  until?: number;
+  /** File lines around each hunk in the prompt (default 20 each side) */
+  contextLines?: number;
+}
+
+/**
+ * One chosen/rejected pair for preference tuning (a line of the export's JSONL)
+ * chosen and rejected are the text that should and should not replace
+ * prompt.original; keeping the original is itself a completion.
+ */
+export interface PreferenceRecord {
+  /** Hash of the prompt and both completions; duplicates are dropped */
+  id: string;
+  /**
+   * - modified: the reviewer's edit over the AI's hunk
+   * - accepted: the AI's hunk over leaving the code alone
+   * - rejected: leaving the code alone over the AI's hunk
+   * - revised: the version the reviewer accepted over one they asked to change
+   */
+  source: "modified" | "accepted" | "rejected" | "revised";
+  prompt: {
+    file: string;
+    /** Suggestion description */
+    description?: string;
+    /** The reviewer's comment, or the change they asked for (revised) */
+    instruction?: string;
+    /** File lines above the hunk, when it could still be found in the file */
+    contextBefore: string[];
+    /** Old side of the hunk, its own context lines included */
+    original: string[];
+    contextAfter: string[];
+  };
+  chosen: string;
+  rejected: string;
+  suggestionId: string;
+  hunkId: string;
+  /** When the decision was made (ms since epoch) */
+  timestamp: number;
+  workingDirectory?: string;
+}
+
 // ============================================
 // Tool Return Types
 // ============================================
@@ -160,11 +671,65 @@ export interface PublishSuggestionResult {
   files: string[];
 }
 
+export interface UpdateSuggestionResult {
+  suggestionId: string;
+  added: string[];
+  changed: string[];
+  removed: string[];
+  /** Hunks still waiting for review after the update */
+  hunkCount: number;
+  files: string[];
+}
+
+/**
+ * How a hunk ended up being applied
+ * - clean: context matched at the position in the @@ header
+ * - offset: context matched, but some lines away from the header position
+ * - fuzzed: applied after ignoring outer context lines, or merged without conflicts
+ * - conflicted: three-way merge wrote git-style conflict markers into the file
+ */
+export type ApplyStatus = "clean" | "offset" | "fuzzed" | "conflicted";
+
 export interface FeedbackResult {
   success: boolean;
   applied: boolean;
   reverted?: boolean;
   remainingHunks: number;
+  /** How the patch was applied to the working copy (clean, offset, fuzzed, conflicted) */
+  applyStatus?: ApplyStatus;
+  /** Partial decision: the rest of the hunk, still pending */
+  remainingHunk?: Hunk;
+  error?: string;
+  /** "conflict" when the hunk changed or was decided since the reviewer saw it */
+  code?: "conflict";
+  /** Conflict: the hunk as it is now (absent if it was already decided) */
+  currentHunk?: Hunk;
+}
+
+export interface FeedbackBatchResult {
+  success: boolean;
+  /** Hunks decided, in batch order */
+  decided?: string[];
+  remainingHunks: number;
+  error?: string;
+  /** "conflict" when a hunk changed or was decided since the reviewer saw it */
+  code?: "conflict";
+  /** Failure: the hunk that refused the batch */
+  hunkId?: string;
+  /** Conflict: that hunk as it is now (absent if it was already decided) */
+  currentHunk?: Hunk;
+}
+
+export interface UndoResult {
+  success: boolean;
+  /** The hunk that is back up for review */
+  hunk?: Hunk;
+  previousAction?: SuggestionHunkRevertedEvent["previousAction"];
+  /** Diff the editor applies to take the decision back out of its files (absent for rejections) */
+  revertDiff?: string;
+  /** Same as revertDiff for file-level hunks */
+  revertFileChange?: FileChange;
+  remainingHunks: number;
   error?: string;
 }
 
@@ -180,3 +745,179 @@ export interface ListSuggestionsResult {
     workingDirectory?: string;
   }>;
 }
+
+/**
+ * A project the server serves (every registered one, under the daemon)
+ */
+export interface ProjectSummary {
+  workingDirectory: string;
+  suggestions: ListSuggestionsResult["suggestions"];
+}
+
+// ============================================
+// WebSocket Protocol (Editor <-> Server)
+// ============================================
+// src/protocol.schema.json is generated from these types
+// (bun run schema); both ends validate messages against it.
+
+/**
+ * Fields every client command carries
+ */
+export interface CommandEnvelope {
+  /** Chosen by the client and echoed in the response */
+  id: string;
+  workingDirectory: string;
+}
+
+export interface FeedbackCommand extends CommandEnvelope, HunkFeedback {
+  type: "feedback";
+}
+
+export interface FeedbackBatchCommand extends CommandEnvelope, HunkFeedbackBatch {
+  type: "feedback_batch";
+}
+
+export interface UndoCommand extends CommandEnvelope, HunkUndo {
+  type: "feedback";
+  action: "undo";
+}
+
+export interface CommentCommand extends CommandEnvelope, HunkComment {
+  type: "comment";
+}
+
+export interface SplitCommand extends CommandEnvelope, HunkSplit {
+  type: "split";
+}
+
+export interface MergeCommand extends CommandEnvelope, HunkMerge {
+  type: "merge";
+}
+
+export interface CompleteCommand extends CommandEnvelope, SuggestionComplete {
+  type: "complete";
+}
+
+export interface RunChecksCommand extends CommandEnvelope {
+  type: "run_checks";
+  suggestionId: string;
+}
+
+export interface ListCommand extends CommandEnvelope {
+  type: "list";
+}
+
+export interface GetCommand extends CommandEnvelope {
+  type: "get";
+  suggestionId: string;
+}
+
+/**
+ * HistoryQuery over the WebSocket; dates may also be strings Date.parse understands
+ */
+export interface SearchCommand extends CommandEnvelope {
+  type: "search";
+  query?: string;
+  file?: string;
+  action?: FeedbackLogEntry["action"];
+  since?: number | string;
+  until?: number | string;
+  jjChangeId?: string;
+  limit?: number;
+}
+
+/**
+ * StatsQuery over the WebSocket; dates may also be strings Date.parse understands
+ */
+export interface StatsCommand extends CommandEnvelope {
+  type: "stats";
+  since?: number | string;
+  until?: number | string;
+  interval?: StatsQuery["interval"];
+  limit?: number;
+}
+
+export interface SubscribeCommand extends CommandEnvelope {
+  type: "subscribe";
+  /** Highest protocol version the client speaks (1 if omitted) */
+  protocolVersion?: number;
+  clientName?: string;
+  /** Last event seq the client saw; missed events are replayed (or a snapshot sent) */
+  sinceSeq?: number;
+}
+
+/**
+ * Every project the server serves, with its pending suggestions
+ */
+export interface ProjectsCommand {
+  type: "projects";
+  id: string;
+}
+
+/**
+ * What the editor is looking at; neither field means nothing
+ */
+export interface PresenceCommand {
+  type: "presence";
+  id: string;
+  suggestionId?: string;
+  hunkId?: string;
+}
+
+export type ClientCommand =
+  | FeedbackCommand
+  | FeedbackBatchCommand
+  | UndoCommand
+  | CommentCommand
+  | SplitCommand
+  | MergeCommand
+  | CompleteCommand
+  | RunChecksCommand
+  | ListCommand
+  | GetCommand
+  | SearchCommand
+  | StatsCommand
+  | SubscribeCommand
+  | ProjectsCommand
+  | PresenceCommand;
+
+/**
+ * A field of a message that failed validation
+ */
+export interface ProtocolError {
+  /** JSON path of the field, e.g. "$.hunkIds[1]" */
+  path: string;
+  message: string;
+}
+
+/**
+ * Reply to a client command; command-specific fields sit next to these
+ */
+export interface CommandResponse {
+  type: "response";
+  /** The command's id (absent if the message could not be read at all) */
+  id?: string;
+  success: boolean;
+  error?: string;
+  /** Machine-readable failure: invalid_message, unsupported_version, conflict, ... */
+  code?: string;
+  /** invalid_message: what is wrong with the command */
+  errors?: ProtocolError[];
+}
+
+/**
+ * First message on a new connection
+ */
+export interface ConnectedMessage {
+  type: "connected";
+  message: string;
+  /** Highest protocol version the server speaks */
+  protocolVersion: number;
+}
+
+export type ServerMessage =
+  | ConnectedMessage
+  | CommandResponse
+  | SuggestionPresenceEvent
+  | SuggestionSnapshotEvent
+  | SuggestionEvent;
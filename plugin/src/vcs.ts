@@ -0,0 +1,450 @@
+/**
+ * Version control backends
+ *
+ * Suggestions are read from and finalized into the project's VCS. jj is the
+ * original backend: a suggestion is a jj change, diffed against its parent.
+ * With plain git (e.g. worktrees) a suggestion is the working tree diffed
+ * against a base ref (HEAD by default), or a stash entry.
+ *
+ * The backend is picked from `vcs.backend` in codeforge.json, or detected by
+ * looking for `.jj` / `.git` from the project directory upwards (`.jj` wins in
+ * colocated repos).
+ *
+ * Finalizing builds a fresh commit on the change's parent from exactly the
+ * accepted and modified hunks, so rejected hunks never reach history.
+ */
+
+import { existsSync, rmSync, writeFileSync } from "fs";
+import { basename, dirname, join, resolve } from "path";
+import { tmpdir } from "os";
+import { formatFileChange, parseHunkHeader } from "./diff-parser.ts";
+import { applyHunkToFile } from "./patch-applier.ts";
+import type { FileChange, Hunk } from "./types.ts";
+
+export type VcsKind = "jj" | "git";
+
+/**
+ * Shell from the plugin context, bound to the project directory
+ */
+type Shell = any;
+
+/**
+ * What a suggestion is read from and finalized into
+ */
+export interface VcsBackend {
+  readonly kind: VcsKind;
+  /** Diff of a change in git format (defaults to the current change) */
+  getDiff(changeId?: string): Promise<string>;
+  /** ID of the current change (jj commit id, or the git base commit id) */
+  getCurrentChangeId(): Promise<string>;
+  /** Restore paths to their content from before the change */
+  restorePaths(changeId: string, paths: string[]): Promise<void>;
+  /** Commit the accepted hunks onto the change's parent, optionally pushing */
+  finalize(plan: FinalizePlan): Promise<FinalizeResult>;
+  /** A directory holding the change's files, for running checks against it */
+  checkout(changeId: string): Promise<Checkout>;
+}
+
+/**
+ * A change checked out for checks: the working copy when it holds the change,
+ * otherwise a temporary jj workspace or git worktree
+ */
+export interface Checkout {
+  dir: string;
+  /** Remove a temporary checkout (the working copy stays) */
+  remove(): Promise<void>;
+}
+
+/**
+ * Error code of suggestion.error events for a backend's failures
+ * jj keeps the "jj_error" clients have always matched on; other backends use "vcs_error".
+ */
+export function vcsErrorCode(kind: VcsKind): "jj_error" | "vcs_error" {
+  return kind === "jj" ? "jj_error" : "vcs_error";
+}
+
+/**
+ * What finalizing a suggestion commits
+ */
+export interface FinalizePlan {
+  /** The suggestion's change (the commit goes on its parent) */
+  changeId: string;
+  message: string;
+  /** Accepted hunks with their final diffs, in the order they were decided */
+  hunks: Hunk[];
+  /** Bookmark (jj) or branch (git) to point at the commit */
+  name: string;
+  push: boolean;
+  /** Remote to push to (jj's default remote / "origin" if omitted) */
+  remote?: string;
+}
+
+export interface FinalizeResult {
+  commitId: string;
+  name: string;
+  pushed: boolean;
+}
+
+/**
+ * Build a commit message: the suggestion description, the accepted hunks'
+ * descriptions, and a Rejected-Hunk trailer per rejected hunk
+ */
+export function formatCommitMessage(
+  suggestionId: string,
+  description: string,
+  accepted: Hunk[],
+  rejected: Hunk[]
+): string {
+  const paragraphs = [description.trim()];
+
+  const notes = [...new Set(
+    accepted.filter((h) => h.description).map((h) => `- ${h.file}: ${h.description}`)
+  )];
+  if (notes.length > 0) {
+    paragraphs.push(notes.join("\n"));
+  }
+
+  if (rejected.length > 0) {
+    const prefix = `${suggestionId}:`;
+    paragraphs.push(rejected.map((h) => {
+      const id = h.id.startsWith(prefix) ? h.id.slice(prefix.length) : h.id;
+      return `Rejected-Hunk: ${id}${h.description ? ` (${h.description})` : ""}`;
+    }).join("\n"));
+  }
+
+  return paragraphs.join("\n\n") + "\n";
+}
+
+/**
+ * Expand a bookmark/branch name template
+ * Placeholders: {id} (suggestion ID), {short_id} (its first 8 characters) and
+ * {slug} (the description, lowercased and dash-separated).
+ */
+export function formatRefName(template: string, suggestionId: string, description: string): string {
+  const slug = description
+    .toLowerCase()
+    .replace(/[^a-z0-9]+/g, "-")
+    .slice(0, 40)
+    .replace(/^-+|-+$/g, "");
+  return template
+    .replaceAll("{id}", suggestionId)
+    .replaceAll("{short_id}", suggestionId.slice(0, 8))
+    .replaceAll("{slug}", slug || "suggestion");
+}
+
+/**
+ * Paths a binary file change touches (restored from the change as a whole)
+ */
+function binaryPaths(change: FileChange): string[] {
+  return [...new Set([change.oldPath, change.newPath])].filter((p) => p !== "/dev/null");
+}
+
+/**
+ * Where a temporary checkout goes
+ */
+function checkoutDir(): string {
+  return join(tmpdir(), `codeforge-checkout-${process.pid}-${Date.now()}`);
+}
+
+/**
+ * Find the VCS a directory belongs to, walking up to the filesystem root
+ * Returns undefined outside of any repository.
+ */
+export function detectVcs(dir: string): VcsKind | undefined {
+  let current = resolve(dir);
+  while (true) {
+    if (existsSync(join(current, ".jj"))) return "jj";
+    // .git is a directory in a clone and a file in a worktree
+    if (existsSync(join(current, ".git"))) return "git";
+    const parent = dirname(current);
+    if (parent === current) return undefined;
+    current = parent;
+  }
+}
+
+/**
+ * Resolve the configured backend ("auto" detects; jj when nothing is found)
+ */
+export function resolveVcsKind(configured: VcsKind | "auto", dir: string): VcsKind {
+  if (configured !== "auto") return configured;
+  return detectVcs(dir) ?? "jj";
+}
+
+/**
+ * Create the backend for a project directory
+ */
+export function createVcs(kind: VcsKind, $: Shell, dir: string, options: { baseRef?: string } = {}): VcsBackend {
+  return kind === "git" ? new GitBackend($, dir, options.baseRef) : new JjBackend($, dir);
+}
+
+/**
+ * jj: a suggestion is a jj change, diffed against its parent
+ */
+export class JjBackend implements VcsBackend {
+  readonly kind = "jj" as const;
+
+  constructor(private $: Shell, private dir: string) {}
+
+  async getDiff(changeId?: string): Promise<string> {
+    try {
+      if (changeId) {
+        return await this.$`jj diff -r ${changeId} --git`.text();
+      }
+      return await this.$`jj diff --git`.text();
+    } catch (error) {
+      throw new Error(`Failed to get jj diff: ${errorMessage(error)}`);
+    }
+  }
+
+  async getCurrentChangeId(): Promise<string> {
+    try {
+      const result = await this.$`jj log -r @ --no-graph -T commit_id`.text();
+      return result.trim();
+    } catch (error) {
+      throw new Error(`Failed to get current change ID: ${errorMessage(error)}`);
+    }
+  }
+
+  async restorePaths(changeId: string, paths: string[]): Promise<void> {
+    await this.$`jj restore --from ${changeId + "-"} ${paths}`.text();
+  }
+
+  /**
+   * Build the commit in a new change on the parent, through the working copy
+   * The working copy ends up on an empty change on top of the new one.
+   */
+  async finalize(plan: FinalizePlan): Promise<FinalizeResult> {
+    const previous = (await this.$`jj log -r @ --no-graph -T change_id`.text()).trim();
+    await this.$`jj new ${plan.changeId + "-"} -m ${plan.message}`.quiet();
+
+    let commitId: string;
+    try {
+      for (const hunk of plan.hunks) {
+        if (hunk.fileChange?.binary) {
+          await this.$`jj restore --from ${plan.changeId} ${binaryPaths(hunk.fileChange)}`.quiet();
+          continue;
+        }
+        const result = await applyHunkToFile(join(this.dir, hunk.file), hunk.diff, { maxOffset: 200, rootDir: this.dir });
+        if (!result.success) {
+          throw new Error(`Failed to apply ${hunk.id}: ${result.error}`);
+        }
+      }
+      await this.$`jj bookmark set ${plan.name} -r @ --allow-backwards`.quiet();
+      commitId = (await this.$`jj log -r @ --no-graph -T commit_id`.text()).trim();
+    } catch (error) {
+      // Drop the half-built change and go back to where we were
+      await this.$`jj abandon @`.nothrow().quiet();
+      await this.$`jj edit ${previous}`.nothrow().quiet();
+      throw error;
+    }
+
+    // Later edits go into a new change rather than the finalized one
+    await this.$`jj new`.quiet();
+
+    if (plan.push) {
+      const remote = plan.remote ? ["--remote", plan.remote] : [];
+      await this.$`jj git push ${remote} --bookmark ${plan.name} --allow-new`.quiet();
+    }
+
+    return { commitId, name: plan.name, pushed: plan.push };
+  }
+
+  /**
+   * The working copy if it is the change, else a new workspace on top of it
+   * The workspace's working-copy commit is empty, so its files are the change's.
+   */
+  async checkout(changeId: string): Promise<Checkout> {
+    try {
+      const current = (await this.$`jj log -r @ --no-graph -T commit_id`.text()).trim();
+      const target = (await this.$`jj log -r ${changeId} --no-graph -T commit_id`.text()).trim();
+      if (target === current) {
+        return { dir: this.dir, remove: async () => {} };
+      }
+
+      const dir = checkoutDir();
+      const name = basename(dir);
+      await this.$`jj workspace add --name ${name} -r ${target} ${dir}`.quiet();
+      return {
+        dir,
+        remove: async () => {
+          // Forgetting the workspace abandons its empty working-copy commit
+          await this.$`jj workspace forget ${name}`.nothrow().quiet();
+          rmSync(dir, { recursive: true, force: true });
+        },
+      };
+    } catch (error) {
+      throw new Error(`Failed to check out ${changeId}: ${errorMessage(error)}`);
+    }
+  }
+}
+
+/**
+ * Whether a git change ID names a stash entry (stash@{N})
+ */
+export function isStashRef(ref: string): boolean {
+  return /^stash@\{\d+\}$/.test(ref);
+}
+
+/**
+ * git: a suggestion is the working tree against a base commit, or a stash entry
+ *
+ * The change ID of a working tree suggestion is the base commit id, so the diff
+ * stays stable while HEAD moves. Finalizing commits onto a branch without
+ * touching HEAD, the index or the working tree.
+ */
+export class GitBackend implements VcsBackend {
+  readonly kind = "git" as const;
+
+  constructor(private $: Shell, private dir: string, private baseRef = "HEAD") {}
+
+  async getDiff(changeId?: string): Promise<string> {
+    const ref = changeId ?? this.baseRef;
+    try {
+      if (isStashRef(ref)) {
+        return await this.$`git stash show -p --include-untracked --no-color --no-ext-diff --src-prefix=a/ --dst-prefix=b/ ${ref}`.text();
+      }
+
+      const tracked = await this.$`git diff -M --no-color --no-ext-diff --src-prefix=a/ --dst-prefix=b/ ${ref} --`.text();
+
+      // git diff leaves out new files that were never added
+      const untracked = (await this.$`git ls-files --others --exclude-standard -z`.text())
+        .split("\0")
+        .filter((path: string) => path !== "");
+      const added: string[] = [];
+      for (const path of untracked) {
+        // --no-index exits with 1 when the files differ
+        const result = await this.$`git diff --no-index --no-color --no-ext-diff --src-prefix=a/ --dst-prefix=b/ -- /dev/null ${path}`.nothrow().quiet();
+        added.push(result.stdout.toString());
+      }
+
+      return tracked + added.join("");
+    } catch (error) {
+      throw new Error(`Failed to get git diff: ${errorMessage(error)}`);
+    }
+  }
+
+  async getCurrentChangeId(): Promise<string> {
+    if (isStashRef(this.baseRef)) {
+      return this.baseRef;
+    }
+    try {
+      const result = await this.$`git rev-parse --verify ${this.baseRef + "^{commit}"}`.text();
+      return result.trim();
+    } catch (error) {
+      throw new Error(`Failed to resolve ${this.baseRef}: ${errorMessage(error)}`);
+    }
+  }
+
+  async restorePaths(changeId: string, paths: string[]): Promise<void> {
+    const base = this.parentOf(changeId);
+    for (const path of paths) {
+      if (await this.existsAt(base, path)) {
+        await this.$`git checkout ${base} -- ${path}`.quiet();
+      } else {
+        // Added by the change
+        rmSync(join(this.dir, path), { force: true });
+      }
+    }
+  }
+
+  async finalize(plan: FinalizePlan): Promise<FinalizeResult> {
+    const parent = (await this.$`git rev-parse --verify ${this.parentOf(plan.changeId) + "^{commit}"}`.text()).trim();
+
+    // Build the tree in a scratch index so the user's index and HEAD stay untouched
+    const scratch = join(tmpdir(), `codeforge-index-${process.pid}-${Date.now()}`);
+    const patchFile = `${scratch}.patch`;
+    const env = { ...process.env, GIT_INDEX_FILE: scratch };
+    let commitId: string;
+    try {
+      await this.$`git read-tree ${parent}`.env(env).quiet();
+
+      for (const hunk of plan.hunks) {
+        if (hunk.fileChange?.binary) {
+          // Binary content can't come from a diff: take it from the working tree
+          await this.$`git add -A -- ${binaryPaths(hunk.fileChange)}`.env(env).quiet();
+          continue;
+        }
+        const patch = hunk.fileChange
+          ? formatFileChange(hunk.fileChange) + "\n"
+          : await this.contentPatch(hunk, env);
+        writeFileSync(patchFile, patch);
+        const result = await this.$`git apply --cached --whitespace=nowarn ${patchFile}`.env(env).nothrow().quiet();
+        if (result.exitCode !== 0) {
+          throw new Error(`Failed to apply ${hunk.id}: ${result.stderr.toString().trim()}`);
+        }
+      }
+
+      const tree = (await this.$`git write-tree`.env(env).text()).trim();
+      commitId = (await this.$`git commit-tree ${tree} -p ${parent} -m ${plan.message}`.text()).trim();
+      await this.$`git update-ref ${"refs/heads/" + plan.name} ${commitId}`.quiet();
+    } finally {
+      rmSync(scratch, { force: true });
+      rmSync(patchFile, { force: true });
+    }
+
+    if (plan.push) {
+      await this.$`git push ${plan.remote ?? "origin"} ${"refs/heads/" + plan.name}`.quiet();
+    }
+
+    return { commitId, name: plan.name, pushed: plan.push };
+  }
+
+  /**
+   * The working tree for working tree suggestions (it is the change); stash
+   * entries are applied in a new worktree on their base commit
+   */
+  async checkout(changeId: string): Promise<Checkout> {
+    if (!isStashRef(changeId)) {
+      return { dir: this.dir, remove: async () => {} };
+    }
+
+    const dir = checkoutDir();
+    const remove = async () => {
+      await this.$`git worktree remove --force ${dir}`.nothrow().quiet();
+      rmSync(dir, { recursive: true, force: true });
+    };
+    try {
+      await this.$`git worktree add --detach ${dir} ${this.parentOf(changeId)}`.quiet();
+      await this.$`git -C ${dir} stash apply ${changeId}`.quiet();
+    } catch (error) {
+      await remove();
+      throw new Error(`Failed to check out ${changeId}: ${errorMessage(error)}`);
+    }
+    return { dir, remove };
+  }
+
+  /**
+   * Wrap a content hunk in file headers for git apply
+   * Whole-file adds/deletes (+0,0 / -0,0) get /dev/null sides.
+   */
+  private async contentPatch(hunk: Hunk, env: Record<string, string | undefined>): Promise<string> {
+    const header = parseHunkHeader(hunk.diff.split("\n")[0] ?? "");
+    const staged = await this.$`git cat-file -e ${":" + hunk.file}`.env(env).nothrow().quiet();
+    const added = header?.oldStart === 0 && header.oldCount === 0 && staged.exitCode !== 0;
+    const deleted = header?.newStart === 0 && header.newCount === 0;
+
+    const lines = [`diff --git a/${hunk.file} b/${hunk.file}`];
+    if (added) lines.push(`new file mode ${hunk.fileMode ?? "100644"}`);
+    if (deleted) lines.push(`deleted file mode ${hunk.fileMode ?? "100644"}`);
+    lines.push(added ? "--- /dev/null" : `--- a/${hunk.file}`);
+    lines.push(deleted ? "+++ /dev/null" : `+++ b/${hunk.file}`);
+    lines.push(hunk.diff.replace(/\n+$/, ""));
+    return lines.join("\n") + "\n";
+  }
+
+  /**
+   * The commit a change applies to (the stash's base commit for stash entries)
+   */
+  private parentOf(changeId: string): string {
+    return isStashRef(changeId) ? `${changeId}^1` : changeId;
+  }
+
+  private async existsAt(rev: string, path: string): Promise<boolean> {
+    const result = await this.$`git cat-file -e ${rev + ":" + path}`.nothrow().quiet();
+    return result.exitCode === 0;
+  }
+}
+
+function errorMessage(error: unknown): string {
+  return error instanceof Error ? error.message : String(error);
+}
@@ -0,0 +1,99 @@
+import { describe, expect, test, beforeEach, afterEach } from "bun:test";
+import {
+  getTokenPath,
+  loadOrCreateToken,
+  ensureTokenFile,
+  extractRequestToken,
+  tokensMatch,
+  unauthorizedResponse,
+} from "../src/auth.ts";
+import { mkdirSync, readFileSync, rmSync, existsSync, statSync } from "fs";
+import { join } from "path";
+import { tmpdir } from "os";
+
+describe("token storage", () => {
+  let testDir: string;
+
+  beforeEach(() => {
+    testDir = join(tmpdir(), `codeforge-auth-test-${Date.now()}`);
+    mkdirSync(testDir, { recursive: true });
+  });
+
+  afterEach(() => {
+    if (existsSync(testDir)) {
+      rmSync(testDir, { recursive: true });
+    }
+  });
+
+  test("token path sits next to the database", () => {
+    expect(getTokenPath("/project")).toBe("/project/.opencode/codeforge.token");
+  });
+
+  test("generates a token on first start", () => {
+    const tokenPath = getTokenPath(testDir);
+
+    const token = loadOrCreateToken(tokenPath);
+
+    expect(token).toMatch(/^[0-9a-f]{64}$/);
+    expect(readFileSync(tokenPath, "utf-8").trim()).toBe(token);
+    expect(statSync(tokenPath).mode & 0o077).toBe(0o040);
+  });
+
+  test("reuses an existing token", () => {
+    const tokenPath = getTokenPath(testDir);
+
+    const first = loadOrCreateToken(tokenPath);
+    const second = loadOrCreateToken(tokenPath);
+
+    expect(second).toBe(first);
+  });
+
+  test("ensureTokenFile overwrites a stale token", () => {
+    const tokenPath = getTokenPath(testDir);
+    loadOrCreateToken(tokenPath);
+
+    ensureTokenFile(tokenPath, "server-token");
+
+    expect(readFileSync(tokenPath, "utf-8").trim()).toBe("server-token");
+  });
+});
+
+describe("request authentication", () => {
+  test("extracts bearer token from Authorization header", () => {
+    const req = new Request("http://127.0.0.1:4097/health", {
+      headers: { Authorization: "Bearer abc123" },
+    });
+
+    expect(extractRequestToken(req, new URL(req.url))).toBe("abc123");
+  });
+
+  test("falls back to token query parameter", () => {
+    const req = new Request("http://127.0.0.1:4097/ws?token=abc123");
+
+    expect(extractRequestToken(req, new URL(req.url))).toBe("abc123");
+  });
+
+  test("returns null when no token is presented", () => {
+    const req = new Request("http://127.0.0.1:4097/health");
+
+    expect(extractRequestToken(req, new URL(req.url))).toBeNull();
+  });
+
+  test("tokensMatch compares exactly", () => {
+    expect(tokensMatch("abc123", "abc123")).toBe(true);
+    expect(tokensMatch("abc124", "abc123")).toBe(false);
+    expect(tokensMatch("abc", "abc123")).toBe(false);
+    expect(tokensMatch(null, "abc123")).toBe(false);
+  });
+
+  test("unauthorized response is structured", async () => {
+    const res = unauthorizedResponse({}, false);
+
+    expect(res.status).toBe(401);
+    expect(res.headers.get("WWW-Authenticate")).toContain("Bearer");
+    const body = await res.json() as { success: boolean; code: string; error: string };
+    expect(body.success).toBe(false);
+    expect(body.code).toBe("unauthorized");
+    expect(body.error).toContain("Missing");
+  });
+});
@@ -0,0 +1,131 @@
+import { describe, expect, test, beforeEach, afterEach } from "bun:test";
+import { mkdirSync, writeFileSync, rmSync } from "fs";
+import { join } from "path";
+import { tmpdir } from "os";
+import { parseDiagnostics, mapDiagnosticsToHunks, runCommand } from "../src/checks.ts";
+import type { Hunk } from "../src/types.ts";
+
+describe("parseDiagnostics", () => {
+  let testDir: string;
+
+  beforeEach(() => {
+    testDir = join(tmpdir(), `codeforge-checks-test-${Date.now()}`);
+    mkdirSync(join(testDir, "src"), { recursive: true });
+    writeFileSync(join(testDir, "src", "main.rs"), "");
+    writeFileSync(join(testDir, "src", "a.ts"), "");
+  });
+
+  afterEach(() => {
+    rmSync(testDir, { recursive: true, force: true });
+  });
+
+  test("reads rustc errors, warnings and test panics", () => {
+    const output = [
+      "   Compiling demo v0.1.0",
+      "error[E0308]: mismatched types",
+      "  --> src/main.rs:10:5",
+      "   |",
+      "warning: unused variable: `x`",
+      " --> src/main.rs:3:9",
+      "thread 'tests::it_works' panicked at src/main.rs:20:9:",
+    ].join("\n");
+
+    expect(parseDiagnostics(output, testDir)).toEqual([
+      { file: "src/main.rs", line: 10, column: 5, severity: "error", message: "mismatched types" },
+      { file: "src/main.rs", line: 3, column: 9, severity: "warning", message: "unused variable: `x`" },
+      { file: "src/main.rs", line: 20, column: 9, severity: "error", message: "panicked" },
+    ]);
+  });
+
+  test("reads tsc and path:line:col output", () => {
+    const output = [
+      "src/a.ts(4,7): error TS2322: Type 'string' is not assignable",
+      "src/a.ts:12:3: warning  Unexpected any",
+      `${join(testDir, "src", "a.ts")}:15: missing return`,
+    ].join("\n");
+
+    expect(parseDiagnostics(output, testDir)).toEqual([
+      { file: "src/a.ts", line: 4, column: 7, severity: "error", message: "TS2322: Type 'string' is not assignable" },
+      { file: "src/a.ts", line: 12, column: 3, severity: "warning", message: "Unexpected any" },
+      { file: "src/a.ts", line: 15, column: undefined, severity: "error", message: "missing return" },
+    ]);
+  });
+
+  test("reads cargo --message-format=json output", () => {
+    const message = (level: string, text: string, code: string | null, spans: object[]) =>
+      JSON.stringify({
+        reason: "compiler-message",
+        package_id: "demo 0.1.0",
+        message: { level, message: text, code: code ? { code, explanation: null } : null, spans, children: [], rendered: text },
+      });
+    const span = (line: number, column: number, isPrimary: boolean) => ({
+      file_name: "src/main.rs",
+      line_start: line,
+      line_end: line,
+      column_start: column,
+      column_end: column + 1,
+      is_primary: isPrimary,
+    });
+    const output = [
+      JSON.stringify({ reason: "compiler-artifact", package_id: "dep 1.0.0", target: { name: "dep" } }),
+      message("warning", "unused variable: `x`", "unused_variables", [span(3, 9, true)]),
+      message("error", "mismatched types", "E0308", [span(7, 1, false), span(10, 5, true)]),
+      message("error", "aborting due to 1 previous error", null, []),
+      message("note", "some note", null, [span(1, 1, true)]),
+      JSON.stringify({ reason: "build-finished", success: false }),
+    ].join("\n");
+
+    expect(parseDiagnostics(output, testDir)).toEqual([
+      { file: "src/main.rs", line: 3, column: 9, severity: "warning", message: "unused variable: `x`", code: "unused_variables" },
+      { file: "src/main.rs", line: 10, column: 5, severity: "error", message: "mismatched types", code: "E0308" },
+    ]);
+  });
+
+  test("skips files outside the project or that don't exist", () => {
+    const output = [
+      "/usr/lib/rustlib/src/core.rs:1:1: error: outside",
+      "src/gone.rs:3: error: gone",
+    ].join("\n");
+
+    expect(parseDiagnostics(output, testDir)).toEqual([]);
+  });
+});
+
+describe("mapDiagnosticsToHunks", () => {
+  const hunks: Hunk[] = [
+    { id: "s:src/a.ts:0", file: "src/a.ts", diff: "@@ -1,3 +1,4 @@\n a\n+b\n c\n d" },
+    { id: "s:src/a.ts:1", file: "src/a.ts", diff: "@@ -10,2 +11,0 @@\n-x\n-y" },
+  ];
+
+  test("attaches diagnostics inside a hunk's proposed lines", () => {
+    const mapped = mapDiagnosticsToHunks(
+      [
+        { file: "src/a.ts", line: 2, severity: "error", message: "in first" },
+        { file: "src/a.ts", line: 11, severity: "error", message: "at removal" },
+        { file: "src/a.ts", line: 8, severity: "error", message: "between" },
+        { file: "src/b.ts", line: 2, severity: "error", message: "other file" },
+      ],
+      hunks
+    );
+
+    expect(mapped.map((d) => d.hunkId)).toEqual(["s:src/a.ts:0", "s:src/a.ts:1", undefined, undefined]);
+  });
+});
+
+describe("runCommand", () => {
+  test("collects output and exit code", async () => {
+    const result = await runCommand("echo out; echo err >&2; exit 3", tmpdir());
+
+    expect(result.exitCode).toBe(3);
+    expect(result.output).toContain("out");
+    expect(result.output).toContain("err");
+    expect(result.timedOut).toBe(false);
+  });
+
+  test("kills a command that runs too long", async () => {
+    const result = await runCommand("sleep 5", tmpdir(), 0.1);
+
+    expect(result.timedOut).toBe(true);
+    expect(result.exitCode).not.toBe(0);
+  });
+});
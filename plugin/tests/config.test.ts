@@ -2,7 +2,7 @@ import { describe, expect, test, beforeEach, afterEach } from "bun:test";
 import { loadConfig } from "../src/index.ts";
 import { mkdirSync, writeFileSync, rmSync, existsSync } from "fs";
 import { join } from "path";
-import { tmpdir } from "os";
+import { homedir, tmpdir } from "os";
 
 describe("loadConfig", () => {
   let testDir: string;
@@ -17,11 +17,43 @@ describe("loadConfig", () => {
     originalEnv.CODEFORGE_SERVER_ENABLED = process.env.CODEFORGE_SERVER_ENABLED;
     originalEnv.CODEFORGE_SERVER_PORT = process.env.CODEFORGE_SERVER_PORT;
     originalEnv.CODEFORGE_SERVER_HOST = process.env.CODEFORGE_SERVER_HOST;
+    originalEnv.CODEFORGE_SERVER_AUTH = process.env.CODEFORGE_SERVER_AUTH;
+    originalEnv.CODEFORGE_VCS = process.env.CODEFORGE_VCS;
+    originalEnv.CODEFORGE_VCS_BASE_REF = process.env.CODEFORGE_VCS_BASE_REF;
+    originalEnv.CODEFORGE_VCS_BRANCH = process.env.CODEFORGE_VCS_BRANCH;
+    originalEnv.CODEFORGE_VCS_PUSH = process.env.CODEFORGE_VCS_PUSH;
+    originalEnv.CODEFORGE_VCS_REMOTE = process.env.CODEFORGE_VCS_REMOTE;
+    originalEnv.CODEFORGE_CHECKS_ON_PUBLISH = process.env.CODEFORGE_CHECKS_ON_PUBLISH;
+    originalEnv.CODEFORGE_CARGO_CHECK = process.env.CODEFORGE_CARGO_CHECK;
+    originalEnv.CODEFORGE_SERVER_SOCKET = process.env.CODEFORGE_SERVER_SOCKET;
+    originalEnv.CODEFORGE_SERVER_SOCKET_MODE = process.env.CODEFORGE_SERVER_SOCKET_MODE;
+    originalEnv.CODEFORGE_SERVER_SOCKET_GROUP = process.env.CODEFORGE_SERVER_SOCKET_GROUP;
+    originalEnv.CODEFORGE_DAEMON = process.env.CODEFORGE_DAEMON;
+    originalEnv.CODEFORGE_DAEMON_SOCKET = process.env.CODEFORGE_DAEMON_SOCKET;
+    originalEnv.CODEFORGE_DAEMON_SOCKET_MODE = process.env.CODEFORGE_DAEMON_SOCKET_MODE;
+    originalEnv.CODEFORGE_DAEMON_SOCKET_GROUP = process.env.CODEFORGE_DAEMON_SOCKET_GROUP;
+    originalEnv.CODEFORGE_DAEMON_PROJECTS = process.env.CODEFORGE_DAEMON_PROJECTS;
 
     // Clear env vars for testing
     delete process.env.CODEFORGE_SERVER_ENABLED;
     delete process.env.CODEFORGE_SERVER_PORT;
     delete process.env.CODEFORGE_SERVER_HOST;
+    delete process.env.CODEFORGE_SERVER_AUTH;
+    delete process.env.CODEFORGE_VCS;
+    delete process.env.CODEFORGE_VCS_BASE_REF;
+    delete process.env.CODEFORGE_VCS_BRANCH;
+    delete process.env.CODEFORGE_VCS_PUSH;
+    delete process.env.CODEFORGE_VCS_REMOTE;
+    delete process.env.CODEFORGE_CHECKS_ON_PUBLISH;
+    delete process.env.CODEFORGE_CARGO_CHECK;
+    delete process.env.CODEFORGE_SERVER_SOCKET;
+    delete process.env.CODEFORGE_SERVER_SOCKET_MODE;
+    delete process.env.CODEFORGE_SERVER_SOCKET_GROUP;
+    delete process.env.CODEFORGE_DAEMON;
+    delete process.env.CODEFORGE_DAEMON_SOCKET;
+    delete process.env.CODEFORGE_DAEMON_SOCKET_MODE;
+    delete process.env.CODEFORGE_DAEMON_SOCKET_GROUP;
+    delete process.env.CODEFORGE_DAEMON_PROJECTS;
   });
 
   afterEach(() => {
@@ -46,6 +78,62 @@ describe("loadConfig", () => {
     } else {
       delete process.env.CODEFORGE_SERVER_HOST;
     }
+    if (originalEnv.CODEFORGE_SERVER_AUTH !== undefined) {
+      process.env.CODEFORGE_SERVER_AUTH = originalEnv.CODEFORGE_SERVER_AUTH;
+    } else {
+      delete process.env.CODEFORGE_SERVER_AUTH;
+    }
+    if (originalEnv.CODEFORGE_VCS !== undefined) {
+      process.env.CODEFORGE_VCS = originalEnv.CODEFORGE_VCS;
+    } else {
+      delete process.env.CODEFORGE_VCS;
+    }
+    if (originalEnv.CODEFORGE_VCS_BASE_REF !== undefined) {
+      process.env.CODEFORGE_VCS_BASE_REF = originalEnv.CODEFORGE_VCS_BASE_REF;
+    } else {
+      delete process.env.CODEFORGE_VCS_BASE_REF;
+    }
+    if (originalEnv.CODEFORGE_VCS_BRANCH !== undefined) {
+      process.env.CODEFORGE_VCS_BRANCH = originalEnv.CODEFORGE_VCS_BRANCH;
+    } else {
+      delete process.env.CODEFORGE_VCS_BRANCH;
+    }
+    if (originalEnv.CODEFORGE_VCS_PUSH !== undefined) {
+      process.env.CODEFORGE_VCS_PUSH = originalEnv.CODEFORGE_VCS_PUSH;
+    } else {
+      delete process.env.CODEFORGE_VCS_PUSH;
+    }
+    if (originalEnv.CODEFORGE_VCS_REMOTE !== undefined) {
+      process.env.CODEFORGE_VCS_REMOTE = originalEnv.CODEFORGE_VCS_REMOTE;
+    } else {
+      delete process.env.CODEFORGE_VCS_REMOTE;
+    }
+    if (originalEnv.CODEFORGE_CHECKS_ON_PUBLISH !== undefined) {
+      process.env.CODEFORGE_CHECKS_ON_PUBLISH = originalEnv.CODEFORGE_CHECKS_ON_PUBLISH;
+    } else {
+      delete process.env.CODEFORGE_CHECKS_ON_PUBLISH;
+    }
+    if (originalEnv.CODEFORGE_CARGO_CHECK !== undefined) {
+      process.env.CODEFORGE_CARGO_CHECK = originalEnv.CODEFORGE_CARGO_CHECK;
+    } else {
+      delete process.env.CODEFORGE_CARGO_CHECK;
+    }
+    for (const name of [
+      "CODEFORGE_SERVER_SOCKET",
+      "CODEFORGE_SERVER_SOCKET_MODE",
+      "CODEFORGE_SERVER_SOCKET_GROUP",
+      "CODEFORGE_DAEMON",
+      "CODEFORGE_DAEMON_SOCKET",
+      "CODEFORGE_DAEMON_SOCKET_MODE",
+      "CODEFORGE_DAEMON_SOCKET_GROUP",
+      "CODEFORGE_DAEMON_PROJECTS",
+    ]) {
+      if (originalEnv[name] !== undefined) {
+        process.env[name] = originalEnv[name];
+      } else {
+        delete process.env[name];
+      }
+    }
   });
 
   test("returns defaults when no config files exist", () => {
@@ -54,6 +142,9 @@ describe("loadConfig", () => {
     expect(config.enabled).toBe(true);
     expect(config.port).toBe(4097);
     expect(config.host).toBe("127.0.0.1");
+    expect(config.auth).toBe(true);
+    expect(config.vcs).toBe("auto");
+    expect(config.baseRef).toBeUndefined();
   });
 
   test("loads project config from .opencode/codeforge.json", () => {
@@ -170,4 +261,201 @@ describe("loadConfig", () => {
 
     expect(config.port).toBe(4097); // default
   });
+
+  test("project config can disable auth", () => {
+    const configDir = join(testDir, ".opencode");
+    mkdirSync(configDir, { recursive: true });
+    writeFileSync(
+      join(configDir, "codeforge.json"),
+      JSON.stringify({ server: { auth: false } })
+    );
+
+    const config = loadConfig(testDir);
+
+    expect(config.auth).toBe(false);
+  });
+
+  test("CODEFORGE_SERVER_AUTH=false disables auth", () => {
+    process.env.CODEFORGE_SERVER_AUTH = "false";
+
+    const config = loadConfig(testDir);
+
+    expect(config.auth).toBe(false);
+  });
+
+  test("project config selects the VCS backend", () => {
+    const configDir = join(testDir, ".opencode");
+    mkdirSync(configDir, { recursive: true });
+    writeFileSync(
+      join(configDir, "codeforge.json"),
+      JSON.stringify({ vcs: { backend: "git", baseRef: "main" } })
+    );
+
+    const config = loadConfig(testDir);
+
+    expect(config.vcs).toBe("git");
+    expect(config.baseRef).toBe("main");
+  });
+
+  test("finalize defaults to a codeforge/{id} branch without pushing", () => {
+    const config = loadConfig(testDir);
+
+    expect(config.branch).toBe("codeforge/{id}");
+    expect(config.push).toBe(false);
+    expect(config.remote).toBeUndefined();
+  });
+
+  test("project config sets finalize naming and pushing", () => {
+    const configDir = join(testDir, ".opencode");
+    mkdirSync(configDir, { recursive: true });
+    writeFileSync(
+      join(configDir, "codeforge.json"),
+      JSON.stringify({ vcs: { branch: "review/{slug}", push: true, remote: "upstream" } })
+    );
+
+    const config = loadConfig(testDir);
+
+    expect(config.branch).toBe("review/{slug}");
+    expect(config.push).toBe(true);
+    expect(config.remote).toBe("upstream");
+  });
+
+  test("CODEFORGE_VCS overrides the backend and ignores unknown values", () => {
+    process.env.CODEFORGE_VCS = "git";
+    expect(loadConfig(testDir).vcs).toBe("git");
+
+    process.env.CODEFORGE_VCS = "svn";
+    expect(loadConfig(testDir).vcs).toBe("auto");
+  });
+
+  test("no checks are configured by default", () => {
+    const config = loadConfig(testDir);
+
+    expect(config.checks).toEqual([]);
+    expect(config.checksOnPublish).toBe(true);
+  });
+
+  test("project config sets the check pipeline, dropping invalid entries", () => {
+    const configDir = join(testDir, ".opencode");
+    mkdirSync(configDir, { recursive: true });
+    writeFileSync(
+      join(configDir, "codeforge.json"),
+      JSON.stringify({
+        checks: {
+          onPublish: false,
+          pipeline: [
+            { name: "test", command: "cargo test", timeout: 120 },
+            { name: "broken" },
+          ],
+        },
+      })
+    );
+
+    const config = loadConfig(testDir);
+
+    expect(config.checks).toEqual([{ name: "test", command: "cargo test", timeout: 120 }]);
+    expect(config.checksOnPublish).toBe(false);
+  });
+
+  test("CODEFORGE_CHECKS_ON_PUBLISH=false stops checks on publish", () => {
+    process.env.CODEFORGE_CHECKS_ON_PUBLISH = "false";
+
+    expect(loadConfig(testDir).checksOnPublish).toBe(false);
+  });
+
+  test("cargo check is left to detection unless configured", () => {
+    expect(loadConfig(testDir).cargoCheck).toBeUndefined();
+
+    process.env.CODEFORGE_CARGO_CHECK = "false";
+    expect(loadConfig(testDir).cargoCheck).toBe(false);
+  });
+
+  test("listens on TCP unless a socket is configured", () => {
+    const config = loadConfig(testDir);
+
+    expect(config.socket).toBeUndefined();
+    expect(config.socketMode).toBe(0o660);
+    expect(config.socketGroup).toBeUndefined();
+  });
+
+  test("project config sets the socket, relative to the project", () => {
+    const configDir = join(testDir, ".opencode");
+    mkdirSync(configDir, { recursive: true });
+    writeFileSync(
+      join(configDir, "codeforge.json"),
+      JSON.stringify({ server: { socket: ".opencode/codeforge.sock", socketMode: "600", socketGroup: "1234" } })
+    );
+
+    const config = loadConfig(testDir);
+
+    expect(config.socket).toBe(join(testDir, ".opencode/codeforge.sock"));
+    expect(config.socketMode).toBe(0o600);
+    expect(config.socketGroup).toBe(1234);
+  });
+
+  test("socket env vars override config, ignoring a bad mode", () => {
+    process.env.CODEFORGE_SERVER_SOCKET = "/run/codeforge.sock";
+    process.env.CODEFORGE_SERVER_SOCKET_MODE = "rw-rw----";
+
+    const config = loadConfig(testDir);
+
+    expect(config.socket).toBe("/run/codeforge.sock");
+    expect(config.socketMode).toBe(0o660);
+  });
+
+  test("serves editors in-process unless the daemon is enabled", () => {
+    expect(loadConfig(testDir).daemon).toBeUndefined();
+
+    process.env.CODEFORGE_DAEMON = "true";
+
+    expect(loadConfig(testDir).daemon).toBe(join(homedir(), ".local/share/codeforge/daemon.sock"));
+  });
+
+  test("project config picks the daemon socket", () => {
+    const configDir = join(testDir, ".opencode");
+    mkdirSync(configDir, { recursive: true });
+    writeFileSync(
+      join(configDir, "codeforge.json"),
+      JSON.stringify({ daemon: { enabled: true, socket: "/run/user/1000/codeforge.sock" } })
+    );
+
+    expect(loadConfig(testDir).daemon).toBe("/run/user/1000/codeforge.sock");
+
+    process.env.CODEFORGE_DAEMON = "false";
+
+    expect(loadConfig(testDir).daemon).toBeUndefined();
+  });
+
+  test("the daemon socket is owner-only unless configured otherwise", () => {
+    expect(loadConfig(testDir).daemonSocketMode).toBe(0o600);
+
+    const configDir = join(testDir, ".opencode");
+    mkdirSync(configDir, { recursive: true });
+    writeFileSync(
+      join(configDir, "codeforge.json"),
+      JSON.stringify({ daemon: { socketMode: "660", socketGroup: "1234" } })
+    );
+
+    const config = loadConfig(testDir);
+    expect(config.daemonSocketMode).toBe(0o660);
+    expect(config.daemonSocketGroup).toBe(1234);
+
+    process.env.CODEFORGE_DAEMON_SOCKET_MODE = "not-a-mode";
+    process.env.CODEFORGE_DAEMON_SOCKET_GROUP = "";
+
+    expect(loadConfig(testDir).daemonSocketMode).toBe(0o600);
+    expect(loadConfig(testDir).daemonSocketGroup).toBeUndefined();
+  });
+
+  test("projects can't allow themselves to register with the daemon", () => {
+    const configDir = join(testDir, ".opencode");
+    mkdirSync(configDir, { recursive: true });
+    writeFileSync(join(configDir, "codeforge.json"), JSON.stringify({ daemon: { projects: ["/"] } }));
+
+    expect(loadConfig(testDir).daemonProjects).toEqual([]);
+
+    process.env.CODEFORGE_DAEMON_PROJECTS = "/srv/projects:~/work";
+
+    expect(loadConfig(testDir).daemonProjects).toEqual(["/srv/projects", join(homedir(), "work")]);
+  });
 });
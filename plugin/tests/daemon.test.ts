@@ -0,0 +1,211 @@
+import { describe, expect, test, beforeEach, afterEach, mock } from "bun:test";
+import { checkProjectAllowed, connectToDaemon, forwardTools, listenForPlugins, remoteProcess, type PluginListener } from "../src/daemon.ts";
+import { getTokenPath } from "../src/auth.ts";
+import { mkdirSync, rmSync, existsSync, statSync, readFileSync, symlinkSync } from "fs";
+import { join } from "path";
+import { tmpdir } from "os";
+
+// Mock OpenCode client of a plugin instance
+function createMockClient() {
+  return {
+    app: { log: mock(async () => ({ data: true })) },
+    session: {
+      list: mock(async () => ({ data: [{ id: "session-1", title: "main" }] })),
+      prompt: mock(async () => ({ data: undefined })),
+    },
+    tui: { showToast: mock(async () => ({ data: true })) },
+  };
+}
+
+// Version control and command runner of a plugin instance
+function createMockProcess() {
+  const remove = mock(async () => {});
+  return {
+    vcs: {
+      kind: "git",
+      getDiff: mock(async (changeId?: string) => `diff of ${changeId}`),
+      getCurrentChangeId: mock(async () => "HEAD"),
+      restorePaths: mock(async () => {}),
+      finalize: mock(async () => ({ commitId: "abc", name: "codeforge/s", pushed: false })),
+      checkout: mock(async () => ({ dir: "/tmp/checkout", remove })),
+    },
+    run: mock(async () => ({ exitCode: 0, output: "ok", timedOut: false })),
+    remove,
+  };
+}
+
+describe("daemon IPC", () => {
+  let testDir: string;
+  let socketPath: string;
+  let listener: PluginListener | undefined;
+
+  beforeEach(() => {
+    testDir = join(tmpdir(), `codeforge-daemon-test-${Date.now()}`);
+    mkdirSync(testDir, { recursive: true });
+    socketPath = join(testDir, "daemon.sock");
+  });
+
+  afterEach(async () => {
+    await new Promise((resolve) => (listener ? listener.server.close(resolve) : resolve(undefined)));
+    listener = undefined;
+    if (existsSync(testDir)) {
+      rmSync(testDir, { recursive: true });
+    }
+  });
+
+  test("runs tool calls for the project a plugin registered", async () => {
+    const registered: string[] = [];
+    listener = await listenForPlugins(socketPath, (registration) => {
+      registered.push(registration.workingDirectory);
+      return {
+        echo: { execute: async (args: { text: string }) => `${registration.workingDirectory}: ${args.text}` },
+      };
+    });
+
+    const daemon = await connectToDaemon(socketPath, { workingDirectory: "/project-a" }, createMockClient() as any, createMockProcess() as any);
+
+    expect(daemon).not.toBeNull();
+    expect(registered).toEqual(["/project-a"]);
+    expect(await daemon!.call("echo", { text: "hi" })).toBe("/project-a: hi");
+    expect(JSON.parse((await daemon!.call("missing", {}))!).success).toBe(false);
+    daemon!.close();
+  });
+
+  test("routes OpenCode client calls to the project's plugin instance", async () => {
+    listener = await listenForPlugins(socketPath, (_registration, client) => ({
+      sessions: {
+        execute: async () => {
+          const sessions = await client.session.list();
+          return JSON.stringify(sessions.data);
+        },
+      },
+    }));
+    const clientA = createMockClient();
+    const clientB = createMockClient();
+    clientB.session.list = mock(async () => ({ data: [{ id: "session-b", title: "other" }] }));
+
+    const daemonA = await connectToDaemon(socketPath, { workingDirectory: "/project-a" }, clientA as any, createMockProcess() as any);
+    const daemonB = await connectToDaemon(socketPath, { workingDirectory: "/project-b" }, clientB as any, createMockProcess() as any);
+
+    expect(JSON.parse((await daemonA!.call("sessions", {}))!)[0].id).toBe("session-1");
+    expect(JSON.parse((await daemonB!.call("sessions", {}))!)[0].id).toBe("session-b");
+    expect(clientA.session.list).toHaveBeenCalledTimes(1);
+    expect(clientB.session.list).toHaveBeenCalledTimes(1);
+    daemonA!.close();
+    daemonB!.close();
+  });
+
+  test("runs commands and version control in the project's plugin instance", async () => {
+    listener = await listenForPlugins(socketPath, (_registration, _client, plugin) => {
+      const host = remoteProcess("git", plugin);
+      return {
+        check: {
+          execute: async () => {
+            const checkout = await host.vcs.checkout("stash@{0}");
+            const result = await host.run("make test", checkout.dir, 30);
+            await checkout.remove();
+            return JSON.stringify({ diff: await host.vcs.getDiff("HEAD"), result });
+          },
+        },
+      };
+    });
+    const local = createMockProcess();
+
+    const daemon = await connectToDaemon(socketPath, { workingDirectory: "/project-a" }, createMockClient() as any, local as any);
+
+    expect(JSON.parse((await daemon!.call("check", {}))!)).toEqual({
+      diff: "diff of HEAD",
+      result: { exitCode: 0, output: "ok", timedOut: false },
+    });
+    expect(local.vcs.checkout).toHaveBeenCalledWith("stash@{0}");
+    expect(local.run).toHaveBeenCalledWith("make test", "/tmp/checkout", 30);
+    expect(local.remove).toHaveBeenCalledTimes(1);
+    daemon!.close();
+  });
+
+  test("hands its token to the plugin, which stores it in the project", async () => {
+    listener = await listenForPlugins(socketPath, () => ({}), { token: "secret" });
+
+    const daemon = await connectToDaemon(socketPath, { workingDirectory: testDir }, createMockClient() as any, createMockProcess() as any);
+
+    expect(readFileSync(getTokenPath(testDir), "utf-8").trim()).toBe("secret");
+    daemon!.close();
+  });
+
+  test("a refused registration leaves the plugin in-process", async () => {
+    listener = await listenForPlugins(socketPath, () => {
+      throw new Error("not allowed");
+    });
+
+    expect(await connectToDaemon(socketPath, { workingDirectory: "/project-a" }, createMockClient() as any, createMockProcess() as any)).toBeNull();
+  });
+
+  test("the socket is owner-only unless configured otherwise", async () => {
+    listener = await listenForPlugins(socketPath, () => ({}));
+    expect(statSync(socketPath).mode & 0o777).toBe(0o600);
+    await new Promise((resolve) => listener!.server.close(resolve));
+
+    listener = await listenForPlugins(socketPath, () => ({}), { mode: 0o660 });
+    expect(statSync(socketPath).mode & 0o777).toBe(0o660);
+  });
+
+  test("no daemon listening", async () => {
+    expect(await connectToDaemon(socketPath, { workingDirectory: "/project-a" }, createMockClient() as any, createMockProcess() as any)).toBeNull();
+  });
+
+  test("tools run in-process while the daemon is gone", async () => {
+    listener = await listenForPlugins(socketPath, () => ({
+      where: { execute: async () => "daemon" },
+    }));
+    const daemon = await connectToDaemon(socketPath, { workingDirectory: "/project-a" }, createMockClient() as any, createMockProcess() as any);
+    const tools = forwardTools({ where: { execute: async () => "in-process" } }, daemon!);
+
+    expect(await tools.where.execute({}, {})).toBe("daemon");
+
+    daemon!.close();
+    await new Promise((resolve) => listener!.server.close(resolve));
+    listener = undefined;
+
+    expect(await tools.where.execute({}, {})).toBe("in-process");
+  });
+});
+
+describe("checkProjectAllowed", () => {
+  let testDir: string;
+
+  beforeEach(() => {
+    testDir = join(tmpdir(), `codeforge-daemon-roots-test-${Date.now()}`);
+    mkdirSync(join(testDir, "projects", "app"), { recursive: true });
+    mkdirSync(join(testDir, "elsewhere"), { recursive: true });
+  });
+
+  afterEach(() => {
+    rmSync(testDir, { recursive: true, force: true });
+  });
+
+  test("any directory may register on an owner-only socket", () => {
+    expect(() => checkProjectAllowed(join(testDir, "elsewhere"), [], false)).not.toThrow();
+    expect(() => checkProjectAllowed("relative/path", [], false)).toThrow("Not an absolute path");
+  });
+
+  test("a shared socket needs the allowed roots", () => {
+    expect(() => checkProjectAllowed(join(testDir, "projects", "app"), [], true)).toThrow("daemon.projects");
+  });
+
+  test("only directories under an allowed root may register", () => {
+    const roots = [join(testDir, "projects")];
+
+    expect(() => checkProjectAllowed(join(testDir, "projects"), roots, true)).not.toThrow();
+    expect(() => checkProjectAllowed(join(testDir, "projects", "app"), roots, true)).not.toThrow();
+    expect(() => checkProjectAllowed(join(testDir, "elsewhere"), roots, true)).toThrow("not under any of daemon.projects");
+    expect(() => checkProjectAllowed(join(testDir, "projects", "..", "elsewhere"), roots, true)).toThrow();
+  });
+
+  test("a symlink can't lead out of an allowed root", () => {
+    symlinkSync(join(testDir, "elsewhere"), join(testDir, "projects", "link"));
+
+    expect(() => checkProjectAllowed(join(testDir, "projects", "link"), [join(testDir, "projects")], true)).toThrow(
+      "not under any of daemon.projects"
+    );
+  });
+});
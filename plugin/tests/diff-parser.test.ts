@@ -1,4 +1,5 @@
 import { describe, expect, test } from "bun:test";
+import type { Hunk } from "../src/types.ts";
 import {
   parseDiff,
   fileDiffsToHunks,
@@ -7,6 +8,12 @@ import {
   filterFileDiffs,
   calculateLineOffset,
   adjustHunkLineNumbers,
+  parseFileChange,
+  formatFileChange,
+  matchHunks,
+  splitHunkSelection,
+  splitHunkDiff,
+  mergeHunkDiffs,
   type FileDiff,
   type FilterOptions,
 } from "../src/diff-parser.ts";
@@ -224,6 +231,165 @@ describe("fileDiffsToHunks", () => {
   });
 });
 
+describe("file-level changes", () => {
+  test("parses a rename with similarity and content changes", () => {
+    const diff = `diff --git a/src/old.ts b/src/new.ts
+similarity index 90%
+rename from src/old.ts
+rename to src/new.ts
+--- a/src/old.ts
++++ b/src/new.ts
+@@ -1 +1 @@
+-old
++new`;
+
+    const result = parseDiff(diff);
+
+    expect(result).toHaveLength(1);
+    expect(result[0]!.changeKind).toBe("rename");
+    expect(result[0]!.similarity).toBe(90);
+    expect(result[0]!.oldPath).toBe("src/old.ts");
+    expect(result[0]!.newPath).toBe("src/new.ts");
+    expect(result[0]!.hunks).toHaveLength(1);
+  });
+
+  test("parses a mode change", () => {
+    const diff = `diff --git a/bin/run b/bin/run
+old mode 100644
+new mode 100755`;
+
+    const result = parseDiff(diff);
+
+    expect(result[0]!.changeKind).toBe("mode");
+    expect(result[0]!.oldMode).toBe("100644");
+    expect(result[0]!.newMode).toBe("100755");
+    expect(result[0]!.hunks).toHaveLength(0);
+  });
+
+  test("parses a binary change", () => {
+    const diff = `diff --git a/assets/logo.png b/assets/logo.png
+index 1234567..89abcde 100644
+Binary files a/assets/logo.png and b/assets/logo.png differ`;
+
+    const result = parseDiff(diff);
+
+    expect(result[0]!.changeKind).toBe("binary");
+    expect(result[0]!.binary).toBe(true);
+  });
+
+  test("parses a deleted empty file", () => {
+    const diff = `diff --git a/src/empty.ts b/src/empty.ts
+deleted file mode 100644
+index e69de29..0000000`;
+
+    const result = parseDiff(diff);
+
+    expect(result[0]!.changeKind).toBe("delete");
+    expect(result[0]!.oldMode).toBe("100644");
+  });
+
+  test("content hunks of added and deleted files carry the file mode", () => {
+    const diff = `diff --git a/bin/run b/bin/run
+new file mode 100755
+index 0000000..1234567
+--- /dev/null
++++ b/bin/run
+@@ -0,0 +1 @@
++#!/bin/sh
+diff --git a/bin/old b/bin/old
+deleted file mode 100755
+index 1234567..0000000
+--- a/bin/old
++++ /dev/null
+@@ -1 +0,0 @@
+-#!/bin/sh`;
+
+    const hunks = fileDiffsToHunks(parseDiff(diff), "sugg-1");
+
+    expect(hunks.map((h) => [h.file, h.fileMode])).toEqual([
+      ["bin/run", "100755"],
+      ["bin/old", "100755"],
+    ]);
+  });
+
+  test("emits a file-level hunk before content hunks", () => {
+    const diff = `diff --git a/src/old.ts b/src/new.ts
+similarity index 90%
+rename from src/old.ts
+rename to src/new.ts
+--- a/src/old.ts
++++ b/src/new.ts
+@@ -1 +1 @@
+-old
++new`;
+
+    const hunks = fileDiffsToHunks(parseDiff(diff), "sugg-1");
+
+    expect(hunks).toHaveLength(2);
+    expect(hunks[0]!.id).toBe("sugg-1:src/new.ts:file");
+    expect(hunks[0]!.fileChange).toEqual({
+      kind: "rename",
+      oldPath: "src/old.ts",
+      newPath: "src/new.ts",
+      similarity: 90,
+      oldMode: undefined,
+      newMode: undefined,
+      binary: undefined,
+    });
+    expect(hunks[1]!.id).toBe("sugg-1:src/new.ts:0");
+    expect(hunks[1]!.fileChange).toBeUndefined();
+  });
+
+  test("uses oldPath for deleted files", () => {
+    const diff = `diff --git a/src/deleted.ts b/src/deleted.ts
+deleted file mode 100644
+--- a/src/deleted.ts
++++ /dev/null
+@@ -1 +0,0 @@
+-content`;
+
+    const hunks = fileDiffsToHunks(parseDiff(diff), "sugg-1");
+
+    // Content hunk covers the deletion, no file-level hunk needed
+    expect(hunks).toHaveLength(1);
+    expect(hunks[0]!.file).toBe("src/deleted.ts");
+  });
+
+  test("does not emit file-level hunks for plain modifications", () => {
+    const diff = `diff --git a/src/a.ts b/src/a.ts
+index 1234567..89abcde 100644
+--- a/src/a.ts
++++ b/src/a.ts
+@@ -1 +1 @@
+-a
++b`;
+
+    const hunks = fileDiffsToHunks(parseDiff(diff), "sugg-1");
+
+    expect(hunks).toHaveLength(1);
+    expect(hunks[0]!.fileChange).toBeUndefined();
+  });
+
+  test("formatFileChange round-trips through parseFileChange", () => {
+    const change = {
+      kind: "rename" as const,
+      oldPath: "a.sh",
+      newPath: "b.sh",
+      similarity: 100,
+      oldMode: "100644",
+      newMode: "100755",
+    };
+
+    const parsed = parseFileChange(formatFileChange(change));
+
+    expect(parsed).toMatchObject(change);
+  });
+
+  test("parseFileChange ignores regular hunks", () => {
+    expect(parseFileChange("@@ -1 +1 @@\n-a\n+b")).toBeNull();
+  });
+});
+
 describe("getFilesFromDiff", () => {
   test("extracts unique file paths", () => {
     const diff = `diff --git a/src/a.ts b/src/a.ts
@@ -635,6 +801,20 @@ describe("calculateLineOffset", () => {
     // Hunk removes 2 lines (5 - 7)
     expect(offset).toBe(-2);
   });
+
+  test("uses the given hunk IDs instead of positions", () => {
+    const hunks = [
+      { oldStart: 10, oldCount: 5, newStart: 10, newCount: 7, content: "@@ -10,5 +10,7 @@" },
+      { oldStart: 20, oldCount: 1, newStart: 22, newCount: 2, content: "@@ -20,1 +22,2 @@" },
+    ];
+
+    // The second hunk is the pending rest of a partially decided one
+    const appliedHunkIds = new Set(["suggestion-123:file.ts:1"]);
+    const ids = ["suggestion-123:file.ts:1", "suggestion-123:file.ts:2"];
+    const offset = calculateLineOffset(hunks, appliedHunkIds, "file.ts", "suggestion-123", ids);
+
+    expect(offset).toBe(2);
+  });
 });
 
 describe("adjustHunkLineNumbers", () => {
@@ -708,3 +888,147 @@ describe("adjustHunkLineNumbers", () => {
     expect(adjusted).toContain("@@ -10 +13 @@");
   });
 });
+
+describe("splitHunkSelection", () => {
+  const hunkDiff = "@@ -10,3 +10,4 @@ fn\n ctx\n-old\n+new1\n+new2\n end";
+  // Replace "old" with "new1", leave "new2" out
+  const selection = "@@ -10,3 +10,3 @@ fn\n ctx\n-old\n+new1\n end";
+
+  test("expresses the rest against the file with accepted lines applied", () => {
+    expect(splitHunkSelection(hunkDiff, selection, "accept")).toEqual({
+      remaining: "@@ -10,3 +10,4 @@ fn\n ctx\n new1\n+new2\n end",
+    });
+  });
+
+  test("expresses the rest against the unchanged file for rejected lines", () => {
+    expect(splitHunkSelection(hunkDiff, selection, "reject")).toEqual({
+      remaining: "@@ -10,3 +10,4 @@ fn\n ctx\n old\n+new2\n end",
+    });
+  });
+
+  test("keeps unselected removals in the rest", () => {
+    const onlyNew2 = "@@ -10,3 +10,4 @@ fn\n ctx\n old\n+new2\n end";
+
+    expect(splitHunkSelection(hunkDiff, onlyNew2, "accept")).toEqual({
+      remaining: "@@ -10,4 +10,4 @@ fn\n ctx\n-old\n+new1\n new2\n end",
+    });
+  });
+
+  test("leaves nothing when every change is selected", () => {
+    expect(splitHunkSelection(hunkDiff, hunkDiff, "accept")).toEqual({ remaining: null });
+  });
+
+  test("rejects diffs that aren't a selection of the hunk", () => {
+    expect(splitHunkSelection(hunkDiff, "@@ -10,3 +10,3 @@\n ctx\n-old\n+other\n end", "accept")).toBeUndefined();
+    expect(splitHunkSelection(hunkDiff, "@@ -10,3 +10,3 @@\n ctx\n old\n end", "accept")).toBeUndefined();
+    expect(splitHunkSelection(hunkDiff, "not a diff", "accept")).toBeUndefined();
+  });
+});
+
+describe("splitHunkDiff", () => {
+  const hunkDiff = "@@ -10,7 +10,7 @@ fn x\n a\n-b\n+B\n c\n d\n e\n-f\n+F\n g";
+
+  test("cuts at the context between groups of changes, keeping it on both sides", () => {
+    expect(splitHunkDiff(hunkDiff)).toEqual([
+      "@@ -10,5 +10,5 @@ fn x\n a\n-b\n+B\n c\n d\n e",
+      "@@ -12,5 +12,5 @@ fn x\n c\n d\n e\n-f\n+F\n g",
+    ]);
+  });
+
+  test("cuts just before a chosen line, even inside a group of changes", () => {
+    expect(splitHunkDiff(hunkDiff, 3)).toEqual([
+      "@@ -10,2 +10,1 @@ fn x\n a\n-b",
+      "@@ -12,5 +11,6 @@ fn x\n+B\n c\n d\n e\n-f\n+F\n g",
+    ]);
+  });
+
+  test("refuses cuts that leave a piece without changes", () => {
+    expect(splitHunkDiff(hunkDiff, 1)).toBeUndefined();
+    expect(splitHunkDiff(hunkDiff, 2)).toBeUndefined();
+    expect(splitHunkDiff("@@ -1,2 +1,2 @@\n a\n-b\n+c")).toBeUndefined();
+  });
+});
+
+describe("mergeHunkDiffs", () => {
+  test("joins the pieces of a split back together", () => {
+    const hunkDiff = "@@ -10,7 +10,7 @@ fn x\n a\n-b\n+B\n c\n d\n e\n-f\n+F\n g";
+    const [first, second] = splitHunkDiff(hunkDiff)!;
+
+    expect(mergeHunkDiffs(first!, second!)).toBe(hunkDiff);
+  });
+
+  test("fills the space between hunks with the gap lines", () => {
+    const first = "@@ -1,2 +1,2 @@\n a\n-b\n+c";
+    const second = "@@ -6,2 +6,2 @@\n-x\n+y\n z";
+
+    expect(mergeHunkDiffs(first, second, ["3", "4", "5"])).toBe(
+      "@@ -1,7 +1,7 @@\n a\n-b\n+c\n 3\n 4\n 5\n-x\n+y\n z"
+    );
+    expect(mergeHunkDiffs(first, second, ["3"])).toBeUndefined();
+    expect(mergeHunkDiffs(second, first)).toBeUndefined();
+  });
+
+  test("refuses hunks that change the same lines", () => {
+    expect(mergeHunkDiffs("@@ -1,2 +1,2 @@\n a\n-b\n+c", "@@ -2,1 +2,1 @@\n-b\n+d")).toBeUndefined();
+  });
+});
+
+describe("matchHunks", () => {
+  const hunk = (id: string, diff: string, file = "src/a.ts"): Hunk => ({
+    id,
+    file,
+    diff,
+    originalStartLine: parseInt(diff.match(/^@@ -(\d+)/)![1]!, 10),
+  });
+
+  test("keeps the previous ID for the same change at a new position", () => {
+    const previous = [hunk("s:src/a.ts:0", "@@ -10,2 +10,2 @@\n-old\n+new\n ctx")];
+    const next = [hunk("s:src/a.ts:1", "@@ -10,2 +14,2 @@\n-old\n+new\n ctx")];
+
+    const result = matchHunks(previous, next);
+
+    expect(result.unchanged.map((h) => h.id)).toEqual(["s:src/a.ts:0"]);
+    expect(result.unchanged[0]?.diff).toBe("@@ -10,2 +14,2 @@\n-old\n+new\n ctx");
+    expect(result.changed).toHaveLength(0);
+    expect(result.added).toHaveLength(0);
+    expect(result.removed).toHaveLength(0);
+  });
+
+  test("treats a different change over the same lines as changed", () => {
+    const previous = [{ ...hunk("s:src/a.ts:0", "@@ -10,2 +10,2 @@\n-old\n+new\n ctx"), version: 2 }];
+    const next = [hunk("s:src/a.ts:0", "@@ -10,2 +10,2 @@\n-old\n+newer\n ctx")];
+
+    const result = matchHunks(previous, next);
+
+    expect(result.changed.map((h) => h.id)).toEqual(["s:src/a.ts:0"]);
+    expect(result.changed[0]?.version).toBe(2);
+    expect(result.unchanged).toHaveLength(0);
+  });
+
+  test("reports hunks elsewhere as added and missing ones as removed", () => {
+    const previous = [
+      hunk("s:src/a.ts:0", "@@ -10,2 +10,2 @@\n-old\n+new\n ctx"),
+      hunk("s:src/b.ts:0", "@@ -3 +3 @@\n-x\n+y", "src/b.ts"),
+    ];
+    const next = [
+      hunk("s:src/a.ts:0", "@@ -10,2 +10,2 @@\n-old\n+new\n ctx"),
+      hunk("s:src/a.ts:1", "@@ -50 +50,2 @@\n ctx\n+added"),
+    ];
+
+    const result = matchHunks(previous, next);
+
+    expect(result.unchanged.map((h) => h.id)).toEqual(["s:src/a.ts:0"]);
+    expect(result.added.map((h) => h.id)).toEqual(["s:src/a.ts:1"]);
+    expect(result.removed.map((h) => h.id)).toEqual(["s:src/b.ts:0"]);
+  });
+
+  test("doesn't match changes across files", () => {
+    const previous = [hunk("s:src/a.ts:0", "@@ -10 +10 @@\n-old\n+new")];
+    const next = [hunk("s:src/b.ts:0", "@@ -10 +10 @@\n-old\n+new", "src/b.ts")];
+
+    const result = matchHunks(previous, next);
+
+    expect(result.added).toHaveLength(1);
+    expect(result.removed).toHaveLength(1);
+  });
+});
@@ -51,6 +51,20 @@ describe("SuggestionEventEmitter", () => {
         },
       });
     });
+
+    test("numbers state changes in the store's event log", async () => {
+      const { client, logCalls } = createMockClient();
+      const appended: SuggestionEvent[] = [];
+      const store = { appendEvent: (event: SuggestionEvent) => appended.push(event) + 6 };
+      const emitter = new SuggestionEventEmitter(client as any, { store: store as any });
+
+      await emitter.emitHunkApplied("sugg-1", "hunk-1", "accepted");
+      await emitter.emitStatus("applied", "0 hunks remaining", "sugg-1");
+
+      expect(appended.map((e) => e.type)).toEqual(["suggestion.hunk_applied"]);
+      expect(JSON.parse((logCalls[0]?.body as any).message).seq).toBe(7);
+      expect(JSON.parse((logCalls[1]?.body as any).message).seq).toBeUndefined();
+    });
   });
 
   describe("emitReady", () => {
@@ -153,6 +167,53 @@ describe("SuggestionEventEmitter", () => {
     });
   });
 
+  describe("emitCheck", () => {
+    test("emits suggestion.check event", async () => {
+      const { client, logCalls } = createMockClient();
+      const emitter = new SuggestionEventEmitter(client as any);
+
+      await emitter.emitCheck({
+        suggestionId: "sugg-1",
+        name: "test",
+        command: "bun test",
+        status: "passed",
+        exitCode: 0,
+        diagnostics: [],
+        startedAt: 1000,
+        finishedAt: 2000,
+      });
+
+      const body = logCalls[0]?.body as any;
+      expect(body.extra.eventType).toBe("suggestion.check");
+
+      const event = JSON.parse(body.message);
+      expect(event.type).toBe("suggestion.check");
+      expect(event.suggestionId).toBe("sugg-1");
+      expect(event.check.name).toBe("test");
+      expect(event.check.status).toBe("passed");
+    });
+  });
+
+  describe("emitHunksChanged", () => {
+    test("emits suggestion.hunks_changed event", async () => {
+      const { client, logCalls } = createMockClient();
+      const emitter = new SuggestionEventEmitter(client as any);
+      const hunks = [
+        { id: "sugg-1:a.ts:0", file: "a.ts", diff: "@@ -1,2 +1,2 @@\n-a\n+b\n c" },
+        { id: "sugg-1:a.ts:1", file: "a.ts", diff: "@@ -2,2 +2,2 @@\n c\n-d\n+e" },
+      ];
+
+      await emitter.emitHunksChanged("sugg-1", ["sugg-1:a.ts:0"], hunks);
+
+      const body = logCalls[0]?.body as any;
+      expect(body.extra.eventType).toBe("suggestion.hunks_changed");
+
+      const event = JSON.parse(body.message);
+      expect(event.replaced).toEqual(["sugg-1:a.ts:0"]);
+      expect(event.hunks).toEqual(hunks);
+    });
+  });
+
   describe("emitList", () => {
     test("emits suggestion.list event", async () => {
       const { client, logCalls } = createMockClient();
@@ -0,0 +1,97 @@
+import { describe, expect, test, beforeEach, afterEach, mock } from "bun:test";
+import { MAX_DELIVERY_ATTEMPTS, nextAttemptAt, retryDueNotifications, sendNotification } from "../src/notifications.ts";
+import { SuggestionStore } from "../src/suggestion-store.ts";
+import { existsSync, unlinkSync } from "fs";
+
+// Mock OpenCode client; the prompt result is swapped per test
+function createMockClient() {
+  return {
+    session: {
+      list: mock(async () => ({ data: [{ id: "ses_main", title: "main" }] })),
+      prompt: mock(async (_params: any): Promise<any> => ({ data: {} })),
+    },
+    tui: { showToast: mock(async () => ({ data: true })) },
+  };
+}
+
+describe("AI notifications", () => {
+  let store: SuggestionStore;
+  const testDbPath = ".opencode/test-notifications.db";
+
+  beforeEach(() => {
+    if (existsSync(testDbPath)) {
+      unlinkSync(testDbPath);
+    }
+    store = new SuggestionStore({ dbPath: testDbPath });
+    store.createSuggestion({
+      id: "s1",
+      jjChangeId: "abc123",
+      description: "Test",
+      files: ["src/a.ts"],
+      hunks: [{ id: "s1:src/a.ts:0", file: "src/a.ts", diff: "@@ -1 +1 @@\n-old\n+new" }],
+      workingDirectory: "/test/project",
+      sessionId: "ses_publisher",
+    });
+  });
+
+  afterEach(() => {
+    store.close();
+    if (existsSync(testDbPath)) {
+      unlinkSync(testDbPath);
+    }
+  });
+
+  test("are delivered to the session that published the suggestion", async () => {
+    const client = createMockClient();
+
+    const notification = await sendNotification(store, client as any, { suggestionId: "s1", message: "accepted" });
+
+    expect(client.session.prompt).toHaveBeenCalledTimes(1);
+    expect(client.session.prompt.mock.calls[0][0].path.id).toBe("ses_publisher");
+    expect(client.session.list).not.toHaveBeenCalled();
+    expect(notification.deliveredVia).toBe("session");
+  });
+
+  test("failed deliveries are retried once due", async () => {
+    const client = createMockClient();
+    client.session.prompt = mock(async () => ({ error: "session busy" }));
+
+    const notification = await sendNotification(store, client as any, { suggestionId: "s1", message: "rejected" });
+    expect(notification.deliveredAt).toBeUndefined();
+    expect(notification.attempts).toBe(1);
+    expect(notification.lastError).toBe("session busy");
+
+    client.session.prompt = mock(async () => ({ data: {} }));
+    expect(await retryDueNotifications(store, client as any)).toBe(0);
+    expect(await retryDueNotifications(store, client as any, Date.now() + 60_000)).toBe(1);
+    expect(store.getNotification(notification.id)?.deliveredVia).toBe("session");
+  });
+
+  test("are retried if the first attempt never ran", async () => {
+    const client = createMockClient();
+    // Stored, then the process died before delivering it
+    const notification = store.addNotification({ suggestionId: "s1", message: "accepted" });
+
+    expect(await retryDueNotifications(store, client as any)).toBe(1);
+    expect(store.getNotification(notification.id)?.deliveredVia).toBe("session");
+  });
+
+  test("are not retried while the first attempt is under way", async () => {
+    const client = createMockClient();
+    let finish = () => {};
+    client.session.prompt = mock(() => new Promise<any>((resolve) => (finish = () => resolve({ data: {} }))));
+
+    const sending = sendNotification(store, client as any, { suggestionId: "s1", message: "accepted" });
+    expect(await retryDueNotifications(store, client as any)).toBe(0);
+    finish();
+    await sending;
+
+    expect(client.session.prompt).toHaveBeenCalledTimes(1);
+  });
+
+  test("retries back off and eventually give up", () => {
+    expect(nextAttemptAt(1, 0)).toBe(5_000);
+    expect(nextAttemptAt(2, 0)).toBe(10_000);
+    expect(nextAttemptAt(MAX_DELIVERY_ATTEMPTS, 0)).toBeUndefined();
+  });
+});
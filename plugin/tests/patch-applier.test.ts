@@ -1,6 +1,7 @@
 import { describe, expect, test, beforeEach, afterEach } from "bun:test";
-import { applyHunk, applyHunkToFile, reverseHunk } from "../src/patch-applier.ts";
-import { mkdtemp, rm } from "node:fs/promises";
+import { applyHunk, applyHunkToFile, reverseHunk, DRIFT_TOLERANT_OPTIONS } from "../src/patch-applier.ts";
+import { mkdtemp, rm, stat } from "node:fs/promises";
+import { existsSync } from "node:fs";
 import { tmpdir } from "node:os";
 import { join } from "node:path";
 
@@ -190,6 +191,116 @@ line 3`);
   });
 });
 
+describe("applyHunk with drifted context", () => {
+  test("reports clean status for an exact match", () => {
+    const result = applyHunk("a\nb\nc", "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c", DRIFT_TOLERANT_OPTIONS);
+
+    expect(result.success).toBe(true);
+    expect(result.status).toBe("clean");
+    expect(result.newContent).toBe("a\nB\nc");
+  });
+
+  test("finds the hunk at a nearby offset", () => {
+    const original = `new 1
+new 2
+a
+b
+c`;
+
+    const result = applyHunk(original, "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c", DRIFT_TOLERANT_OPTIONS);
+
+    expect(result.success).toBe(true);
+    expect(result.status).toBe("offset");
+    expect(result.offset).toBe(2);
+    expect(result.newContent).toBe(`new 1
+new 2
+a
+B
+c`);
+  });
+
+  test("ignores edited outer context lines", () => {
+    const original = `a
+b edited
+c
+d`;
+
+    const hunk = `@@ -1,4 +1,4 @@
+ a
+ b
+-c
++C
+ d`;
+
+    const result = applyHunk(original, hunk, DRIFT_TOLERANT_OPTIONS);
+
+    expect(result.success).toBe(true);
+    expect(result.status).toBe("fuzzed");
+    expect(result.fuzz).toBe(2);
+    expect(result.newContent).toBe(`a
+b edited
+C
+d`);
+  });
+
+  test("merges non-overlapping edits against the base", () => {
+    const original = `a
+b
+c
+d edited`;
+
+    const hunk = `@@ -1,4 +1,4 @@
+ a
+-b
++B
+ c
+ d`;
+
+    const result = applyHunk(original, hunk, { merge: true, base: ["a", "b", "c", "d"] });
+
+    expect(result.success).toBe(true);
+    expect(result.status).toBe("fuzzed");
+    expect(result.conflicts).toBe(0);
+    expect(result.newContent).toBe(`a
+B
+c
+d edited`);
+  });
+
+  test("writes conflict markers when both sides changed the same lines", () => {
+    const original = `a
+b edited
+c`;
+
+    const result = applyHunk(original, "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c", DRIFT_TOLERANT_OPTIONS);
+
+    expect(result.success).toBe(true);
+    expect(result.status).toBe("conflicted");
+    expect(result.conflicts).toBe(1);
+    expect(result.newContent).toBe(`a
+<<<<<<< working copy
+b edited
+=======
+B
+>>>>>>> suggestion
+c`);
+  });
+
+  test("fails when nothing of the hunk can be found", () => {
+    const result = applyHunk("x\ny\nz", "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c", DRIFT_TOLERANT_OPTIONS);
+
+    expect(result.success).toBe(false);
+    expect(result.error).toContain("Context mismatch");
+  });
+
+  test("stays strict without options", () => {
+    const result = applyHunk("new\na\nb\nc", "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c");
+
+    expect(result.success).toBe(false);
+    expect(result.error).toContain("Context mismatch");
+  });
+});
+
 describe("applyHunkToFile", () => {
   let tempDir: string;
 
@@ -254,6 +365,77 @@ line 3`);
   });
 });
 
+describe("file-level hunks", () => {
+  let tempDir: string;
+
+  beforeEach(async () => {
+    tempDir = await mkdtemp(join(tmpdir(), "patch-test-"));
+  });
+
+  afterEach(async () => {
+    await rm(tempDir, { recursive: true, force: true });
+  });
+
+  const renameDiff = `diff --git a/old.txt b/nested/new.txt
+similarity index 100%
+rename from old.txt
+rename to nested/new.txt`;
+
+  test("applies a rename", async () => {
+    await Bun.write(join(tempDir, "old.txt"), "content");
+
+    const result = await applyHunkToFile(join(tempDir, "nested/new.txt"), renameDiff, { rootDir: tempDir });
+
+    expect(result.success).toBe(true);
+    expect(existsSync(join(tempDir, "old.txt"))).toBe(false);
+    expect(await Bun.file(join(tempDir, "nested/new.txt")).text()).toBe("content");
+  });
+
+  test("reverts a rename with the reversed hunk", async () => {
+    await Bun.write(join(tempDir, "nested/new.txt"), "content");
+
+    const result = await applyHunkToFile(join(tempDir, "nested/new.txt"), reverseHunk(renameDiff));
+
+    expect(result.success).toBe(true);
+    expect(await Bun.file(join(tempDir, "old.txt")).text()).toBe("content");
+  });
+
+  test("applies a mode change", async () => {
+    const filePath = join(tempDir, "run.sh");
+    await Bun.write(filePath, "#!/bin/sh");
+
+    const result = await applyHunkToFile(filePath, `diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755`, { rootDir: tempDir });
+
+    expect(result.success).toBe(true);
+    expect((await stat(filePath)).mode & 0o777).toBe(0o755);
+  });
+
+  test("refuses binary content changes", async () => {
+    const filePath = join(tempDir, "logo.png");
+    await Bun.write(filePath, "png");
+
+    const result = await applyHunkToFile(filePath, `diff --git a/logo.png b/logo.png
+Binary files a/logo.png and b/logo.png differ`, { rootDir: tempDir });
+
+    expect(result.success).toBe(false);
+    expect(result.error).toContain("Binary");
+  });
+
+  test("deletes the file when a hunk removes all of it", async () => {
+    const filePath = join(tempDir, "gone.txt");
+    await Bun.write(filePath, "line 1\nline 2");
+
+    const result = await applyHunkToFile(filePath, `@@ -1,2 +0,0 @@
+-line 1
+-line 2`);
+
+    expect(result.success).toBe(true);
+    expect(existsSync(filePath)).toBe(false);
+  });
+});
+
 describe("reverseHunk", () => {
   test("reverses a simple hunk", () => {
     const hunk = `@@ -10,3 +10,4 @@
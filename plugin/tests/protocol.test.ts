@@ -0,0 +1,110 @@
+import { describe, expect, test } from "bun:test";
+import { readFileSync } from "fs";
+import { generateSchema, renderSchema, SCHEMA_PATHS } from "../src/protocol-schema.ts";
+import { negotiateVersion, validateMessage, PROTOCOL_VERSION } from "../src/protocol.ts";
+
+describe("generateSchema", () => {
+  test("flattens interfaces and resolves indexed access", () => {
+    const schema = generateSchema(`
+      /** Base */
+      export interface Base { id: string; }
+      export interface Pair extends Base {
+        type: "pair";
+        /** Two of them */
+        ids: [string, string];
+        kind?: Other["kind"];
+        counts: Map<string, number>;
+      }
+      interface Other { kind: "a" | "b"; }
+      export type Either = Pair | Other;
+    `);
+    const defs = schema.$defs as Record<string, unknown>;
+
+    expect(defs.Pair).toEqual({
+      type: "object",
+      properties: {
+        id: { type: "string" },
+        type: { const: "pair" },
+        ids: { description: "Two of them", type: "array", prefixItems: [{ type: "string" }, { type: "string" }], minItems: 2, maxItems: 2 },
+        kind: { type: "string", enum: ["a", "b"] },
+        counts: { type: "object", additionalProperties: { type: "number" } },
+      },
+      required: ["id", "type", "ids", "counts"],
+    });
+    expect(defs.Either).toEqual({ anyOf: [{ $ref: "#/$defs/Pair" }, { $ref: "#/$defs/Other" }] });
+  });
+
+  test("the committed schemas match types.ts", () => {
+    const rendered = renderSchema();
+
+    for (const path of SCHEMA_PATHS) {
+      expect(readFileSync(path, "utf-8")).toBe(rendered);
+    }
+  });
+});
+
+describe("validateMessage", () => {
+  const feedback = { type: "feedback", id: "1", workingDirectory: "project", suggestionId: "s", hunkId: "h", action: "accept" };
+
+  test("accepts valid commands", () => {
+    expect(validateMessage("ClientCommand", feedback)).toEqual([]);
+    expect(validateMessage("ClientCommand", { type: "feedback", id: "2", workingDirectory: "project", suggestionId: "s", action: "undo" })).toEqual([]);
+    expect(validateMessage("ClientCommand", { type: "search", id: "3", workingDirectory: "project", since: "2024-01-01" })).toEqual([]);
+    expect(validateMessage("ClientCommand", {
+      type: "feedback_batch",
+      id: "4",
+      workingDirectory: "project",
+      suggestionId: "s",
+      decisions: [{ hunkId: "a", action: "accept" }, { hunkId: "b", action: "reject", stateVersion: 2 }],
+    })).toEqual([]);
+    expect(validateMessage("ClientCommand", { type: "stats", id: "5", workingDirectory: "project", since: "2024-01-01", interval: "month" })).toEqual([]);
+    expect(validateMessage("ClientCommand", { type: "projects", id: "6" })).toEqual([]);
+  });
+
+  test("reports each bad field by path", () => {
+    const { id: _, ...withoutId } = feedback;
+
+    expect(validateMessage("ClientCommand", { ...withoutId, hunkId: 3 })).toEqual([
+      { path: "$.id", message: "is required" },
+      { path: "$.hunkId", message: "must be a string" },
+    ]);
+    expect(validateMessage("ClientCommand", { ...feedback, action: "explode" })).toEqual([
+      { path: "$.action", message: 'must be one of "accept", "reject", "modify", "request_changes"' },
+    ]);
+    expect(validateMessage("ClientCommand", { type: "merge", id: "4", workingDirectory: "project", suggestionId: "s", hunkIds: ["a", 2] })).toEqual([
+      { path: "$.hunkIds[1]", message: "must be a string" },
+    ]);
+    expect(validateMessage("ClientCommand", {
+      type: "feedback_batch",
+      id: "5",
+      workingDirectory: "project",
+      suggestionId: "s",
+      decisions: [{ hunkId: "a", action: "request_changes" }],
+    })).toEqual([
+      { path: "$.decisions[0].action", message: 'must be one of "accept", "reject", "modify"' },
+    ]);
+  });
+
+  test("names an unknown message type", () => {
+    expect(validateMessage("ClientCommand", { type: "explode", id: "5" })).toEqual([
+      { path: "$.type", message: 'unknown value "explode"' },
+    ]);
+    expect(validateMessage("ClientCommand", "feedback")).toEqual([{ path: "$", message: "must be an object" }]);
+  });
+
+  test("checks server messages too", () => {
+    expect(validateMessage("ServerMessage", { type: "suggestion.presence", clients: [{ clientId: "c1" }] })).toEqual([]);
+    expect(validateMessage("ServerMessage", { type: "response", id: "1", success: true, suggestions: [] })).toEqual([]);
+    expect(validateMessage("ServerMessage", { type: "suggestion.hunk_applied", suggestionId: "s", hunkId: "h", action: "maybe" })).toEqual([
+      { path: "$.action", message: 'must be one of "accepted", "modified", "rejected"' },
+    ]);
+  });
+});
+
+describe("negotiateVersion", () => {
+  test("settles on the highest version both sides speak", () => {
+    expect(negotiateVersion(undefined)).toBe(1);
+    expect(negotiateVersion(PROTOCOL_VERSION + 1)).toBe(PROTOCOL_VERSION);
+    expect(negotiateVersion(0)).toBeUndefined();
+  });
+});
@@ -0,0 +1,146 @@
+import { describe, expect, test } from "bun:test";
+import { readFileSync } from "fs";
+import { join } from "path";
+import { parseRustItems, describeRustHunks, itemPathAt, type RustItem } from "../src/rust-items.ts";
+import type { Hunk } from "../src/types.ts";
+
+const outline = (items: RustItem[]): unknown[] =>
+  items.map((it) =>
+    it.children.length > 0
+      ? [it.label, it.startLine, it.endLine, outline(it.children)]
+      : [it.label, it.startLine, it.endLine]
+  );
+
+describe("parseRustItems", () => {
+  test("outlines items with their attributes and doc comments", () => {
+    const source = [
+      "#![allow(dead_code)]",
+      "use std::fmt;",
+      "use std::{io, fs};",
+      "",
+      "/// A thing",
+      "#[derive(Debug)]",
+      "pub(crate) struct Thing<'a> {",
+      "    name: &'a str,",
+      "}",
+      "",
+      "const ORIGIN: Point = Point { x: 0, y: 0 };",
+      "",
+      "impl<'a> fmt::Display for Thing<'a> where Self: Sized {",
+      "    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {",
+      "        write!(f, \"{}\", self.name)",
+      "    }",
+      "}",
+      "",
+      "#[cfg(test)]",
+      "mod tests {",
+      "    #[test]",
+      "    fn it_works() {}",
+      "}",
+    ].join("\n");
+
+    expect(outline(parseRustItems(source))).toEqual([
+      ["use block", 2, 3],
+      ["struct Thing", 5, 9],
+      ["const ORIGIN", 11, 11],
+      ["impl fmt::Display for Thing<'a>", 13, 17, [["fn fmt", 14, 16]]],
+      ["mod tests", 19, 23, [["fn it_works", 21, 22]]],
+    ]);
+  });
+
+  test("ignores braces in strings, chars and comments", () => {
+    const source = [
+      "fn braces() {",
+      "    let c = '{';",
+      "    let s = \"}}\";",
+      "    let r = r#\"{\"a\": \"}\"}\"#;",
+      "    /* { nested /* } */ } */",
+      "    // }",
+      "}",
+      "",
+      "macro_rules! square {",
+      "    ($x:expr) => { $x * $x };",
+      "}",
+    ].join("\n");
+
+    expect(outline(parseRustItems(source))).toEqual([
+      ["fn braces", 1, 7],
+      ["macro_rules! square", 9, 11],
+    ]);
+  });
+
+  test("finds the items enclosing a line", () => {
+    const items = parseRustItems("impl A {\n    fn b() {\n        c();\n    }\n}\n");
+
+    expect(itemPathAt(items, 3).map((it) => it.label)).toEqual(["impl A", "fn b"]);
+    expect(itemPathAt(items, 6)).toEqual([]);
+  });
+
+  test("reads the mesh_grid fixture", () => {
+    const source = readFileSync(join(import.meta.dir, "../../test-harness/fixtures/mesh_grid.rs"), "utf-8");
+    const items = parseRustItems(source);
+
+    expect(items[0]?.label).toBe("use block");
+    expect(items.map((it) => it.label)).toContain("impl MeshGridInner");
+    expect(items.map((it) => it.label)).toContain("impl From<&IcoSphere<T>> for Adjacency<Cell>");
+    const inner = items.find((it) => it.label === "impl MeshGridInner")!;
+    expect(inner.children.map((it) => it.label)).toContain("fn calculate_trivial_connection");
+    expect(items[items.length - 1]?.label).toBe("mod test");
+  });
+});
+
+describe("describeRustHunks", () => {
+  const newSource = [
+    "use std::io;",
+    "use std::fs;",
+    "",
+    "impl Grid {",
+    "    fn connect(&self, pole: usize) -> Vec<f32> {",
+    "        let pole = pole + 1;",
+    "        vec![]",
+    "    }",
+    "",
+    "    fn find_pole(&self) -> usize {",
+    "        0",
+    "    }",
+    "}",
+    "",
+  ].join("\n");
+
+  const hunks: Hunk[] = [
+    { id: "s:src/grid.rs:0", file: "src/grid.rs", diff: "@@ -1,2 +1,3 @@\n use std::io;\n+use std::fs;\n " },
+    {
+      id: "s:src/grid.rs:1",
+      file: "src/grid.rs",
+      diff: "@@ -3,4 +4,4 @@\n impl Grid {\n-    fn connect(&self) -> Vec<f32> {\n+    fn connect(&self, pole: usize) -> Vec<f32> {\n+        let pole = pole + 1;\n         vec![]\n-        // TODO",
+    },
+    {
+      id: "s:src/grid.rs:2",
+      file: "src/grid.rs",
+      diff: "@@ -7,2 +8,6 @@\n     }\n+\n+    fn find_pole(&self) -> usize {\n+        0\n+    }\n }",
+    },
+  ];
+
+  test("labels hunks by the items they touch", () => {
+    const described = describeRustHunks(hunks, newSource);
+
+    expect(described.map((h) => [h.item, h.description])).toEqual([
+      ["use block", "use block"],
+      ["impl Grid", "fn connect (signature changed)"],
+      ["impl Grid", "fn find_pole (added)"],
+    ]);
+  });
+
+  test("keeps descriptions the hunks already have", () => {
+    const described = describeRustHunks([{ ...hunks[0]!, description: "Import fs" }, ...hunks.slice(1)], newSource);
+
+    expect(described[0]?.description).toBe("Import fs");
+    expect(described[0]?.item).toBe("use block");
+  });
+
+  test("leaves hunks alone when they don't match the source", () => {
+    const described = describeRustHunks(hunks, "fn other() {}\n");
+
+    expect(described).toEqual(hunks);
+  });
+});
@@ -0,0 +1,89 @@
+import { describe, expect, test, beforeEach, afterEach } from "bun:test";
+import {
+  applySocketPermissions,
+  parseSocketMode,
+  prepareSocketPath,
+  resolveGroupId,
+  resolveSocketPath,
+} from "../src/socket.ts";
+import { mkdirSync, rmSync, existsSync, statSync, writeFileSync } from "fs";
+import { createServer } from "net";
+import { join } from "path";
+import { tmpdir } from "os";
+
+describe("socket config", () => {
+  test("relative socket paths are relative to the project", () => {
+    expect(resolveSocketPath(".opencode/codeforge.sock", "/project")).toBe("/project/.opencode/codeforge.sock");
+    expect(resolveSocketPath("/run/codeforge.sock", "/project")).toBe("/run/codeforge.sock");
+  });
+
+  test("parses octal modes", () => {
+    expect(parseSocketMode("660")).toBe(0o660);
+    expect(parseSocketMode("0600")).toBe(0o600);
+    expect(parseSocketMode("0o770")).toBe(0o770);
+    expect(parseSocketMode("rw-rw----")).toBeUndefined();
+    expect(parseSocketMode("999")).toBeUndefined();
+  });
+
+  test("resolves groups by name or ID", () => {
+    const groupFile = join(tmpdir(), `codeforge-group-${Date.now()}`);
+    writeFileSync(groupFile, "root:x:0:\ndevs:x:1042:alice,sandbox\n");
+    try {
+      expect(resolveGroupId("devs", groupFile)).toBe(1042);
+      expect(resolveGroupId("1042", groupFile)).toBe(1042);
+      expect(resolveGroupId("nobody-here", groupFile)).toBeUndefined();
+    } finally {
+      rmSync(groupFile);
+    }
+  });
+});
+
+describe("socket file", () => {
+  let testDir: string;
+
+  beforeEach(() => {
+    testDir = join(tmpdir(), `codeforge-socket-test-${Date.now()}`);
+    mkdirSync(testDir, { recursive: true });
+  });
+
+  afterEach(() => {
+    if (existsSync(testDir)) {
+      rmSync(testDir, { recursive: true });
+    }
+  });
+
+  test("a missing path is free", async () => {
+    expect(await prepareSocketPath(join(testDir, "codeforge.sock"))).toBe(true);
+  });
+
+  test("leaves files that aren't sockets alone", async () => {
+    const path = join(testDir, "codeforge.sock");
+    writeFileSync(path, "not a socket");
+
+    expect(await prepareSocketPath(path)).toBe(false);
+    expect(existsSync(path)).toBe(true);
+  });
+
+  test("refuses a socket another server is listening on", async () => {
+    const path = join(testDir, "codeforge.sock");
+    const server = createServer();
+    await new Promise<void>((resolve) => server.listen(path, resolve));
+    try {
+      expect(await prepareSocketPath(path)).toBe(false);
+    } finally {
+      await new Promise((resolve) => server.close(resolve));
+    }
+  });
+
+  test("applies the configured mode", async () => {
+    const path = join(testDir, "codeforge.sock");
+    const server = createServer();
+    await new Promise<void>((resolve) => server.listen(path, resolve));
+    try {
+      applySocketPermissions(path, 0o600);
+      expect(statSync(path).mode & 0o777).toBe(0o600);
+    } finally {
+      await new Promise((resolve) => server.close(resolve));
+    }
+  });
+});
@@ -0,0 +1,104 @@
+import { describe, expect, test } from "bun:test";
+import { computeReviewStats, modificationSize, periodStart, sizeBucket, type ReviewRecord } from "../src/stats.ts";
+
+const HOUR = 60 * 60 * 1000;
+// Wednesday 2024-01-03, 12:00 UTC
+const WEDNESDAY = Date.UTC(2024, 0, 3, 12);
+
+function record(overrides: Partial<ReviewRecord>): ReviewRecord {
+  return {
+    timestamp: WEDNESDAY,
+    suggestionId: "s1",
+    action: "accept",
+    file: "src/a.ts",
+    suggestionCreatedAt: WEDNESDAY - HOUR,
+    suggestionHunks: 1,
+    ...overrides,
+  };
+}
+
+describe("stats helpers", () => {
+  test("periods start on the first day, weeks on Monday", () => {
+    expect(periodStart(WEDNESDAY, "day")).toBe("2024-01-03");
+    expect(periodStart(WEDNESDAY, "week")).toBe("2024-01-01");
+    expect(periodStart(Date.UTC(2024, 0, 7, 23), "week")).toBe("2024-01-01");
+    expect(periodStart(WEDNESDAY, "month")).toBe("2024-01-01");
+  });
+
+  test("buckets suggestions by hunk count", () => {
+    expect([1, 2, 3, 4, 15, 16, 100].map(sizeBucket)).toEqual(["1", "2-3", "2-3", "4-7", "8-15", "16+", "16+"]);
+  });
+
+  test("measures modifications on the new side of the diffs", () => {
+    const original = "@@ -1,2 +1,3 @@\n ctx\n+one\n+two";
+    expect(modificationSize(original, original)).toBe(0);
+    expect(modificationSize(original, "@@ -1,2 +1,3 @@\n ctx\n+one\n+2")).toBe(2);
+    expect(modificationSize(original, "@@ -1,2 +1,2 @@\n ctx\n+one")).toBe(1);
+  });
+});
+
+describe("computeReviewStats", () => {
+  test("acceptance overall and by file, directory and extension", () => {
+    const stats = computeReviewStats([
+      record({ file: "src/a.ts" }),
+      record({ file: "src/a.ts", action: "reject" }),
+      record({ file: "src/b.ts", action: "modify" }),
+      record({ file: "README", action: "reject" }),
+    ]);
+
+    expect(stats.overall).toEqual({ total: 4, accepted: 1, rejected: 2, modified: 1, acceptanceRate: 0.5 });
+    expect(stats.byFile.map((g) => [g.key, g.total])).toEqual([["src/a.ts", 2], ["README", 1], ["src/b.ts", 1]]);
+    expect(stats.byDirectory.map((g) => [g.key, g.acceptanceRate])).toEqual([["src", 2 / 3], [".", 0]]);
+    expect(stats.byExtension.map((g) => g.key)).toEqual([".ts", "(none)"]);
+  });
+
+  test("acceptance over time and by suggestion size", () => {
+    const stats = computeReviewStats(
+      [
+        record({ timestamp: WEDNESDAY + 24 * HOUR, suggestionHunks: 20 }),
+        record({ action: "reject", suggestionHunks: 2 }),
+        record({}),
+      ],
+      { interval: "day" }
+    );
+
+    expect(stats.overTime.map((g) => [g.key, g.total])).toEqual([["2024-01-03", 2], ["2024-01-04", 1]]);
+    expect(stats.bySuggestionSize.map((g) => [g.key, g.acceptanceRate])).toEqual([["1", 1], ["2-3", 0], ["16+", 1]]);
+  });
+
+  test("median time to review skips removed suggestions", () => {
+    const stats = computeReviewStats([
+      record({ suggestionCreatedAt: WEDNESDAY - HOUR }),
+      record({ suggestionCreatedAt: WEDNESDAY - 3 * HOUR }),
+      record({ suggestionCreatedAt: undefined }),
+    ]);
+
+    expect(stats.timeToReview).toEqual({ medianMs: 2 * HOUR, samples: 2 });
+  });
+
+  test("modification rate and size", () => {
+    const originalDiff = "@@ -1,1 +1,2 @@\n ctx\n+added";
+    const stats = computeReviewStats([
+      record({ action: "modify", originalDiff, modifiedDiff: "@@ -1,1 +1,2 @@\n ctx\n+changed" }),
+      record({ action: "modify", originalDiff, modifiedDiff: "@@ -1,1 +1,3 @@\n ctx\n+added\n+more" }),
+      record({}),
+      record({ action: "reject" }),
+    ]);
+
+    expect(stats.modifications).toEqual({
+      count: 2,
+      rate: 0.5,
+      medianLinesChanged: 1.5,
+      meanLinesChanged: 1.5,
+      totalLinesChanged: 3,
+    });
+  });
+
+  test("no decisions", () => {
+    const stats = computeReviewStats([]);
+
+    expect(stats.overall.acceptanceRate).toBe(0);
+    expect(stats.timeToReview).toEqual({ medianMs: undefined, samples: 0 });
+    expect(stats.modifications.rate).toBe(0);
+  });
+});
@@ -1,7 +1,7 @@
 import { describe, expect, test, beforeEach, afterEach } from "bun:test";
-import { SuggestionStore, generateSuggestionId } from "../src/suggestion-store.ts";
+import { SuggestionStore, generateSuggestionId, toFtsQuery, EVENT_LOG_SIZE } from "../src/suggestion-store.ts";
 import type { Hunk, HunkFeedback } from "../src/types.ts";
-import { existsSync, unlinkSync } from "fs";
+import { existsSync, readFileSync, unlinkSync } from "fs";
 
 describe("SuggestionStore", () => {
   let store: SuggestionStore;
@@ -256,6 +256,294 @@ describe("SuggestionStore", () => {
     });
   });
 
+  describe("undoHunkState", () => {
+    const createSuggestion = (id: string) =>
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts", "src/b.ts"],
+        hunks: createTestHunks(id),
+        workingDirectory: "/test/project",
+      });
+
+    test("restores a reviewed hunk as pending", () => {
+      const id = "test-suggestion-undo-1";
+      createSuggestion(id);
+      const hunkId = `${id}:src/a.ts:0`;
+      store.updateHunkState(id, hunkId, { suggestionId: id, hunkId, action: "accept" }, true);
+
+      const undone = store.undoHunkState(id, hunkId);
+
+      expect(undone?.hunk.id).toBe(hunkId);
+      expect(undone?.hunk.diff).toBe("@@ -1,3 +1,4 @@\n+added\n context");
+      expect(undone?.entry.action).toBe("accept");
+      expect(store.getHunk(id, hunkId)).toBeDefined();
+      expect(store.getHunkState(id, hunkId)?.reviewed).toBe(false);
+      expect(store.getRemainingCount(id)).toBe(3);
+      expect(store.getSuggestion(id)?.status).toBe("pending");
+    });
+
+    test("undoes the most recent decision when no hunk is given", () => {
+      const id = "test-suggestion-undo-2";
+      createSuggestion(id);
+      const first = `${id}:src/a.ts:0`;
+      const second = `${id}:src/b.ts:0`;
+      store.updateHunkState(id, first, { suggestionId: id, hunkId: first, action: "accept" }, true);
+      store.updateHunkState(id, second, { suggestionId: id, hunkId: second, action: "reject" }, false);
+
+      const undone = store.undoHunkState(id);
+
+      expect(undone?.hunk.id).toBe(second);
+      expect(undone?.entry.action).toBe("reject");
+      expect(store.getHunk(id, first)).toBeUndefined();
+      expect(store.getSuggestion(id)?.status).toBe("partial");
+    });
+
+    test("keeps the modified diff of the undone decision", () => {
+      const id = "test-suggestion-undo-3";
+      createSuggestion(id);
+      const hunkId = `${id}:src/a.ts:0`;
+      const modifiedDiff = "@@ -1,3 +1,4 @@\n+changed\n context";
+      store.updateHunkState(id, hunkId, { suggestionId: id, hunkId, action: "modify", modifiedDiff }, true);
+
+      const undone = store.undoHunkState(id, hunkId);
+
+      expect(undone?.entry.modifiedDiff).toBe(modifiedDiff);
+    });
+
+    test("returns undefined when there is nothing to undo", () => {
+      const id = "test-suggestion-undo-4";
+      createSuggestion(id);
+      const hunkId = `${id}:src/a.ts:0`;
+
+      expect(store.undoHunkState(id, hunkId)).toBeUndefined();
+
+      store.updateHunkState(id, hunkId, { suggestionId: id, hunkId, action: "accept" }, true);
+      store.undoHunkState(id, hunkId);
+
+      // The decision was already undone
+      expect(store.undoHunkState(id, hunkId)).toBeUndefined();
+      expect(store.undoHunkState("non-existent")).toBeUndefined();
+    });
+  });
+
+  describe("decidePartially", () => {
+    const id = "partial-suggestion";
+    const hunkId = `${id}:src/a.ts:0`;
+    const hunkDiff = "@@ -1,1 +1,3 @@\n ctx\n+kept\n+rest";
+
+    beforeEach(() => {
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts"],
+        hunks: [{ id: hunkId, file: "src/a.ts", diff: hunkDiff, originalStartLine: 1 }],
+        workingDirectory: "/test/project",
+      });
+    });
+
+    const decide = () =>
+      store.decidePartially(
+        id,
+        hunkId,
+        { suggestionId: id, hunkId, action: "modify", modifiedDiff: "@@ -1,1 +1,2 @@\n ctx\n+kept", partial: true },
+        true,
+        "@@ -1,2 +1,3 @@\n ctx\n kept\n+rest"
+      );
+
+    test("logs the decision and keeps the rest pending under a new ID", () => {
+      const rest = decide();
+
+      expect(rest?.id).toBe(`${id}:src/a.ts:1`);
+      expect(rest?.diff).toBe("@@ -1,2 +1,3 @@\n ctx\n kept\n+rest");
+      expect(rest?.originalLines).toEqual(["ctx", "kept"]);
+      expect(store.getHunk(id, hunkId)).toBeUndefined();
+      expect(store.getRemainingCount(id)).toBe(1);
+      expect(store.getSuggestion(id)?.status).toBe("partial");
+      expect(store.getFeedbackLog()[0]?.modifiedDiff).toBe("@@ -1,1 +1,2 @@\n ctx\n+kept");
+    });
+
+    test("undo folds the pending rest back into the hunk", () => {
+      const rest = decide()!;
+
+      const undone = store.undoHunkState(id, hunkId);
+
+      expect(undone?.hunk.diff).toBe(hunkDiff);
+      expect(undone?.replaces).toBe(rest.id);
+      expect(store.getHunk(id, rest.id)).toBeUndefined();
+      expect(store.getRemainingCount(id)).toBe(1);
+      expect(store.getSuggestion(id)?.status).toBe("pending");
+    });
+
+    test("undo is refused once the rest has been decided", () => {
+      const rest = decide()!;
+      store.updateHunkState(id, rest.id, { suggestionId: id, hunkId: rest.id, action: "reject" }, false);
+
+      expect(store.undoHunkState(id, hunkId)).toBeUndefined();
+      expect(store.undoHunkState(id, rest.id)?.hunk.id).toBe(rest.id);
+    });
+  });
+
+  describe("decideHunks", () => {
+    const id = "batch-suggestion";
+
+    beforeEach(() => {
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts", "src/b.ts"],
+        hunks: createTestHunks(id),
+        workingDirectory: "/test/project",
+      });
+    });
+
+    test("records every decision", () => {
+      store.decideHunks(id, [
+        { feedback: { suggestionId: id, hunkId: `${id}:src/a.ts:0`, action: "accept" }, applied: true },
+        { feedback: { suggestionId: id, hunkId: `${id}:src/b.ts:0`, action: "reject" }, applied: true },
+      ]);
+
+      expect(store.getRemainingCount(id)).toBe(1);
+      expect(store.getSuggestion(id)?.status).toBe("partial");
+      expect(store.getFeedbackLog().map((e) => e.action).sort()).toEqual(["accept", "reject"]);
+    });
+
+    test("records nothing if one hunk is gone", () => {
+      expect(() =>
+        store.decideHunks(id, [
+          { feedback: { suggestionId: id, hunkId: `${id}:src/a.ts:0`, action: "accept" }, applied: true },
+          { feedback: { suggestionId: id, hunkId: `${id}:src/c.ts:0`, action: "accept" }, applied: true },
+        ])
+      ).toThrow("Hunk not found");
+
+      expect(store.getRemainingCount(id)).toBe(3);
+      expect(store.getHunk(id, `${id}:src/a.ts:0`)).toBeDefined();
+      expect(store.getFeedbackLog()).toEqual([]);
+    });
+
+    describe("feedback JSONL", () => {
+      const logPath = ".opencode/test-batch-feedback.jsonl";
+      let logged: SuggestionStore;
+
+      beforeEach(() => {
+        if (existsSync(logPath)) {
+          unlinkSync(logPath);
+        }
+        logged = new SuggestionStore({ dbPath: ".opencode/test-batch-feedback.db", feedbackLogPath: logPath });
+        logged.clear();
+        logged.createSuggestion({
+          id,
+          jjChangeId: "abc123",
+          description: "Test",
+          files: ["src/a.ts", "src/b.ts"],
+          hunks: createTestHunks(id),
+          workingDirectory: "/test/project",
+        });
+      });
+
+      afterEach(() => {
+        logged.close();
+        for (const path of [logPath, ".opencode/test-batch-feedback.db"]) {
+          if (existsSync(path)) {
+            unlinkSync(path);
+          }
+        }
+      });
+
+      test("gets a line for every decision in the batch", () => {
+        logged.decideHunks(id, [
+          { feedback: { suggestionId: id, hunkId: `${id}:src/a.ts:0`, action: "accept" }, applied: true },
+          { feedback: { suggestionId: id, hunkId: `${id}:src/a.ts:1`, action: "accept" }, applied: true },
+          { feedback: { suggestionId: id, hunkId: `${id}:src/b.ts:0`, action: "reject" }, applied: true },
+        ]);
+
+        const lines = readFileSync(logPath, "utf-8").trimEnd().split("\n");
+        expect(lines.map((line) => JSON.parse(line).hunkId)).toEqual([
+          `${id}:src/a.ts:0`,
+          `${id}:src/a.ts:1`,
+          `${id}:src/b.ts:0`,
+        ]);
+      });
+
+      test("gets nothing from a batch that is rolled back", () => {
+        expect(() =>
+          logged.decideHunks(id, [
+            { feedback: { suggestionId: id, hunkId: `${id}:src/a.ts:0`, action: "accept" }, applied: true },
+            { feedback: { suggestionId: id, hunkId: `${id}:src/a.ts:1`, action: "accept" }, applied: true },
+            { feedback: { suggestionId: id, hunkId: `${id}:src/c.ts:0`, action: "accept" }, applied: true },
+          ])
+        ).toThrow("Hunk not found");
+
+        expect(existsSync(logPath)).toBe(false);
+      });
+    });
+  });
+
+  describe("splitHunk and mergeHunks", () => {
+    const id = "reshape-suggestion";
+    const hunkId = `${id}:src/a.ts:0`;
+    const hunkDiff = "@@ -1,5 +1,5 @@\n-a\n+A\n b\n c\n-d\n+D\n e";
+
+    beforeEach(() => {
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts"],
+        hunks: [{ id: hunkId, file: "src/a.ts", diff: hunkDiff, originalStartLine: 1, description: "Rename things", item: "impl Things" }],
+        workingDirectory: "/test/project",
+      });
+    });
+
+    test("split keeps the ID on the first piece and adds the rest as pending", () => {
+      store.addComment({ suggestionId: id, hunkId, author: "reviewer", body: "Why?" });
+
+      const pieces = store.splitHunk(id, hunkId);
+
+      expect(pieces?.map((h) => h.id)).toEqual([hunkId, `${id}:src/a.ts:1`]);
+      expect(pieces?.[0]?.diff).toBe("@@ -1,3 +1,3 @@\n-a\n+A\n b\n c");
+      expect(pieces?.[1]?.diff).toBe("@@ -2,4 +2,4 @@\n b\n c\n-d\n+D\n e");
+      expect(pieces?.[1]?.originalStartLine).toBe(2);
+      expect(pieces?.every((h) => h.description === "Rename things")).toBe(true);
+      expect(pieces?.every((h) => h.item === "impl Things")).toBe(true);
+      expect(store.getHunkState(id, pieces![1]!.id)?.reviewed).toBe(false);
+      expect(store.getThread(id, hunkId)).toHaveLength(1);
+      expect(store.getRemainingCount(id)).toBe(2);
+    });
+
+    test("split refuses a hunk with nothing to split", () => {
+      expect(store.splitHunk(id, hunkId, 1)).toBeUndefined();
+      expect(store.splitHunk(id, `${id}:src/a.ts:9`)).toBeUndefined();
+      expect(store.getHunk(id, hunkId)?.diff).toBe(hunkDiff);
+    });
+
+    test("merge undoes a split and takes over the thread", () => {
+      const [first, second] = store.splitHunk(id, hunkId)!;
+      store.addComment({ suggestionId: id, hunkId: second!.id, author: "reviewer", body: "And this?" });
+
+      const merged = store.mergeHunks(id, [second!.id, first!.id]);
+
+      expect(merged?.id).toBe(hunkId);
+      expect(merged?.diff).toBe(hunkDiff);
+      expect(merged?.description).toBe("Rename things");
+      expect(store.getHunk(id, second!.id)).toBeUndefined();
+      expect(store.getThread(id, hunkId)).toHaveLength(1);
+      expect(store.getRemainingCount(id)).toBe(1);
+    });
+
+    test("merge refuses hunks with another hunk between them", () => {
+      const pieces = store.splitHunk(id, hunkId)!;
+      store.splitHunk(id, pieces[1]!.id, 4);
+      const ids = store.getSuggestion(id)!.hunks.map((h) => h.id);
+
+      expect(ids).toHaveLength(3);
+      expect(store.mergeHunks(id, [ids[0]!, ids[2]!])).toBeUndefined();
+    });
+  });
+
   describe("suggestion status updates", () => {
     test("status is pending when no hunks reviewed", () => {
       const id = "test-suggestion-10";
@@ -515,6 +803,675 @@ describe("SuggestionStore", () => {
     });
   });
 
+  describe("hunk revisions", () => {
+    const id = "revision-suggestion";
+    const hunkId = `${id}:src/b.ts:0`;
+
+    beforeEach(() => {
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts", "src/b.ts"],
+        hunks: createTestHunks(id),
+        workingDirectory: "/test/project",
+      });
+    });
+
+    test("request_changes keeps the hunk pending with the instruction", () => {
+      const result = store.updateHunkState(id, hunkId, {
+        suggestionId: id,
+        hunkId,
+        action: "request_changes",
+        comment: "use a constant",
+      }, false);
+
+      expect(result).toBe(true);
+      expect(store.getHunk(id, hunkId)).toBeDefined();
+      expect(store.getRemainingCount(id)).toBe(3);
+      expect(store.getHunkState(id, hunkId)).toEqual({
+        reviewed: false,
+        state: "revision_requested",
+        instruction: "use a constant",
+      });
+      expect(store.getFeedbackLog()).toHaveLength(0);
+    });
+
+    test("new hunks start at version 1", () => {
+      expect(store.getHunk(id, hunkId)?.version).toBe(1);
+    });
+
+    test("reviseHunk replaces the diff and bumps the version", () => {
+      store.requestRevision(id, hunkId, "use a constant");
+
+      const revised = store.reviseHunk(id, hunkId, {
+        diff: "@@ -5 +5 @@\n-old\n+NEW_VALUE",
+        originalStartLine: 5,
+        originalLines: ["old"],
+      });
+
+      expect(revised?.hunk.version).toBe(2);
+      expect(revised?.hunk.diff).toBe("@@ -5 +5 @@\n-old\n+NEW_VALUE");
+      expect(revised?.previous.version).toBe(1);
+      expect(revised?.previous.diff).toBe("@@ -5 +5 @@\n-old\n+new");
+      expect(revised?.previous.instruction).toBe("use a constant");
+
+      // Request is cleared once the new version is up
+      expect(store.getHunkState(id, hunkId)?.state).toBeUndefined();
+    });
+
+    test("keeps superseded versions in order", () => {
+      store.reviseHunk(id, hunkId, { diff: "@@ -5 +5 @@\n-old\n+v2" });
+      store.reviseHunk(id, hunkId, { diff: "@@ -5 +5 @@\n-old\n+v3" });
+
+      const versions = store.getHunkVersions(id, hunkId);
+      expect(versions.map((v) => v.version)).toEqual([1, 2]);
+      expect(versions[1]?.diff).toBe("@@ -5 +5 @@\n-old\n+v2");
+      expect(store.getHunk(id, hunkId)?.version).toBe(3);
+    });
+
+    test("returns undefined for reviewed hunks", () => {
+      store.updateHunkState(id, hunkId, { suggestionId: id, hunkId, action: "accept" }, true);
+
+      expect(store.reviseHunk(id, hunkId, { diff: "@@ -5 +5 @@\n-old\n+v2" })).toBeUndefined();
+      expect(store.requestRevision(id, hunkId, "too late")).toBe(false);
+    });
+
+    test("bumps the state version on each change to the hunk", () => {
+      expect(store.getHunk(id, hunkId)?.stateVersion).toBe(1);
+
+      store.requestRevision(id, hunkId, "use a constant");
+      expect(store.getHunk(id, hunkId)?.stateVersion).toBe(2);
+
+      store.reviseHunk(id, hunkId, { diff: "@@ -5 +5 @@\n-old\n+v2" });
+      expect(store.getHunk(id, hunkId)?.stateVersion).toBe(3);
+      expect(store.getSuggestion(id)?.hunks.find((h) => h.id === hunkId)?.stateVersion).toBe(3);
+    });
+
+    test("a hunk restored by undo gets a newer state version", () => {
+      store.updateHunkState(id, hunkId, { suggestionId: id, hunkId, action: "reject" }, true);
+      expect(store.getDecision(id, hunkId)?.action).toBe("reject");
+
+      store.undoHunkState(id, hunkId);
+
+      expect(store.getHunk(id, hunkId)?.stateVersion).toBe(2);
+      expect(store.getDecision(id, hunkId)).toBeUndefined();
+    });
+  });
+
+  describe("updateSuggestion", () => {
+    const id = "update-suggestion";
+    const hunkA = `${id}:src/a.ts:0`;
+    const hunkB = `${id}:src/b.ts:0`;
+
+    const hunks = (): Hunk[] => [
+      { id: hunkA, file: "src/a.ts", diff: "@@ -1,2 +1,2 @@\n-one\n+ONE\n ctx", originalStartLine: 1 },
+      { id: hunkB, file: "src/b.ts", diff: "@@ -5 +5 @@\n-old\n+new", originalStartLine: 5 },
+    ];
+
+    beforeEach(() => {
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts", "src/b.ts"],
+        hunks: hunks(),
+        workingDirectory: "/test/project",
+      });
+    });
+
+    test("keeps unchanged hunks and their state", () => {
+      store.requestRevision(id, hunkA, "shout less");
+
+      const result = store.updateSuggestion(id, { jjChangeId: "def456", hunks: hunks() });
+
+      expect(result).toMatchObject({ added: [], changed: [], removed: [] });
+      expect(result?.suggestion.jjChangeId).toBe("def456");
+      expect(store.getHunkState(id, hunkA)?.state).toBe("revision_requested");
+    });
+
+    test("versions changed hunks and reports added and removed ones", () => {
+      const result = store.updateSuggestion(id, {
+        jjChangeId: "def456",
+        description: "Test, take two",
+        hunks: [
+          { id: hunkA, file: "src/a.ts", diff: "@@ -1,2 +1,2 @@\n-one\n+One\n ctx", originalStartLine: 1 },
+          { id: `${id}:src/c.ts:0`, file: "src/c.ts", diff: "@@ -1 +1 @@\n-c\n+C", originalStartLine: 1 },
+        ],
+      });
+
+      expect(result?.changed).toEqual([hunkA]);
+      expect(result?.added).toEqual([`${id}:src/c.ts:0`]);
+      expect(result?.removed).toEqual([hunkB]);
+      expect(result?.suggestion.description).toBe("Test, take two");
+      expect(store.getHunk(id, hunkA)?.version).toBe(2);
+      expect(store.getHunkVersions(id, hunkA)[0]?.diff).toBe("@@ -1,2 +1,2 @@\n-one\n+ONE\n ctx");
+      expect(store.getHunk(id, hunkB)).toBeUndefined();
+    });
+
+    test("puts an accepted hunk back up for review when its change differs", () => {
+      store.updateHunkState(id, hunkA, { suggestionId: id, hunkId: hunkA, action: "accept" }, true);
+
+      const result = store.updateSuggestion(id, {
+        jjChangeId: "def456",
+        hunks: [
+          { id: hunkA, file: "src/a.ts", diff: "@@ -1,2 +1,2 @@\n-one\n+One\n ctx", originalStartLine: 1 },
+          hunks()[1]!,
+        ],
+      });
+
+      expect(result?.changed).toEqual([hunkA]);
+      expect(store.getHunk(id, hunkA)?.version).toBe(2);
+      expect(store.getHunkState(id, hunkA)?.reviewed).toBe(false);
+      expect(store.getRemainingCount(id)).toBe(2);
+    });
+
+    test("leaves an accepted hunk alone when its change is the same", () => {
+      store.updateHunkState(id, hunkA, { suggestionId: id, hunkId: hunkA, action: "accept" }, true);
+
+      const result = store.updateSuggestion(id, { jjChangeId: "def456", hunks: hunks() });
+
+      expect(result).toMatchObject({ added: [], changed: [], removed: [] });
+      expect(store.getHunk(id, hunkA)).toBeUndefined();
+      expect(store.getRemainingCount(id)).toBe(1);
+    });
+
+    test("keeps a rejected hunk rejected while the change still has it", () => {
+      store.updateHunkState(id, hunkB, { suggestionId: id, hunkId: hunkB, action: "reject" }, true);
+
+      const result = store.updateSuggestion(id, { jjChangeId: "def456", hunks: hunks() });
+
+      expect(result).toMatchObject({ added: [], changed: [], removed: [] });
+      expect(result?.suggestion.hunks.map((h) => h.id)).toEqual([hunkA]);
+      expect(store.getRemainingCount(id)).toBe(1);
+    });
+
+    test("keeps a modified hunk decided while the change still has the published version", () => {
+      store.updateHunkState(
+        id,
+        hunkA,
+        { suggestionId: id, hunkId: hunkA, action: "modify", modifiedDiff: "@@ -1,2 +1,2 @@\n-one\n+Once\n ctx" },
+        true
+      );
+
+      const result = store.updateSuggestion(id, { jjChangeId: "def456", hunks: hunks() });
+
+      expect(result).toMatchObject({ added: [], changed: [], removed: [] });
+      expect(store.getHunk(id, hunkA)).toBeUndefined();
+      expect(store.getDecidedHunks(id).accepted.map((h) => h.diff)).toEqual(["@@ -1,2 +1,2 @@\n-one\n+Once\n ctx"]);
+    });
+
+    test("gives new hunks IDs not used by earlier ones", () => {
+      store.updateHunkState(id, hunkB, { suggestionId: id, hunkId: hunkB, action: "reject" }, true);
+
+      // Same generated ID as the rejected hunk, but a different change elsewhere
+      const result = store.updateSuggestion(id, {
+        jjChangeId: "def456",
+        hunks: [hunks()[0]!, { id: hunkB, file: "src/b.ts", diff: "@@ -40 +40 @@\n-x\n+y", originalStartLine: 40 }],
+      });
+
+      expect(result?.added).toHaveLength(1);
+      expect(result?.added[0]).not.toBe(hunkB);
+      expect(store.getHunk(id, result!.added[0]!)?.file).toBe("src/b.ts");
+    });
+
+    test("returns undefined for unknown suggestions", () => {
+      expect(store.updateSuggestion("missing", { jjChangeId: "x", hunks: [] })).toBeUndefined();
+    });
+  });
+
+  describe("getDecidedHunks", () => {
+    const id = "decided-suggestion";
+    const hunkA = `${id}:src/a.ts:0`;
+    const hunkB = `${id}:src/b.ts:0`;
+    const hunkC = `${id}:src/c.ts:0`;
+
+    beforeEach(() => {
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts", "src/b.ts", "src/c.ts"],
+        hunks: [
+          { id: hunkA, file: "src/a.ts", diff: "@@ -1 +1 @@\n-a\n+A", description: "Shout" },
+          { id: hunkB, file: "src/b.ts", diff: "@@ -1 +1 @@\n-b\n+B" },
+          { id: hunkC, file: "src/c.ts", diff: "@@ -1 +1 @@\n-c\n+C" },
+        ],
+        workingDirectory: "/test/project",
+      });
+    });
+
+    test("returns accepted diffs in decision order and rejected hunks", () => {
+      store.updateHunkState(id, hunkC, { suggestionId: id, hunkId: hunkC, action: "modify", modifiedDiff: "@@ -1 +1 @@\n-c\n+Cc" }, true);
+      store.updateHunkState(id, hunkB, { suggestionId: id, hunkId: hunkB, action: "reject" }, true);
+      store.updateHunkState(id, hunkA, { suggestionId: id, hunkId: hunkA, action: "accept" }, true);
+
+      const { accepted, rejected } = store.getDecidedHunks(id);
+
+      expect(accepted.map((h) => [h.id, h.diff])).toEqual([
+        [hunkC, "@@ -1 +1 @@\n-c\n+Cc"],
+        [hunkA, "@@ -1 +1 @@\n-a\n+A"],
+      ]);
+      expect(accepted[1]?.description).toBe("Shout");
+      expect(rejected.map((h) => h.id)).toEqual([hunkB]);
+    });
+
+    test("leaves out undone decisions", () => {
+      store.updateHunkState(id, hunkA, { suggestionId: id, hunkId: hunkA, action: "accept" }, true);
+      store.undoHunkState(id, hunkA);
+
+      expect(store.getDecidedHunks(id)).toEqual({ accepted: [], rejected: [] });
+    });
+  });
+
+  describe("check results", () => {
+    const id = "check-suggestion";
+
+    beforeEach(() => {
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts", "src/b.ts"],
+        hunks: createTestHunks(id),
+        workingDirectory: "/test/project",
+      });
+    });
+
+    test("a finished result replaces the running one", () => {
+      const running = { suggestionId: id, name: "test", command: "bun test", status: "running" as const, diagnostics: [], startedAt: 1000 };
+      store.saveCheck(running);
+      store.saveCheck({ suggestionId: id, name: "lint", command: "eslint .", status: "running", diagnostics: [], startedAt: 2000 });
+      store.saveCheck({
+        ...running,
+        status: "failed",
+        exitCode: 1,
+        output: "src/a.ts:3: error: boom",
+        diagnostics: [{ file: "src/a.ts", line: 3, severity: "error", message: "boom", hunkId: `${id}:src/a.ts:0` }],
+        finishedAt: 1500,
+      });
+
+      const checks = store.getChecks(id);
+
+      expect(checks.map((c) => [c.name, c.status])).toEqual([["test", "failed"], ["lint", "running"]]);
+      expect(checks[0]?.exitCode).toBe(1);
+      expect(checks[0]?.diagnostics[0]?.hunkId).toBe(`${id}:src/a.ts:0`);
+      expect(checks[1]?.exitCode).toBeUndefined();
+    });
+
+    test("clearChecks drops a previous run", () => {
+      store.saveCheck({ suggestionId: id, name: "test", command: "bun test", status: "passed", diagnostics: [], startedAt: 1000 });
+
+      store.clearChecks(id);
+
+      expect(store.getChecks(id)).toEqual([]);
+    });
+  });
+
+  describe("setHunkDiagnostics", () => {
+    const id = "diag-suggestion";
+    const hunkA = `${id}:src/a.ts:0`;
+    const hunkB = `${id}:src/b.ts:0`;
+
+    beforeEach(() => {
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts", "src/b.ts"],
+        hunks: createTestHunks(id),
+        workingDirectory: "/test/project",
+      });
+    });
+
+    test("attaches diagnostics to their hunks and replaces earlier ones", () => {
+      const warning = { file: "src/a.ts", line: 2, severity: "warning" as const, message: "unused", hunkId: hunkA };
+      store.setHunkDiagnostics(id, [
+        { file: "src/b.ts", line: 1, severity: "error", message: "old", hunkId: hunkB },
+      ]);
+
+      store.setHunkDiagnostics(id, [warning, { file: "src/c.ts", line: 1, severity: "error", message: "elsewhere" }]);
+
+      expect(store.getHunk(id, hunkA)?.diagnostics).toEqual([warning]);
+      expect(store.getHunk(id, hunkB)?.diagnostics).toBeUndefined();
+      expect(store.getSuggestion(id)?.hunks.find((h) => h.id === hunkA)?.diagnostics).toEqual([warning]);
+    });
+  });
+
+  describe("review threads", () => {
+    const id = "thread-suggestion";
+    const hunkId = `${id}:src/a.ts:0`;
+
+    beforeEach(() => {
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts", "src/b.ts"],
+        hunks: createTestHunks(id),
+        workingDirectory: "/test/project",
+      });
+    });
+
+    test("appends comments in order", () => {
+      const question = store.addComment({ suggestionId: id, hunkId, author: "reviewer", body: "why this?" });
+      store.addComment({ suggestionId: id, hunkId, author: "ai", body: "to avoid a race" });
+
+      expect(question.id).toBeGreaterThan(0);
+      const thread = store.getThread(id, hunkId);
+      expect(thread.map((c) => [c.author, c.body])).toEqual([
+        ["reviewer", "why this?"],
+        ["ai", "to avoid a race"],
+      ]);
+    });
+
+    test("groups threads by hunk", () => {
+      store.addComment({ suggestionId: id, hunkId, author: "reviewer", body: "one" });
+      store.addComment({ suggestionId: id, hunkId: `${id}:src/b.ts:0`, author: "reviewer", body: "two" });
+
+      const threads = store.getThreads(id);
+      expect(Object.keys(threads).sort()).toEqual([hunkId, `${id}:src/b.ts:0`]);
+      expect(threads[hunkId]?.[0]?.body).toBe("one");
+    });
+
+    test("hasHunk covers pending and reviewed hunks", () => {
+      expect(store.hasHunk(id, hunkId)).toBe(true);
+
+      store.updateHunkState(id, hunkId, { suggestionId: id, hunkId, action: "reject" }, false);
+      expect(store.hasHunk(id, hunkId)).toBe(true);
+      expect(store.hasHunk(id, `${id}:missing:0`)).toBe(false);
+    });
+  });
+
+  describe("archiveSuggestion", () => {
+    test("hides suggestion from list and get", () => {
+      const id = "archived-1";
+
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["a.ts"],
+        hunks: [{ id: `${id}:a.ts:0`, file: "a.ts", diff: "diff" }],
+        workingDirectory: "/test/project",
+      });
+
+      expect(store.archiveSuggestion(id)).toBe(true);
+      expect(store.getSuggestion(id)).toBeUndefined();
+      expect(store.listSuggestions().suggestions).toHaveLength(0);
+      expect(store.archiveSuggestion(id)).toBe(false);
+    });
+
+    test("keeps archived suggestions searchable", () => {
+      const id = "archived-2";
+
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Rename the parser module",
+        files: ["a.ts"],
+        hunks: [{ id: `${id}:a.ts:0`, file: "a.ts", diff: "diff" }],
+        workingDirectory: "/test/project",
+      });
+      store.discardSuggestion(id);
+      store.archiveSuggestion(id);
+
+      const { entries } = store.searchHistory({ query: "parser" });
+      expect(entries).toHaveLength(1);
+      expect(entries[0]?.kind).toBe("suggestion");
+      expect(entries[0]?.status).toBe("discarded");
+      expect(entries[0]?.archivedAt).toBeDefined();
+    });
+
+    test("archives fully reviewed suggestions on startup", () => {
+      const id = "archived-3";
+      const hunkId = `${id}:a.ts:0`;
+
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["a.ts"],
+        hunks: [{ id: hunkId, file: "a.ts", diff: "diff" }],
+        workingDirectory: "/test/project",
+      });
+      store.updateHunkState(id, hunkId, { suggestionId: id, hunkId, action: "accept" }, true);
+      store.close();
+
+      store = new SuggestionStore({ dbPath: testDbPath });
+
+      expect(store.getSuggestion(id)).toBeUndefined();
+      const { entries } = store.searchHistory({});
+      expect(entries.map((e) => e.kind).sort()).toEqual(["decision", "suggestion"]);
+    });
+  });
+
+  describe("searchHistory", () => {
+    const seed = () => {
+      store.createSuggestion({
+        id: "hist-1",
+        jjChangeId: "qpvuntsm",
+        description: "Add retry to fetchUser",
+        files: ["src/api/user.ts", "src/util.ts"],
+        hunks: [
+          { id: "hist-1:src/api/user.ts:0", file: "src/api/user.ts", diff: "@@ -1 +1 @@\n-fetchUser()\n+withRetry(fetchUser)" },
+          { id: "hist-1:src/util.ts:0", file: "src/util.ts", diff: "@@ -1 +1 @@\n-a\n+b" },
+        ],
+        workingDirectory: "/test/project",
+      });
+      store.createSuggestion({
+        id: "hist-2",
+        jjChangeId: "zzxkmwro",
+        description: "Tidy logging",
+        files: ["src/log.ts"],
+        hunks: [{ id: "hist-2:src/log.ts:0", file: "src/log.ts", diff: "@@ -1 +1 @@\n-console.log(x)\n+logger.debug(x)" }],
+        workingDirectory: "/other/project",
+      });
+
+      store.updateHunkState("hist-1", "hist-1:src/api/user.ts:0", {
+        suggestionId: "hist-1",
+        hunkId: "hist-1:src/api/user.ts:0",
+        action: "reject",
+        comment: "retries belong in the client",
+      }, false);
+      store.updateHunkState("hist-2", "hist-2:src/log.ts:0", {
+        suggestionId: "hist-2",
+        hunkId: "hist-2:src/log.ts:0",
+        action: "accept",
+      }, true);
+    };
+
+    test("matches comments and diffs of reviewed hunks", () => {
+      seed();
+
+      const byComment = store.searchHistory({ query: "client" }).entries;
+      expect(byComment).toHaveLength(1);
+      expect(byComment[0]?.kind).toBe("decision");
+      expect(byComment[0]?.action).toBe("reject");
+      expect(byComment[0]?.comment).toBe("retries belong in the client");
+      expect(byComment[0]?.snippet).toContain("[client]");
+
+      const byDiff = store.searchHistory({ query: "logger.debug" }).entries;
+      expect(byDiff).toHaveLength(1);
+      expect(byDiff[0]?.suggestionId).toBe("hist-2");
+    });
+
+    test("matches pending hunks", () => {
+      seed();
+
+      const { entries } = store.searchHistory({ query: "b", file: "src/util.ts" });
+      expect(entries).toHaveLength(1);
+      expect(entries[0]?.kind).toBe("hunk");
+      expect(entries[0]?.hunkId).toBe("hist-1:src/util.ts:0");
+    });
+
+    test("filters by action, file prefix, jj change and working directory", () => {
+      seed();
+
+      expect(store.searchHistory({ action: "accept" }).entries.map((e) => e.suggestionId)).toEqual(["hist-2"]);
+      expect(store.searchHistory({ file: "src/api" }).entries.map((e) => e.file)).toEqual(["src/api/user.ts"]);
+      expect(store.searchHistory({ jjChangeId: "zzx", action: "accept" }).entries).toHaveLength(1);
+      expect(store.searchHistory({ jjChangeId: "zzx", action: "reject" }).entries).toHaveLength(0);
+      expect(
+        store.searchHistory({ workingDirectory: "/other/project" }).entries.every((e) => e.suggestionId === "hist-2")
+      ).toBe(true);
+    });
+
+    test("filters by date range", () => {
+      seed();
+
+      const now = Date.now();
+      expect(store.searchHistory({ since: now + 60_000 }).entries).toHaveLength(0);
+      expect(store.searchHistory({ until: now - 60_000 }).entries).toHaveLength(0);
+      expect(store.searchHistory({ since: now - 60_000, action: "reject" }).entries).toHaveLength(1);
+    });
+
+    test("marks undone decisions", () => {
+      seed();
+      store.undoHunkState("hist-2");
+
+      const decision = store.searchHistory({ action: "accept" }).entries[0];
+      expect(decision?.undone).toBe(true);
+    });
+
+    test("tolerates query syntax characters", () => {
+      seed();
+
+      expect(() => store.searchHistory({ query: 'fetchUser() "AND -x' })).not.toThrow();
+    });
+  });
+
+  describe("event log", () => {
+    const applied = (hunkId: string) => ({
+      type: "suggestion.hunk_applied" as const,
+      suggestionId: "s",
+      hunkId,
+      action: "accepted" as const,
+    });
+
+    test("numbers events and replays the ones after a seq", () => {
+      expect(store.getLatestSeq()).toBe(0);
+      const first = store.appendEvent(applied("h1"));
+      const second = store.appendEvent(applied("h2"));
+
+      expect(second).toBe(first + 1);
+      expect(store.getLatestSeq()).toBe(second);
+      expect(store.getEventsSince(first, 10)).toEqual([{ ...applied("h2"), seq: second }]);
+      expect(store.getEventsSince(second, 10)).toEqual([]);
+    });
+
+    test("can't replay more than the limit, pruned events or a seq from another database", () => {
+      const first = store.appendEvent(applied("h1"));
+      store.appendEvent(applied("h2"));
+      const last = store.appendEvent(applied("h3"));
+
+      expect(store.getEventsSince(first - 1, 2)).toBeUndefined();
+      expect(store.getEventsSince(last + 5, 10)).toBeUndefined();
+
+      store.clear();
+      expect(store.getLatestSeq()).toBe(last);
+      expect(store.getEventsSince(first, 10)).toBeUndefined();
+      expect(store.appendEvent(applied("h4"))).toBe(last + 1);
+    });
+
+    test("drops the oldest events beyond EVENT_LOG_SIZE", () => {
+      let seq = 0;
+      for (let i = 0; i <= EVENT_LOG_SIZE; i++) {
+        seq = store.appendEvent(applied(`h${i}`));
+      }
+
+      expect(store.getEventsSince(seq - EVENT_LOG_SIZE, EVENT_LOG_SIZE)).toHaveLength(EVENT_LOG_SIZE);
+      expect(store.getEventsSince(seq - EVENT_LOG_SIZE - 1, EVENT_LOG_SIZE + 1)).toBeUndefined();
+    });
+  });
+
+  describe("getReviewRecords", () => {
+    test("returns decisions with their suggestion's publish time and size", () => {
+      const id = "stats-suggestion";
+      const suggestion = store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts", "src/b.ts"],
+        hunks: createTestHunks(id),
+        workingDirectory: "/test/project",
+      });
+      store.updateHunkState(id, `${id}:src/a.ts:0`, { suggestionId: id, hunkId: `${id}:src/a.ts:0`, action: "accept" }, true);
+      store.updateHunkState(id, `${id}:src/b.ts:0`, { suggestionId: id, hunkId: `${id}:src/b.ts:0`, action: "reject" }, true);
+      store.undoHunkState(id, `${id}:src/b.ts:0`);
+
+      const records = store.getReviewRecords();
+
+      expect(records).toHaveLength(1);
+      expect(records[0]).toMatchObject({
+        suggestionId: id,
+        action: "accept",
+        file: "src/a.ts",
+        suggestionCreatedAt: suggestion.createdAt,
+        suggestionHunks: 3,
+      });
+      expect(store.getReviewRecords(records[0]!.timestamp + 1)).toEqual([]);
+    });
+  });
+
+  describe("notifications", () => {
+    const publish = (id: string, sessionId?: string) =>
+      store.createSuggestion({
+        id,
+        jjChangeId: "abc123",
+        description: "Test",
+        files: ["src/a.ts"],
+        hunks: createTestHunks(id),
+        workingDirectory: "/test/project",
+        sessionId,
+      });
+
+    test("are addressed to the session that published the suggestion", () => {
+      publish("s1", "ses_publisher");
+      publish("s2");
+
+      const first = store.addNotification({ suggestionId: "s1", hunkId: "s1:src/a.ts:0", message: "accepted" });
+      const second = store.addNotification({ suggestionId: "s2", message: "discarded" });
+
+      expect(first.sessionId).toBe("ses_publisher");
+      expect(first.hunkId).toBe("s1:src/a.ts:0");
+      expect(first.attempts).toBe(0);
+      expect(first.deliveredAt).toBeUndefined();
+      expect(second.sessionId).toBeUndefined();
+    });
+
+    test("track delivery and failed attempts", () => {
+      publish("s1", "ses_publisher");
+      const delivered = store.addNotification({ suggestionId: "s1", message: "accepted" });
+      const failing = store.addNotification({ suggestionId: "s1", message: "rejected" });
+
+      store.markNotificationDelivered(delivered.id, "session");
+      store.recordNotificationFailure(failing.id, "session not found", 1000);
+
+      expect(store.getNotification(delivered.id)?.deliveredVia).toBe("session");
+      expect(store.getNotifications({ undelivered: true }).map((n) => n.id)).toEqual([failing.id]);
+      expect(store.getNotification(failing.id)).toMatchObject({ attempts: 1, lastError: "session not found" });
+      expect(store.getDueNotifications(999)).toEqual([]);
+      expect(store.getDueNotifications(1000).map((n) => n.id)).toEqual([failing.id]);
+
+      // Giving up leaves it for get_review_feedback
+      store.recordNotificationFailure(failing.id, "session not found");
+      expect(store.getDueNotifications(Date.now())).toEqual([]);
+      expect(store.getNotifications({ undelivered: true })).toHaveLength(1);
+    });
+
+    test("list the most recent, oldest first", () => {
+      publish("s1");
+      publish("s2");
+      store.addNotification({ suggestionId: "s1", message: "one" });
+      store.addNotification({ suggestionId: "s2", message: "two" });
+      store.addNotification({ suggestionId: "s1", message: "three" });
+
+      expect(store.getNotifications({ limit: 2 }).map((n) => n.message)).toEqual(["two", "three"]);
+      expect(store.getNotifications({ suggestionId: "s1" }).map((n) => n.message)).toEqual(["one", "three"]);
+    });
+  });
+
   describe("clear", () => {
     test("clears all suggestions and feedback log", () => {
       store.createSuggestion({
@@ -623,6 +1580,20 @@ describe("SuggestionStore", () => {
   });
 });
 
+describe("toFtsQuery", () => {
+  test("quotes each word", () => {
+    expect(toFtsQuery("foo.bar  baz")).toBe('"foo.bar" "baz"');
+  });
+
+  test("keeps trailing prefix wildcard", () => {
+    expect(toFtsQuery("fetch*")).toBe('"fetch"*');
+  });
+
+  test("escapes double quotes", () => {
+    expect(toFtsQuery('say "hi"')).toBe('"say" """hi"""');
+  });
+});
+
 describe("generateSuggestionId", () => {
   test("generates unique UUIDs", () => {
     const id1 = generateSuggestionId();
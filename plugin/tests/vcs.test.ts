@@ -0,0 +1,238 @@
+import { describe, expect, test, beforeEach, afterEach } from "bun:test";
+import { $ } from "bun";
+import { mkdirSync, writeFileSync, readFileSync, rmSync, existsSync } from "fs";
+import { join } from "path";
+import { tmpdir } from "os";
+import { detectVcs, resolveVcsKind, isStashRef, GitBackend, formatCommitMessage, formatRefName, vcsErrorCode } from "../src/vcs.ts";
+import type { Hunk } from "../src/types.ts";
+
+describe("detectVcs", () => {
+  let testDir: string;
+
+  beforeEach(() => {
+    testDir = join(tmpdir(), `codeforge-vcs-test-${Date.now()}`);
+    mkdirSync(testDir, { recursive: true });
+  });
+
+  afterEach(() => {
+    rmSync(testDir, { recursive: true, force: true });
+  });
+
+  test("detects git from a parent directory", () => {
+    mkdirSync(join(testDir, ".git"));
+    mkdirSync(join(testDir, "src", "lib"), { recursive: true });
+
+    expect(detectVcs(join(testDir, "src", "lib"))).toBe("git");
+  });
+
+  test("detects a git worktree (.git file)", () => {
+    writeFileSync(join(testDir, ".git"), "gitdir: /elsewhere/.git/worktrees/feature\n");
+
+    expect(detectVcs(testDir)).toBe("git");
+  });
+
+  test("prefers jj in a colocated repo", () => {
+    mkdirSync(join(testDir, ".git"));
+    mkdirSync(join(testDir, ".jj"));
+
+    expect(detectVcs(testDir)).toBe("jj");
+  });
+
+  test("configured backend wins over detection", () => {
+    mkdirSync(join(testDir, ".jj"));
+
+    expect(resolveVcsKind("git", testDir)).toBe("git");
+    expect(resolveVcsKind("auto", testDir)).toBe("jj");
+  });
+});
+
+describe("vcsErrorCode", () => {
+  test("jj keeps jj_error, other backends report vcs_error", () => {
+    expect(vcsErrorCode("jj")).toBe("jj_error");
+    expect(vcsErrorCode("git")).toBe("vcs_error");
+  });
+});
+
+describe("isStashRef", () => {
+  test("matches stash entries only", () => {
+    expect(isStashRef("stash@{0}")).toBe(true);
+    expect(isStashRef("stash@{12}")).toBe(true);
+    expect(isStashRef("stash")).toBe(false);
+    expect(isStashRef("HEAD")).toBe(false);
+  });
+});
+
+describe("GitBackend", () => {
+  let repo: string;
+  let git: GitBackend;
+
+  beforeEach(async () => {
+    repo = join(tmpdir(), `codeforge-git-test-${Date.now()}`);
+    mkdirSync(repo, { recursive: true });
+    const sh = $.cwd(repo);
+    await sh`git init -q`;
+    await sh`git config user.email test@example.com`;
+    await sh`git config user.name Test`;
+    writeFileSync(join(repo, "a.txt"), "one\ntwo\nthree\n");
+    await sh`git add a.txt`;
+    await sh`git commit -q -m initial`;
+    git = new GitBackend(sh, repo);
+  });
+
+  afterEach(() => {
+    rmSync(repo, { recursive: true, force: true });
+  });
+
+  test("change ID is the base commit", async () => {
+    const head = (await $.cwd(repo)`git rev-parse HEAD`.text()).trim();
+
+    expect(await git.getCurrentChangeId()).toBe(head);
+  });
+
+  test("diffs the working tree against the base, including untracked files", async () => {
+    writeFileSync(join(repo, "a.txt"), "one\n2\nthree\n");
+    writeFileSync(join(repo, "b.txt"), "new\n");
+
+    const diff = await git.getDiff(await git.getCurrentChangeId());
+
+    expect(diff).toContain("diff --git a/a.txt b/a.txt");
+    expect(diff).toContain("+2");
+    expect(diff).toContain("diff --git a/b.txt b/b.txt");
+    expect(diff).toContain("new file mode");
+  });
+
+  test("restores changed and removes added paths", async () => {
+    const base = await git.getCurrentChangeId();
+    writeFileSync(join(repo, "a.txt"), "changed\n");
+    writeFileSync(join(repo, "b.bin"), "new\n");
+
+    await git.restorePaths(base, ["a.txt", "b.bin"]);
+
+    expect(readFileSync(join(repo, "a.txt"), "utf-8")).toBe("one\ntwo\nthree\n");
+    expect(existsSync(join(repo, "b.bin"))).toBe(false);
+  });
+
+  test("finalize commits only the given hunks onto a branch", async () => {
+    const base = await git.getCurrentChangeId();
+    // The working tree holds a rejected change too
+    writeFileSync(join(repo, "a.txt"), "ONE\n2\nthree\n");
+    const hunks: Hunk[] = [
+      { id: "s:a.txt:0", file: "a.txt", diff: "@@ -1,3 +1,3 @@\n one\n-two\n+2\n three" },
+      { id: "s:b.txt:0", file: "b.txt", diff: "@@ -0,0 +1,1 @@\n+new" },
+    ];
+
+    const result = await git.finalize({
+      changeId: base,
+      message: "Update files\n",
+      hunks,
+      name: "codeforge/s",
+      push: false,
+    });
+
+    const sh = $.cwd(repo);
+    expect(result).toEqual({ commitId: expect.any(String), name: "codeforge/s", pushed: false });
+    expect((await sh`git rev-parse codeforge/s`.text()).trim()).toBe(result.commitId);
+    expect(await sh`git show codeforge/s:a.txt`.text()).toBe("one\n2\nthree\n");
+    expect(await sh`git show codeforge/s:b.txt`.text()).toBe("new\n");
+    expect((await sh`git rev-parse codeforge/s^`.text()).trim()).toBe(base);
+    expect((await sh`git log -1 --format=%s codeforge/s`.text()).trim()).toBe("Update files");
+    // HEAD, the index and the working tree are untouched
+    expect((await sh`git rev-parse HEAD`.text()).trim()).toBe(base);
+    expect((await sh`git diff --cached --name-only`.text()).trim()).toBe("");
+    expect(readFileSync(join(repo, "a.txt"), "utf-8")).toBe("ONE\n2\nthree\n");
+  });
+
+  test("finalize keeps the mode of files it adds and deletes", async () => {
+    const sh = $.cwd(repo);
+    writeFileSync(join(repo, "old.sh"), "#!/bin/sh\n", { mode: 0o755 });
+    await sh`git add old.sh`;
+    await sh`git commit -q -m executable`;
+    const base = await git.getCurrentChangeId();
+    const hunks: Hunk[] = [
+      { id: "s:run.sh:0", file: "run.sh", diff: "@@ -0,0 +1,1 @@\n+#!/bin/sh", fileMode: "100755" },
+      { id: "s:old.sh:0", file: "old.sh", diff: "@@ -1,1 +0,0 @@\n-#!/bin/sh", fileMode: "100755" },
+    ];
+
+    await git.finalize({ changeId: base, message: "Scripts\n", hunks, name: "codeforge/s", push: false });
+
+    expect((await sh`git ls-tree codeforge/s run.sh`.text()).split(" ")[0]).toBe("100755");
+    expect((await sh`git ls-tree codeforge/s old.sh`.text()).trim()).toBe("");
+  });
+
+  test("finalize fails on a hunk that doesn't apply", async () => {
+    const base = await git.getCurrentChangeId();
+    const hunks: Hunk[] = [
+      { id: "s:a.txt:0", file: "a.txt", diff: "@@ -1,2 +1,2 @@\n-missing\n+line\n context" },
+    ];
+
+    await expect(
+      git.finalize({ changeId: base, message: "x", hunks, name: "codeforge/s", push: false })
+    ).rejects.toThrow("Failed to apply s:a.txt:0");
+    expect((await $.cwd(repo)`git branch --list codeforge/s`.text()).trim()).toBe("");
+  });
+
+  test("checks out a working tree suggestion in place", async () => {
+    const checkout = await git.checkout(await git.getCurrentChangeId());
+
+    expect(checkout.dir).toBe(repo);
+    await checkout.remove();
+    expect(existsSync(join(repo, "a.txt"))).toBe(true);
+  });
+
+  test("checks out a stash entry in a temporary worktree", async () => {
+    const sh = $.cwd(repo);
+    writeFileSync(join(repo, "a.txt"), "ONE\ntwo\nthree\n");
+    writeFileSync(join(repo, "b.txt"), "new\n");
+    await sh`git stash push -q --include-untracked`;
+
+    const checkout = await git.checkout("stash@{0}");
+
+    expect(checkout.dir).not.toBe(repo);
+    expect(readFileSync(join(checkout.dir, "a.txt"), "utf-8")).toBe("ONE\ntwo\nthree\n");
+    expect(readFileSync(join(checkout.dir, "b.txt"), "utf-8")).toBe("new\n");
+    // The working tree is untouched
+    expect(readFileSync(join(repo, "a.txt"), "utf-8")).toBe("one\ntwo\nthree\n");
+
+    await checkout.remove();
+    expect(existsSync(checkout.dir)).toBe(false);
+    expect((await sh`git worktree list`.text()).trim().split("\n")).toHaveLength(1);
+    expect((await sh`git stash list`.text()).trim()).not.toBe("");
+  });
+});
+
+describe("formatCommitMessage", () => {
+  test("lists hunk descriptions and a trailer per rejected hunk", () => {
+    const accepted: Hunk[] = [
+      { id: "s1:src/a.ts:0", file: "src/a.ts", diff: "", description: "Add error handling" },
+      { id: "s1:src/a.ts:1", file: "src/a.ts", diff: "", description: "Add error handling" },
+      { id: "s1:src/b.ts:0", file: "src/b.ts", diff: "" },
+    ];
+    const rejected: Hunk[] = [
+      { id: "s1:src/c.ts:0", file: "src/c.ts", diff: "", description: "Rename helper" },
+      { id: "s1:src/c.ts:1", file: "src/c.ts", diff: "" },
+    ];
+
+    expect(formatCommitMessage("s1", "Harden parser", accepted, rejected)).toBe(
+      "Harden parser\n\n" +
+      "- src/a.ts: Add error handling\n\n" +
+      "Rejected-Hunk: src/c.ts:0 (Rename helper)\n" +
+      "Rejected-Hunk: src/c.ts:1\n"
+    );
+  });
+
+  test("is just the description when everything was accepted as is", () => {
+    expect(formatCommitMessage("s1", "Fix typo", [{ id: "s1:a:0", file: "a", diff: "" }], [])).toBe("Fix typo\n");
+  });
+});
+
+describe("formatRefName", () => {
+  test("expands placeholders", () => {
+    const id = "0123456789abcdef";
+
+    expect(formatRefName("codeforge/{id}", id, "x")).toBe("codeforge/0123456789abcdef");
+    expect(formatRefName("review/{short_id}-{slug}", id, "Fix: the parser's edge cases!")).toBe(
+      "review/01234567-fix-the-parser-s-edge-cases"
+    );
+    expect(formatRefName("{slug}", id, "???")).toBe("suggestion");
+  });
+});
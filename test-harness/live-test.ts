@@ -6,11 +6,28 @@
  * 
  * Usage:
  *   bun run live-test.ts
+ *
+ * Authenticates with $CODEFORGE_TOKEN or .opencode/codeforge.token.
  */
 
+import { readFileSync } from "fs";
+
 const PORT = process.env.SUGGESTION_MANAGER_PORT ?? "4097";
 const BASE_URL = `http://127.0.0.1:${PORT}`;
 
+// Server token: $CODEFORGE_TOKEN, or the project's .opencode/codeforge.token
+// (run from the project directory)
+function readToken(): string | undefined {
+  if (process.env.CODEFORGE_TOKEN) return process.env.CODEFORGE_TOKEN;
+  try {
+    return readFileSync(".opencode/codeforge.token", "utf-8").trim();
+  } catch {
+    return undefined;
+  }
+}
+const TOKEN = readToken();
+const headers: Record<string, string> = TOKEN ? { Authorization: `Bearer ${TOKEN}` } : {};
+
 const c = {
   reset: "\x1b[0m",
   green: "\x1b[32m",
@@ -27,7 +44,7 @@ async function main() {
   // 1. Health check
   console.log("1. Health check...");
   try {
-    const health = await fetch(`${BASE_URL}/health`);
+    const health = await fetch(`${BASE_URL}/health`, { headers });
     const healthData = await health.json();
     console.log(`   ${c.green}✓${c.reset} Server healthy:`, healthData);
   } catch (e) {
@@ -37,7 +54,7 @@ async function main() {
 
   // 2. List suggestions
   console.log("\n2. Listing suggestions...");
-  const listRes = await fetch(`${BASE_URL}/suggestions`);
+  const listRes = await fetch(`${BASE_URL}/suggestions`, { headers });
   const listData = await listRes.json() as { suggestions: Array<{ id: string; description: string; hunkCount: number; reviewedCount: number }> };
   
   if (listData.suggestions.length === 0) {
@@ -61,7 +78,7 @@ async function main() {
   const firstSuggestion = listData.suggestions[0]!;
   console.log(`3. Getting details for: ${firstSuggestion.id}`);
   
-  const detailRes = await fetch(`${BASE_URL}/suggestions/${firstSuggestion.id}`);
+  const detailRes = await fetch(`${BASE_URL}/suggestions/${firstSuggestion.id}`, { headers });
   const detail = await detailRes.json() as { 
     id: string; 
     hunks: Array<{ id: string; file: string; diff: string }>; 
@@ -87,17 +104,20 @@ async function main() {
     console.log(`${c.green}# Accept:${c.reset}`);
     console.log(`curl -X POST ${BASE_URL}/feedback \\`);
     console.log(`  -H "Content-Type: application/json" \\`);
+    console.log(`  -H "Authorization: Bearer $(cat .opencode/codeforge.token)" \\`);
     console.log(`  -d '{"suggestionId":"${firstSuggestion.id}","hunkId":"${firstPending.id}","action":"accept"}'`);
     
     console.log(`\n${c.red}# Reject:${c.reset}`);
     console.log(`curl -X POST ${BASE_URL}/feedback \\`);
     console.log(`  -H "Content-Type: application/json" \\`);
+    console.log(`  -H "Authorization: Bearer $(cat .opencode/codeforge.token)" \\`);
     console.log(`  -d '{"suggestionId":"${firstSuggestion.id}","hunkId":"${firstPending.id}","action":"reject"}'`);
   } else {
     console.log(`\n${c.green}All hunks have been reviewed!${c.reset}`);
     console.log(`\nTo finalize or discard:\n`);
     console.log(`curl -X POST ${BASE_URL}/complete \\`);
     console.log(`  -H "Content-Type: application/json" \\`);
+    console.log(`  -H "Authorization: Bearer $(cat .opencode/codeforge.token)" \\`);
     console.log(`  -d '{"suggestionId":"${firstSuggestion.id}","action":"finalize"}'`);
   }
 
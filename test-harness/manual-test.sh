@@ -7,26 +7,33 @@ set -e
 PORT=${SUGGESTION_MANAGER_PORT:-4097}
 BASE_URL="http://127.0.0.1:$PORT"
 
+# Server token: $CODEFORGE_TOKEN, or the project's .opencode/codeforge.token
+TOKEN=${CODEFORGE_TOKEN:-$(cat .opencode/codeforge.token 2>/dev/null || true)}
+AUTH=()
+if [ -n "$TOKEN" ]; then
+    AUTH=(-H "Authorization: Bearer $TOKEN")
+fi
+
 echo "=== Testing Suggestion Manager ==="
 echo "Server: $BASE_URL"
 echo
 
 # 1. Health check
 echo "1. Health check..."
-curl -s "$BASE_URL/health" | jq .
+curl -s "${AUTH[@]}" "$BASE_URL/health" | jq .
 echo
 
 # 2. List suggestions (should be empty or have existing ones)
 echo "2. List suggestions..."
-curl -s "$BASE_URL/suggestions" | jq .
+curl -s "${AUTH[@]}" "$BASE_URL/suggestions" | jq .
 echo
 
 # 3. If there are suggestions, get details of the first one
 echo "3. Getting first suggestion (if any)..."
-FIRST_ID=$(curl -s "$BASE_URL/suggestions" | jq -r '.suggestions[0].id // empty')
+FIRST_ID=$(curl -s "${AUTH[@]}" "$BASE_URL/suggestions" | jq -r '.suggestions[0].id // empty')
 if [ -n "$FIRST_ID" ]; then
     echo "   Found suggestion: $FIRST_ID"
-    curl -s "$BASE_URL/suggestions/$FIRST_ID" | jq .
+    curl -s "${AUTH[@]}" "$BASE_URL/suggestions/$FIRST_ID" | jq .
 else
     echo "   No suggestions found. Ask the AI to publish one!"
     echo
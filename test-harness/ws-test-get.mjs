@@ -2,13 +2,26 @@
 /**
  * Test getting full suggestion details via WebSocket
  */
+import { readFileSync } from "fs";
 import WebSocket from "ws";
 
 const WS_URL = "ws://127.0.0.1:4097/ws";
 
+// Server token: $CODEFORGE_TOKEN, or the project's .opencode/codeforge.token
+// (run from the project directory)
+function readToken() {
+  if (process.env.CODEFORGE_TOKEN) return process.env.CODEFORGE_TOKEN;
+  try {
+    return readFileSync(".opencode/codeforge.token", "utf-8").trim();
+  } catch {
+    return undefined;
+  }
+}
+const TOKEN = readToken();
+
 console.log("Connecting to", WS_URL);
 
-const ws = new WebSocket(WS_URL);
+const ws = new WebSocket(WS_URL, TOKEN ? { headers: { Authorization: `Bearer ${TOKEN}` } } : {});
 
 let messageId = 0;
 const pending = new Map();
@@ -2,11 +2,24 @@
 /**
  * WebSocket Test Client for Suggestion Manager (Node.js version)
  */
+import { readFileSync } from "fs";
 import WebSocket from "ws";
 
 const PORT = process.env.SUGGESTION_MANAGER_PORT ?? "4097";
 const WS_URL = `ws://127.0.0.1:${PORT}/ws`;
 
+// Server token: $CODEFORGE_TOKEN, or the project's .opencode/codeforge.token
+// (run from the project directory)
+function readToken() {
+  if (process.env.CODEFORGE_TOKEN) return process.env.CODEFORGE_TOKEN;
+  try {
+    return readFileSync(".opencode/codeforge.token", "utf-8").trim();
+  } catch {
+    return undefined;
+  }
+}
+const TOKEN = readToken();
+
 const c = {
   reset: "\x1b[0m",
   green: "\x1b[32m",
@@ -19,7 +32,7 @@ const c = {
 console.log(`\n${c.cyan}=== WebSocket Test: Suggestion Manager ===${c.reset}`);
 console.log(`${c.dim}Connecting to: ${WS_URL}${c.reset}\n`);
 
-const ws = new WebSocket(WS_URL);
+const ws = new WebSocket(WS_URL, TOKEN ? { headers: { Authorization: `Bearer ${TOKEN}` } } : {});
 
 let messageId = 0;
 const pendingRequests = new Map();
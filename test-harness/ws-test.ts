@@ -6,11 +6,27 @@
  * 
  * Usage:
  *   bun run ws-test.ts
+ *
+ * Authenticates with $CODEFORGE_TOKEN or .opencode/codeforge.token.
  */
 
+import { readFileSync } from "fs";
+
 const PORT = process.env.SUGGESTION_MANAGER_PORT ?? "4097";
 const WS_URL = `ws://127.0.0.1:${PORT}/ws`;
 
+// Server token: $CODEFORGE_TOKEN, or the project's .opencode/codeforge.token
+// (run from the project directory)
+function readToken(): string | undefined {
+  if (process.env.CODEFORGE_TOKEN) return process.env.CODEFORGE_TOKEN;
+  try {
+    return readFileSync(".opencode/codeforge.token", "utf-8").trim();
+  } catch {
+    return undefined;
+  }
+}
+const TOKEN = readToken();
+
 const c = {
   reset: "\x1b[0m",
   green: "\x1b[32m",
@@ -23,7 +39,8 @@ const c = {
 console.log(`\n${c.cyan}=== WebSocket Test: Suggestion Manager ===${c.reset}`);
 console.log(`${c.dim}Connecting to: ${WS_URL}${c.reset}\n`);
 
-const ws = new WebSocket(WS_URL);
+// Browser-style WebSocket has no headers: the token goes in the query string
+const ws = new WebSocket(TOKEN ? `${WS_URL}?token=${encodeURIComponent(TOKEN)}` : WS_URL);
 
 let messageId = 0;
 const pendingRequests = new Map<string, { resolve: (data: any) => void; reject: (error: any) => void }>();